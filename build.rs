@@ -22,7 +22,31 @@ fn main() {
     std::fs::copy(&src, &dst)
         .unwrap_or_else(|e| panic!("failed to copy {} to {}: {e}", src.display(), dst.display()));
 
+    warn_on_undescribed_definitions(&src);
+
     // Re-run if the schema file changes.
     println!("cargo:rerun-if-changed=schema/agent-exec.schema.json");
     println!("cargo:rerun-if-changed=build.rs");
 }
+
+/// Emit a cargo warning for every top-level schema definition that lacks a
+/// non-empty `description`, so the `schema` output stays self-documenting.
+fn warn_on_undescribed_definitions(path: &std::path::Path) {
+    let raw = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+    let schema: serde_json::Value = serde_json::from_str(&raw)
+        .unwrap_or_else(|e| panic!("{} is not valid JSON: {e}", path.display()));
+    let Some(definitions) = schema.get("definitions").and_then(|d| d.as_object()) else {
+        println!("cargo:warning={} has no definitions", path.display());
+        return;
+    };
+    for (name, definition) in definitions {
+        let described = definition
+            .get("description")
+            .and_then(|d| d.as_str())
+            .is_some_and(|d| !d.trim().is_empty());
+        if !described {
+            println!("cargo:warning=schema definition {name} has no description");
+        }
+    }
+}
@@ -187,10 +187,13 @@ pub fn complete_created_jobs(_current: &std::ffi::OsStr) -> Vec<CompletionCandid
     list_job_candidates(&resolve_root_for_completion(), Some(&["created"]))
 }
 
-/// Complete only jobs in `running` state.
-/// Used by: `kill` (only running jobs can be killed).
+/// Complete only jobs with a live process (`running`, `stopped`).
+/// Used by: `kill` (only running or paused jobs can be signalled).
 pub fn complete_running_jobs(_current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
-    list_job_candidates(&resolve_root_for_completion(), Some(&["running"]))
+    list_job_candidates(
+        &resolve_root_for_completion(),
+        Some(&["running", "stopped"]),
+    )
 }
 
 /// Complete only jobs in terminal states (`exited`, `killed`, `failed`).
@@ -202,12 +205,12 @@ pub fn complete_terminal_jobs(_current: &std::ffi::OsStr) -> Vec<CompletionCandi
     )
 }
 
-/// Complete jobs in non-terminal states (`created`, `running`).
+/// Complete jobs in non-terminal states (`created`, `running`, `stopped`).
 /// Used by: `wait` (waiting on a terminal job is a no-op).
 pub fn complete_waitable_jobs(_current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
     list_job_candidates(
         &resolve_root_for_completion(),
-        Some(&["created", "running"]),
+        Some(&["created", "running", "stopped"]),
     )
 }
 
@@ -7,10 +7,11 @@
 use anyhow::{Context, Result};
 use tracing::info;
 
+use crate::duration::DurationMs;
 use crate::jobstore::{JobDir, generate_job_id, resolve_root};
 use crate::run::{
-    mask_env_vars, materialize_stdin_for_job, pre_create_log_files, resolve_effective_cwd,
-    validate_stdin_source,
+    command_hash, dedup_env_vars, mask_env_vars, materialize_stdin_for_job, pre_create_log_files,
+    resolve_effective_cwd, validate_stdin_source,
 };
 use crate::schema::{CreateData, JobMeta, JobMetaJob, Response};
 use crate::tag::dedup_tags;
@@ -30,9 +31,12 @@ pub struct CreateOpts<'a> {
     /// Override for jobs root directory.
     pub root: Option<&'a str>,
     /// Timeout in milliseconds; 0 = no timeout.
-    pub timeout_ms: u64,
-    /// Milliseconds after SIGTERM before SIGKILL; 0 = immediate SIGKILL.
-    pub kill_after_ms: u64,
+    pub timeout_ms: DurationMs,
+    /// Grace period (ms) between the timeout SIGTERM and SIGKILL; 0 = immediate SIGKILL.
+    pub kill_after_ms: DurationMs,
+    /// Extra milliseconds after the kill-after grace period before the process group
+    /// is SIGKILLed; 0 = no extra delay.
+    pub keep_going_ms: DurationMs,
     /// Working directory for the command.
     pub cwd: Option<&'a str>,
     /// Environment variables as KEY=VALUE strings (persisted as durable config).
@@ -48,7 +52,7 @@ pub struct CreateOpts<'a> {
     /// Maximum bytes allowed for materialized stdin.bin.
     pub stdin_max_bytes: u64,
     /// Interval (ms) for state.json updated_at refresh; 0 = disabled.
-    pub progress_every_ms: u64,
+    pub progress_every_ms: DurationMs,
     /// Shell command string for command notification sink.
     pub notify_command: Option<String>,
     /// File path for NDJSON notification sink.
@@ -67,6 +71,8 @@ pub struct CreateOpts<'a> {
     pub output_command: Option<String>,
     /// File path for output-match NDJSON file sink.
     pub output_file: Option<String>,
+    /// Encoding label used by `tail` to decode log bytes.
+    pub output_encoding: String,
 }
 
 /// Execute `create`: persist job definition and return JSON.
@@ -82,15 +88,16 @@ pub fn execute(opts: CreateOpts) -> Result<()> {
     let job_id = generate_job_id(&root)?;
     let created_at = crate::run::now_rfc3339_pub();
 
-    let env_keys: Vec<String> = opts
-        .env_vars
+    let env_vars = dedup_env_vars(&opts.env_vars);
+    let env_keys: Vec<String> = env_vars
         .iter()
         .map(|kv| kv.split('=').next().unwrap_or(kv.as_str()).to_string())
         .collect();
 
-    let masked_env_vars = mask_env_vars(&opts.env_vars, &opts.mask);
+    let masked_env_vars = mask_env_vars(&env_vars, &opts.mask);
 
     let effective_cwd = resolve_effective_cwd(opts.cwd);
+    let command_hash = command_hash(&opts.command, Some(&effective_cwd), &env_keys);
 
     // Build output-match config from definition-time options (same logic as `notify set`).
     let on_output_match = crate::notify::build_output_match_config(
@@ -123,6 +130,7 @@ pub fn execute(opts: CreateOpts) -> Result<()> {
     let meta = JobMeta {
         job: JobMetaJob { id: job_id.clone() },
         schema_version: crate::schema::SCHEMA_VERSION.to_string(),
+        agent_exec_version: crate::schema::AGENT_EXEC_VERSION.to_string(),
         command: opts.command.clone(),
         created_at: created_at.clone(),
         root: root.display().to_string(),
@@ -131,19 +139,65 @@ pub fn execute(opts: CreateOpts) -> Result<()> {
         // Persist actual (unmasked) env vars for runtime use by `start`.
         // --mask only affects display/metadata views; the real values are needed
         // so `start` can apply them to the child process environment.
-        env_vars_runtime: opts.env_vars.clone(),
+        env_vars_runtime: env_vars,
         mask: opts.mask.clone(),
         cwd: Some(effective_cwd),
+        command_hash,
         notification,
         tags,
         // Execution-definition fields persisted for `start`.
         inherit_env: opts.inherit_env,
         env_files: opts.env_files.clone(),
-        timeout_ms: opts.timeout_ms,
-        kill_after_ms: opts.kill_after_ms,
-        progress_every_ms: opts.progress_every_ms,
+        timeout_ms: opts.timeout_ms.as_millis(),
+        kill_after_ms: opts.kill_after_ms.as_millis(),
+        keep_going_ms: opts.keep_going_ms.as_millis(),
+        progress_every_ms: opts.progress_every_ms.as_millis(),
         shell_wrapper: Some(opts.shell_wrapper.clone()),
         stdin_file: None,
+        detached: false,
+        signal_on_parent_exit: None,
+        output_encoding: opts.output_encoding.clone(),
+        pty: false,
+        health_check: None,
+        event_log: false,
+        status_report: None,
+        core_dump_disabled: false,
+        max_output_lines_per_second: 0,
+        env_expand: false,
+        log_buffering: Default::default(),
+        priority: Default::default(),
+        nice: None,
+        stdout_file: None,
+        full_log_file: None,
+        env_unset: vec![],
+        env_inherit_prefix: vec![],
+        rlimits: vec![],
+        pid_namespace: false,
+        require_pid_namespace: false,
+        windows_job_object: true,
+        require_clean_exit: false,
+        snapshot_on_sigusr1: false,
+        max_line_length: 0,
+        log_rotate_size_bytes: 0,
+        log_rotate_count: 0,
+        startup_wait_ms: None,
+        env_from_json_keys: vec![],
+        env_source_job_id: None,
+        ssh_host: None,
+        original_command: None,
+        hostname: None,
+        username: None,
+        report_file: None,
+        profile_output: None,
+        checkpoint_dir: None,
+        checkpoint_interval_ms: None,
+        checkpoint_count: None,
+        completion_webhook_url: None,
+        completion_webhook_timeout_ms: None,
+        notify_timeout_ms: None,
+        notify_file_append: true,
+        notify_file_snapshot: false,
+        capture_output_max_bytes: None,
     };
 
     let job_dir = JobDir::create(&root, &job_id, &meta)?;
@@ -160,7 +214,7 @@ pub fn execute(opts: CreateOpts) -> Result<()> {
     pre_create_log_files(&job_dir)?;
 
     // Write state.json with `created` status — no process spawned.
-    job_dir.init_state_created()?;
+    job_dir.init_state_created(&meta.created_at)?;
 
     let stdout_log_path = job_dir.stdout_path().display().to_string();
     let stderr_log_path = job_dir.stderr_path().display().to_string();
@@ -107,7 +107,7 @@ fn delete_single(
     // Reject running jobs.
     if state_opt
         .as_ref()
-        .map(|s| *s.status() == JobStatus::Running)
+        .map(|s| s.status().is_active())
         .unwrap_or(false)
     {
         return Err(anyhow::Error::new(InvalidJobState(format!(
@@ -290,6 +290,7 @@ fn delete_all(root: &std::path::Path, root_str: &str, dry_run: bool) -> Result<(
         if !is_terminal {
             let reason = match status.as_ref() {
                 Some(JobStatus::Running) => "running",
+                Some(JobStatus::Stopped) => "stopped",
                 Some(JobStatus::Created) => "created",
                 _ => "non_terminal",
             };
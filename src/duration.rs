@@ -0,0 +1,101 @@
+//! Millisecond durations used by command options.
+//!
+//! [`DurationMs`] wraps a raw millisecond count so timeouts and intervals
+//! cannot be mixed up with byte counts or seconds. Arithmetic saturates
+//! instead of overflowing, and the value serializes as a plain integer.
+
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Sub};
+
+/// A duration in whole milliseconds; `0` usually means "disabled".
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct DurationMs(pub u64);
+
+impl DurationMs {
+    pub const ZERO: DurationMs = DurationMs(0);
+
+    pub const fn from_millis(ms: u64) -> Self {
+        DurationMs(ms)
+    }
+
+    /// Convert fractional seconds, rounding to the nearest millisecond.
+    /// Negative and NaN inputs become zero; values too large saturate.
+    pub fn from_secs(secs: f64) -> Self {
+        // Float-to-int `as` casts saturate and map NaN to 0.
+        DurationMs((secs * 1000.0).round() as u64)
+    }
+
+    pub const fn as_millis(self) -> u64 {
+        self.0
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn as_duration(self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.0)
+    }
+}
+
+impl From<u64> for DurationMs {
+    fn from(ms: u64) -> Self {
+        DurationMs(ms)
+    }
+}
+
+impl std::fmt::Display for DurationMs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Add for DurationMs {
+    type Output = DurationMs;
+
+    fn add(self, rhs: DurationMs) -> DurationMs {
+        DurationMs(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for DurationMs {
+    type Output = DurationMs;
+
+    fn sub(self, rhs: DurationMs) -> DurationMs {
+        DurationMs(self.0.saturating_sub(rhs.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_saturates() {
+        let max = DurationMs(u64::MAX);
+        assert_eq!(max + DurationMs(1), max);
+        assert_eq!(DurationMs(1) - DurationMs(2), DurationMs::ZERO);
+        assert_eq!(DurationMs(1500) + DurationMs(500), DurationMs(2000));
+        assert_eq!(DurationMs(1500) - DurationMs(500), DurationMs(1000));
+    }
+
+    #[test]
+    fn from_secs_rounds_and_clamps() {
+        assert_eq!(DurationMs::from_secs(1.5), DurationMs(1500));
+        assert_eq!(DurationMs::from_secs(0.0004), DurationMs(0));
+        assert_eq!(DurationMs::from_secs(-3.0), DurationMs(0));
+        assert_eq!(DurationMs::from_secs(f64::NAN), DurationMs(0));
+        assert_eq!(DurationMs::from_secs(f64::INFINITY), DurationMs(u64::MAX));
+    }
+
+    #[test]
+    fn serializes_as_plain_integer() {
+        let d = DurationMs::from_millis(250);
+        assert_eq!(serde_json::to_string(&d).unwrap(), "250");
+        assert_eq!(serde_json::from_str::<DurationMs>("250").unwrap(), d);
+        assert_eq!(d.as_duration(), std::time::Duration::from_millis(250));
+    }
+}
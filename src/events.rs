@@ -0,0 +1,55 @@
+//! Implementation of the `events` sub-command.
+//!
+//! `events` reads the structured event log (`events.ndjson`) that the
+//! supervisor records for jobs launched with `run --event-log`, optionally
+//! filtered by event type.
+
+use anyhow::Result;
+use tracing::debug;
+
+use crate::jobstore::{JobDir, resolve_root};
+use crate::schema::{EventsData, Response};
+
+/// Event types recorded in `events.ndjson`.
+pub const EVENT_TYPES: [&str; 6] = [
+    "spawn",
+    "stdout_chunk",
+    "stderr_chunk",
+    "timeout_signal",
+    "exit",
+    "progress_tick",
+];
+
+/// Options for the `events` sub-command.
+#[derive(Debug)]
+pub struct EventsOpts<'a> {
+    pub job_id: &'a str,
+    pub root: Option<&'a str>,
+    /// Only return events of this type; None = all events.
+    pub kind: Option<&'a str>,
+}
+
+/// Execute `events`: read the job's event log and emit JSON.
+pub fn execute(opts: EventsOpts) -> Result<()> {
+    let root = resolve_root(opts.root);
+    let job_dir = JobDir::open(&root, opts.job_id)?;
+
+    let mut events = Vec::new();
+    for event in job_dir.iter_events() {
+        let event = event?;
+        if opts.kind.is_none_or(|kind| event.kind == kind) {
+            events.push(event);
+        }
+    }
+    debug!(job_id = %job_dir.job_id, kind = ?opts.kind, count = events.len(), "events query");
+
+    Response::new(
+        "events",
+        EventsData {
+            job_id: job_dir.job_id.clone(),
+            events,
+        },
+    )
+    .print();
+    Ok(())
+}
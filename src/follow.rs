@@ -0,0 +1,457 @@
+//! Implementation of the `follow` sub-command.
+//!
+//! `follow` attaches to a job's `stdout.log` and `stderr.log` at their current
+//! end and prints one NDJSON `chunk` line per batch of new bytes, then a
+//! `finished` line once the job is terminal and its logs are drained. Unlike
+//! `tail`, which is a one-shot snapshot, it keeps running while the job does.
+//! Chunks are decoded with the job's `output_encoding`, like `tail`. A log
+//! replaced by rotation is recognised by its inode; the unread rest of the
+//! archived file is printed before the new file is read from the start.
+//! `tail --stream` reuses the same chunk reader after its initial snapshot.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use crate::duration::DurationMs;
+use crate::jobstore::{BASE64_ENCODING, JobDir, resolve_root};
+use crate::schema::{FollowEvent, JobState, print_to_stdout};
+
+/// Options for the `follow` sub-command.
+#[derive(Debug)]
+pub struct FollowOpts<'a> {
+    pub job_id: &'a str,
+    pub root: Option<&'a str>,
+    /// Interval between checks for new output and state changes.
+    pub poll_ms: DurationMs,
+    /// How long to wait for the log files to appear before giving up.
+    pub timeout_ms: DurationMs,
+    /// Only follow stdout.log.
+    pub stdout_only: bool,
+    /// Only follow stderr.log.
+    pub stderr_only: bool,
+}
+
+/// The log files did not appear within `--timeout-ms`.
+#[derive(Debug)]
+pub struct FollowTimeout(pub String);
+
+impl std::fmt::Display for FollowTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for FollowTimeout {}
+
+/// How the bytes of a followed log are turned into chunk text.
+enum ChunkDecoder {
+    /// Lossy UTF-8; the trailing bytes of an incomplete sequence are held
+    /// back until the rest arrives.
+    Utf8 { pending: Vec<u8> },
+    /// Raw bytes, base64-encoded per chunk.
+    Base64,
+    /// Any other encoding; the decoder keeps split sequences between reads.
+    Other(encoding_rs::Decoder),
+}
+
+impl ChunkDecoder {
+    /// The decoder for an encoding label, matching
+    /// [`crate::jobstore::decode_log_bytes`].
+    fn for_label(encoding: &str) -> Self {
+        if encoding.eq_ignore_ascii_case(BASE64_ENCODING) {
+            return ChunkDecoder::Base64;
+        }
+        match encoding_rs::Encoding::for_label(encoding.as_bytes()) {
+            Some(enc) if enc != encoding_rs::UTF_8 => {
+                ChunkDecoder::Other(enc.new_decoder_without_bom_handling())
+            }
+            _ => ChunkDecoder::Utf8 {
+                pending: Vec::new(),
+            },
+        }
+    }
+
+    /// Bytes held back from earlier reads.
+    fn pending_len(&self) -> usize {
+        match self {
+            ChunkDecoder::Utf8 { pending } => pending.len(),
+            _ => 0,
+        }
+    }
+
+    /// Forget held-back bytes; the log started over.
+    fn reset(&mut self) {
+        match self {
+            ChunkDecoder::Utf8 { pending } => pending.clear(),
+            ChunkDecoder::Other(decoder) => {
+                *decoder = decoder.encoding().new_decoder_without_bom_handling();
+            }
+            ChunkDecoder::Base64 => {}
+        }
+    }
+
+    /// Decode newly read `bytes`; `last` flushes anything held back.
+    fn decode(&mut self, bytes: &[u8], last: bool) -> String {
+        match self {
+            ChunkDecoder::Utf8 { pending } => {
+                let mut data = std::mem::take(pending);
+                data.extend_from_slice(bytes);
+                if !last {
+                    let complete = complete_utf8_prefix(&data);
+                    *pending = data.split_off(complete);
+                }
+                String::from_utf8_lossy(&data).into_owned()
+            }
+            ChunkDecoder::Base64 => crate::jobstore::decode_log_bytes(bytes, BASE64_ENCODING),
+            ChunkDecoder::Other(decoder) => {
+                let mut text = String::with_capacity(
+                    decoder
+                        .max_utf8_buffer_length(bytes.len())
+                        .unwrap_or(bytes.len() * 3),
+                );
+                let _ = decoder.decode_to_string(bytes, &mut text, last);
+                if last {
+                    *decoder = decoder.encoding().new_decoder_without_bom_handling();
+                }
+                text
+            }
+        }
+    }
+}
+
+/// One followed log file.
+pub(crate) struct FollowedLog {
+    stream: &'static str,
+    path: PathBuf,
+    /// Byte offset of the next unread byte.
+    offset: u64,
+    /// Device and inode of the file `offset` points into; a change means the
+    /// log was rotated (`run --log-rotate-size-mb`).
+    identity: Option<(u64, u64)>,
+    decoder: ChunkDecoder,
+}
+
+impl FollowedLog {
+    /// Follow `path` from byte `offset` on, decoding chunks with `encoding`
+    /// (an `output_encoding` label or `base64`).
+    pub(crate) fn new(stream: &'static str, path: PathBuf, offset: u64, encoding: &str) -> Self {
+        let identity = std::fs::metadata(&path)
+            .ok()
+            .and_then(|m| file_identity(&m));
+        FollowedLog {
+            stream,
+            path,
+            offset,
+            identity,
+            decoder: ChunkDecoder::for_label(encoding),
+        }
+    }
+
+    /// Emit everything appended since the last call as one `chunk` event.
+    fn emit_new_bytes(&mut self, emit: &mut impl FnMut(FollowEvent)) -> Result<()> {
+        let Ok(file) = std::fs::File::open(&self.path) else {
+            return Ok(());
+        };
+        let metadata = file.metadata().ok();
+        let identity = metadata.as_ref().and_then(file_identity);
+        let len = metadata.map(|m| m.len()).unwrap_or(0);
+        if self.identity.is_some() && identity.is_some() && identity != self.identity {
+            // The log was rotated: finish the archived file, then start over.
+            debug!(path = %self.path.display(), "follow: log rotated, restarting at 0");
+            self.drain_rotated(emit)?;
+            self.offset = 0;
+        } else if len < self.offset {
+            // The log was truncated; start over.
+            debug!(path = %self.path.display(), "follow: log shrank, restarting at 0");
+            self.offset = 0;
+            self.decoder.reset();
+        }
+        self.identity = identity;
+        self.emit_from(emit, file, len)
+    }
+
+    /// Emit the unread rest of the rotated file this log was reading, found
+    /// among `<name>.1`, `<name>.2`, ... by its identity, followed by any
+    /// newer archives in full. Bytes of a character split by the rotation
+    /// are held back and completed from the next file. Nothing is emitted
+    /// when the file was not archived (e.g. `--log-rotate-count 0`).
+    fn drain_rotated(&mut self, emit: &mut impl FnMut(FollowEvent)) -> Result<()> {
+        let archive = |n: u32| {
+            let mut path = self.path.clone().into_os_string();
+            path.push(format!(".{n}"));
+            PathBuf::from(path)
+        };
+        let mut newer = Vec::new();
+        let mut n = 1;
+        let found = loop {
+            let path = archive(n);
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                break None;
+            };
+            if file_identity(&metadata) == self.identity {
+                break Some(path);
+            }
+            newer.push(path);
+            n += 1;
+        };
+        let Some(found) = found else {
+            self.decoder.reset();
+            return Ok(());
+        };
+        for path in std::iter::once(found).chain(newer.into_iter().rev()) {
+            let Ok(file) = std::fs::File::open(&path) else {
+                continue;
+            };
+            let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+            self.emit_from(emit, file, len)?;
+            self.offset = 0;
+        }
+        Ok(())
+    }
+
+    /// Emit the bytes of `file` from `offset` up to `len` as one `chunk` event.
+    fn emit_from(
+        &mut self,
+        emit: &mut impl FnMut(FollowEvent),
+        mut file: std::fs::File,
+        len: u64,
+    ) -> Result<()> {
+        if len <= self.offset {
+            return Ok(());
+        }
+        file.seek(SeekFrom::Start(self.offset))
+            .with_context(|| format!("seek {}", self.path.display()))?;
+        let mut bytes = Vec::new();
+        file.take(len - self.offset)
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("read {}", self.path.display()))?;
+        // Bytes held back from a rotated file count from the start of this one.
+        let chunk_offset = self
+            .offset
+            .saturating_sub(self.decoder.pending_len() as u64);
+        self.offset += bytes.len() as u64;
+
+        let data = self.decoder.decode(&bytes, false);
+        if data.is_empty() {
+            return Ok(());
+        }
+        emit(FollowEvent::Chunk {
+            stream: self.stream.to_string(),
+            data,
+            offset: chunk_offset,
+        });
+        Ok(())
+    }
+
+    /// Emit any held-back bytes; used once no more output can arrive.
+    fn flush_pending(&mut self, emit: &mut impl FnMut(FollowEvent)) -> Result<()> {
+        let chunk_offset = self
+            .offset
+            .saturating_sub(self.decoder.pending_len() as u64);
+        let data = self.decoder.decode(&[], true);
+        if data.is_empty() {
+            return Ok(());
+        }
+        emit(FollowEvent::Chunk {
+            stream: self.stream.to_string(),
+            data,
+            offset: chunk_offset,
+        });
+        Ok(())
+    }
+}
+
+/// Device and inode of a file, which stay the same while it is appended to
+/// and change when rotation replaces it.
+#[cfg(unix)]
+fn file_identity(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Length of the longest prefix of `bytes` that does not end inside an
+/// incomplete UTF-8 sequence. Invalid bytes count as complete; they are
+/// replaced when the chunk is decoded.
+pub(crate) fn complete_utf8_prefix(bytes: &[u8]) -> usize {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => {
+            // An invalid sequence earlier on; only hold back a truncated tail.
+            let tail_start = bytes.len().saturating_sub(3);
+            (tail_start..bytes.len())
+                .find(|&i| {
+                    std::str::from_utf8(&bytes[i..])
+                        .err()
+                        .is_some_and(|e| e.valid_up_to() == 0 && e.error_len().is_none())
+                })
+                .unwrap_or(bytes.len())
+        }
+    }
+}
+
+/// Wait until every path in `paths` exists, or fail after `timeout_ms`.
+fn wait_for_logs(paths: &[&Path], timeout_ms: DurationMs, poll: std::time::Duration) -> Result<()> {
+    let deadline = std::time::Instant::now() + timeout_ms.as_duration();
+    while let Some(missing) = paths.iter().find(|p| !p.exists()) {
+        if std::time::Instant::now() >= deadline {
+            return Err(anyhow::Error::new(FollowTimeout(format!(
+                "{} did not appear within {timeout_ms} ms",
+                missing.display()
+            ))));
+        }
+        std::thread::sleep(poll);
+    }
+    Ok(())
+}
+
+/// Execute `follow`: stream new output as NDJSON until the job finishes.
+pub fn execute(opts: FollowOpts) -> Result<()> {
+    let root = resolve_root(opts.root);
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
+    let poll = opts.poll_ms.max(DurationMs::from_millis(1)).as_duration();
+    let encoding = job_dir.read_meta()?.output_encoding;
+
+    let mut logs = Vec::new();
+    if !opts.stderr_only {
+        logs.push(("stdout", job_dir.stdout_path()));
+    }
+    if !opts.stdout_only {
+        logs.push(("stderr", job_dir.stderr_path()));
+    }
+    let paths: Vec<&Path> = logs.iter().map(|(_, path)| path.as_path()).collect();
+    wait_for_logs(&paths, opts.timeout_ms, poll)?;
+
+    // Attach at the current end: only output written from now on is printed.
+    let mut logs: Vec<FollowedLog> = logs
+        .into_iter()
+        .map(|(stream, path)| {
+            let offset = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            FollowedLog::new(stream, path, offset, &encoding)
+        })
+        .collect();
+    debug!(job_id = %job_dir.job_id, "follow: attached");
+
+    let state = stream_until_drained(&job_dir, &mut logs, poll, &mut |event| {
+        print_to_stdout(&event)
+    })?;
+    print_to_stdout(&FollowEvent::Finished {
+        state: state.status().as_str().to_string(),
+        exit_code: state.exit_code(),
+    });
+    Ok(())
+}
+
+/// Pass `chunk` events for `logs` to `emit` until the job is terminal and its
+/// logs are drained, then return the final state.
+pub(crate) fn stream_until_drained(
+    job_dir: &JobDir,
+    logs: &mut [FollowedLog],
+    poll: std::time::Duration,
+    emit: &mut impl FnMut(FollowEvent),
+) -> Result<JobState> {
+    loop {
+        // Read the state first so output written before the job finished is
+        // emitted before the caller's final line.
+        let state = job_dir.read_state()?;
+        for log in logs.iter_mut() {
+            log.emit_new_bytes(emit)?;
+        }
+        if !state.status().is_non_terminal() && state.logs_drained {
+            for log in logs.iter_mut() {
+                log.flush_pending(emit)?;
+            }
+            return Ok(state);
+        }
+        std::thread::sleep(poll);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The events `log.emit_new_bytes` produces, as JSON.
+    fn new_events(log: &mut FollowedLog) -> Vec<serde_json::Value> {
+        let mut events = Vec::new();
+        log.emit_new_bytes(&mut |event| events.push(serde_json::to_value(event).unwrap()))
+            .unwrap();
+        events
+    }
+
+    #[test]
+    fn complete_utf8_prefix_holds_back_split_sequences() {
+        assert_eq!(complete_utf8_prefix(b"abc"), 3);
+        // "é" is 0xC3 0xA9; a chunk ending after 0xC3 keeps it for later.
+        assert_eq!(complete_utf8_prefix(b"ab\xC3"), 2);
+        assert_eq!(complete_utf8_prefix(b"ab\xC3\xA9"), 4);
+        assert_eq!(complete_utf8_prefix(b"\xFFab\xE2\x82"), 3);
+        assert_eq!(complete_utf8_prefix(b"\xFFab"), 3);
+    }
+
+    #[test]
+    fn emit_new_bytes_prints_chunks_with_offsets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdout.log");
+        std::fs::write(&path, b"old").unwrap();
+        let mut log = FollowedLog::new("stdout", path.clone(), 3, "utf-8");
+        let mut lines = new_events(&mut log);
+        assert!(lines.is_empty());
+
+        std::fs::write(&path, b"old new\xC3").unwrap();
+        lines.extend(new_events(&mut log));
+        std::fs::write(&path, b"old new\xC3\xA9").unwrap();
+        lines.extend(new_events(&mut log));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "chunk");
+        assert_eq!(lines[0]["data"], " new");
+        assert_eq!(lines[0]["offset"], 3);
+        assert_eq!(lines[1]["data"], "é");
+        assert_eq!(lines[1]["offset"], 7);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn emit_new_bytes_drains_rotated_log_before_restarting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdout.log");
+        std::fs::write(&path, b"one\n").unwrap();
+        let mut log = FollowedLog::new("stdout", path.clone(), 0, "utf-8");
+        let mut lines = new_events(&mut log);
+
+        // More output lands, then the log is rotated and the new file grows
+        // past the old offset before the next poll.
+        std::fs::write(&path, b"one\ntwo\n").unwrap();
+        crate::jobstore::rotate_log_file(dir.path(), "stdout.log", 0, 2).unwrap();
+        std::fs::write(&path, b"three\nfour\nfive\n").unwrap();
+        lines.extend(new_events(&mut log));
+        let text: String = lines.iter().map(|l| l["data"].as_str().unwrap()).collect();
+        assert_eq!(text, "one\ntwo\nthree\nfour\nfive\n");
+        assert_eq!(lines.last().unwrap()["offset"], 0);
+    }
+
+    #[test]
+    fn emit_new_bytes_decodes_with_output_encoding() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdout.log");
+        // "あ" in Shift_JIS is 0x82 0xA0, split across two reads.
+        std::fs::write(&path, b"\x82").unwrap();
+        let mut sjis = FollowedLog::new("stdout", path.clone(), 0, "shift_jis");
+        let mut b64 = FollowedLog::new("stdout", path.clone(), 0, "base64");
+        let mut lines = new_events(&mut sjis);
+        std::fs::write(&path, b"\x82\xA0").unwrap();
+        lines.extend(new_events(&mut sjis));
+        lines.extend(new_events(&mut b64));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["data"], "あ");
+        assert_eq!(lines[1]["data"], "gqA=");
+    }
+}
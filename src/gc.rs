@@ -262,7 +262,7 @@ fn run_gc(root: &Path, policy: &GcPolicy) -> Result<GcOutcome> {
         };
 
         let status = state.status().clone();
-        if matches!(status, JobStatus::Running | JobStatus::Created) {
+        if status.is_non_terminal() {
             skipped += 1;
             out_of_scope += 1;
             continue;
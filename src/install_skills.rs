@@ -32,8 +32,10 @@ pub fn execute(opts: InstallSkillsOpts) -> Result<()> {
         source_type: installed.source_type.clone(),
         installed_at: now_rfc3339(),
         path: installed.path.to_string_lossy().into_owned(),
+        previous_installed_at: None,
+        checksums: installed.checksums.clone(),
     };
-    lock.upsert(entry);
+    let updated = lock.upsert(entry);
     lock.write(&lock_path)?;
 
     // Build and print the response.
@@ -42,6 +44,7 @@ pub fn execute(opts: InstallSkillsOpts) -> Result<()> {
             name: installed.name,
             source_type: installed.source_type,
             path: installed.path.to_string_lossy().into_owned(),
+            updated,
         }],
         global: opts.global,
         lock_file_path: lock_path.to_string_lossy().into_owned(),
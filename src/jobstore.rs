@@ -11,7 +11,7 @@ use directories::BaseDirs;
 use rand::RngCore;
 use std::path::{Path, PathBuf};
 
-use crate::schema::{JobMeta, JobState, JobStatus};
+use crate::schema::{JobEvent, JobMeta, JobState, JobStatus};
 
 /// Sentinel error type to distinguish "job not found" from other I/O errors.
 /// Used by callers to emit `error.code = "job_not_found"` instead of `internal_error`.
@@ -71,6 +71,23 @@ impl std::fmt::Display for JobIdCollisionExhausted {
 
 impl std::error::Error for JobIdCollisionExhausted {}
 
+/// Sentinel error type when `JobDir::create` finds the job directory already present.
+/// Used by callers to emit `error.code = "io_error"` instead of `internal_error`.
+#[derive(Debug)]
+pub struct JobIdCollision(pub String);
+
+impl std::fmt::Display for JobIdCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "job ID collision: job directory {} already exists",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for JobIdCollision {}
+
 /// Sentinel error type for invalid job state transitions.
 /// Used by callers to emit `error.code = "invalid_state"` instead of `internal_error`.
 #[derive(Debug)]
@@ -153,12 +170,50 @@ pub fn resolve_root(cli_root: Option<&str>) -> PathBuf {
     PathBuf::from("~/.local/share/agent-exec/jobs")
 }
 
+/// Default log encoding persisted in `meta.json.output_encoding`.
+pub const DEFAULT_OUTPUT_ENCODING: &str = "utf-8";
+
+/// Pseudo-encoding label that returns log bytes base64-encoded instead of
+/// decoding them as text (`tail --encoding base64`, `run --snapshot-encoding base64`).
+pub const BASE64_ENCODING: &str = "base64";
+
+/// Decode raw log bytes using the given encoding label (e.g. `utf-8`, `latin1`,
+/// `shift_jis`). Malformed sequences are replaced with U+FFFD; unknown labels
+/// fall back to lossy UTF-8. [`BASE64_ENCODING`] base64-encodes the raw bytes.
+pub fn decode_log_bytes(bytes: &[u8], encoding: &str) -> String {
+    if encoding.eq_ignore_ascii_case(BASE64_ENCODING) {
+        use base64::Engine;
+        return base64::engine::general_purpose::STANDARD.encode(bytes);
+    }
+    match encoding_rs::Encoding::for_label(encoding.as_bytes()) {
+        Some(enc) if enc != encoding_rs::UTF_8 => {
+            enc.decode_without_bom_handling(bytes).0.into_owned()
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Name reported in the `encoding` response field for a given encoding label.
+///
+/// UTF-8 keeps the historical `"utf-8-lossy"` value; other encodings report
+/// their canonical lowercase name (e.g. `"windows-1252"`), and base64 reports
+/// `"base64"`.
+pub fn response_encoding_name(encoding: &str) -> String {
+    if encoding.eq_ignore_ascii_case(BASE64_ENCODING) {
+        return BASE64_ENCODING.to_string();
+    }
+    match encoding_rs::Encoding::for_label(encoding.as_bytes()) {
+        Some(enc) if enc != encoding_rs::UTF_8 => enc.name().to_ascii_lowercase(),
+        _ => "utf-8-lossy".to_string(),
+    }
+}
+
 /// Metrics returned by [`JobDir::read_tail_metrics`].
 ///
 /// Bundles the tail content together with the raw byte ranges used in the
 /// `tail` JSON responses, so that callers share the same calculation logic.
 pub struct TailMetrics {
-    /// The tail text (lossy UTF-8, last N lines / max_bytes).
+    /// The tail text (decoded with the requested encoding, last N lines / max_bytes).
     pub tail: String,
     /// Total file size in bytes (0 if the file does not exist).
     pub observed_bytes: u64,
@@ -168,7 +223,7 @@ pub struct TailMetrics {
 
 /// Metrics for the head slice of a log file.
 pub struct HeadMetrics {
-    /// The head text (lossy UTF-8, first max_bytes bytes).
+    /// The head text (first max_bytes bytes, decoded with the requested encoding).
     pub head: String,
     /// Total file size in bytes (0 if the file does not exist).
     pub observed_bytes: u64,
@@ -185,6 +240,12 @@ pub struct JobDir {
     pub job_id: String,
 }
 
+/// Whether a job argument names a directory rather than an ID: job IDs never
+/// contain a path separator.
+fn looks_like_path(arg: &str) -> bool {
+    arg.contains('/') || arg.contains(std::path::MAIN_SEPARATOR)
+}
+
 impl JobDir {
     /// Open an existing job directory by ID or unambiguous prefix.
     ///
@@ -198,6 +259,31 @@ impl JobDir {
         Self::resolve_matching(root, job_id, |_| true)
     }
 
+    /// Open a job directory given by path, e.g. the `job_dir` of a `run`
+    /// response. The directory must exist and contain `meta.json`; the job ID is
+    /// its final path component.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let job_id = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .filter(|_| path.join("meta.json").is_file())
+            .ok_or_else(|| anyhow::Error::new(JobNotFound(path.display().to_string())))?;
+        Ok(JobDir {
+            path: path.to_path_buf(),
+            job_id,
+        })
+    }
+
+    /// Open `arg` with [`JobDir::from_path`] when it looks like a path (contains
+    /// a path separator), otherwise as a job ID or prefix under `root`.
+    pub fn open_id_or_path(root: &Path, arg: &str) -> Result<Self> {
+        if looks_like_path(arg) {
+            Self::from_path(Path::new(arg))
+        } else {
+            Self::open(root, arg)
+        }
+    }
+
     /// Open an existing job directory by ID or unambiguous prefix, restricted to
     /// jobs whose persisted state matches `predicate`.
     pub fn open_matching<F>(root: &std::path::Path, job_id: &str, predicate: F) -> Result<Self>
@@ -268,19 +354,35 @@ impl JobDir {
     }
 
     /// Create a new job directory and write `meta.json` atomically.
+    ///
+    /// Fails with [`JobIdCollision`] if the job directory already exists, so a
+    /// concurrent creator can never overwrite another job's `meta.json`.
     pub fn create(root: &std::path::Path, job_id: &str, meta: &JobMeta) -> Result<Self> {
+        let job_dir = Self::create_dir(root, job_id)?;
+        job_dir.write_meta_atomic(meta)?;
+        Ok(job_dir)
+    }
+
+    /// Create a new, empty job directory; [`JobDir::create`] without the
+    /// `meta.json` write. Fails with [`JobIdCollision`] like `create`.
+    pub fn create_dir(root: &std::path::Path, job_id: &str) -> Result<Self> {
+        std::fs::create_dir_all(root)
+            .with_context(|| format!("create jobs root {}", root.display()))?;
         let path = root.join(job_id);
-        std::fs::create_dir_all(&path)
-            .with_context(|| format!("create job dir {}", path.display()))?;
+        match std::fs::create_dir(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(anyhow::Error::new(JobIdCollision(job_id.to_string())));
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("create job dir {}", path.display()));
+            }
+        }
 
-        let job_dir = JobDir {
+        Ok(JobDir {
             path,
             job_id: job_id.to_string(),
-        };
-
-        job_dir.write_meta_atomic(meta)?;
-
-        Ok(job_dir)
+        })
     }
 
     pub fn meta_path(&self) -> PathBuf {
@@ -304,6 +406,33 @@ impl JobDir {
     pub fn notification_events_path(&self) -> PathBuf {
         self.path.join("notification_events.ndjson")
     }
+    pub fn events_path(&self) -> PathBuf {
+        self.path.join("events.ndjson")
+    }
+    pub fn child_pid_path(&self) -> PathBuf {
+        self.path.join("child.pid")
+    }
+    pub fn timing_path(&self) -> PathBuf {
+        self.path.join("timing.json")
+    }
+    pub fn snapshots_dir(&self) -> PathBuf {
+        self.path.join("snapshots")
+    }
+    /// The job directory as an absolute path (resolved against the cwd when
+    /// the root was given relative).
+    pub fn absolute_path(&self) -> PathBuf {
+        std::path::absolute(&self.path).unwrap_or_else(|_| self.path.clone())
+    }
+    /// The absolute jobs root containing this job directory.
+    pub fn absolute_root(&self) -> PathBuf {
+        let path = self.absolute_path();
+        path.parent().map(Path::to_path_buf).unwrap_or(path)
+    }
+
+    /// Total size in bytes of every file under this job directory.
+    pub fn total_dir_bytes(&self) -> u64 {
+        crate::gc::dir_size_bytes(&self.path)
+    }
 
     /// Write `completion_event.json` atomically.
     pub fn write_completion_event_atomic(
@@ -316,6 +445,25 @@ impl JobDir {
         Ok(())
     }
 
+    /// Rotate a log file in the job directory once it exceeds `max_size_bytes`.
+    ///
+    /// The current file is renamed to `<filename>.1`, existing archives are
+    /// shifted (`.1` → `.2`, ...) and anything beyond `archive_count` is
+    /// removed.  A fresh empty `<filename>` is then put in place atomically so
+    /// readers never observe a missing log.  With `archive_count == 0` the old
+    /// content is discarded.  Files at or below the limit are left untouched.
+    ///
+    /// The supervisor detects the replaced file and reopens it before its next
+    /// write, so rotation is safe while the job is running.
+    pub fn rotate_log(
+        &self,
+        filename: &str,
+        max_size_bytes: u64,
+        archive_count: u32,
+    ) -> Result<()> {
+        rotate_log_file(&self.path, filename, max_size_bytes, archive_count)
+    }
+
     pub fn read_meta(&self) -> Result<JobMeta> {
         let raw = std::fs::read(self.meta_path())?;
         Ok(serde_json::from_slice(&raw)?)
@@ -342,56 +490,181 @@ impl JobDir {
         Ok(())
     }
 
+    /// Write the child PID to `child.pid` as a bare integer so `kill` can
+    /// still reach the child when `state.json` is unreadable.
+    pub fn write_child_pid(&self, pid: u32) -> Result<()> {
+        write_atomic(
+            &self.path,
+            &self.child_pid_path(),
+            pid.to_string().as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Remove `child.pid` once the child has been reaped, so a later `kill`
+    /// cannot signal a recycled PID. A missing file is not an error.
+    pub fn remove_child_pid(&self) -> Result<()> {
+        match std::fs::remove_file(self.child_pid_path()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write `timing.json` atomically.
+    pub fn write_timing(&self, timing: &crate::schema::JobTiming) -> Result<()> {
+        let contents = serde_json::to_string_pretty(timing)?;
+        write_atomic(&self.path, &self.timing_path(), contents.as_bytes())?;
+        Ok(())
+    }
+
+    /// Read `timing.json`; `None` when the supervisor has not written it.
+    pub fn read_timing(&self) -> Result<Option<crate::schema::JobTiming>> {
+        match std::fs::read(self.timing_path()) {
+            Ok(raw) => Ok(Some(serde_json::from_slice(&raw)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read `child.pid`; `None` when the supervisor has not written it.
+    pub fn read_child_pid(&self) -> Result<Option<u32>> {
+        match std::fs::read_to_string(self.child_pid_path()) {
+            Ok(raw) => Ok(Some(raw.trim().parse().with_context(|| {
+                format!("parse {}", self.child_pid_path().display())
+            })?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write `<dir>/checkpoint-<job_id>-<label>.json` holding the current
+    /// state and a stdout/stderr tail snapshot (`run --checkpoint-dir`).
+    pub fn write_checkpoint(&self, dir: &Path, label: &str, encoding: &str) -> Result<PathBuf> {
+        let target = dir.join(format!("checkpoint-{}-{label}.json", self.job_id));
+        let contents = serde_json::to_string_pretty(&self.checkpoint(encoding)?)?;
+        write_atomic(dir, &target, contents.as_bytes())?;
+        Ok(target)
+    }
+
+    /// Write `snapshots/manual-<epoch-ms>.json` in the job directory, shaped
+    /// like a checkpoint (`run --snapshot-on-sigusr1`).
+    pub fn write_manual_snapshot(&self, encoding: &str) -> Result<PathBuf> {
+        let dir = self.snapshots_dir();
+        std::fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+        let label = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        let target = dir.join(format!("manual-{label}.json"));
+        let contents = serde_json::to_string_pretty(&self.checkpoint(encoding)?)?;
+        write_atomic(&dir, &target, contents.as_bytes())?;
+        Ok(target)
+    }
+
+    /// The current state plus a 50-line tail snapshot of each stream.
+    fn checkpoint(&self, encoding: &str) -> Result<crate::schema::Checkpoint> {
+        let stdout = self.read_tail_metrics("stdout.log", 50, 65536, encoding);
+        let stderr = self.read_tail_metrics("stderr.log", 50, 65536, encoding);
+        Ok(crate::schema::Checkpoint {
+            ts: crate::run::now_rfc3339_pub(),
+            state: self.read_state()?,
+            snapshot: crate::schema::Snapshot {
+                truncated: stdout.range[0] > 0 || stderr.range[0] > 0,
+                encoding: response_encoding_name(encoding),
+                stdout_observed_bytes: stdout.observed_bytes,
+                stderr_observed_bytes: stderr.observed_bytes,
+                stdout_included_bytes: stdout.tail.len() as u64,
+                stderr_included_bytes: stderr.tail.len() as u64,
+                stdout_tail: stdout.tail,
+                stderr_tail: stderr.tail,
+            },
+        })
+    }
+
+    /// This job's checkpoint files in its `meta.json` checkpoint directory,
+    /// oldest first with the final checkpoint last; empty when checkpoints
+    /// are not enabled.
+    pub fn list_checkpoints(&self) -> Result<Vec<PathBuf>> {
+        match self.read_meta()?.checkpoint_dir {
+            Some(dir) => checkpoints_in(Path::new(&dir), &self.job_id),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Delete the oldest periodic checkpoints in `dir` so at most `keep` remain.
+    pub fn prune_checkpoints(&self, dir: &Path, keep: u64) -> Result<()> {
+        let final_name = format!("checkpoint-{}-final.json", self.job_id);
+        let periodic: Vec<PathBuf> = checkpoints_in(dir, &self.job_id)?
+            .into_iter()
+            .filter(|p| p.file_name().is_some_and(|n| *n != *final_name))
+            .collect();
+        let excess = periodic.len().saturating_sub(keep as usize);
+        for path in &periodic[..excess] {
+            std::fs::remove_file(path).with_context(|| format!("remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Append one event to `events.ndjson`.
+    ///
+    /// The serialized line is written with a single `write_all` on an append-mode
+    /// handle, so events from concurrent writers never interleave within a line.
+    pub fn append_event(&self, kind: &str, data: serde_json::Value) -> Result<()> {
+        use std::io::Write;
+
+        let event = JobEvent {
+            ts: crate::run::now_rfc3339_pub(),
+            kind: kind.to_string(),
+            data,
+        };
+        let mut line = serde_json::to_vec(&event)?;
+        line.push(b'\n');
+        let mut file = cloexec_options()
+            .create(true)
+            .append(true)
+            .open(self.events_path())
+            .with_context(|| format!("open {}", self.events_path().display()))?;
+        file.write_all(&line).context("append event")?;
+        Ok(())
+    }
+
+    /// Iterate over the events recorded in `events.ndjson`, oldest first.
+    ///
+    /// Yields nothing when the job has no event log. Blank lines are skipped;
+    /// unreadable or malformed lines surface as `Err` items.
+    pub fn iter_events(&self) -> impl Iterator<Item = Result<JobEvent>> {
+        use std::io::BufRead;
+
+        open_with_cloexec(&self.events_path())
+            .ok()
+            .map(std::io::BufReader::new)
+            .into_iter()
+            .flat_map(|reader| reader.lines())
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|line| {
+                let line = line.context("read events.ndjson")?;
+                serde_json::from_str(&line).context("parse events.ndjson line")
+            })
+    }
+
     /// Read tail content and raw byte range metrics for a single log file.
+    ///
+    /// Offsets are derived from a single read of the file, so a log that grows
+    /// or shrinks (rotation) between calls cannot push the slice out of bounds.
     pub fn read_tail_metrics(
         &self,
         filename: &str,
         tail_lines: u64,
         max_bytes: u64,
+        encoding: &str,
     ) -> TailMetrics {
-        let path = self.path.join(filename);
-        let Ok(data) = std::fs::read(&path) else {
-            return TailMetrics {
-                tail: String::new(),
-                observed_bytes: 0,
-                range: [0, 0],
-            };
-        };
-
-        let observed_bytes = data.len() as u64;
-        let window_start = observed_bytes.saturating_sub(max_bytes) as usize;
-        let window = &data[window_start..];
-
-        let line_start_in_window = if tail_lines == 0 {
-            0
-        } else {
-            let mut chunks: Vec<&[u8]> = window.split(|b| *b == b'\n').collect();
-            if window.ends_with(b"\n") {
-                let _ = chunks.pop();
-            }
-            let keep_from = chunks.len().saturating_sub(tail_lines as usize);
-            chunks[..keep_from]
-                .iter()
-                .map(|c| c.len() + 1)
-                .sum::<usize>()
-        };
-
-        let selected = &window[line_start_in_window..];
-        let tail = String::from_utf8_lossy(selected).into_owned();
-        let begin = (window_start + line_start_in_window) as u64;
-
-        TailMetrics {
-            tail,
-            observed_bytes,
-            range: [begin, observed_bytes],
-        }
+        read_tail_metrics_at(&self.path.join(filename), tail_lines, max_bytes, encoding)
     }
 
     /// Read head content and byte metrics for a single log file.
     ///
-    /// Returns the first `max_bytes` bytes (decoded as UTF-8 lossy) with
-    /// canonical raw byte range metadata.
-    pub fn read_head_metrics(&self, filename: &str, max_bytes: u64) -> HeadMetrics {
+    /// Returns the first `max_bytes` bytes (decoded with `encoding`, see
+    /// [`decode_log_bytes`]) with canonical raw byte range metadata.
+    pub fn read_head_metrics(&self, filename: &str, max_bytes: u64, encoding: &str) -> HeadMetrics {
         let path = self.path.join(filename);
         let Ok(data) = std::fs::read(&path) else {
             return HeadMetrics {
@@ -404,7 +677,7 @@ impl JobDir {
 
         let observed_bytes = data.len() as u64;
         let included_len = observed_bytes.min(max_bytes) as usize;
-        let head = String::from_utf8_lossy(&data[..included_len]).into_owned();
+        let head = decode_log_bytes(&data[..included_len], encoding);
         let included_bytes = included_len as u64;
 
         HeadMetrics {
@@ -418,7 +691,7 @@ impl JobDir {
     /// Write the initial JobState for a `created` (not-yet-started) job.
     ///
     /// The state is `created`, no process has been spawned, and `started_at` is absent.
-    pub fn init_state_created(&self) -> Result<JobState> {
+    pub fn init_state_created(&self, created_at: &str) -> Result<JobState> {
         let state = JobState {
             job: crate::schema::JobStateJob {
                 id: self.job_id.clone(),
@@ -429,12 +702,22 @@ impl JobDir {
                 exit_code: None,
                 signal: None,
                 duration_ms: None,
+                cpu_user_ms: None,
+                cpu_sys_ms: None,
+                max_rss_kb: None,
             },
             pid: None,
+            created_at: Some(created_at.to_string()),
             finished_at: None,
             updated_at: crate::run::now_rfc3339_pub(),
             logs_drained: true,
             windows_job_name: None,
+            killed_reason: None,
+            captured_stdout: None,
+            captured_stdout_truncated: false,
+            agent_version: None,
+            webhook_status_code: None,
+            resource_usage: None,
         };
         self.write_state(&state)?;
         Ok(state)
@@ -452,12 +735,22 @@ impl JobDir {
     /// always find the Job Object identifier, without waiting for the supervisor
     /// to perform its first `write_state` call. The supervisor will confirm the
     /// same name (or update to `failed`) after it successfully assigns the child
-    /// process to the named Job Object.
-    pub fn init_state(&self, pid: u32, started_at: &str) -> Result<JobState> {
+    /// process to the named Job Object. With `windows_job_object` false
+    /// (`run --windows-no-job-object`) no name is recorded.
+    pub fn init_state(
+        &self,
+        pid: u32,
+        created_at: &str,
+        started_at: &str,
+        windows_job_object: bool,
+    ) -> Result<JobState> {
         #[cfg(windows)]
-        let windows_job_name = Some(format!("AgentExec-{}", self.job_id));
+        let windows_job_name = windows_job_object.then(|| format!("AgentExec-{}", self.job_id));
         #[cfg(not(windows))]
-        let windows_job_name: Option<String> = None;
+        let windows_job_name: Option<String> = {
+            let _ = windows_job_object;
+            None
+        };
 
         let state = JobState {
             job: crate::schema::JobStateJob {
@@ -469,22 +762,179 @@ impl JobDir {
                 exit_code: None,
                 signal: None,
                 duration_ms: None,
+                cpu_user_ms: None,
+                cpu_sys_ms: None,
+                max_rss_kb: None,
             },
             pid: Some(pid),
+            created_at: Some(created_at.to_string()),
             finished_at: None,
             updated_at: crate::run::now_rfc3339_pub(),
             logs_drained: true,
             windows_job_name,
+            killed_reason: None,
+            captured_stdout: None,
+            captured_stdout_truncated: false,
+            agent_version: Some(crate::schema::AGENT_EXEC_VERSION.to_string()),
+            webhook_status_code: None,
+            resource_usage: None,
         };
         self.write_state(&state)?;
         Ok(state)
     }
 }
 
+/// [`JobDir::read_tail_metrics`] for a log file at any path, such as a
+/// `run --log` target outside the job directory.
+pub fn read_tail_metrics_at(
+    path: &Path,
+    tail_lines: u64,
+    max_bytes: u64,
+    encoding: &str,
+) -> TailMetrics {
+    let Ok(data) = std::fs::read(path) else {
+        return TailMetrics {
+            tail: String::new(),
+            observed_bytes: 0,
+            range: [0, 0],
+        };
+    };
+
+    let observed_bytes = data.len() as u64;
+    let window_start = observed_bytes.saturating_sub(max_bytes) as usize;
+    let window = &data[window_start..];
+
+    let line_start_in_window = if tail_lines == 0 {
+        0
+    } else {
+        let mut chunks: Vec<&[u8]> = window.split(|b| *b == b'\n').collect();
+        if window.ends_with(b"\n") {
+            let _ = chunks.pop();
+        }
+        let keep_from = chunks.len().saturating_sub(tail_lines as usize);
+        chunks[..keep_from]
+            .iter()
+            .map(|c| c.len() + 1)
+            .sum::<usize>()
+    };
+
+    let selected = &window[line_start_in_window..];
+    let tail = decode_log_bytes(selected, encoding);
+    let begin = (window_start + line_start_in_window) as u64;
+
+    TailMetrics {
+        tail,
+        observed_bytes,
+        range: [begin, observed_bytes],
+    }
+}
+
+/// [`JobDir::rotate_log`] for a log file in any directory; also used by the
+/// supervisor to rotate stream logs while the job runs.
+pub fn rotate_log_file(
+    dir: &Path,
+    filename: &str,
+    max_size_bytes: u64,
+    archive_count: u32,
+) -> Result<()> {
+    let target = dir.join(filename);
+    let size = match std::fs::metadata(&target) {
+        Ok(m) => m.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("stat log file {}", target.display()));
+        }
+    };
+    if size <= max_size_bytes {
+        return Ok(());
+    }
+
+    let archive = |n: u32| dir.join(format!("{filename}.{n}"));
+    if archive_count > 0 {
+        let oldest = archive(archive_count);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)
+                .with_context(|| format!("remove oldest archive {}", oldest.display()))?;
+        }
+        for n in (1..archive_count).rev() {
+            let from = archive(n);
+            if from.exists() {
+                let to = archive(n + 1);
+                std::fs::rename(&from, &to).with_context(|| {
+                    format!("shift archive {} to {}", from.display(), to.display())
+                })?;
+            }
+        }
+        let first = archive(1);
+        std::fs::rename(&target, &first)
+            .with_context(|| format!("archive {} to {}", target.display(), first.display()))?;
+    }
+    write_atomic(dir, &target, b"")?;
+    Ok(())
+}
+
+/// `OpenOptions` that request close-on-exec explicitly (`O_CLOEXEC` on Unix).
+///
+/// The supervisor holds job files open while it spawns the child; none of them
+/// may leak into the child. On Windows, handles opened through the standard
+/// library are created with `bInheritHandle = FALSE`, so no extra flag is needed.
+/// `custom_flags` replaces the flag set, so callers adding their own flags (such
+/// as `O_NONBLOCK`) must include `O_CLOEXEC` again.
+pub fn cloexec_options() -> std::fs::OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = std::fs::OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_CLOEXEC);
+    }
+    options
+}
+
+/// Open `path` for reading with close-on-exec set (see [`cloexec_options`]).
+pub fn open_with_cloexec(path: &Path) -> Result<std::fs::File> {
+    cloexec_options()
+        .read(true)
+        .open(path)
+        .with_context(|| format!("open {}", path.display()))
+}
+
+/// Create or truncate `path` for writing with close-on-exec set (see [`cloexec_options`]).
+pub fn create_with_cloexec(path: &Path) -> Result<std::fs::File> {
+    cloexec_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .with_context(|| format!("create {}", path.display()))
+}
+
+/// Checkpoint files for `job_id` in `dir`, sorted by name. Periodic
+/// checkpoints are labelled with epoch milliseconds, so name order is time
+/// order and `-final` sorts last.
+fn checkpoints_in(dir: &Path, job_id: &str) -> Result<Vec<PathBuf>> {
+    let prefix = format!("checkpoint-{job_id}-");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("read checkpoint directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".json"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
 /// Write `contents` to `target` atomically by writing to a temp file in the
 /// same directory and then renaming. This prevents readers from observing a
 /// partially-written file.
-fn write_atomic(dir: &std::path::Path, target: &std::path::Path, contents: &[u8]) -> Result<()> {
+pub(crate) fn write_atomic(
+    dir: &std::path::Path,
+    target: &std::path::Path,
+    contents: &[u8],
+) -> Result<()> {
     use std::io::Write;
 
     // Create a named temporary file in the same directory so that rename is
@@ -574,6 +1024,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn decode_log_bytes_latin1_is_not_lossy_utf8() {
+        let bytes = [0xE9, 0x74, 0xE9];
+        assert_eq!(decode_log_bytes(&bytes, "latin1"), "été");
+        assert_eq!(decode_log_bytes(&bytes, "utf-8"), "\u{FFFD}t\u{FFFD}");
+    }
+
+    #[test]
+    fn response_encoding_name_keeps_utf8_lossy_label() {
+        assert_eq!(response_encoding_name("utf-8"), "utf-8-lossy");
+        assert_eq!(response_encoding_name("latin1"), "windows-1252");
+        assert_eq!(response_encoding_name("base64"), "base64");
+    }
+
+    #[test]
+    fn decode_log_bytes_base64_encodes_raw_bytes() {
+        assert_eq!(decode_log_bytes(&[0xFF, 0xFE], BASE64_ENCODING), "//4=");
+    }
+
+    #[test]
+    fn read_tail_metrics_handles_log_shrinking_between_reads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let meta = make_meta("test-job-shrink", root);
+        let job_dir = JobDir::create(root, "test-job-shrink", &meta).unwrap();
+        std::fs::write(job_dir.stdout_path(), b"one\ntwo\nthree\n").unwrap();
+        let before = job_dir.read_tail_metrics("stdout.log", 2, 1024, DEFAULT_OUTPUT_ENCODING);
+        assert_eq!(before.tail, "two\nthree\n");
+        assert_eq!(before.range, [4, 14]);
+
+        // Rotation replaces the log with a smaller (here empty) file.
+        std::fs::write(job_dir.stdout_path(), b"").unwrap();
+        let after = job_dir.read_tail_metrics("stdout.log", 2, 1024, DEFAULT_OUTPUT_ENCODING);
+        assert_eq!(after.tail, "");
+        assert_eq!(after.observed_bytes, 0);
+        assert_eq!(after.range, [0, 0]);
+
+        std::fs::write(job_dir.stdout_path(), b"x").unwrap();
+        let regrown = job_dir.read_tail_metrics("stdout.log", 2, 1024, DEFAULT_OUTPUT_ENCODING);
+        assert_eq!(regrown.tail, "x");
+        assert_eq!(regrown.range, [0, 1]);
+    }
+
+    #[test]
+    fn rotate_log_archives_and_truncates_oversized_log() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let meta = make_meta("test-job-rotate", root);
+        let job_dir = JobDir::create(root, "test-job-rotate", &meta).unwrap();
+        std::fs::write(job_dir.stdout_path(), b"first\n").unwrap();
+        std::fs::write(job_dir.path.join("stdout.log.1"), b"older\n").unwrap();
+
+        // Below the limit: untouched.
+        job_dir.rotate_log("stdout.log", 1024, 2).unwrap();
+        assert_eq!(std::fs::read(job_dir.stdout_path()).unwrap(), b"first\n");
+
+        job_dir.rotate_log("stdout.log", 1, 2).unwrap();
+        assert_eq!(std::fs::read(job_dir.stdout_path()).unwrap(), b"");
+        assert_eq!(
+            std::fs::read(job_dir.path.join("stdout.log.1")).unwrap(),
+            b"first\n"
+        );
+        assert_eq!(
+            std::fs::read(job_dir.path.join("stdout.log.2")).unwrap(),
+            b"older\n"
+        );
+    }
+
+    #[test]
+    fn append_event_round_trips_through_iter_events() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let meta = make_meta("test-job-events", root);
+        let job_dir = JobDir::create(root, "test-job-events", &meta).unwrap();
+        assert_eq!(job_dir.iter_events().count(), 0, "no log yet");
+
+        job_dir
+            .append_event("spawn", serde_json::json!({ "pid": 42 }))
+            .unwrap();
+        job_dir
+            .append_event("stdout_chunk", serde_json::json!({ "text": "hi\n" }))
+            .unwrap();
+
+        let events: Vec<_> = job_dir.iter_events().map(|e| e.unwrap()).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, "spawn");
+        assert_eq!(events[0].data["pid"], 42);
+        assert_eq!(events[1].kind, "stdout_chunk");
+        assert_eq!(events[1].data["text"], "hi\n");
+    }
+
     // ---------- Job directory structure tests ----------
 
     fn make_meta(job_id: &str, root: &std::path::Path) -> crate::schema::JobMeta {
@@ -582,6 +1123,7 @@ mod tests {
                 id: job_id.to_string(),
             },
             schema_version: "0.1".to_string(),
+            agent_exec_version: "0.0.0".to_string(),
             command: vec!["echo".to_string(), "hello".to_string()],
             created_at: "2024-01-01T00:00:00Z".to_string(),
             root: root.display().to_string(),
@@ -590,15 +1132,61 @@ mod tests {
             env_vars_runtime: vec![],
             mask: vec![],
             cwd: None,
+            command_hash: String::new(),
             notification: None,
             tags: vec![],
             inherit_env: true,
             env_files: vec![],
             timeout_ms: 0,
             kill_after_ms: 0,
+            keep_going_ms: 0,
             progress_every_ms: 0,
             shell_wrapper: None,
             stdin_file: None,
+            detached: false,
+            signal_on_parent_exit: None,
+            output_encoding: DEFAULT_OUTPUT_ENCODING.to_string(),
+            pty: false,
+            health_check: None,
+            event_log: false,
+            status_report: None,
+            core_dump_disabled: false,
+            max_output_lines_per_second: 0,
+            env_expand: false,
+            log_buffering: Default::default(),
+            priority: Default::default(),
+            nice: None,
+            stdout_file: None,
+            full_log_file: None,
+            env_unset: vec![],
+            env_inherit_prefix: vec![],
+            rlimits: vec![],
+            pid_namespace: false,
+            require_pid_namespace: false,
+            windows_job_object: true,
+            require_clean_exit: false,
+            snapshot_on_sigusr1: false,
+            max_line_length: 0,
+            log_rotate_size_bytes: 0,
+            log_rotate_count: 0,
+            startup_wait_ms: None,
+            env_from_json_keys: vec![],
+            env_source_job_id: None,
+            ssh_host: None,
+            original_command: None,
+            hostname: None,
+            username: None,
+            report_file: None,
+            profile_output: None,
+            checkpoint_dir: None,
+            checkpoint_interval_ms: None,
+            checkpoint_count: None,
+            completion_webhook_url: None,
+            completion_webhook_timeout_ms: None,
+            notify_timeout_ms: None,
+            notify_file_append: true,
+            notify_file_snapshot: false,
+            capture_output_max_bytes: None,
         }
     }
 
@@ -661,12 +1249,22 @@ mod tests {
                 exit_code: None,
                 signal: None,
                 duration_ms: None,
+                cpu_user_ms: None,
+                cpu_sys_ms: None,
+                max_rss_kb: None,
             },
             pid: Some(12345),
+            created_at: None,
             finished_at: None,
             updated_at: "2024-01-01T00:00:01Z".to_string(),
             logs_drained: true,
             windows_job_name: None,
+            killed_reason: None,
+            captured_stdout: None,
+            captured_stdout_truncated: false,
+            agent_version: None,
+            webhook_status_code: None,
+            resource_usage: None,
         };
         job_dir.write_state(&state).unwrap();
 
@@ -705,12 +1303,22 @@ mod tests {
                     exit_code: None,
                     signal: None,
                     duration_ms: None,
+                    cpu_user_ms: None,
+                    cpu_sys_ms: None,
+                    max_rss_kb: None,
                 },
                 pid: Some(100 + i),
+                created_at: None,
                 finished_at: None,
                 updated_at: format!("2024-01-01T00:00:{:02}Z", i),
                 logs_drained: true,
                 windows_job_name: None,
+                killed_reason: None,
+                captured_stdout: None,
+                captured_stdout_truncated: false,
+                agent_version: None,
+                webhook_status_code: None,
+                resource_usage: None,
             };
             job_dir.write_state(&state).unwrap();
 
@@ -724,6 +1332,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn checkpoints_are_listed_in_order_and_pruned() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let checkpoints = tmp.path().join("checkpoints");
+        std::fs::create_dir(&checkpoints).unwrap();
+        let mut meta = make_meta("test-job-cp", root);
+        meta.checkpoint_dir = Some(checkpoints.display().to_string());
+        let job_dir = JobDir::create(root, "test-job-cp", &meta).unwrap();
+        job_dir.init_state_created(&meta.created_at).unwrap();
+
+        for label in ["1000", "2000", "3000", "final"] {
+            job_dir
+                .write_checkpoint(&checkpoints, label, "utf-8")
+                .unwrap();
+        }
+        std::fs::write(checkpoints.join("checkpoint-other-1000.json"), "{}").unwrap();
+        job_dir.prune_checkpoints(&checkpoints, 2).unwrap();
+
+        let names: Vec<String> = job_dir
+            .list_checkpoints()
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "checkpoint-test-job-cp-2000.json",
+                "checkpoint-test-job-cp-3000.json",
+                "checkpoint-test-job-cp-final.json",
+            ]
+        );
+    }
+
     /// Verify that meta.json atomic write works correctly.
     #[test]
     fn meta_json_atomic_write() {
@@ -738,6 +1381,7 @@ mod tests {
                 id: "test-job-05".to_string(),
             },
             schema_version: "0.1".to_string(),
+            agent_exec_version: "0.0.0".to_string(),
             command: vec!["ls".to_string()],
             created_at: "2024-06-01T12:00:00Z".to_string(),
             root: root.display().to_string(),
@@ -746,15 +1390,61 @@ mod tests {
             env_vars_runtime: vec![],
             mask: vec![],
             cwd: None,
+            command_hash: String::new(),
             notification: None,
             tags: vec![],
             inherit_env: true,
             env_files: vec![],
             timeout_ms: 0,
             kill_after_ms: 0,
+            keep_going_ms: 0,
             progress_every_ms: 0,
             shell_wrapper: None,
             stdin_file: None,
+            detached: false,
+            signal_on_parent_exit: None,
+            output_encoding: DEFAULT_OUTPUT_ENCODING.to_string(),
+            pty: false,
+            health_check: None,
+            event_log: false,
+            status_report: None,
+            core_dump_disabled: false,
+            max_output_lines_per_second: 0,
+            env_expand: false,
+            log_buffering: Default::default(),
+            priority: Default::default(),
+            nice: None,
+            stdout_file: None,
+            full_log_file: None,
+            env_unset: vec![],
+            env_inherit_prefix: vec![],
+            rlimits: vec![],
+            pid_namespace: false,
+            require_pid_namespace: false,
+            windows_job_object: true,
+            require_clean_exit: false,
+            snapshot_on_sigusr1: false,
+            max_line_length: 0,
+            log_rotate_size_bytes: 0,
+            log_rotate_count: 0,
+            startup_wait_ms: None,
+            env_from_json_keys: vec![],
+            env_source_job_id: None,
+            ssh_host: None,
+            original_command: None,
+            hostname: None,
+            username: None,
+            report_file: None,
+            profile_output: None,
+            checkpoint_dir: None,
+            checkpoint_interval_ms: None,
+            checkpoint_count: None,
+            completion_webhook_url: None,
+            completion_webhook_timeout_ms: None,
+            notify_timeout_ms: None,
+            notify_file_append: true,
+            notify_file_snapshot: false,
+            capture_output_max_bytes: None,
         };
         job_dir.write_meta_atomic(&updated_meta).unwrap();
 
@@ -775,7 +1465,9 @@ mod tests {
         let job_id = "01TESTJOBID0000000000000";
         let meta = make_meta(job_id, root);
         let job_dir = JobDir::create(root, job_id, &meta).unwrap();
-        let state = job_dir.init_state(1234, "2024-01-01T00:00:00Z").unwrap();
+        let state = job_dir
+            .init_state(1234, "2024-01-01T00:00:00Z", "2024-01-01T00:00:00Z", true)
+            .unwrap();
 
         // Verify in-memory state.
         #[cfg(windows)]
@@ -807,6 +1499,21 @@ mod tests {
 
     // ---------- Prefix-based job ID resolution tests ----------
 
+    #[test]
+    fn job_dir_create_rejects_existing_job_id() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let job_id = "01JQXK3M8E5PQRSTVWYZ12ABCD";
+        let meta = make_meta(job_id, root);
+        JobDir::create(root, job_id, &meta).unwrap();
+
+        let err = JobDir::create(root, job_id, &meta).unwrap_err();
+        let collision = err
+            .downcast_ref::<JobIdCollision>()
+            .expect("expected JobIdCollision");
+        assert_eq!(collision.0, job_id);
+    }
+
     #[test]
     fn job_dir_open_exact_match() {
         let tmp = tempfile::tempdir().unwrap();
@@ -819,6 +1526,27 @@ mod tests {
         assert_eq!(result.job_id, job_id);
     }
 
+    #[test]
+    fn job_dir_open_id_or_path_accepts_directory_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let job_id = "01JQXK3M8E5PQRSTVWYZ12ABCD";
+        let meta = make_meta(job_id, root);
+        JobDir::create(root, job_id, &meta).unwrap();
+
+        let other_root = tempfile::tempdir().unwrap();
+        let path = root.join(job_id);
+        let result = JobDir::open_id_or_path(other_root.path(), path.to_str().unwrap()).unwrap();
+        assert_eq!(result.job_id, job_id);
+        assert_eq!(result.path, path);
+
+        let err = JobDir::open_id_or_path(other_root.path(), root.to_str().unwrap()).unwrap_err();
+        assert!(
+            err.downcast_ref::<JobNotFound>().is_some(),
+            "a directory without meta.json is not a job: {err}"
+        );
+    }
+
     #[test]
     fn job_dir_open_unique_prefix_resolves() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1,12 +1,23 @@
 //! Implementation of the `kill` sub-command.
 //!
-//! Signals supported: TERM, INT, KILL (case-insensitive).
+//! Signals supported: TERM, INT, KILL, HUP, QUIT, USR1, USR2, STOP, CONT
+//! (case-insensitive; see `run::signal_number`).
+//!
+//! `STOP` pauses a job and `CONT` resumes it. `kill` only sends the signal: the
+//! supervisor, which observes the stop and the resume through `wait4`, records
+//! `stopped` / `running` in `state.json`, so it stays the only writer of that
+//! file while the job runs. On Windows threads are suspended instead, which the
+//! supervisor cannot observe, so there `kill` records the transition itself.
+//! A terminating signal sent to a stopped job is followed by `CONT` so the job
+//! can act on it.
 //!
 //! Signal mapping on Windows:
 //!   TERM → TerminateJobObject (graceful intent; Windows has no SIGTERM, so
 //!           tree termination is the closest equivalent)
 //!   INT  → TerminateJobObject (same; Windows has no SIGINT for arbitrary PIDs)
 //!   KILL → TerminateJobObject (forced; semantically the same on Windows)
+//!   STOP → SuspendThread on every thread of the process tree
+//!   CONT → ResumeThread on every thread of the process tree
 //!   *    → TerminateJobObject (unknown signals treated as KILL per design.md)
 //!
 //! On Windows the supervisor records a `windows_job_name` in `state.json`.
@@ -26,7 +37,7 @@ use crate::schema::{JobStatus, KillData, Response};
 pub struct KillOpts<'a> {
     pub job_id: &'a str,
     pub root: Option<&'a str>,
-    /// Signal name: TERM | INT | KILL (default: TERM).
+    /// Signal name from `run::SIGNAL_NAMES` (default: TERM).
     pub signal: &'a str,
     /// Skip post-signal observation and return immediately (legacy shape).
     pub no_wait: bool,
@@ -56,10 +67,13 @@ pub fn kill_response(opts: KillOpts) -> Result<Response<KillData>> {
 /// Core kill logic returning `KillData`. Shared by CLI and HTTP handler.
 pub fn execute_inner(opts: KillOpts) -> Result<KillData> {
     let root = resolve_root(opts.root);
-    let job_dir = JobDir::open(&root, opts.job_id)?;
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
 
-    let state = job_dir.read_state()?;
     let signal_upper = opts.signal.to_uppercase();
+    let state = match job_dir.read_state() {
+        Ok(state) => state,
+        Err(e) => return kill_child_pid(&job_dir, signal_upper, e),
+    };
 
     if *state.status() == JobStatus::Created {
         return Err(anyhow::Error::new(InvalidJobState(format!(
@@ -68,7 +82,7 @@ pub fn execute_inner(opts: KillOpts) -> Result<KillData> {
         ))));
     }
 
-    if *state.status() != JobStatus::Running {
+    if !state.status().is_active() {
         return Ok(KillData {
             job_id: job_dir.job_id.clone(),
             signal: signal_upper,
@@ -88,16 +102,82 @@ pub fn execute_inner(opts: KillOpts) -> Result<KillData> {
                 state.result.signal.clone()
             },
             observed_within_ms: if opts.no_wait { None } else { Some(0) },
+            stale_pid: false,
         });
     }
 
     if let Some(pid) = state.pid {
+        // The supervisor died without recording a terminal state: the process
+        // the job points at is already gone, so there is nothing to signal.
+        if process_gone(pid) {
+            info!(job_id = %job_dir.job_id, pid, "recorded pid no longer exists");
+            return Ok(KillData {
+                job_id: job_dir.job_id.clone(),
+                signal: signal_upper,
+                state: (!opts.no_wait).then(|| JobStatus::Killed.as_str().to_string()),
+                exit_code: None,
+                terminated_signal: None,
+                observed_within_ms: (!opts.no_wait).then_some(0),
+                stale_pid: true,
+            });
+        }
+
         #[cfg(windows)]
-        send_signal(pid, &signal_upper, state.windows_job_name.as_deref())?;
+        let job_object = job_dir
+            .read_meta()
+            .map(|meta| meta.windows_job_object)
+            .unwrap_or(true);
+        #[cfg(windows)]
+        send_signal(
+            pid,
+            &signal_upper,
+            state.windows_job_name.as_deref(),
+            job_object,
+        )?;
         #[cfg(not(windows))]
         send_signal(pid, &signal_upper)?;
 
         info!(job_id = %job_dir.job_id, pid, signal = %signal_upper, "signal sent");
+
+        // A stopped job only acts on a terminating signal once it is resumed.
+        if *state.status() == JobStatus::Stopped
+            && !matches!(signal_upper.as_str(), "STOP" | "CONT")
+        {
+            #[cfg(windows)]
+            let _ = send_signal(pid, "CONT", state.windows_job_name.as_deref(), job_object);
+            #[cfg(not(windows))]
+            let _ = send_signal(pid, "CONT");
+        }
+    }
+
+    // STOP / CONT pause and resume the job: wait for the transition instead of
+    // a terminal state.
+    let paused = match signal_upper.as_str() {
+        "STOP" => Some(JobStatus::Stopped),
+        "CONT" => Some(JobStatus::Running),
+        _ => None,
+    };
+    if let Some(status) = paused {
+        #[cfg(windows)]
+        {
+            let mut st = job_dir.read_state()?;
+            if st.status().is_active() {
+                st.job.status = status.clone();
+                st.updated_at = crate::run::now_rfc3339_pub();
+                job_dir.write_state(&st)?;
+            }
+        }
+        let observed = (!opts.no_wait)
+            .then(|| observe_pause(&job_dir, &status, std::time::Duration::from_secs(3)));
+        return Ok(KillData {
+            job_id: job_dir.job_id.clone(),
+            signal: signal_upper,
+            state: observed.as_ref().map(|(state, _)| state.clone()),
+            exit_code: None,
+            terminated_signal: None,
+            observed_within_ms: observed.map(|(_, ms)| ms),
+            stale_pid: false,
+        });
     }
 
     if opts.no_wait {
@@ -108,6 +188,7 @@ pub fn execute_inner(opts: KillOpts) -> Result<KillData> {
             exit_code: None,
             terminated_signal: None,
             observed_within_ms: None,
+            stale_pid: false,
         });
     }
 
@@ -120,6 +201,53 @@ pub fn execute_inner(opts: KillOpts) -> Result<KillData> {
         exit_code: obs.exit_code,
         terminated_signal: obs.terminated_signal,
         observed_within_ms: Some(obs.observed_within_ms),
+        stale_pid: false,
+    })
+}
+
+/// Fallback for an unreadable `state.json`: signal the PID the supervisor
+/// recorded in `child.pid`. The outcome cannot be observed without
+/// `state.json`, so only the job ID and signal are reported, plus
+/// `stale_pid` when the recorded process no longer exists.
+fn kill_child_pid(
+    job_dir: &JobDir,
+    signal_upper: String,
+    state_err: anyhow::Error,
+) -> Result<KillData> {
+    let Some(pid) = job_dir.read_child_pid()? else {
+        return Err(state_err);
+    };
+    if process_gone(pid) {
+        info!(job_id = %job_dir.job_id, pid, "child.pid no longer exists");
+        return Ok(KillData {
+            job_id: job_dir.job_id.clone(),
+            signal: signal_upper,
+            state: None,
+            exit_code: None,
+            terminated_signal: None,
+            observed_within_ms: None,
+            stale_pid: true,
+        });
+    }
+    #[cfg(windows)]
+    send_signal(pid, &signal_upper, None, false)?;
+    #[cfg(not(windows))]
+    send_signal(pid, &signal_upper)?;
+    info!(
+        job_id = %job_dir.job_id,
+        pid,
+        signal = %signal_upper,
+        error = %state_err,
+        "state.json unreadable; signal sent to child.pid"
+    );
+    Ok(KillData {
+        job_id: job_dir.job_id.clone(),
+        signal: signal_upper,
+        state: None,
+        exit_code: None,
+        terminated_signal: None,
+        observed_within_ms: None,
+        stale_pid: false,
     })
 }
 
@@ -169,14 +297,36 @@ fn observe_post_signal(job_dir: &JobDir, budget: std::time::Duration) -> PostSig
     }
 }
 
+/// Poll `state.json` until the supervisor has recorded `expected` or the job is
+/// no longer active, for at most `budget`. Returns the state then recorded and
+/// the elapsed milliseconds.
+fn observe_pause(
+    job_dir: &JobDir,
+    expected: &JobStatus,
+    budget: std::time::Duration,
+) -> (String, u64) {
+    let start = std::time::Instant::now();
+    let poll_interval = std::time::Duration::from_millis(20);
+    loop {
+        let status = job_dir.read_state().ok().map(|st| st.status().clone());
+        let settled = status
+            .as_ref()
+            .is_some_and(|s| s == expected || !s.is_active());
+        if settled || start.elapsed() >= budget {
+            let state = status.unwrap_or(JobStatus::Running);
+            return (
+                state.as_str().to_string(),
+                start.elapsed().as_millis() as u64,
+            );
+        }
+        std::thread::sleep(poll_interval);
+    }
+}
+
 #[cfg(unix)]
 fn send_signal(pid: u32, signal: &str) -> Result<()> {
-    let signum: libc::c_int = match signal {
-        "TERM" => libc::SIGTERM,
-        "INT" => libc::SIGINT,
-        "KILL" => libc::SIGKILL,
-        _ => libc::SIGKILL, // Unknown → KILL (per design.md)
-    };
+    // Unknown → KILL (per design.md)
+    let signum = crate::run::signal_number(signal).unwrap_or(libc::SIGKILL);
     // Send signal to the process group (negative PID) so the shell wrapper
     // and all its descendants receive it.  Fall back to single-process kill
     // if the process-group kill fails (e.g. process is not a group leader).
@@ -201,6 +351,22 @@ fn send_signal(pid: u32, signal: &str) -> Result<()> {
     Ok(())
 }
 
+/// True when neither the process group nor the process `pid` exists (ESRCH).
+#[cfg(unix)]
+fn process_gone(pid: u32) -> bool {
+    let gone = |target: libc::pid_t| {
+        // SAFETY: signal 0 performs only the existence and permission check.
+        let ret = unsafe { libc::kill(target, 0) };
+        ret != 0 && std::io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH)
+    };
+    gone(-(pid as libc::pid_t)) && gone(pid as libc::pid_t)
+}
+
+#[cfg(not(unix))]
+fn process_gone(_pid: u32) -> bool {
+    false
+}
+
 /// Windows signal dispatch.
 ///
 /// Signal mapping (per design.md):
@@ -209,12 +375,20 @@ fn send_signal(pid: u32, signal: &str) -> Result<()> {
 ///
 /// Strategy:
 /// 1. If `job_name` is Some, open the named Job Object and call TerminateJobObject.
-/// 2. Otherwise fall back to snapshot-based tree enumeration starting at `pid`.
+/// 2. If the job opted out of Job Objects (`job_object` false), terminate the
+///    tree by snapshot enumeration right away.
+/// 3. Otherwise fall back to snapshot-based tree enumeration starting at `pid`.
 #[cfg(windows)]
-fn send_signal(pid: u32, signal: &str, job_name: Option<&str>) -> Result<()> {
+fn send_signal(pid: u32, signal: &str, job_name: Option<&str>, job_object: bool) -> Result<()> {
     use tracing::debug;
     use windows::Win32::Foundation::CloseHandle;
 
+    match signal {
+        "STOP" => return suspend_process_tree(pid, true),
+        "CONT" => return suspend_process_tree(pid, false),
+        _ => {}
+    }
+
     // Log the signal mapping for observability.
     let _mapped = match signal {
         "TERM" => "TerminateJobObject (TERM→process-tree kill)",
@@ -247,7 +421,12 @@ fn send_signal(pid: u32, signal: &str, job_name: Option<&str>) -> Result<()> {
         }
     }
 
-    // Path 2: no named Job Object — try ad-hoc assignment then terminate.
+    // Path 2: the job runs without a Job Object by request.
+    if !job_object {
+        return terminate_process_tree(pid);
+    }
+
+    // Path 3: no named Job Object — try ad-hoc assignment then terminate.
     send_signal_no_job(pid)
 }
 
@@ -303,21 +482,14 @@ fn send_signal_no_job(pid: u32) -> Result<()> {
     Ok(())
 }
 
-/// Recursively terminate a process and all its descendants using
-/// CreateToolhelp32Snapshot. This is the fallback path when Job Object
-/// assignment fails (e.g., nested job objects on older Windows or CI).
-///
-/// Returns `Ok(())` only when the entire process tree (root + all descendants)
-/// has been terminated. Returns an error if snapshot enumeration fails or if
-/// the root process itself cannot be opened for termination, because in those
-/// cases tree-wide termination cannot be guaranteed (spec.md:55-63 MUST).
+/// Pids of `root_pid` and all its descendants (BFS order, root first),
+/// enumerated with CreateToolhelp32Snapshot.
 #[cfg(windows)]
-fn terminate_process_tree(root_pid: u32) -> Result<()> {
+fn process_tree_pids(root_pid: u32) -> Result<Vec<u32>> {
     use windows::Win32::Foundation::CloseHandle;
     use windows::Win32::System::Diagnostics::ToolHelp::{
         CreateToolhelp32Snapshot, PROCESSENTRY32, Process32First, Process32Next, TH32CS_SNAPPROCESS,
     };
-    use windows::Win32::System::Threading::{OpenProcess, PROCESS_TERMINATE, TerminateProcess};
 
     unsafe {
         // Build a list of (pid, parent_pid) for all running processes.
@@ -347,18 +519,82 @@ fn terminate_process_tree(root_pid: u32) -> Result<()> {
         let _ = CloseHandle(snapshot);
 
         // Collect all pids in the subtree rooted at root_pid (BFS).
-        let mut to_kill: Vec<u32> = vec![root_pid];
+        let mut pids: Vec<u32> = vec![root_pid];
         let mut i = 0;
-        while i < to_kill.len() {
-            let parent = to_kill[i];
+        while i < pids.len() {
+            let parent = pids[i];
             for &(child_pid, parent_pid) in &entries {
-                if parent_pid == parent && !to_kill.contains(&child_pid) {
-                    to_kill.push(child_pid);
+                if parent_pid == parent && !pids.contains(&child_pid) {
+                    pids.push(child_pid);
                 }
             }
             i += 1;
         }
+        Ok(pids)
+    }
+}
+
+/// Suspend (`STOP`) or resume (`CONT`) every thread of `root_pid` and its
+/// descendants. Processes that exit meanwhile are skipped.
+#[cfg(windows)]
+fn suspend_process_tree(root_pid: u32, suspend: bool) -> Result<()> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next,
+    };
+    use windows::Win32::System::Threading::{
+        OpenThread, ResumeThread, SuspendThread, THREAD_SUSPEND_RESUME,
+    };
 
+    let pids = process_tree_pids(root_pid)?;
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)
+            .map_err(|e| anyhow::anyhow!("CreateToolhelp32Snapshot failed: {}", e))?;
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+        if Thread32First(snapshot, &mut entry).is_ok() {
+            loop {
+                if pids.contains(&entry.th32OwnerProcessID)
+                    && let Ok(thread) = OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID)
+                {
+                    if suspend {
+                        SuspendThread(thread);
+                    } else {
+                        ResumeThread(thread);
+                    }
+                    let _ = CloseHandle(thread);
+                }
+                entry = THREADENTRY32 {
+                    dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+                    ..Default::default()
+                };
+                if Thread32Next(snapshot, &mut entry).is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    Ok(())
+}
+
+/// Recursively terminate a process and all its descendants using
+/// CreateToolhelp32Snapshot. This is the fallback path when Job Object
+/// assignment fails (e.g., nested job objects on older Windows or CI).
+///
+/// Returns `Ok(())` only when the entire process tree (root + all descendants)
+/// has been terminated. Returns an error if snapshot enumeration fails or if
+/// the root process itself cannot be opened for termination, because in those
+/// cases tree-wide termination cannot be guaranteed (spec.md:55-63 MUST).
+#[cfg(windows)]
+fn terminate_process_tree(root_pid: u32) -> Result<()> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_TERMINATE, TerminateProcess};
+
+    let to_kill = process_tree_pids(root_pid)?;
+    unsafe {
         // Terminate all collected processes (children first, then root).
         // Per spec.md:55-63, tree-wide termination is a MUST.  Every process
         // in the subtree must be confirmed terminated; failure to terminate
@@ -2,12 +2,15 @@
 ///
 /// Provides JSON output types, job-directory management, and the
 /// implementation of the sub-commands: create, run, start, restart, status, tail,
-/// wait, kill, list, schema, install-skills, and completions.
+/// wait, follow, kill, list, purge, replay, rotate, schema, install-skills, verify-skills, and completions.
 pub mod completions;
 pub mod compress;
 pub mod config;
 pub mod create;
 pub mod delete;
+pub mod duration;
+pub mod events;
+pub mod follow;
 pub mod gc;
 pub mod install_skills;
 pub mod jobstore;
@@ -15,7 +18,12 @@ pub mod kill;
 pub mod list;
 pub mod mcp;
 pub mod notify;
+pub mod profile;
+pub mod purge;
+pub mod replay;
 pub mod restart;
+pub mod rlimit;
+pub mod rotate;
 pub mod run;
 pub mod schema;
 pub mod schema_cmd;
@@ -25,4 +33,6 @@ pub mod start;
 pub mod status;
 pub mod tag;
 pub mod tail;
+pub mod verify_skills;
+pub mod version;
 pub mod wait;
@@ -11,6 +11,8 @@
 //!
 //! - `--cwd <PATH>`: show only jobs created from `<PATH>` (overrides auto-detect).
 //! - `--all`: disable cwd filtering entirely and show all jobs.
+//! - `--cwd-recursive`: also match jobs created from any subdirectory of the
+//!   filter directory (explicit `--cwd` or the current directory).
 //!
 //! Jobs that were created before this feature (i.e. `meta.json.cwd` is absent)
 //! are treated as having no cwd and will therefore not appear in the default
@@ -25,14 +27,36 @@
 //!   - Namespace prefix: `hoge.*`, `hoge.fuga.*`
 //!
 //! Tag filtering composes with cwd and state filtering.
+//!
+//! ## Summary cache
+//!
+//! With `--cache`, summaries of finished jobs are kept in `.list-cache.json`
+//! in the root. An entry is reused while the job's `meta.json` and
+//! `state.json` are older than the entry's `cached_at`; other jobs are read
+//! as usual and the cache is rewritten atomically, dropping jobs whose
+//! directories are gone.
+//!
+//! ## Cursor pagination
+//!
+//! `--before <JOB_ID>` keeps only jobs listed after that job (older ones) and
+//! `--after <JOB_ID>` only jobs listed before it (newer ones), in the usual
+//! started_at/job_id order. Job IDs are random, so the cursor job's position
+//! comes from its own `started_at`; it does not have to pass the filters. The
+//! response's `next_cursor` and `prev_cursor` are the last and first returned
+//! job when more jobs remain in that direction. The cursors do not apply to
+//! the unsorted NDJSON stream, so combining them with it is rejected with
+//! `cursor_with_ndjson`.
 
 use anyhow::Result;
+use std::cmp::Ordering;
 use tracing::debug;
 
+use crate::jobstore::JobDir;
 use crate::jobstore::resolve_root;
 use crate::jobstore::short_job_id;
+use crate::jobstore::write_atomic;
 use crate::run::resolve_effective_cwd;
-use crate::schema::{JobStatus, JobSummary, ListData, Response};
+use crate::schema::{JobStatus, JobSummary, ListData, ListStats, Response};
 use crate::tag::{matches_all_patterns, validate_filter_pattern};
 
 #[cfg(unix)]
@@ -72,23 +96,28 @@ fn pid_is_alive(_pid: u32) -> bool {
 }
 
 fn effective_state(state: &crate::schema::JobState) -> String {
-    if *state.status() != JobStatus::Running {
-        return state.status().as_str().to_string();
+    effective_status(state.job_id(), state.status(), state.pid)
+}
+
+/// Persisted status, except that an active job whose process is gone (or
+/// unknown) is presented as `unknown`.
+fn effective_status(job_id: &str, status: &JobStatus, pid: Option<u32>) -> String {
+    if !status.is_active() {
+        return status.as_str().to_string();
     }
 
-    match state.pid {
-        Some(pid) if pid_is_alive(pid) => "running".to_string(),
+    match pid {
+        Some(pid) if pid_is_alive(pid) => status.as_str().to_string(),
         Some(pid) => {
             debug!(
-                job_id = %state.job_id(),
-                pid,
-                "list: persisted running job has dead pid; presenting as unknown"
+                job_id,
+                pid, "list: persisted running job has dead pid; presenting as unknown"
             );
             "unknown".to_string()
         }
         None => {
             debug!(
-                job_id = %state.job_id(),
+                job_id,
                 "list: persisted running job has no pid; presenting as unknown"
             );
             "unknown".to_string()
@@ -96,13 +125,27 @@ fn effective_state(state: &crate::schema::JobState) -> String {
     }
 }
 
+/// Maximum characters of the joined command kept in `command_preview`.
+const COMMAND_PREVIEW_CHARS: usize = 80;
+
+/// Display-oriented command line: the argv joined by spaces, cut to
+/// [`COMMAND_PREVIEW_CHARS`] characters with `...` appended when truncated.
+fn command_preview(command: &[String]) -> String {
+    let joined = command.join(" ");
+    let mut preview: String = joined.chars().take(COMMAND_PREVIEW_CHARS).collect();
+    if preview.len() < joined.len() {
+        preview.push_str("...");
+    }
+    preview
+}
+
 /// Options for the `list` sub-command.
 #[derive(Debug)]
 pub struct ListOpts<'a> {
     pub root: Option<&'a str>,
     /// Maximum number of jobs to return; 0 = no limit.
     pub limit: u64,
-    /// Optional state filter: running|exited|killed|failed|unknown.
+    /// Optional state filter: running|stopped|exited|killed|failed|unknown.
     pub state: Option<&'a str>,
     /// Optional cwd filter: show only jobs created from this directory.
     /// Conflicts with `all`.
@@ -110,8 +153,305 @@ pub struct ListOpts<'a> {
     /// When true, disable cwd filtering and show all jobs.
     /// Conflicts with `cwd`.
     pub all: bool,
+    /// When true, the cwd filter also matches jobs created from its
+    /// subdirectories. Conflicts with `all`.
+    pub cwd_recursive: bool,
     /// Tag filter patterns (AND semantics); empty means no tag filtering.
     pub tags: Vec<String>,
+    /// Optional `command_hash` filter. Without `cwd`, it also disables the
+    /// default current-directory filter.
+    pub command_hash: Option<&'a str>,
+    /// Only jobs whose program is exactly this, or whose command joined by
+    /// spaces starts with it.
+    pub command_prefix: Option<&'a str>,
+    /// Only count jobs by state across the whole root (no per-job summaries,
+    /// no meta.json reads, no filters).
+    pub stats_only: bool,
+    /// Include each job's full `meta.json` in its summary as `meta`.
+    pub full: bool,
+    /// Stream one summary per line as jobs are read (unsorted), then a final
+    /// `list` envelope with `jobs: []` and `stats` for the streamed jobs.
+    pub ndjson: bool,
+    /// Reuse finished jobs' summaries from `.list-cache.json` in the root and
+    /// refresh it; reports `cache_hits` and `cache_misses`.
+    pub cache: bool,
+    /// Only return jobs listed after this job (older); conflicts with `after`.
+    pub before: Option<&'a str>,
+    /// Only return jobs listed before this job (newer); conflicts with `before`.
+    pub after: Option<&'a str>,
+}
+
+/// Listing order: started_at descending, then job_id descending. Jobs without
+/// `started_at` (created jobs) sort last.
+fn listing_order(a: (Option<&str>, &str), b: (Option<&str>, &str)) -> Ordering {
+    b.0.cmp(&a.0).then_with(|| b.1.cmp(a.1))
+}
+
+/// `--before`/`--after` was combined with `--format ndjson`.
+#[derive(Debug)]
+pub struct CursorWithNdjson(pub String);
+
+impl std::fmt::Display for CursorWithNdjson {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CursorWithNdjson {}
+
+/// The listing position of a `--before`/`--after` cursor job: its own
+/// summary when it is listed, otherwise its state.json `started_at`.
+fn cursor_position(
+    root: &std::path::Path,
+    jobs: &[JobSummary],
+    cursor: &str,
+) -> Result<(Option<String>, String)> {
+    if let Some(job) = jobs.iter().find(|j| j.job_id == cursor) {
+        return Ok((job.started_at.clone(), job.job_id.clone()));
+    }
+    let job_dir = JobDir::open(root, cursor)?;
+    let started_at = job_dir
+        .read_state()
+        .ok()
+        .and_then(|state| state.started_at().map(str::to_string));
+    Ok((started_at, job_dir.job_id))
+}
+
+/// The part of `state.json` that `--stats-only` needs.
+#[derive(serde::Deserialize)]
+struct StateStatus {
+    job: StateStatusJob,
+    #[serde(default)]
+    pid: Option<u32>,
+}
+
+#[derive(serde::Deserialize)]
+struct StateStatusJob {
+    id: String,
+    status: JobStatus,
+}
+
+/// Count jobs under `root` by effective state for `list --stats-only`.
+///
+/// A directory is a job when it holds a `meta.json` (checked, not parsed);
+/// only the status and pid of `state.json` are deserialized.
+fn collect_stats(root: &std::path::Path) -> Result<(ListStats, u64)> {
+    let (paths, skipped) = scan_job_dirs(root)?;
+    let mut stats = ListStats::default();
+    for path in paths {
+        let state = std::fs::read(path.join("state.json"))
+            .ok()
+            .and_then(|b| serde_json::from_slice::<StateStatus>(&b).ok())
+            .map_or_else(
+                || "unknown".to_string(),
+                |s| effective_status(&s.job.id, &s.job.status, s.pid),
+            );
+        stats.record(&state);
+    }
+    Ok((stats, skipped))
+}
+
+/// Job directories directly under `root`, plus the number of entries that
+/// could not be read or hold no `meta.json` (checked, not parsed).
+pub(crate) fn scan_job_dirs(root: &std::path::Path) -> Result<(Vec<std::path::PathBuf>, u64)> {
+    let read_dir = std::fs::read_dir(root)
+        .map_err(|e| anyhow::anyhow!("failed to read root directory {}: {}", root.display(), e))?;
+    let mut paths = Vec::new();
+    let mut skipped = 0;
+    for entry in read_dir {
+        let Ok(entry) = entry else {
+            skipped += 1;
+            continue;
+        };
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if !path.join("meta.json").is_file() {
+            skipped += 1;
+            continue;
+        }
+        paths.push(path);
+    }
+    Ok((paths, skipped))
+}
+
+/// Whether a job passes the `--command-prefix`, cwd, tag, and
+/// `--command-hash` filters.
+fn matches_filters(
+    opts: &ListOpts,
+    cwd_filter: Option<&str>,
+    path: &std::path::Path,
+    job_cwd: Option<&str>,
+    job_tags: &[String],
+    job_command: &[String],
+    job_command_hash: &str,
+) -> bool {
+    if let Some(prefix) = opts.command_prefix
+        && !matches_command_prefix(job_command, prefix)
+    {
+        debug!(
+            path = %path.display(),
+            prefix,
+            "list: skipping job (command prefix mismatch)"
+        );
+        return false;
+    }
+
+    // Apply cwd filter: if a filter is active, skip jobs whose cwd doesn't match.
+    if let Some(filter_cwd) = cwd_filter {
+        match job_cwd {
+            Some(job_cwd)
+                if job_cwd == filter_cwd
+                    || (opts.cwd_recursive
+                        && std::path::Path::new(job_cwd).starts_with(filter_cwd)) =>
+            {
+                // Match: include this job.
+            }
+            _ => {
+                // No cwd in meta (old job) or different cwd: exclude.
+                debug!(
+                    path = %path.display(),
+                    job_cwd = ?job_cwd,
+                    filter_cwd = %filter_cwd,
+                    "list: skipping job (cwd mismatch)"
+                );
+                return false;
+            }
+        }
+    }
+
+    // Apply tag filters: all patterns must match (logical AND).
+    if !opts.tags.is_empty() && !matches_all_patterns(job_tags, &opts.tags) {
+        debug!(
+            path = %path.display(),
+            job_tags = ?job_tags,
+            patterns = ?opts.tags,
+            "list: skipping job (tag mismatch)"
+        );
+        return false;
+    }
+
+    // Apply command fingerprint filter.
+    opts.command_hash
+        .is_none_or(|hash| job_command_hash == hash)
+}
+
+/// `--command-prefix`: the program is exactly `prefix`, or the command joined
+/// by spaces starts with it.
+fn matches_command_prefix(command: &[String], prefix: &str) -> bool {
+    command.first().is_some_and(|program| program == prefix)
+        || command.join(" ").starts_with(prefix)
+}
+
+/// Build the summary of the job in `path` from its parsed `meta.json` and,
+/// when readable, its `state.json`. `meta` is left unset.
+fn read_summary(path: &std::path::Path, meta: &crate::schema::JobMeta) -> JobSummary {
+    // state.json is optional: read if available, continue without it if not.
+    let state_opt: Option<crate::schema::JobState> = {
+        let state_path = path.join("state.json");
+        match std::fs::read(&state_path) {
+            Ok(b) => serde_json::from_slice(&b).ok(),
+            Err(_) => None,
+        }
+    };
+
+    let (state_str, exit_code, finished_at, updated_at) = if let Some(ref s) = state_opt {
+        (
+            effective_state(s),
+            s.exit_code(),
+            s.finished_at.clone(),
+            Some(s.updated_at.clone()),
+        )
+    } else {
+        ("unknown".to_string(), None, None, None)
+    };
+
+    let job_started_at = state_opt
+        .as_ref()
+        .and_then(|s| s.started_at().map(|t| t.to_string()));
+    let duration_ms = state_opt.as_ref().and_then(|s| s.duration_ms());
+    // Output volume so far, for gauging the output rate of running jobs.
+    let current_output_bytes = (state_str == "running").then(|| {
+        ["stdout.log", "stderr.log"]
+            .iter()
+            .filter_map(|name| std::fs::metadata(path.join(name)).ok())
+            .map(|m| m.len())
+            .sum()
+    });
+    JobSummary {
+        job_id: meta.job.id.clone(),
+        short_job_id: short_job_id(&meta.job.id),
+        state: state_str,
+        command_preview: command_preview(&meta.command),
+        command: meta.command.clone(),
+        cwd: meta.cwd.clone(),
+        exit_code,
+        created_at: meta.created_at.clone(),
+        started_at: job_started_at,
+        finished_at,
+        updated_at,
+        duration_ms,
+        tags: meta.tags.clone(),
+        meta: None,
+        current_output_bytes,
+        hostname: meta.hostname.clone(),
+    }
+}
+
+/// File in the root holding the `list --cache` summaries.
+const LIST_CACHE_FILE: &str = ".list-cache.json";
+
+/// Contents of [`LIST_CACHE_FILE`], keyed by job directory name.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ListCache {
+    #[serde(default)]
+    jobs: std::collections::BTreeMap<String, CachedJobSummary>,
+}
+
+/// A finished job's summary as stored by `list --cache`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CachedJobSummary {
+    /// Unix milliseconds when the job's files were read. The entry is reused
+    /// while `meta.json` and `state.json` were last modified before this.
+    cached_at: u64,
+    /// `meta.json` `command_hash`, for `--command-hash` filtering.
+    command_hash: String,
+    summary: JobSummary,
+}
+
+/// Read the cache in `root`; a missing or unreadable cache is empty.
+fn read_list_cache(root: &std::path::Path) -> ListCache {
+    std::fs::read(root.join(LIST_CACHE_FILE))
+        .ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default()
+}
+
+fn write_list_cache(root: &std::path::Path, cache: &ListCache) -> Result<()> {
+    write_atomic(
+        root,
+        &root.join(LIST_CACHE_FILE),
+        &serde_json::to_vec(cache)?,
+    )
+}
+
+/// True when neither `meta.json` nor `state.json` in `path` changed at or
+/// after `cached_at`. A missing `state.json` counts as changed.
+fn cache_entry_is_fresh(path: &std::path::Path, cached_at: u64) -> bool {
+    ["meta.json", "state.json"].iter().all(|name| {
+        std::fs::metadata(path.join(name))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .is_some_and(|d| (d.as_millis() as u64) < cached_at)
+    })
+}
+
+fn unix_millis_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
 }
 
 /// Execute `list`: enumerate jobs and emit JSON.
@@ -119,6 +459,13 @@ pub fn execute(opts: ListOpts) -> Result<()> {
     let root = resolve_root(opts.root);
     let root_str = root.display().to_string();
 
+    // The NDJSON stream is unsorted, so a cursor has no position in it.
+    if opts.ndjson && (opts.before.is_some() || opts.after.is_some()) {
+        return Err(anyhow::Error::new(CursorWithNdjson(
+            "--before/--after cannot be combined with --format ndjson".to_string(),
+        )));
+    }
+
     // Validate all tag filter patterns upfront before doing any I/O.
     for pattern in &opts.tags {
         validate_filter_pattern(pattern).map_err(anyhow::Error::from)?;
@@ -126,16 +473,17 @@ pub fn execute(opts: ListOpts) -> Result<()> {
 
     // Determine the cwd filter to apply.
     // Priority: --all (no filter) > --cwd <PATH> > current_dir (default).
-    let cwd_filter: Option<String> = if opts.all {
-        // --all: show every job regardless of cwd.
-        None
-    } else if let Some(cwd_arg) = opts.cwd {
-        // --cwd <PATH>: canonicalize and use as filter.
-        Some(resolve_effective_cwd(Some(cwd_arg)))
-    } else {
-        // Default: filter by current process working directory.
-        Some(resolve_effective_cwd(None))
-    };
+    let cwd_filter: Option<String> =
+        if opts.all || (opts.command_hash.is_some() && opts.cwd.is_none()) {
+            // --all (or a bare --command-hash lookup): show every job regardless of cwd.
+            None
+        } else if let Some(cwd_arg) = opts.cwd {
+            // --cwd <PATH>: canonicalize and use as filter.
+            Some(resolve_effective_cwd(Some(cwd_arg)))
+        } else {
+            // Default: filter by current process working directory.
+            Some(resolve_effective_cwd(None))
+        };
 
     debug!(
         cwd_filter = ?cwd_filter,
@@ -143,6 +491,31 @@ pub fn execute(opts: ListOpts) -> Result<()> {
         "list: cwd filter determined"
     );
 
+    if opts.stats_only {
+        let (stats, skipped) = if root.exists() {
+            collect_stats(&root)?
+        } else {
+            (ListStats::default(), 0)
+        };
+        Response::new(
+            "list",
+            ListData {
+                root: root_str,
+                jobs: vec![],
+                truncated: false,
+                skipped,
+                filter_cwd: None,
+                stats: Some(stats),
+                cache_hits: None,
+                cache_misses: None,
+                next_cursor: None,
+                prev_cursor: None,
+            },
+        )
+        .print();
+        return Ok(());
+    }
+
     // If root does not exist, return an empty list (normal termination).
     if !root.exists() {
         debug!(root = %root_str, "root does not exist; returning empty list");
@@ -153,6 +526,12 @@ pub fn execute(opts: ListOpts) -> Result<()> {
                 jobs: vec![],
                 truncated: false,
                 skipped: 0,
+                filter_cwd: cwd_filter,
+                stats: None,
+                cache_hits: None,
+                cache_misses: None,
+                next_cursor: None,
+                prev_cursor: None,
             },
         );
         response.print();
@@ -165,6 +544,17 @@ pub fn execute(opts: ListOpts) -> Result<()> {
 
     let mut jobs: Vec<JobSummary> = Vec::new();
     let mut skipped: u64 = 0;
+    // NDJSON streaming state: jobs emitted so far and their counts by state.
+    let mut streamed = ListStats::default();
+    let mut stream_truncated = false;
+    // --cache state: the cache as read, the cache being rebuilt, and counters.
+    let mut old_cache = if opts.cache {
+        read_list_cache(&root)
+    } else {
+        ListCache::default()
+    };
+    let mut new_cache = ListCache::default();
+    let (mut cache_hits, mut cache_misses) = (0u64, 0u64);
 
     for entry in read_dir {
         let entry = match entry {
@@ -181,91 +571,146 @@ pub fn execute(opts: ListOpts) -> Result<()> {
             // Skip non-directory entries (e.g. stray files in root).
             continue;
         }
+        let dir_name = entry.file_name().to_string_lossy().into_owned();
 
-        // meta.json must exist and be parseable to consider this a job.
-        let meta_path = path.join("meta.json");
-        let meta_bytes = match std::fs::read(&meta_path) {
-            Ok(b) => b,
-            Err(_) => {
-                debug!(path = %path.display(), "meta.json missing or unreadable; skipping");
-                skipped += 1;
+        let cached = old_cache
+            .jobs
+            .remove(&dir_name)
+            .filter(|c| cache_entry_is_fresh(&path, c.cached_at));
+        let summary = if let Some(cached) = cached {
+            cache_hits += 1;
+            let matched = matches_filters(
+                &opts,
+                cwd_filter.as_deref(),
+                &path,
+                cached.summary.cwd.as_deref(),
+                &cached.summary.tags,
+                &cached.summary.command,
+                &cached.command_hash,
+            );
+            let mut summary = cached.summary.clone();
+            new_cache.jobs.insert(dir_name, cached);
+            if !matched {
                 continue;
             }
-        };
-        let meta: crate::schema::JobMeta = match serde_json::from_slice(&meta_bytes) {
-            Ok(m) => m,
-            Err(e) => {
-                debug!(path = %path.display(), error = %e, "meta.json parse error; skipping");
-                skipped += 1;
-                continue;
+            if opts.full {
+                summary.meta = std::fs::read(path.join("meta.json"))
+                    .ok()
+                    .and_then(|b| serde_json::from_slice(&b).ok());
             }
-        };
+            summary
+        } else {
+            // Taken before reading, so a write racing with this call makes
+            // the entry stale for the next one.
+            let read_started_ms = unix_millis_now();
 
-        // Apply cwd filter: if a filter is active, skip jobs whose cwd doesn't match.
-        if let Some(ref filter_cwd) = cwd_filter {
-            match meta.cwd.as_deref() {
-                Some(job_cwd) if job_cwd == filter_cwd => {
-                    // Match: include this job.
+            // meta.json must exist and be parseable to consider this a job.
+            let meta_path = path.join("meta.json");
+            let meta_bytes = match std::fs::read(&meta_path) {
+                Ok(b) => b,
+                Err(_) => {
+                    debug!(path = %path.display(), "meta.json missing or unreadable; skipping");
+                    skipped += 1;
+                    continue;
                 }
-                _ => {
-                    // No cwd in meta (old job) or different cwd: exclude.
-                    debug!(
-                        path = %path.display(),
-                        job_cwd = ?meta.cwd,
-                        filter_cwd = %filter_cwd,
-                        "list: skipping job (cwd mismatch)"
-                    );
+            };
+            let meta: crate::schema::JobMeta = match serde_json::from_slice(&meta_bytes) {
+                Ok(m) => m,
+                Err(e) => {
+                    debug!(path = %path.display(), error = %e, "meta.json parse error; skipping");
+                    skipped += 1;
                     continue;
                 }
+            };
+            let matched = matches_filters(
+                &opts,
+                cwd_filter.as_deref(),
+                &path,
+                meta.cwd.as_deref(),
+                &meta.tags,
+                &meta.command,
+                &meta.command_hash,
+            );
+            // Without --cache, filtered-out jobs need not read state.json; with
+            // it, every job is summarized so the cache covers the whole root.
+            if !matched && !opts.cache {
+                continue;
+            }
+
+            let mut summary = read_summary(&path, &meta);
+            if opts.cache {
+                cache_misses += 1;
+                // Active jobs change without notice (a supervisor may die), so
+                // only finished jobs are cached.
+                if matches!(summary.state.as_str(), "exited" | "killed" | "failed") {
+                    new_cache.jobs.insert(
+                        dir_name,
+                        CachedJobSummary {
+                            cached_at: read_started_ms,
+                            command_hash: meta.command_hash.clone(),
+                            summary: summary.clone(),
+                        },
+                    );
+                }
             }
-        }
+            if !matched {
+                continue;
+            }
+            if opts.full {
+                summary.meta = serde_json::from_slice(&meta_bytes).ok();
+            }
+            summary
+        };
 
-        // Apply tag filters: all patterns must match (logical AND).
-        if !opts.tags.is_empty() && !matches_all_patterns(&meta.tags, &opts.tags) {
-            debug!(
-                path = %path.display(),
-                job_tags = ?meta.tags,
-                patterns = ?opts.tags,
-                "list: skipping job (tag mismatch)"
-            );
+        if opts.ndjson {
+            if opts.state.is_some_and(|s| summary.state != s) {
+                continue;
+            }
+            if opts.limit > 0 && streamed.total >= opts.limit {
+                stream_truncated = true;
+                break;
+            }
+            streamed.record(&summary.state);
+            crate::schema::print_to_stdout(&summary);
             continue;
         }
+        jobs.push(summary);
+    }
 
-        // state.json is optional: read if available, continue without it if not.
-        let state_opt: Option<crate::schema::JobState> = {
-            let state_path = path.join("state.json");
-            match std::fs::read(&state_path) {
-                Ok(b) => serde_json::from_slice(&b).ok(),
-                Err(_) => None,
+    let (cache_hits, cache_misses) = if opts.cache {
+        // Entries not visited because an NDJSON stream stopped early are kept;
+        // entries whose directory is gone are evicted.
+        for (dir_name, cached) in old_cache.jobs {
+            if root.join(&dir_name).is_dir() {
+                new_cache.jobs.insert(dir_name, cached);
             }
-        };
-
-        let (state_str, exit_code, finished_at, updated_at) = if let Some(ref s) = state_opt {
-            (
-                effective_state(s),
-                s.exit_code(),
-                s.finished_at.clone(),
-                Some(s.updated_at.clone()),
-            )
-        } else {
-            ("unknown".to_string(), None, None, None)
-        };
+        }
+        if let Err(e) = write_list_cache(&root, &new_cache) {
+            debug!(error = %e, "list: failed to write cache");
+        }
+        (Some(cache_hits), Some(cache_misses))
+    } else {
+        (None, None)
+    };
 
-        let job_started_at = state_opt
-            .as_ref()
-            .and_then(|s| s.started_at().map(|t| t.to_string()));
-        jobs.push(JobSummary {
-            job_id: meta.job.id.clone(),
-            short_job_id: short_job_id(&meta.job.id),
-            state: state_str,
-            command: meta.command.clone(),
-            exit_code,
-            created_at: meta.created_at.clone(),
-            started_at: job_started_at,
-            finished_at,
-            updated_at,
-            tags: meta.tags.clone(),
-        });
+    if opts.ndjson {
+        Response::new(
+            "list",
+            ListData {
+                root: root_str,
+                jobs: vec![],
+                truncated: stream_truncated,
+                skipped,
+                filter_cwd: cwd_filter,
+                stats: Some(streamed),
+                cache_hits,
+                cache_misses,
+                next_cursor: None,
+                prev_cursor: None,
+            },
+        )
+        .print();
+        return Ok(());
     }
 
     // Apply state filter before sorting and limiting.
@@ -275,16 +720,53 @@ pub fn execute(opts: ListOpts) -> Result<()> {
 
     // Sort by started_at descending; tie-break by job_id descending.
     jobs.sort_by(|a, b| {
-        b.started_at
-            .cmp(&a.started_at)
-            .then_with(|| b.job_id.cmp(&a.job_id))
+        listing_order(
+            (a.started_at.as_deref(), &a.job_id),
+            (b.started_at.as_deref(), &b.job_id),
+        )
     });
 
-    // Apply limit.
+    // Apply the cursor, then the limit. With --after the page is the jobs
+    // closest to the cursor, i.e. the end of what remains.
+    let mut newer_remaining = false;
+    let mut older_remaining = false;
+    if let Some(before) = opts.before {
+        let (started_at, job_id) = cursor_position(&root, &jobs, before)?;
+        let len = jobs.len();
+        jobs.retain(|j| {
+            listing_order(
+                (j.started_at.as_deref(), &j.job_id),
+                (started_at.as_deref(), &job_id),
+            ) == Ordering::Greater
+        });
+        newer_remaining = jobs.len() < len;
+    } else if let Some(after) = opts.after {
+        let (started_at, job_id) = cursor_position(&root, &jobs, after)?;
+        let len = jobs.len();
+        jobs.retain(|j| {
+            listing_order(
+                (j.started_at.as_deref(), &j.job_id),
+                (started_at.as_deref(), &job_id),
+            ) == Ordering::Less
+        });
+        older_remaining = jobs.len() < len;
+    }
     let truncated = opts.limit > 0 && jobs.len() as u64 > opts.limit;
     if truncated {
-        jobs.truncate(opts.limit as usize);
+        if opts.after.is_some() {
+            jobs.drain(..jobs.len() - opts.limit as usize);
+            newer_remaining = true;
+        } else {
+            jobs.truncate(opts.limit as usize);
+            older_remaining = true;
+        }
     }
+    let next_cursor = older_remaining
+        .then(|| jobs.last().map(|j| j.job_id.clone()))
+        .flatten();
+    let prev_cursor = newer_remaining
+        .then(|| jobs.first().map(|j| j.job_id.clone()))
+        .flatten();
 
     debug!(
         root = %root_str,
@@ -301,6 +783,12 @@ pub fn execute(opts: ListOpts) -> Result<()> {
             jobs,
             truncated,
             skipped,
+            filter_cwd: cwd_filter,
+            stats: None,
+            cache_hits,
+            cache_misses,
+            next_cursor,
+            prev_cursor,
         },
     );
     response.print();
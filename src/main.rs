@@ -11,11 +11,33 @@ use std::ffi::OsString;
 use tracing_subscriber::EnvFilter;
 
 use agent_exec::compress::CompressionMode;
-use agent_exec::jobstore::{AmbiguousJobId, InvalidJobState, JobIdCollisionExhausted, JobNotFound};
+use agent_exec::duration::DurationMs;
+use agent_exec::jobstore::{
+    AmbiguousJobId, InvalidJobState, JobIdCollision, JobIdCollisionExhausted, JobNotFound,
+};
 use agent_exec::mcp::McpStartupConfigError;
 use agent_exec::schema::ErrorResponse;
 use agent_exec::tag::InvalidTag;
 
+/// When to color JSON responses (`--color`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+/// How `run --notify-file` writes completion events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum NotifyFileFormat {
+    /// Append one NDJSON line per event (shared by many jobs).
+    Append,
+    /// Overwrite the file with the latest event.
+    Replace,
+    /// Atomically replace the file with the final state.json plus meta.json.
+    Snapshot,
+}
+
 /// Shell variants supported by the `completions` subcommand.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 enum CompletionShell {
@@ -55,6 +77,29 @@ fn parse_stored_tag(s: &str) -> Result<String, String> {
         .map_err(|e| e.to_string())
 }
 
+/// Clap value parser: validate a log encoding label (used by `run` and `create`).
+fn parse_output_encoding(s: &str) -> Result<String, String> {
+    if encoding_rs::Encoding::for_label(s.as_bytes()).is_some() {
+        Ok(s.to_string())
+    } else {
+        Err(format!("unknown encoding label: {s}"))
+    }
+}
+
+/// Clap value parser: a log encoding label or `base64` (used by `tail`).
+fn parse_tail_encoding(s: &str) -> Result<String, String> {
+    if s.eq_ignore_ascii_case(agent_exec::jobstore::BASE64_ENCODING) {
+        Ok(agent_exec::jobstore::BASE64_ENCODING.to_string())
+    } else {
+        parse_output_encoding(s)
+    }
+}
+
+/// Clap value parser: parse a human-readable byte size (used by `rotate`).
+fn parse_size(s: &str) -> Result<u64, String> {
+    agent_exec::rotate::parse_size(s).ok_or_else(|| format!("invalid size: {s}"))
+}
+
 /// Clap value parser: validate a list filter pattern (used by `list`).
 fn parse_filter_pattern(s: &str) -> Result<String, String> {
     agent_exec::tag::validate_filter_pattern(s)
@@ -83,7 +128,7 @@ impl clap::builder::TypedValueParser for SignalValueParser {
         &self,
     ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
         Some(Box::new(
-            ["TERM", "INT", "KILL", "HUP", "USR1", "USR2"]
+            agent_exec::run::SIGNAL_NAMES
                 .iter()
                 .map(|s| clap::builder::PossibleValue::new(*s)),
         ))
@@ -108,10 +153,27 @@ struct Cli {
     #[arg(long, global = true, default_value = "false", action = clap::ArgAction::SetTrue)]
     yaml: bool,
 
+    /// Pretty-print JSON responses with ANSI colors: `always`, `never`, or
+    /// `auto` (only when stdout is a terminal). Colored output is not valid
+    /// input for JSON parsers.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "never"
+    )]
+    color: ColorWhen,
+
     #[command(subcommand)]
     command: Command,
 }
 
+// Parsed once per process; boxing the option-heavy `Run` variant buys nothing.
+// `Run` and `Supervise` keep their arguments in separate `Args` structs so the
+// parser clap generates for each gets its own stack frame; inlined, they
+// overflow the 2 MiB stack of a test thread in debug builds.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Subcommand)]
 enum Command {
     /// Create a job definition without starting it. Returns JSON with type="create".
@@ -124,10 +186,15 @@ enum Command {
         #[arg(long, default_value = "0")]
         timeout: u64,
 
-        /// Seconds after SIGTERM to send SIGKILL; 0 = immediate SIGKILL on timeout.
+        /// Grace period in seconds between the timeout SIGTERM and SIGKILL; 0 = immediate SIGKILL.
         #[arg(long, default_value = "0")]
         kill_after: u64,
 
+        /// Extra milliseconds after the kill-after grace period before the process group is
+        /// SIGKILLed, so SIGTERM handlers can finish their teardown.
+        #[arg(long, value_name = "MS", default_value = "0")]
+        keep_going: u64,
+
         /// Working directory for the command.
         #[arg(long, value_hint = ValueHint::DirPath)]
         cwd: Option<String>,
@@ -148,7 +215,7 @@ enum Command {
         #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "no_inherit_env")]
         inherit_env: bool,
 
-        /// Mask secret values in JSON output (key name only; may be repeated).
+        /// Mask secret values in JSON output (key name or `*`/`?` glob; may be repeated).
         #[arg(long = "mask", value_name = "KEY")]
         mask: Vec<String>,
 
@@ -208,6 +275,10 @@ enum Command {
         #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
         output_file: Option<String>,
 
+        /// Encoding used by `tail` to decode log bytes (e.g. utf-8, latin1, shift_jis).
+        #[arg(long, value_name = "ENCODING", default_value = "utf-8", value_parser = parse_output_encoding)]
+        output_encoding: String,
+
         /// Command and arguments to run when `start` is called.
         #[arg(required = true, trailing_var_arg = true, value_hint = ValueHint::CommandWithArguments)]
         command: Vec<String>,
@@ -288,85 +359,51 @@ enum Command {
         job_id: String,
     },
 
-    /// Run a command as a background job and return JSON immediately.
-    Run {
-        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
-        no_auto_gc: bool,
-        #[arg(long, value_name = "DURATION")]
-        auto_gc_older_than: Option<String>,
-        #[arg(long, value_name = "N")]
-        auto_gc_max_jobs: Option<u64>,
-        #[arg(long, value_name = "BYTES")]
-        auto_gc_max_bytes: Option<u64>,
-        #[arg(long, default_value = "0")]
-        timeout: u64,
-        #[arg(long, default_value = "0")]
-        kill_after: u64,
-        #[arg(long, value_hint = ValueHint::DirPath)]
-        cwd: Option<String>,
-        #[arg(long = "env", value_name = "KEY=VALUE")]
-        env_vars: Vec<String>,
-        #[arg(long = "env-file", value_name = "FILE", value_hint = ValueHint::FilePath)]
-        env_files: Vec<String>,
-        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "inherit_env")]
-        no_inherit_env: bool,
-        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "no_inherit_env")]
-        inherit_env: bool,
-        #[arg(long = "mask", value_name = "KEY")]
-        mask: Vec<String>,
-        #[arg(long, value_name = "VALUE", conflicts_with = "stdin_file")]
-        stdin: Option<String>,
-        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, conflicts_with = "stdin")]
-        stdin_file: Option<String>,
-        #[arg(long, value_name = "BYTES", default_value_t = agent_exec::run::DEFAULT_STDIN_MAX_BYTES)]
-        stdin_max_bytes: u64,
-        #[arg(long = "tag", value_name = "TAG", value_parser = parse_stored_tag)]
-        tags: Vec<String>,
-        #[arg(long, value_hint = ValueHint::FilePath)]
-        log: Option<String>,
-        #[arg(long, default_value = "0")]
-        progress_every: u64,
-        #[arg(long, value_name = "COMMAND")]
-        notify_command: Option<String>,
-        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
-        notify_file: Option<String>,
-        #[arg(long, value_name = "PATTERN")]
-        output_pattern: Option<String>,
-        #[arg(long, value_name = "TYPE", value_parser = ["contains", "regex"])]
-        output_match_type: Option<String>,
-        #[arg(long, value_name = "STREAM", value_parser = ["stdout", "stderr", "either"])]
-        output_stream: Option<String>,
-        #[arg(long, value_name = "COMMAND")]
-        output_command: Option<String>,
-        #[arg(long = "output-file", value_name = "PATH", value_hint = ValueHint::FilePath)]
-        output_file: Option<String>,
-        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
-        config: Option<String>,
-        #[arg(long, value_name = "PROGRAM AND FLAGS")]
-        shell_wrapper: Option<String>,
-        #[arg(long, default_value_t = true, default_missing_value = "true", num_args = 0..=1, action = clap::ArgAction::Set)]
-        wait: bool,
-        #[arg(long, default_value = "10", conflicts_with = "forever")]
-        until: u64,
-        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "until")]
-        forever: bool,
+    /// Run a finished job's definition again as a new job.
+    Replay {
+        /// Seconds to observe the new job's output before responding.
+        #[arg(long, value_name = "SECONDS", default_value = "10")]
+        snapshot_after: u64,
+
+        /// Respond immediately without observing any output.
         #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
         no_wait: bool,
-        #[arg(long, default_value = "65536")]
-        max_bytes: u64,
-        #[arg(long, value_enum, value_name = "MODE")]
-        compress: Option<CompressionMode>,
-        #[arg(long, value_enum, value_name = "MODE")]
-        rtk: Option<CompressionMode>,
-        #[arg(required = true, trailing_var_arg = true, value_hint = ValueHint::CommandWithArguments)]
-        command: Vec<String>,
+
+        /// Job ID of the finished job to replay.
+        #[arg(value_name = "JOB_ID", add = ArgValueCompleter::new(agent_exec::completions::complete_all_jobs))]
+        job_id: String,
     },
 
+    /// Run a command as a background job and return JSON immediately.
+    Run(RunArgs),
+
     /// Get status of a job.
     Status {
         /// Job ID.
         #[arg(add = ArgValueCompleter::new(agent_exec::completions::complete_all_jobs))]
         job_id: String,
+
+        /// Include the stdout recorded by `run --capture-output`.
+        #[arg(long)]
+        captured: bool,
+
+        /// Include the supervisor's phase timestamps from `timing.json`.
+        #[arg(long)]
+        timing: bool,
+
+        /// Keep emitting status responses (one JSON object per line) until the
+        /// job reaches a terminal state.
+        #[arg(long)]
+        poll: bool,
+
+        /// Interval between polled responses, in milliseconds.
+        #[arg(long, default_value = "1000", requires = "poll")]
+        poll_ms: u64,
+
+        /// Stop polling after this many milliseconds even if the job is still
+        /// running (default: poll until terminal).
+        #[arg(long, requires = "poll")]
+        timeout_ms: Option<u64>,
     },
 
     /// Get stdout/stderr tail of a job.
@@ -387,6 +424,24 @@ enum Command {
         #[arg(long, value_enum, value_name = "MODE")]
         rtk: Option<CompressionMode>,
 
+        /// Encoding used to decode log bytes, or `base64` for the raw bytes
+        /// (default: the job's --output-encoding).
+        #[arg(long, value_name = "ENCODING", value_parser = parse_tail_encoding)]
+        encoding: Option<String>,
+
+        /// Also return the tail of full.log (interleaved, timestamped stdout/stderr).
+        #[arg(long)]
+        full_log: bool,
+
+        /// After the snapshot, print new output as NDJSON chunk lines until
+        /// the job finishes.
+        #[arg(long)]
+        stream: bool,
+
+        /// Poll interval in milliseconds for --stream.
+        #[arg(long, value_name = "MS", default_value = "200", requires = "stream")]
+        stream_poll_ms: u64,
+
         /// Job ID.
         #[arg(add = ArgValueCompleter::new(agent_exec::completions::complete_all_jobs))]
         job_id: String,
@@ -413,6 +468,34 @@ enum Command {
         )]
         forever: bool,
 
+        /// Re-read state.json only when this file's mtime or size changes
+        /// (relative to the job directory; defaults to state.json).
+        #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true, default_missing_value = "state.json")]
+        poll_file: Option<String>,
+
+        /// Job ID.
+        #[arg(add = ArgValueCompleter::new(agent_exec::completions::complete_waitable_jobs))]
+        job_id: String,
+    },
+
+    /// Stream a job's new output as NDJSON until it finishes.
+    Follow {
+        /// Interval in milliseconds between checks for new output.
+        #[arg(long, value_name = "MS", default_value = "200")]
+        poll_ms: u64,
+
+        /// How long to wait for the log files to appear, in milliseconds.
+        #[arg(long, value_name = "MS", default_value = "10000")]
+        timeout_ms: u64,
+
+        /// Only follow stdout.
+        #[arg(long, conflicts_with = "stderr_only")]
+        stdout_only: bool,
+
+        /// Only follow stderr.
+        #[arg(long)]
+        stderr_only: bool,
+
         /// Job ID.
         #[arg(add = ArgValueCompleter::new(agent_exec::completions::complete_waitable_jobs))]
         job_id: String,
@@ -451,6 +534,36 @@ enum Command {
         job_id: Option<String>,
     },
 
+    /// Read the structured event log of a job started with `run --event-log`.
+    Events {
+        /// Only return events of this type.
+        #[arg(long = "type", value_name = "TYPE", value_parser = agent_exec::events::EVENT_TYPES)]
+        kind: Option<String>,
+
+        /// Job ID.
+        #[arg(add = ArgValueCompleter::new(agent_exec::completions::complete_all_jobs))]
+        job_id: String,
+    },
+
+    /// Rotate an oversized job log (stdout.log, stderr.log or full.log).
+    Rotate {
+        /// Job ID.
+        #[arg(long, add = ArgValueCompleter::new(agent_exec::completions::complete_all_jobs))]
+        job_id: String,
+
+        /// Log file to rotate.
+        #[arg(long, value_name = "LOG", value_parser = agent_exec::rotate::ROTATABLE_LOGS)]
+        log: String,
+
+        /// Rotate only when the log exceeds this size (e.g. 512k, 10m, 1g; plain number = bytes).
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_size: u64,
+
+        /// Number of archived generations to keep (<LOG>.1 .. <LOG>.N); 0 discards old content.
+        #[arg(long, value_name = "N", default_value = "5")]
+        archive_count: u32,
+    },
+
     /// Garbage collect old terminal job directories.
     Gc {
         /// Retention duration: jobs older than this are deleted (e.g. 30d, 24h, 7d).
@@ -471,17 +584,39 @@ enum Command {
         dry_run: bool,
     },
 
+    /// Delete finished jobs older than a number of seconds across the root.
+    Purge {
+        /// Minimum age of a job's finished_at, in seconds.
+        #[arg(long, value_name = "SECONDS")]
+        older_than: u64,
+
+        /// Only purge jobs in this terminal state.
+        #[arg(long, value_parser = ["exited", "killed", "failed"])]
+        state: Option<String>,
+
+        /// Report the jobs that would be deleted without deleting them.
+        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+        dry_run: bool,
+
+        /// Delete at most N jobs, oldest first.
+        #[arg(long, value_name = "N")]
+        limit: Option<u64>,
+    },
+
     /// Print the JSON Schema for all CLI response types.
     Schema,
 
+    /// Print the agent-exec version and response schema version.
+    Version,
+
     /// List all jobs under the root directory.
     List {
         /// Maximum number of jobs to return (0 = no limit).
         #[arg(long, default_value = "50")]
         limit: u64,
 
-        /// Filter jobs by state: created|running|exited|killed|failed|unknown.
-        #[arg(long, value_parser = ["created", "running", "exited", "killed", "failed", "unknown"])]
+        /// Filter jobs by state: created|running|stopped|exited|killed|failed|unknown.
+        #[arg(long, value_parser = ["created", "running", "stopped", "exited", "killed", "failed", "unknown"])]
         state: Option<String>,
 
         /// Filter jobs by working directory (conflicts with --all).
@@ -492,10 +627,66 @@ enum Command {
         #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "cwd")]
         all: bool,
 
+        /// Also include jobs created from subdirectories of the working
+        /// directory filter (conflicts with --all).
+        #[arg(long, conflicts_with = "all")]
+        cwd_recursive: bool,
+
         /// Filter jobs by tag pattern (may be repeated; all patterns must match).
         /// Supports exact match (e.g. "aaa") and namespace prefix match (e.g. "hoge.*").
         #[arg(long = "tag", value_name = "PATTERN", value_parser = parse_filter_pattern)]
         tags: Vec<String>,
+
+        /// Show only jobs whose meta.json command_hash matches (searches every
+        /// working directory unless --cwd is given).
+        #[arg(long, value_name = "HASH")]
+        command_hash: Option<String>,
+
+        /// Show only jobs whose program is exactly CMD, or whose command line
+        /// (argv joined by spaces) starts with CMD.
+        #[arg(long, value_name = "CMD")]
+        command_prefix: Option<String>,
+
+        /// Only report job counts by state across the whole root; skips meta.json
+        /// and per-job summaries (conflicts with the filters).
+        #[arg(
+            long,
+            default_value = "false",
+            action = clap::ArgAction::SetTrue,
+            conflicts_with_all = ["state", "cwd", "all", "cwd_recursive", "tags", "command_hash", "command_prefix"]
+        )]
+        stats_only: bool,
+
+        /// Include each job's full meta.json as `meta` in its summary.
+        #[arg(long, conflicts_with = "stats_only")]
+        full: bool,
+
+        /// Output format: `json` (one envelope) or `ndjson` (one job summary
+        /// per line as jobs are read, unsorted, then a closing envelope).
+        #[arg(
+            long,
+            visible_alias = "output-format",
+            value_name = "FORMAT",
+            value_parser = ["json", "ndjson"],
+            default_value = "json",
+            conflicts_with = "stats_only"
+        )]
+        format: String,
+
+        /// Reuse finished jobs' summaries from `.list-cache.json` in the root
+        /// and refresh it, instead of reading every job directory.
+        #[arg(long, conflicts_with = "stats_only")]
+        cache: bool,
+
+        /// Only list jobs that come after this job in the listing (older
+        /// ones); use the previous response's `next_cursor`.
+        #[arg(long, value_name = "JOB_ID", conflicts_with_all = ["stats_only", "after"])]
+        before: Option<String>,
+
+        /// Only list jobs that come before this job in the listing (newer
+        /// ones); use the previous response's `prev_cursor`.
+        #[arg(long, value_name = "JOB_ID", conflicts_with = "stats_only")]
+        after: Option<String>,
     },
 
     /// Shorthand for `list --state running`. Accepts the same filtering knobs
@@ -537,6 +728,18 @@ enum Command {
         claude: bool,
     },
 
+    /// Check installed skills against the checksums recorded at install time.
+    #[command(name = "verify-skills")]
+    VerifySkills {
+        /// Verify skills installed in the home directory instead of the current directory.
+        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+        global: bool,
+
+        /// Use .claude/ root instead of .agents/.
+        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+        claude: bool,
+    },
+
     /// Manage job notification configuration.
     Notify {
         #[command(subcommand)]
@@ -586,75 +789,496 @@ enum Command {
 
     /// [Internal] Supervise a child process — not for direct use.
     #[command(name = "_supervise", hide = true)]
-    Supervise {
-        #[arg(long)]
-        job_id: String,
+    Supervise(SuperviseArgs),
+}
 
-        #[arg(long)]
-        supervise_root: String,
+/// Arguments of `run`.
+#[derive(Debug, clap::Args)]
+struct RunArgs {
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    no_auto_gc: bool,
+    #[arg(long, value_name = "DURATION")]
+    auto_gc_older_than: Option<String>,
+    #[arg(long, value_name = "N")]
+    auto_gc_max_jobs: Option<u64>,
+    #[arg(long, value_name = "BYTES")]
+    auto_gc_max_bytes: Option<u64>,
+    #[arg(long, default_value = "0")]
+    timeout: u64,
+    #[arg(long, default_value = "0")]
+    kill_after: u64,
+    #[arg(long, value_name = "MS", default_value = "0")]
+    keep_going: u64,
+    #[arg(long, value_hint = ValueHint::DirPath)]
+    cwd: Option<String>,
+    #[arg(long = "env", value_name = "KEY=VALUE")]
+    env_vars: Vec<String>,
+    #[arg(long = "env-file", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    env_files: Vec<String>,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "inherit_env")]
+    no_inherit_env: bool,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "no_inherit_env")]
+    inherit_env: bool,
+    #[arg(long = "mask", value_name = "KEY")]
+    mask: Vec<String>,
+    #[arg(long = "mask-from-file", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    mask_files: Vec<String>,
+    #[arg(long, value_name = "VALUE", conflicts_with = "stdin_file")]
+    stdin: Option<String>,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, conflicts_with = "stdin")]
+    stdin_file: Option<String>,
+    #[arg(long, value_name = "BYTES", default_value_t = agent_exec::run::DEFAULT_STDIN_MAX_BYTES)]
+    stdin_max_bytes: u64,
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_stored_tag)]
+    tags: Vec<String>,
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    log: Option<String>,
+    #[arg(long, default_value = "0")]
+    progress_every: u64,
+    #[arg(long, value_name = "COMMAND")]
+    notify_command: Option<String>,
+    #[arg(
+        long = "notify-timeout",
+        value_name = "MS",
+        default_value_t = agent_exec::run::DEFAULT_NOTIFY_TIMEOUT_MS.as_millis(),
+        requires = "notify_command"
+    )]
+    notify_timeout_ms: u64,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    notify_file: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "append",
+        requires = "notify_file"
+    )]
+    notify_file_format: NotifyFileFormat,
+    #[arg(long, value_name = "PATTERN")]
+    output_pattern: Option<String>,
+    #[arg(long, value_name = "TYPE", value_parser = ["contains", "regex"])]
+    output_match_type: Option<String>,
+    #[arg(long, value_name = "STREAM", value_parser = ["stdout", "stderr", "either"])]
+    output_stream: Option<String>,
+    #[arg(long, value_name = "COMMAND")]
+    output_command: Option<String>,
+    #[arg(long = "output-file", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    output_file: Option<String>,
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8", value_parser = parse_output_encoding)]
+    output_encoding: String,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    config: Option<String>,
+    #[arg(long, value_name = "PROGRAM AND FLAGS")]
+    shell_wrapper: Option<String>,
+    #[arg(long, default_value_t = true, default_missing_value = "true", num_args = 0..=1, action = clap::ArgAction::Set)]
+    wait: bool,
+    #[arg(long, default_value = "10", conflicts_with = "forever")]
+    until: u64,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "until")]
+    forever: bool,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    no_wait: bool,
+    #[arg(long, default_value = "65536")]
+    max_bytes: u64,
+    #[arg(long, value_enum, value_name = "MODE")]
+    compress: Option<CompressionMode>,
+    #[arg(long, value_enum, value_name = "MODE")]
+    rtk: Option<CompressionMode>,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    detach: bool,
+    #[arg(long, value_name = "SIGNAL", value_parser = SignalValueParser)]
+    signal_on_parent_exit: Option<String>,
+    #[arg(long, visible_alias = "stdin-tty", default_value = "false", action = clap::ArgAction::SetTrue)]
+    pty: bool,
+    #[arg(long, value_name = "COMMAND")]
+    health_check: Option<String>,
+    #[arg(long, value_name = "SECONDS", default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+    health_check_interval: u64,
+    #[arg(long, value_name = "N", default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+    health_check_retries: u32,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with_all = ["stdin", "stdin_file"])]
+    stdin_forward: bool,
+    #[arg(long, conflicts_with_all = ["stdin", "stdin_file", "stdin_forward", "stdin_null"])]
+    stdin_inherit: bool,
+    #[arg(long, conflicts_with_all = ["stdin", "stdin_file", "stdin_forward"])]
+    stdin_null: bool,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    event_log: bool,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    report_pipe: Option<String>,
+    #[arg(long, value_name = "MS", default_value = "1000", value_parser = clap::value_parser!(u64).range(1..), requires = "report_pipe")]
+    report_interval_ms: u64,
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    disable_core_dump: bool,
+    #[arg(long, value_name = "N", default_value = "0")]
+    max_output_lines_per_second: u64,
+    #[arg(long, visible_alias = "env-expand-recursive", default_value = "false", action = clap::ArgAction::SetTrue)]
+    env_expand: bool,
+    #[arg(long = "deduplicate", value_name = "HOURS")]
+    deduplicate_hours: Option<u64>,
+    #[arg(long, value_name = "MODE", default_value = "none", value_parser = ["none", "line", "full"])]
+    log_buffering: String,
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8-lossy", value_parser = ["utf-8-lossy", "base64"])]
+    snapshot_encoding: String,
+    #[arg(long, value_name = "LEVEL", default_value = "normal", value_parser = ["low", "normal", "high"], ignore_case = true)]
+    priority: String,
+    #[arg(long, value_name = "N", allow_negative_numbers = true)]
+    nice: Option<i32>,
+    #[arg(long, value_name = "COMMAND")]
+    startup_probe: Option<String>,
+    #[arg(long, value_name = "MS", default_value = "500", value_parser = clap::value_parser!(u64).range(1..), requires = "startup_probe")]
+    startup_probe_interval_ms: u64,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value = "30000",
+        requires = "startup_probe"
+    )]
+    startup_probe_timeout_ms: u64,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "0",
+        requires = "startup_probe"
+    )]
+    startup_probe_retries: u32,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, conflicts_with = "pty")]
+    stdout_file: Option<String>,
+    #[arg(long = "env-unset", value_name = "KEY")]
+    env_unset: Vec<String>,
+    #[arg(long = "env-prefix", value_name = "PREFIX")]
+    env_inherit_prefix: Vec<String>,
+    #[arg(long = "ulimit", value_name = "NAME=SOFT:HARD")]
+    rlimits: Vec<String>,
+    #[arg(long)]
+    pid_namespace: bool,
+    #[arg(long, requires = "pid_namespace")]
+    require_pid_namespace: bool,
+    #[arg(long)]
+    windows_no_job_object: bool,
+    #[arg(long)]
+    require_clean_exit: bool,
+    #[arg(long, visible_alias = "working-snapshot-on-signal")]
+    snapshot_on_sigusr1: bool,
+    #[arg(
+        long,
+        visible_alias = "output-max-line-length",
+        value_name = "BYTES",
+        default_value = "0"
+    )]
+    max_line_length: u64,
+    #[arg(long, value_name = "MB")]
+    log_rotate_size_mb: Option<f64>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "5",
+        requires = "log_rotate_size_mb"
+    )]
+    log_rotate_count: u32,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    wait_for_file: Option<String>,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value = "60000",
+        requires = "wait_for_file"
+    )]
+    wait_for_file_timeout_ms: u64,
+    #[arg(long, visible_alias = "env-from-json-string", value_name = "JSON")]
+    env_from_json: Option<String>,
+    #[arg(long, value_name = "JOB_ID")]
+    read_env_from_state: Option<String>,
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    report_file: Option<String>,
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    profile_output: Option<String>,
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    checkpoint_dir: Option<String>,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value = "5000",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "checkpoint_dir"
+    )]
+    checkpoint_interval_ms: u64,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "5",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "checkpoint_dir"
+    )]
+    checkpoint_count: u64,
+    #[arg(long = "completion-webhook", value_name = "URL")]
+    completion_webhook_url: Option<String>,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value = "10000",
+        requires = "completion_webhook_url"
+    )]
+    completion_webhook_timeout_ms: u64,
+    #[arg(long)]
+    capture_output: bool,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value = "4096",
+        requires = "capture_output"
+    )]
+    capture_output_max_bytes: u64,
+    #[arg(long)]
+    benchmark_mode: bool,
+    #[arg(long)]
+    record_host_info: bool,
+    #[arg(long, value_name = "SHELL_TEMPLATE")]
+    wrap_command: Option<String>,
+    #[arg(long, value_name = "HOST")]
+    ssh_host: Option<String>,
+    #[arg(long, value_name = "USER", requires = "ssh_host")]
+    ssh_user: Option<String>,
+    #[arg(long, value_name = "PATH", requires = "ssh_host", value_hint = ValueHint::FilePath)]
+    ssh_key: Option<String>,
+    #[arg(long, value_name = "PORT", default_value = "22", requires = "ssh_host")]
+    ssh_port: u16,
+    #[arg(long, requires = "ssh_host")]
+    ssh_no_host_key_check: bool,
+    #[arg(long, value_name = "MS", default_value = "5000")]
+    startup_timeout_ms: u64,
+    #[arg(
+        long,
+        visible_alias = "quota-bytes",
+        value_name = "BYTES",
+        default_value = "0"
+    )]
+    output_quota_bytes: u64,
+    #[arg(long)]
+    dry_run: bool,
+    #[arg(required = true, trailing_var_arg = true, value_hint = ValueHint::CommandWithArguments)]
+    command: Vec<String>,
+}
 
-        /// Override full.log path.
-        #[arg(long)]
-        full_log: Option<String>,
+/// Arguments of the internal `_supervise` command.
+#[derive(Debug, clap::Args)]
+struct SuperviseArgs {
+    #[arg(long)]
+    job_id: String,
 
-        /// Timeout in seconds; 0 = no timeout.
-        #[arg(long, default_value = "0")]
-        timeout: u64,
+    #[arg(long)]
+    supervise_root: String,
 
-        /// Seconds after SIGTERM to send SIGKILL; 0 = immediate SIGKILL on timeout.
-        #[arg(long, default_value = "0")]
-        kill_after: u64,
+    /// Override full.log path.
+    #[arg(long)]
+    full_log: Option<String>,
 
-        /// Working directory for the child process.
-        #[arg(long)]
-        cwd: Option<String>,
+    /// Timeout in seconds; 0 = no timeout.
+    #[arg(long, default_value = "0")]
+    timeout: u64,
 
-        /// Environment variable KEY=VALUE (may be repeated).
-        #[arg(long = "env", value_name = "KEY=VALUE")]
-        env_vars: Vec<String>,
+    /// Grace period in seconds between the timeout SIGTERM and SIGKILL; 0 = immediate SIGKILL.
+    #[arg(long, default_value = "0")]
+    kill_after: u64,
 
-        /// Load environment variables from a file (may be repeated).
-        #[arg(long = "env-file", value_name = "FILE")]
-        env_files: Vec<String>,
+    /// Extra milliseconds after the kill-after grace period before the process group is
+    /// SIGKILLed, so SIGTERM handlers can finish their teardown.
+    #[arg(long, value_name = "MS", default_value = "0")]
+    keep_going: u64,
 
-        /// Do not inherit the current process environment.
-        #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "supervise_inherit_env")]
-        no_inherit_env: bool,
+    /// Working directory for the child process.
+    #[arg(long)]
+    cwd: Option<String>,
 
-        /// Inherit the current process environment (default; conflicts with --no-inherit-env).
-        #[arg(long = "inherit-env", default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "no_inherit_env", id = "supervise_inherit_env")]
-        inherit_env: bool,
+    /// Environment variable KEY=VALUE (may be repeated).
+    #[arg(long = "env", value_name = "KEY=VALUE")]
+    env_vars: Vec<String>,
 
-        /// Interval in seconds for state.json updated_at refresh; 0 = disabled.
-        #[arg(long, default_value = "0")]
-        progress_every: u64,
+    /// Load environment variables from a file (may be repeated).
+    #[arg(long = "env-file", value_name = "FILE")]
+    env_files: Vec<String>,
 
-        /// Materialized stdin file path relative to the job directory (internal use).
-        #[arg(long, value_name = "PATH", hide = true)]
-        stdin_file: Option<String>,
+    /// Do not inherit the current process environment.
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "supervise_inherit_env")]
+    no_inherit_env: bool,
 
-        /// Shell command string to run on job completion; executed via the configured shell
-        /// wrapper. Event JSON is sent to stdin.
-        /// Also sets AGENT_EXEC_EVENT_PATH, AGENT_EXEC_JOB_ID, and AGENT_EXEC_EVENT_TYPE.
-        #[arg(long, value_name = "COMMAND")]
-        notify_command: Option<String>,
+    /// Inherit the current process environment (default; conflicts with --no-inherit-env).
+    #[arg(long = "inherit-env", default_value = "false", action = clap::ArgAction::SetTrue, conflicts_with = "no_inherit_env", id = "supervise_inherit_env")]
+    inherit_env: bool,
 
-        /// File path that receives one NDJSON `job.finished` event per completed job.
-        #[arg(long, value_name = "PATH")]
-        notify_file: Option<String>,
+    /// Interval in seconds for state.json updated_at refresh; 0 = disabled.
+    #[arg(long, default_value = "0")]
+    progress_every: u64,
 
-        /// Shell wrapper override as a string (for direct user invocation; not used by `run`).
-        #[arg(long, value_name = "PROGRAM AND FLAGS")]
-        shell_wrapper: Option<String>,
+    /// Stdin file path, relative to the job directory or absolute (internal use).
+    #[arg(long, value_name = "PATH", hide = true)]
+    stdin_file: Option<String>,
 
-        /// Pre-resolved shell wrapper argv as a JSON array (set by `run`, not by users).
-        /// Takes precedence over --shell-wrapper when present.
-        #[arg(long, value_name = "JSON", hide = true)]
-        shell_wrapper_resolved: Option<String>,
+    /// Pass the supervisor's stdin through to the child (internal use).
+    #[arg(long, hide = true, conflicts_with = "stdin_file")]
+    stdin_inherit: bool,
 
-        #[arg(required = true, trailing_var_arg = true)]
-        command: Vec<String>,
-    },
+    /// Shell command string to run on job completion; executed via the configured shell
+    /// wrapper. Event JSON is sent to stdin.
+    /// Also sets AGENT_EXEC_EVENT_PATH, AGENT_EXEC_JOB_ID, and AGENT_EXEC_EVENT_TYPE.
+    #[arg(long, value_name = "COMMAND")]
+    notify_command: Option<String>,
+
+    /// File path that receives one NDJSON `job.finished` event per completed job.
+    #[arg(long, value_name = "PATH")]
+    notify_file: Option<String>,
+
+    /// Shell wrapper override as a string (for direct user invocation; not used by `run`).
+    #[arg(long, value_name = "PROGRAM AND FLAGS")]
+    shell_wrapper: Option<String>,
+
+    /// Pre-resolved shell wrapper argv as a JSON array (set by `run`, not by users).
+    /// Takes precedence over --shell-wrapper when present.
+    #[arg(long, value_name = "JSON", hide = true)]
+    shell_wrapper_resolved: Option<String>,
+
+    /// Signal delivered to the child if this supervisor exits before it.
+    #[arg(long, value_name = "SIGNAL")]
+    signal_on_parent_exit: Option<String>,
+
+    /// Attach the child to a pseudo-terminal instead of pipes.
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    pty: bool,
+
+    /// Shell command string run periodically; the job is terminated after repeated failures.
+    #[arg(long, value_name = "COMMAND")]
+    health_check: Option<String>,
+
+    /// Milliseconds between health checks.
+    #[arg(long, default_value = "10000")]
+    health_check_interval_ms: u64,
+
+    /// Consecutive failed health checks before the job is terminated.
+    #[arg(long, default_value = "3")]
+    health_check_retries: u32,
+
+    /// Record a structured event stream in events.ndjson.
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    event_log: bool,
+
+    /// FIFO or regular file that receives periodic JSON status lines.
+    #[arg(long, value_name = "PATH")]
+    report_pipe: Option<String>,
+
+    /// Milliseconds between status reports; 0 = disabled.
+    #[arg(long, default_value = "0")]
+    report_interval_ms: u64,
+
+    /// Set RLIMIT_CORE to 0 in the child so it cannot write core dumps.
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    disable_core_dump: bool,
+
+    /// Lines per second written to each individual log; 0 = unlimited.
+    #[arg(long, default_value = "0")]
+    max_output_lines_per_second: u64,
+
+    /// Expand $NAME / ${NAME} references between --env-file and --env values.
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    env_expand: bool,
+
+    /// Write strategy for stdout.log / stderr.log: none, line, or full.
+    #[arg(long, default_value = "none", value_parser = ["none", "line", "full"])]
+    log_buffering: String,
+
+    /// Scheduling priority of the child: low, normal, or high.
+    #[arg(long, default_value = "normal", value_parser = ["low", "normal", "high"])]
+    priority: String,
+
+    /// Nice value (-20..=19) set in the child before exec; overrides --priority.
+    #[arg(long, allow_negative_numbers = true, value_parser = clap::value_parser!(i8).range(-20..=19))]
+    nice: Option<i8>,
+
+    /// Write the child's stdout directly to this file instead of stdout.log.
+    #[arg(long)]
+    stdout_file: Option<String>,
+
+    /// Remove an environment variable from the child environment (may be repeated).
+    #[arg(long = "env-unset", value_name = "KEY")]
+    env_unset: Vec<String>,
+
+    /// Keep only inherited variables whose key starts with this prefix (may be repeated).
+    #[arg(long = "env-prefix", value_name = "PREFIX")]
+    env_inherit_prefix: Vec<String>,
+
+    /// Resource limit set on the child before exec (may be repeated).
+    #[arg(long = "ulimit", value_name = "NAME=SOFT:HARD")]
+    rlimits: Vec<String>,
+
+    /// Spawn the child as PID 1 of a new PID namespace (Linux only).
+    #[arg(long)]
+    pid_namespace: bool,
+
+    /// Fail the job instead of warning when the PID namespace cannot be created.
+    #[arg(long, requires = "pid_namespace")]
+    require_pid_namespace: bool,
+
+    /// Do not assign the child to a Job Object (Windows only).
+    #[arg(long)]
+    windows_no_job_object: bool,
+
+    /// Record a non-zero exit as `failed` instead of `exited`.
+    #[arg(long)]
+    require_clean_exit: bool,
+
+    /// Write snapshots/manual-<epoch-ms>.json when the supervisor receives SIGUSR1 (Unix only).
+    #[arg(long)]
+    snapshot_on_sigusr1: bool,
+
+    /// Maximum bytes per full.log line; longer lines are cut (0 = unlimited).
+    #[arg(long, default_value = "0")]
+    max_line_length: u64,
+
+    /// Rotate stdout.log / stderr.log past this many bytes while running (0 = never).
+    #[arg(long, default_value = "0")]
+    log_rotate_size_bytes: u64,
+
+    /// Archived generations kept by in-run log rotation.
+    #[arg(long, default_value = "5")]
+    log_rotate_count: u32,
+
+    /// Write a JSON summary report to this path once the job finishes.
+    #[arg(long)]
+    report_file: Option<String>,
+
+    /// Write a folded-stack sampling profile of the child to this path.
+    #[arg(long)]
+    profile_output: Option<String>,
+
+    /// Directory receiving periodic checkpoint files.
+    #[arg(long)]
+    checkpoint_dir: Option<String>,
+
+    /// Interval between checkpoints, in milliseconds.
+    #[arg(long, default_value = "5000")]
+    checkpoint_interval_ms: u64,
+
+    /// Most periodic checkpoints kept per job.
+    #[arg(long, default_value = "5")]
+    checkpoint_count: u64,
+
+    /// POST the completion event to this URL once the job finishes.
+    #[arg(long = "completion-webhook")]
+    completion_webhook_url: Option<String>,
+
+    /// Timeout for each webhook attempt, in milliseconds.
+    #[arg(long, default_value = "10000")]
+    completion_webhook_timeout_ms: u64,
+
+    /// Record the final stdout in state.json once the job finishes.
+    #[arg(long)]
+    capture_output: bool,
+
+    /// Most stdout bytes recorded by --capture-output.
+    #[arg(long, default_value = "4096")]
+    capture_output_max_bytes: u64,
+
+    #[arg(required = true, trailing_var_arg = true)]
+    command: Vec<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -725,6 +1349,11 @@ fn main() {
 
     // Set output format before any subcommand runs (including error paths).
     agent_exec::schema::set_yaml_output(cli.yaml);
+    agent_exec::schema::set_color_output(match cli.color {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        ColorWhen::Auto => std::io::IsTerminal::is_terminal(&std::io::stdout()),
+    });
 
     let default_level = match cli.verbose {
         0 => "warn",
@@ -769,14 +1398,59 @@ fn main() {
             .is_some()
         {
             ErrorResponse::new("config_error", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::replay::JobStillRunning>()
+            .is_some()
+        {
+            ErrorResponse::new("job_still_running", format!("{e:#}"), false).print();
         } else if e.downcast_ref::<InvalidJobState>().is_some() {
             ErrorResponse::new("invalid_state", format!("{e:#}"), false).print();
-        } else if e.downcast_ref::<JobIdCollisionExhausted>().is_some() {
+        } else if e.downcast_ref::<JobIdCollisionExhausted>().is_some()
+            || e.downcast_ref::<JobIdCollision>().is_some()
+        {
             ErrorResponse::new("io_error", format!("{e:#}"), false).print();
         } else if e.downcast_ref::<agent_exec::run::StdinRequired>().is_some() {
             ErrorResponse::new("stdin_required", format!("{e:#}"), false).print();
         } else if e.downcast_ref::<agent_exec::run::StdinTooLarge>().is_some() {
             ErrorResponse::new("stdin_too_large", format!("{e:#}"), false).print();
+        } else if e.downcast_ref::<agent_exec::run::QuotaExceeded>().is_some() {
+            ErrorResponse::new("quota_exceeded", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::run::MaskFileNotFound>()
+            .is_some()
+        {
+            ErrorResponse::new("mask_file_not_found", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::run::StdinFileNotFound>()
+            .is_some()
+        {
+            ErrorResponse::new("stdin_file_not_found", format!("{e:#}"), false).print();
+        } else if e.downcast_ref::<agent_exec::run::CwdNotFound>().is_some() {
+            ErrorResponse::new("cwd_not_found", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::follow::FollowTimeout>()
+            .is_some()
+        {
+            ErrorResponse::new("follow_timeout", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::run::WaitForFileTimeout>()
+            .is_some()
+        {
+            ErrorResponse::new("wait_for_file_timeout", format!("{e:#}"), false).print();
+        } else if e.downcast_ref::<agent_exec::run::InvalidEnvVar>().is_some() {
+            ErrorResponse::new("invalid_env_var", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::rlimit::InvalidRlimit>()
+            .is_some()
+        {
+            ErrorResponse::new("invalid_rlimit", format!("{e:#}"), false).print();
+        } else if e
+            .downcast_ref::<agent_exec::list::CursorWithNdjson>()
+            .is_some()
+        {
+            ErrorResponse::new("cursor_with_ndjson", format!("{e:#}"), false).print();
+        } else if e.downcast_ref::<agent_exec::run::EnvCycle>().is_some() {
+            ErrorResponse::new("env_cycle", format!("{e:#}"), false).print();
         } else if format!("{e:#}").contains("parse config file") {
             ErrorResponse::new("config_error", format!("{e:#}"), false).print();
         } else {
@@ -866,6 +1540,7 @@ fn resolve_compression_or_exit(
 struct DefinitionOptions {
     timeout: u64,
     kill_after: u64,
+    keep_going: u64,
     cwd: Option<String>,
     env_vars: Vec<String>,
     env_files: Vec<String>,
@@ -885,12 +1560,14 @@ struct DefinitionOptions {
     output_stream: Option<String>,
     output_command: Option<String>,
     output_file: Option<String>,
+    output_encoding: String,
 }
 
 #[derive(Debug)]
 struct ResolvedDefinitionOptions {
-    timeout_ms: u64,
-    kill_after_ms: u64,
+    timeout_ms: DurationMs,
+    kill_after_ms: DurationMs,
+    keep_going_ms: DurationMs,
     cwd: Option<String>,
     env_vars: Vec<String>,
     env_files: Vec<String>,
@@ -898,7 +1575,7 @@ struct ResolvedDefinitionOptions {
     mask: Vec<String>,
     stdin: Option<agent_exec::run::StdinSource>,
     stdin_max_bytes: u64,
-    progress_every_ms: u64,
+    progress_every_ms: DurationMs,
     notify_command: Option<String>,
     notify_file: Option<String>,
     shell_wrapper: Vec<String>,
@@ -908,6 +1585,7 @@ struct ResolvedDefinitionOptions {
     output_stream: Option<String>,
     output_command: Option<String>,
     output_file: Option<String>,
+    output_encoding: String,
 }
 
 impl DefinitionOptions {
@@ -917,8 +1595,9 @@ impl DefinitionOptions {
             self.config.as_deref(),
         )?;
         Ok(ResolvedDefinitionOptions {
-            timeout_ms: self.timeout.saturating_mul(1000),
-            kill_after_ms: self.kill_after.saturating_mul(1000),
+            timeout_ms: DurationMs::from_millis(self.timeout.saturating_mul(1000)),
+            kill_after_ms: DurationMs::from_millis(self.kill_after.saturating_mul(1000)),
+            keep_going_ms: DurationMs::from_millis(self.keep_going),
             cwd: self.cwd,
             env_vars: self.env_vars,
             env_files: self.env_files,
@@ -926,7 +1605,7 @@ impl DefinitionOptions {
             mask: self.mask,
             stdin: agent_exec::run::resolve_stdin_source(self.stdin, self.stdin_file),
             stdin_max_bytes: self.stdin_max_bytes,
-            progress_every_ms: self.progress_every.saturating_mul(1000),
+            progress_every_ms: DurationMs::from_millis(self.progress_every.saturating_mul(1000)),
             notify_command: self.notify_command,
             notify_file: self.notify_file,
             shell_wrapper,
@@ -936,6 +1615,7 @@ impl DefinitionOptions {
             output_stream: self.output_stream,
             output_command: self.output_command,
             output_file: self.output_file,
+            output_encoding: self.output_encoding,
         })
     }
 }
@@ -1012,6 +1692,7 @@ fn run(cli: Cli) -> Result<()> {
             root,
             timeout,
             kill_after,
+            keep_going,
             cwd,
             env_vars,
             env_files,
@@ -1032,11 +1713,13 @@ fn run(cli: Cli) -> Result<()> {
             output_stream,
             output_command,
             output_file,
+            output_encoding,
             command,
         } => {
             let definition = DefinitionOptions {
                 timeout,
                 kill_after,
+                keep_going,
                 cwd,
                 env_vars,
                 env_files,
@@ -1056,6 +1739,7 @@ fn run(cli: Cli) -> Result<()> {
                 output_stream,
                 output_command,
                 output_file,
+                output_encoding,
             }
             .resolve()?;
             agent_exec::create::execute(agent_exec::create::CreateOpts {
@@ -1063,6 +1747,7 @@ fn run(cli: Cli) -> Result<()> {
                 root: root.as_deref(),
                 timeout_ms: definition.timeout_ms,
                 kill_after_ms: definition.kill_after_ms,
+                keep_going_ms: definition.keep_going_ms,
                 cwd: definition.cwd.as_deref(),
                 env_vars: definition.env_vars,
                 env_files: definition.env_files,
@@ -1080,6 +1765,7 @@ fn run(cli: Cli) -> Result<()> {
                 output_stream: definition.output_stream,
                 output_command: definition.output_command,
                 output_file: definition.output_file,
+                output_encoding: definition.output_encoding,
             })?;
         }
 
@@ -1183,19 +1869,34 @@ fn run(cli: Cli) -> Result<()> {
             })?;
         }
 
-        Command::Run {
+        Command::Replay {
+            snapshot_after,
+            no_wait,
+            job_id,
+        } => {
+            agent_exec::replay::execute(agent_exec::replay::ReplayOpts {
+                job_id: &job_id,
+                root: root.as_deref(),
+                snapshot_after,
+                wait: !no_wait,
+            })?;
+        }
+
+        Command::Run(RunArgs {
             no_auto_gc,
             auto_gc_older_than,
             auto_gc_max_jobs,
             auto_gc_max_bytes,
             timeout,
             kill_after,
+            keep_going,
             cwd,
             env_vars,
             env_files,
             no_inherit_env,
             inherit_env: _inherit_env,
             mask,
+            mask_files,
             tags,
             log,
             progress_every,
@@ -1206,6 +1907,7 @@ fn run(cli: Cli) -> Result<()> {
             output_stream,
             output_command,
             output_file,
+            output_encoding,
             stdin,
             stdin_file,
             stdin_max_bytes,
@@ -1218,8 +1920,70 @@ fn run(cli: Cli) -> Result<()> {
             max_bytes,
             compress,
             rtk,
+            detach,
+            signal_on_parent_exit,
+            pty,
+            health_check,
+            health_check_interval,
+            health_check_retries,
+            stdin_forward,
+            stdin_inherit,
+            stdin_null: _stdin_null,
+            event_log,
+            report_pipe,
+            report_interval_ms,
+            disable_core_dump,
+            max_output_lines_per_second,
+            env_expand,
+            deduplicate_hours,
+            log_buffering,
+            snapshot_encoding,
+            priority,
+            nice,
+            startup_probe,
+            startup_probe_interval_ms,
+            startup_probe_timeout_ms,
+            startup_probe_retries,
+            stdout_file,
+            env_unset,
+            env_inherit_prefix,
+            rlimits,
+            pid_namespace,
+            require_pid_namespace,
+            windows_no_job_object,
+            require_clean_exit,
+            snapshot_on_sigusr1,
+            max_line_length,
+            log_rotate_size_mb,
+            log_rotate_count,
+            wait_for_file,
+            wait_for_file_timeout_ms,
+            env_from_json,
+            read_env_from_state,
+            report_file,
+            profile_output,
+            checkpoint_dir,
+            checkpoint_interval_ms,
+            checkpoint_count,
+            completion_webhook_url,
+            completion_webhook_timeout_ms,
+            notify_timeout_ms,
+            notify_file_format,
+            capture_output,
+            capture_output_max_bytes,
+            benchmark_mode,
+            record_host_info,
+            wrap_command,
+            ssh_host,
+            ssh_user,
+            ssh_key,
+            ssh_port,
+            ssh_no_host_key_check,
+            startup_timeout_ms,
+            output_quota_bytes,
+            dry_run,
             command,
-        } => {
+        }) => {
             let config_path = config.clone();
             let cfg = agent_exec::config::resolve_config(config_path.as_deref())?;
             let auto_gc = AutoGcOptions {
@@ -1242,6 +2006,7 @@ fn run(cli: Cli) -> Result<()> {
             let definition = DefinitionOptions {
                 timeout,
                 kill_after,
+                keep_going,
                 cwd,
                 env_vars,
                 env_files,
@@ -1261,8 +2026,14 @@ fn run(cli: Cli) -> Result<()> {
                 output_stream,
                 output_command,
                 output_file,
+                output_encoding,
             }
             .resolve()?;
+            let startup_probe_command = startup_probe.map(|cmd| {
+                let mut argv = definition.shell_wrapper.clone();
+                argv.push(cmd);
+                argv
+            });
             agent_exec::run::execute(agent_exec::run::RunOpts {
                 command,
                 root: root.as_deref(),
@@ -1278,11 +2049,13 @@ fn run(cli: Cli) -> Result<()> {
                 compression_mode: inline.compression_mode,
                 timeout_ms: definition.timeout_ms,
                 kill_after_ms: definition.kill_after_ms,
+                keep_going_ms: definition.keep_going_ms,
                 cwd: definition.cwd.as_deref(),
                 env_vars: definition.env_vars,
                 env_files: definition.env_files,
                 inherit_env: definition.inherit_env,
                 mask: definition.mask,
+                mask_files,
                 stdin: definition.stdin,
                 stdin_max_bytes: definition.stdin_max_bytes,
                 tags: definition.tags,
@@ -1295,14 +2068,101 @@ fn run(cli: Cli) -> Result<()> {
                 output_stream: definition.output_stream,
                 output_command: definition.output_command,
                 output_file: definition.output_file,
+                snapshot_encoding: if snapshot_encoding == agent_exec::jobstore::BASE64_ENCODING {
+                    snapshot_encoding
+                } else {
+                    definition.output_encoding.clone()
+                },
+                output_encoding: definition.output_encoding,
                 shell_wrapper: definition.shell_wrapper,
+                detach,
+                signal_on_parent_exit,
+                pty,
+                health_check: health_check.map(|command| agent_exec::schema::HealthCheckConfig {
+                    command,
+                    interval_ms: health_check_interval.saturating_mul(1000),
+                    retries: health_check_retries,
+                }),
+                stdin_forward,
+                stdin_inherit,
+                event_log,
+                status_report: report_pipe.map(|path| agent_exec::schema::StatusReportConfig {
+                    path,
+                    interval_ms: report_interval_ms,
+                }),
+                disable_core_dump,
+                max_output_lines_per_second,
+                env_expand,
+                deduplicate_hours,
+                log_buffering: agent_exec::schema::LogBuffering::from_name(&log_buffering),
+                priority: agent_exec::schema::ProcessPriority::from_name(&priority),
+                nice,
+                startup_probe_command,
+                startup_probe_interval_ms: DurationMs::from_millis(startup_probe_interval_ms),
+                startup_probe_timeout_ms: DurationMs::from_millis(startup_probe_timeout_ms),
+                startup_probe_retries,
+                stdout_file,
+                env_unset,
+                env_inherit_prefix,
+                rlimits,
+                pid_namespace,
+                require_pid_namespace,
+                windows_no_job_object,
+                require_clean_exit,
+                snapshot_on_sigusr1,
+                max_line_length,
+                // Float-to-int `as` saturates, and maps negative and NaN to 0.
+                log_rotate_size_bytes: log_rotate_size_mb
+                    .map_or(0, |mb| (mb * 1024.0 * 1024.0).round() as u64),
+                log_rotate_count,
+                wait_for_file,
+                wait_for_file_timeout_ms: DurationMs::from_millis(wait_for_file_timeout_ms),
+                env_from_json,
+                read_env_from_state_job_id: read_env_from_state,
+                report_file,
+                profile_output,
+                checkpoint_dir,
+                checkpoint_interval_ms: DurationMs::from_millis(checkpoint_interval_ms),
+                checkpoint_count,
+                completion_webhook_url,
+                completion_webhook_timeout_ms: DurationMs::from_millis(
+                    completion_webhook_timeout_ms,
+                ),
+                notify_timeout_ms: DurationMs::from_millis(notify_timeout_ms),
+                notify_file_append: notify_file_format == NotifyFileFormat::Append,
+                notify_file_snapshot: notify_file_format == NotifyFileFormat::Snapshot,
+                capture_output,
+                capture_output_max_bytes,
+                benchmark_mode,
+                record_host_info,
+                wrap_command,
+                ssh_host,
+                ssh_user,
+                ssh_key,
+                ssh_port,
+                ssh_no_host_key_check,
+                startup_timeout_ms: DurationMs::from_millis(startup_timeout_ms),
+                output_quota_bytes,
+                dry_run,
             })?;
         }
 
-        Command::Status { job_id } => {
+        Command::Status {
+            job_id,
+            captured,
+            timing,
+            poll,
+            poll_ms,
+            timeout_ms,
+        } => {
             agent_exec::status::execute(agent_exec::status::StatusOpts {
                 job_id: &job_id,
                 root: root.as_deref(),
+                captured,
+                timing,
+                poll,
+                poll_ms: DurationMs::from_millis(poll_ms),
+                timeout_ms: timeout_ms.map(DurationMs::from_millis),
             })?;
         }
 
@@ -1311,6 +2171,10 @@ fn run(cli: Cli) -> Result<()> {
             max_bytes,
             compress,
             rtk,
+            encoding,
+            full_log,
+            stream,
+            stream_poll_ms,
             job_id,
         } => {
             let cfg = agent_exec::config::resolve_config(None)?;
@@ -1321,6 +2185,10 @@ fn run(cli: Cli) -> Result<()> {
                 tail_lines,
                 max_bytes,
                 compression_mode,
+                encoding,
+                full_log,
+                stream,
+                stream_poll_ms: DurationMs::from_millis(stream_poll_ms),
             })?;
         }
 
@@ -1328,6 +2196,7 @@ fn run(cli: Cli) -> Result<()> {
             poll_seconds,
             until,
             forever,
+            poll_file,
             job_id,
         } => {
             agent_exec::wait::execute(agent_exec::wait::WaitOpts {
@@ -1336,6 +2205,7 @@ fn run(cli: Cli) -> Result<()> {
                 poll_seconds,
                 until_seconds: until.unwrap_or(30),
                 forever,
+                poll_file,
             })?;
         }
 
@@ -1352,6 +2222,46 @@ fn run(cli: Cli) -> Result<()> {
             })?;
         }
 
+        Command::Follow {
+            poll_ms,
+            timeout_ms,
+            stdout_only,
+            stderr_only,
+            job_id,
+        } => {
+            agent_exec::follow::execute(agent_exec::follow::FollowOpts {
+                job_id: &job_id,
+                root: root.as_deref(),
+                poll_ms: DurationMs::from_millis(poll_ms),
+                timeout_ms: DurationMs::from_millis(timeout_ms),
+                stdout_only,
+                stderr_only,
+            })?;
+        }
+
+        Command::Events { kind, job_id } => {
+            agent_exec::events::execute(agent_exec::events::EventsOpts {
+                job_id: &job_id,
+                root: root.as_deref(),
+                kind: kind.as_deref(),
+            })?;
+        }
+
+        Command::Rotate {
+            job_id,
+            log,
+            max_size,
+            archive_count,
+        } => {
+            agent_exec::rotate::execute(agent_exec::rotate::RotateOpts {
+                job_id: &job_id,
+                root: root.as_deref(),
+                log: &log,
+                max_size_bytes: max_size,
+                archive_count,
+            })?;
+        }
+
         Command::Delete {
             all,
             dry_run,
@@ -1380,6 +2290,21 @@ fn run(cli: Cli) -> Result<()> {
             })?;
         }
 
+        Command::Purge {
+            older_than,
+            state,
+            dry_run,
+            limit,
+        } => {
+            agent_exec::purge::execute(agent_exec::purge::PurgeOpts {
+                root: root.as_deref(),
+                older_than_secs: older_than,
+                state_filter: state.as_deref(),
+                dry_run,
+                limit,
+            })?;
+        }
+
         Command::Mcp => {
             let runtime = tokio::runtime::Runtime::new().context("create MCP runtime")?;
             runtime.block_on(agent_exec::mcp::serve(root.clone()))?;
@@ -1408,6 +2333,10 @@ fn run(cli: Cli) -> Result<()> {
             agent_exec::schema_cmd::execute(agent_exec::schema_cmd::SchemaOpts)?;
         }
 
+        Command::Version => {
+            agent_exec::version::execute(agent_exec::version::VersionOpts)?;
+        }
+
         Command::Completions { shell } => {
             let completer = std::env::current_exe()
                 .context("resolve current executable for shell completions")?;
@@ -1431,12 +2360,28 @@ fn run(cli: Cli) -> Result<()> {
             })?
         }
 
+        Command::VerifySkills { global, claude } => {
+            agent_exec::verify_skills::execute(agent_exec::verify_skills::VerifySkillsOpts {
+                global,
+                claude,
+            })?
+        }
+
         Command::List {
             limit,
             state,
             cwd,
             all,
+            cwd_recursive,
             tags,
+            command_hash,
+            command_prefix,
+            stats_only,
+            full,
+            format,
+            cache,
+            before,
+            after,
         } => {
             agent_exec::list::execute(agent_exec::list::ListOpts {
                 root: root.as_deref(),
@@ -1444,7 +2389,16 @@ fn run(cli: Cli) -> Result<()> {
                 state: state.as_deref(),
                 cwd: cwd.as_deref(),
                 all,
+                cwd_recursive,
                 tags,
+                command_hash: command_hash.as_deref(),
+                command_prefix: command_prefix.as_deref(),
+                stats_only,
+                full,
+                ndjson: format == "ndjson",
+                cache,
+                before: before.as_deref(),
+                after: after.as_deref(),
             })?;
         }
 
@@ -1460,7 +2414,16 @@ fn run(cli: Cli) -> Result<()> {
                 state: Some("running"),
                 cwd: cwd.as_deref(),
                 all,
+                cwd_recursive: false,
                 tags,
+                command_hash: None,
+                command_prefix: None,
+                stats_only: false,
+                full: false,
+                ndjson: false,
+                cache: false,
+                before: None,
+                after: None,
             })?;
         }
 
@@ -1499,12 +2462,13 @@ fn run(cli: Cli) -> Result<()> {
             })?;
         }
 
-        Command::Supervise {
+        Command::Supervise(SuperviseArgs {
             job_id,
             supervise_root,
             full_log,
             timeout,
             kill_after,
+            keep_going,
             cwd,
             env_vars,
             env_files,
@@ -1512,12 +2476,48 @@ fn run(cli: Cli) -> Result<()> {
             inherit_env: _inherit_env,
             progress_every,
             stdin_file,
+            stdin_inherit,
             notify_command,
             notify_file,
             shell_wrapper,
             shell_wrapper_resolved,
+            signal_on_parent_exit,
+            pty,
+            health_check,
+            health_check_interval_ms,
+            health_check_retries,
+            event_log,
+            report_pipe,
+            report_interval_ms,
+            disable_core_dump,
+            max_output_lines_per_second,
+            env_expand,
+            log_buffering,
+            priority,
+            nice,
+            stdout_file,
+            env_unset,
+            env_inherit_prefix,
+            rlimits,
+            pid_namespace,
+            require_pid_namespace,
+            windows_no_job_object,
+            require_clean_exit,
+            snapshot_on_sigusr1,
+            max_line_length,
+            log_rotate_size_bytes,
+            log_rotate_count,
+            report_file,
+            profile_output,
+            checkpoint_dir,
+            checkpoint_interval_ms,
+            checkpoint_count,
+            completion_webhook_url,
+            completion_webhook_timeout_ms,
+            capture_output,
+            capture_output_max_bytes,
             command,
-        } => {
+        }) => {
             let should_inherit = !no_inherit_env;
             // Use the pre-resolved JSON wrapper from `run` if present (no join/split round-trip).
             // Fall back to resolving from the string override or defaults.
@@ -1532,17 +2532,63 @@ fn run(cli: Cli) -> Result<()> {
                 root: std::path::Path::new(&supervise_root),
                 command: &command,
                 full_log: full_log.as_deref(),
-                timeout_ms: timeout.saturating_mul(1000),
-                kill_after_ms: kill_after.saturating_mul(1000),
+                timeout_ms: DurationMs::from_millis(timeout.saturating_mul(1000)),
+                kill_after_ms: DurationMs::from_millis(kill_after.saturating_mul(1000)),
+                keep_going_ms: DurationMs::from_millis(keep_going),
                 cwd: cwd.as_deref(),
                 env_vars,
                 env_files,
                 inherit_env: should_inherit,
-                stdin_file,
-                progress_every_ms: progress_every,
+                stdin_mode: match stdin_file {
+                    _ if stdin_inherit => agent_exec::run::StdinMode::Inherit,
+                    Some(path) => agent_exec::run::StdinMode::File(path),
+                    None => agent_exec::run::StdinMode::Null,
+                },
+                progress_every_ms: DurationMs::from_millis(progress_every),
                 notify_command,
                 notify_file,
+                health_check_command: health_check.map(|cmd| {
+                    let mut argv = resolved_wrapper.clone();
+                    argv.push(cmd);
+                    argv
+                }),
+                health_check_interval_ms: DurationMs::from_millis(health_check_interval_ms),
+                health_check_retries,
+                event_log,
+                report_interval_ms: DurationMs::from_millis(report_interval_ms),
+                report_pipe,
                 shell_wrapper: resolved_wrapper,
+                signal_on_parent_exit,
+                pty,
+                disable_core_dump,
+                max_output_lines_per_second,
+                env_expand,
+                log_buffering: agent_exec::schema::LogBuffering::from_name(&log_buffering),
+                priority: agent_exec::schema::ProcessPriority::from_name(&priority),
+                nice,
+                stdout_file,
+                env_unset,
+                env_inherit_prefix,
+                rlimits,
+                pid_namespace,
+                require_pid_namespace,
+                windows_no_job_object,
+                require_clean_exit,
+                snapshot_on_sigusr1,
+                max_line_length,
+                log_rotate_size_bytes,
+                log_rotate_count,
+                report_file,
+                profile_output,
+                checkpoint_dir,
+                checkpoint_interval_ms: DurationMs::from_millis(checkpoint_interval_ms),
+                checkpoint_count,
+                completion_webhook_url,
+                completion_webhook_timeout_ms: DurationMs::from_millis(
+                    completion_webhook_timeout_ms,
+                ),
+                capture_output,
+                capture_output_max_bytes,
             })?;
         }
     }
@@ -10,6 +10,7 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::{Value, json};
 
+use crate::duration::DurationMs;
 use crate::{kill, run, schema::ErrorResponse, status, tail, wait};
 
 #[derive(Debug)]
@@ -235,7 +236,7 @@ impl Mcp {
             root: self.root.as_deref(),
             cwd: params.cwd.as_deref(),
             env_vars,
-            timeout_ms: timeout.saturating_mul(1000),
+            timeout_ms: DurationMs::from_millis(timeout.saturating_mul(1000)),
             until_seconds: until,
             ..Default::default()
         }))
@@ -246,6 +247,11 @@ impl Mcp {
         envelope(status::status_response(status::StatusOpts {
             job_id: &params.job_id,
             root: self.root.as_deref(),
+            captured: false,
+            timing: false,
+            poll: false,
+            poll_ms: DurationMs::ZERO,
+            timeout_ms: None,
         }))
     }
 
@@ -277,6 +283,7 @@ impl Mcp {
             poll_seconds: 1,
             until_seconds: until,
             forever: false,
+            poll_file: None,
         }))
     }
 
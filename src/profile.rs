@@ -0,0 +1,182 @@
+//! Sampling profile written by `run --profile-output`.
+//!
+//! While the child runs, the supervisor reads `/proc/<pid>/task/*/stat` and
+//! `wchan` every [`SAMPLE_INTERVAL`] and counts one folded stack per thread
+//! and sample: `<process>;<thread>;<state>[;<wait channel>]`. The counts are
+//! written in the folded format read by `flamegraph.pl` and
+//! `inferno-flamegraph`. Only the direct child is sampled, and only Linux
+//! exposes these files; elsewhere the profile is empty.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::schema::ResourceUsage;
+
+/// Time between samples.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Background thread sampling a running child.
+pub struct Sampler {
+    started: Instant,
+    stop: Arc<AtomicBool>,
+    handle: std::thread::JoinHandle<BTreeMap<String, u64>>,
+}
+
+/// Folded stack counts collected by a [`Sampler`].
+#[derive(Debug, Default)]
+pub struct Profile {
+    stacks: BTreeMap<String, u64>,
+    wall_clock: Duration,
+}
+
+impl Sampler {
+    /// Start sampling process `pid`.
+    pub fn start(pid: u32) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            let mut stacks = BTreeMap::new();
+            while !stop_thread.load(Ordering::Relaxed) {
+                sample(pid, &mut stacks);
+                std::thread::sleep(SAMPLE_INTERVAL);
+            }
+            stacks
+        });
+        Sampler {
+            started: Instant::now(),
+            stop,
+            handle,
+        }
+    }
+
+    /// Stop sampling and return what was collected.
+    pub fn finish(self) -> Profile {
+        let wall_clock = self.started.elapsed();
+        self.stop.store(true, Ordering::Relaxed);
+        Profile {
+            stacks: self.handle.join().unwrap_or_default(),
+            wall_clock,
+        }
+    }
+}
+
+impl Profile {
+    /// Summary recorded in `state.json` as `resource_usage`.
+    pub fn resource_usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            wall_clock_ms: self.wall_clock.as_millis() as u64,
+            samples: self.stacks.values().sum(),
+        }
+    }
+
+    /// The profile in folded format: one `stack count` line per stack.
+    pub fn folded(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|(stack, count)| format!("{stack} {count}\n"))
+            .collect()
+    }
+
+    /// Write [`Profile::folded`] to `path`, creating parent directories.
+    pub fn write_folded(&self, path: &std::path::Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create {}", parent.display()))?;
+        }
+        std::fs::write(path, self.folded()).with_context(|| format!("write {}", path.display()))
+    }
+}
+
+/// Add one sample of every thread of `pid` to `stacks`.
+#[cfg(target_os = "linux")]
+fn sample(pid: u32, stacks: &mut BTreeMap<String, u64>) {
+    let Some(process) = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()
+        .and_then(|stat| parse_stat(&stat).map(|(comm, _)| comm.to_string()))
+    else {
+        return;
+    };
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{pid}/task")) else {
+        return;
+    };
+    for task in tasks.flatten() {
+        let path = task.path();
+        let Some((thread, state)) = std::fs::read_to_string(path.join("stat"))
+            .ok()
+            .and_then(|stat| parse_stat(&stat).map(|(c, s)| (c.to_string(), s)))
+        else {
+            continue;
+        };
+        let mut stack = format!("{process};{thread};{}", state_name(state));
+        if let Ok(wchan) = std::fs::read_to_string(path.join("wchan")) {
+            let wchan = wchan.trim();
+            if !wchan.is_empty() && wchan != "0" {
+                stack.push(';');
+                stack.push_str(wchan);
+            }
+        }
+        *stacks.entry(stack).or_default() += 1;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sample(_pid: u32, _stacks: &mut BTreeMap<String, u64>) {}
+
+/// The command name and state letter of a `/proc/.../stat` line. The name
+/// is parenthesized and may itself contain spaces and parentheses.
+fn parse_stat(stat: &str) -> Option<(&str, char)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?;
+    let state = stat.get(close + 1..)?.trim_start().chars().next()?;
+    // `;` separates frames in the folded format.
+    (!comm.contains(';')).then_some((comm, state))
+}
+
+fn state_name(state: char) -> String {
+    match state {
+        'R' => "running".to_string(),
+        'S' => "sleeping".to_string(),
+        'D' => "disk_sleep".to_string(),
+        'T' | 't' => "stopped".to_string(),
+        'Z' => "zombie".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_stat_handles_parenthesized_names() {
+        assert_eq!(parse_stat("42 (sleep) S 1 42"), Some(("sleep", 'S')));
+        assert_eq!(
+            parse_stat("7 (tmux: server (x)) R 1 7"),
+            Some(("tmux: server (x)", 'R'))
+        );
+        assert_eq!(parse_stat("7 (a;b) R 1"), None);
+        assert_eq!(parse_stat("garbage"), None);
+    }
+
+    #[test]
+    fn folded_lists_one_line_per_stack() {
+        let profile = Profile {
+            stacks: BTreeMap::from([
+                ("make;make;sleeping".to_string(), 3),
+                ("make;make;running".to_string(), 2),
+            ]),
+            wall_clock: Duration::from_millis(50),
+        };
+        assert_eq!(
+            profile.folded(),
+            "make;make;running 2\nmake;make;sleeping 3\n"
+        );
+        assert_eq!(profile.resource_usage().samples, 5);
+        assert_eq!(profile.resource_usage().wall_clock_ms, 50);
+    }
+}
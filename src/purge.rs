@@ -0,0 +1,209 @@
+//! Implementation of the `purge` sub-command.
+//!
+//! `purge` removes finished job directories across the whole root: jobs whose
+//! `state.json` shows `exited`, `killed`, or `failed` and whose `finished_at`
+//! is at least `--older-than` seconds ago. Unlike `gc`, the age is a plain
+//! number of seconds, jobs without `finished_at` are never selected, and
+//! `--state` narrows the selection to one terminal state.
+//!
+//! Candidates are deleted oldest first. Right before each deletion
+//! `state.json` is read again (it is always replaced atomically, so a read
+//! sees either the old or the new file) and the job is skipped unless it is
+//! still eligible, so a supervisor that is still finishing up is not raced.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::gc::dir_size_bytes;
+use crate::jobstore::resolve_root;
+use crate::list::scan_job_dirs;
+use crate::run::parse_rfc3339_secs;
+use crate::schema::{JobState, JobStatus, PurgeData, Response};
+
+#[derive(Debug)]
+pub struct PurgeOpts<'a> {
+    pub root: Option<&'a str>,
+    /// Minimum age of `finished_at`, in seconds.
+    pub older_than_secs: u64,
+    /// Only purge jobs in this terminal state: exited|killed|failed.
+    pub state_filter: Option<&'a str>,
+    /// When true, report candidates without removing any directories.
+    pub dry_run: bool,
+    /// Delete at most this many directories (the oldest ones).
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Candidate {
+    job_id: String,
+    path: PathBuf,
+    finished_secs: u64,
+    bytes: u64,
+}
+
+/// Read `state.json` in `path`; `None` when missing or unreadable.
+fn read_state(path: &Path) -> Option<JobState> {
+    std::fs::read(path.join("state.json"))
+        .ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+}
+
+/// The `finished_at` of `state` in Unix seconds when the job may be purged:
+/// terminal, drained, matching `state_filter`, and finished at or before
+/// `cutoff_secs`.
+fn eligible_finish(state: &JobState, cutoff_secs: u64, state_filter: Option<&str>) -> Option<u64> {
+    let status = state.status();
+    if !matches!(
+        status,
+        JobStatus::Exited | JobStatus::Killed | JobStatus::Failed
+    ) || !state.logs_drained
+    {
+        return None;
+    }
+    if state_filter.is_some_and(|s| s != status.as_str()) {
+        return None;
+    }
+    let finished_secs = parse_rfc3339_secs(state.finished_at.as_deref()?)?;
+    (finished_secs <= cutoff_secs).then_some(finished_secs)
+}
+
+/// Execute `purge`: delete (or list) old finished job directories.
+pub fn execute(opts: PurgeOpts) -> Result<()> {
+    let root = resolve_root(opts.root);
+    let root_str = root.display().to_string();
+
+    let now_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let cutoff_secs = now_secs.saturating_sub(opts.older_than_secs);
+
+    let mut skipped_count = 0u64;
+    let mut candidates = Vec::new();
+    if root.exists() {
+        let (paths, unreadable) = scan_job_dirs(&root)?;
+        skipped_count += unreadable;
+        for path in paths {
+            let finished_secs = read_state(&path)
+                .and_then(|state| eligible_finish(&state, cutoff_secs, opts.state_filter));
+            let (Some(finished_secs), Some(job_id)) = (
+                finished_secs,
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(str::to_string),
+            ) else {
+                skipped_count += 1;
+                continue;
+            };
+            candidates.push(Candidate {
+                job_id,
+                bytes: dir_size_bytes(&path),
+                path,
+                finished_secs,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        a.finished_secs
+            .cmp(&b.finished_secs)
+            .then_with(|| a.job_id.cmp(&b.job_id))
+    });
+    if let Some(limit) = opts.limit {
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        if candidates.len() > limit {
+            skipped_count += (candidates.len() - limit) as u64;
+            candidates.truncate(limit);
+        }
+    }
+
+    let mut deleted_count = 0u64;
+    let mut freed_bytes = 0u64;
+    let mut would_delete = Vec::new();
+    for c in candidates {
+        if opts.dry_run {
+            freed_bytes = freed_bytes.saturating_add(c.bytes);
+            would_delete.push(c.job_id);
+            continue;
+        }
+
+        // Re-check right before deleting: the job may have been restarted or
+        // its supervisor may still be writing since the scan.
+        let still_eligible = read_state(&c.path)
+            .and_then(|state| eligible_finish(&state, cutoff_secs, opts.state_filter))
+            .is_some();
+        if !still_eligible {
+            debug!(job_id = %c.job_id, "purge: job changed since scan; skipping");
+            skipped_count += 1;
+            continue;
+        }
+
+        match std::fs::remove_dir_all(&c.path) {
+            Ok(()) => {
+                deleted_count += 1;
+                freed_bytes = freed_bytes.saturating_add(c.bytes);
+            }
+            Err(e) => {
+                warn!(job_id = %c.job_id, error = %e, "purge: failed to delete job directory");
+                skipped_count += 1;
+            }
+        }
+    }
+
+    info!(
+        dry_run = opts.dry_run,
+        deleted_count, skipped_count, freed_bytes, "purge complete"
+    );
+
+    Response::new(
+        "purge",
+        PurgeData {
+            root: root_str,
+            dry_run: opts.dry_run,
+            older_than_secs: opts.older_than_secs,
+            would_delete: opts.dry_run.then_some(would_delete),
+            deleted_count,
+            skipped_count,
+            freed_bytes,
+        },
+    )
+    .print();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(status: &str, finished_at: Option<&str>) -> JobState {
+        let mut v = serde_json::json!({
+            "job": { "id": "j1", "status": status },
+            "result": { "exit_code": 0, "signal": null, "duration_ms": 1 },
+            "updated_at": "2024-01-01T00:00:10Z",
+        });
+        if let Some(ts) = finished_at {
+            v["finished_at"] = ts.into();
+        }
+        serde_json::from_value(v).unwrap()
+    }
+
+    #[test]
+    fn eligible_finish_requires_terminal_old_enough_and_matching_state() {
+        let finished = parse_rfc3339_secs("2024-01-01T00:00:10Z").unwrap();
+        let exited = state("exited", Some("2024-01-01T00:00:10Z"));
+        assert_eq!(eligible_finish(&exited, finished, None), Some(finished));
+        assert_eq!(eligible_finish(&exited, finished - 1, None), None);
+        assert_eq!(
+            eligible_finish(&exited, finished, Some("exited")),
+            Some(finished)
+        );
+        assert_eq!(eligible_finish(&exited, finished, Some("failed")), None);
+
+        let running = state("running", Some("2024-01-01T00:00:10Z"));
+        assert_eq!(eligible_finish(&running, finished, None), None);
+        let no_finish = state("killed", None);
+        assert_eq!(eligible_finish(&no_finish, finished, None), None);
+    }
+}
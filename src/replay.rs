@@ -0,0 +1,192 @@
+//! Implementation of the `replay` sub-command.
+//!
+//! `replay` starts a new job from a finished job's persisted `meta.json`
+//! definition: the same command, working directory, environment, masks, and
+//! launch options, run through the same path as `run`. Unlike `restart`, the
+//! original job directory is left untouched.
+//!
+//! Environment values are taken from `meta.json.env_vars`, where masked
+//! values are already replaced by "***"; the original secret values are never
+//! read back and so are never re-submitted.
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::duration::DurationMs;
+use crate::jobstore::{JobDir, resolve_root};
+use crate::run::{RunOpts, STDIN_FIFO_NAME, StdinSource};
+use crate::schema::{JobMeta, OutputMatchStream, OutputMatchType};
+
+/// Options for the `replay` sub-command.
+#[derive(Debug)]
+pub struct ReplayOpts<'a> {
+    /// Job ID (or job directory path) of the finished job to replay.
+    pub job_id: &'a str,
+    /// Override for jobs root directory.
+    pub root: Option<&'a str>,
+    /// Maximum wait duration in seconds for inline observation.
+    pub snapshot_after: u64,
+    /// Wait for inline output observation before returning.
+    pub wait: bool,
+}
+
+/// The job to replay has not finished yet.
+#[derive(Debug)]
+pub struct JobStillRunning(pub String);
+
+impl std::fmt::Display for JobStillRunning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for JobStillRunning {}
+
+/// Execute `replay`: run a finished job's definition again as a new job.
+pub fn execute(opts: ReplayOpts) -> Result<()> {
+    let root = resolve_root(opts.root);
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
+    let state = job_dir.read_state()?;
+    if state.status().is_non_terminal() {
+        return Err(anyhow::Error::new(JobStillRunning(format!(
+            "job {} is {}; only finished jobs can be replayed",
+            job_dir.job_id,
+            state.status().as_str()
+        ))));
+    }
+
+    let meta = job_dir.read_meta()?;
+    let stdin = replay_stdin(&job_dir, &meta);
+    let root_str = root.display().to_string();
+    let run_opts = RunOpts {
+        root: Some(&root_str),
+        wait: opts.wait,
+        until_seconds: if opts.wait { opts.snapshot_after } else { 0 },
+        cwd: meta.cwd.as_deref(),
+        log: meta.full_log_file.as_deref(),
+        stdin,
+        ..replay_run_opts(&meta)
+    };
+
+    let mut response = crate::run::run_response(run_opts)?;
+    info!(
+        job_id = %response.data.job_id,
+        replayed_from = %job_dir.job_id,
+        "job replayed"
+    );
+    response.kind = "replay";
+    response.data.replayed_from = Some(job_dir.job_id.clone());
+    response.print();
+    Ok(())
+}
+
+/// The original stdin source, if it can be read again: the materialized
+/// `stdin.bin` or a direct `--stdin-file` path. A forwarding FIFO cannot.
+fn replay_stdin(job_dir: &JobDir, meta: &JobMeta) -> Option<StdinSource> {
+    let stdin_file = meta.stdin_file.as_deref()?;
+    if stdin_file == STDIN_FIFO_NAME {
+        return None;
+    }
+    let path = std::path::Path::new(stdin_file);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        job_dir.path.join(path)
+    };
+    Some(StdinSource::File(path.display().to_string()))
+}
+
+/// `run` options reproducing the definition recorded in `meta`. `command`
+/// already holds the effective command, so `--wrap-command` and `--ssh-host`
+/// are not applied a second time.
+fn replay_run_opts<'a>(meta: &JobMeta) -> RunOpts<'a> {
+    let notification = meta.notification.as_ref();
+    let output_match = notification.and_then(|n| n.on_output_match.as_ref());
+    RunOpts {
+        command: meta.command.clone(),
+        timeout_ms: DurationMs::from_millis(meta.timeout_ms),
+        kill_after_ms: DurationMs::from_millis(meta.kill_after_ms),
+        keep_going_ms: DurationMs::from_millis(meta.keep_going_ms),
+        env_vars: meta.env_vars.clone(),
+        env_files: meta.env_files.clone(),
+        inherit_env: meta.inherit_env,
+        mask: meta.mask.clone(),
+        tags: meta.tags.clone(),
+        progress_every_ms: DurationMs::from_millis(meta.progress_every_ms),
+        notify_command: notification.and_then(|n| n.notify_command.clone()),
+        notify_file: notification.and_then(|n| n.notify_file.clone()),
+        output_pattern: output_match.map(|m| m.pattern.clone()),
+        output_match_type: output_match.map(|m| {
+            match m.match_type {
+                OutputMatchType::Contains => "contains",
+                OutputMatchType::Regex => "regex",
+            }
+            .to_string()
+        }),
+        output_stream: output_match.map(|m| {
+            match m.stream {
+                OutputMatchStream::Stdout => "stdout",
+                OutputMatchStream::Stderr => "stderr",
+                OutputMatchStream::Either => "either",
+            }
+            .to_string()
+        }),
+        output_command: output_match.and_then(|m| m.command.clone()),
+        output_file: output_match.and_then(|m| m.file.clone()),
+        shell_wrapper: meta
+            .shell_wrapper
+            .clone()
+            .unwrap_or_else(crate::config::default_shell_wrapper),
+        detach: meta.detached,
+        signal_on_parent_exit: meta.signal_on_parent_exit.clone(),
+        output_encoding: meta.output_encoding.clone(),
+        snapshot_encoding: meta.output_encoding.clone(),
+        pty: meta.pty,
+        health_check: meta.health_check.clone(),
+        event_log: meta.event_log,
+        status_report: meta.status_report.clone(),
+        disable_core_dump: meta.core_dump_disabled,
+        max_output_lines_per_second: meta.max_output_lines_per_second,
+        env_expand: meta.env_expand,
+        log_buffering: meta.log_buffering,
+        priority: meta.priority,
+        nice: meta.nice.map(i32::from),
+        stdout_file: meta.stdout_file.clone(),
+        env_unset: meta.env_unset.clone(),
+        env_inherit_prefix: meta.env_inherit_prefix.clone(),
+        rlimits: meta.rlimits.clone(),
+        pid_namespace: meta.pid_namespace,
+        require_pid_namespace: meta.require_pid_namespace,
+        windows_no_job_object: !meta.windows_job_object,
+        require_clean_exit: meta.require_clean_exit,
+        snapshot_on_sigusr1: meta.snapshot_on_sigusr1,
+        max_line_length: meta.max_line_length,
+        log_rotate_size_bytes: meta.log_rotate_size_bytes,
+        log_rotate_count: meta.log_rotate_count,
+        report_file: meta.report_file.clone(),
+        profile_output: meta.profile_output.clone(),
+        checkpoint_dir: meta.checkpoint_dir.clone(),
+        checkpoint_interval_ms: meta.checkpoint_interval_ms.map_or(
+            crate::run::DEFAULT_CHECKPOINT_INTERVAL_MS,
+            DurationMs::from_millis,
+        ),
+        checkpoint_count: meta
+            .checkpoint_count
+            .unwrap_or(crate::run::DEFAULT_CHECKPOINT_COUNT),
+        completion_webhook_url: meta.completion_webhook_url.clone(),
+        completion_webhook_timeout_ms: meta.completion_webhook_timeout_ms.map_or(
+            crate::run::DEFAULT_COMPLETION_WEBHOOK_TIMEOUT_MS,
+            DurationMs::from_millis,
+        ),
+        notify_timeout_ms: meta.notify_timeout_ms.map_or(
+            crate::run::DEFAULT_NOTIFY_TIMEOUT_MS,
+            DurationMs::from_millis,
+        ),
+        notify_file_append: meta.notify_file_append,
+        notify_file_snapshot: meta.notify_file_snapshot,
+        capture_output: meta.capture_output_max_bytes.is_some(),
+        capture_output_max_bytes: meta.capture_output_max_bytes.unwrap_or(4096),
+        record_host_info: meta.hostname.is_some(),
+        ..RunOpts::default()
+    }
+}
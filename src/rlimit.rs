@@ -0,0 +1,151 @@
+//! Resource limits applied to the child by `run --ulimit`.
+//!
+//! A limit is written `NAME=SOFT:HARD` or `NAME=VALUE` (soft and hard alike),
+//! where `NAME` is one of [`RLIMIT_NAMES`] (optionally prefixed with
+//! `RLIMIT_`) and each value is a non-negative integer or `unlimited`.
+//! Specs are validated and normalized by `run`; the supervisor parses them
+//! again and calls `setrlimit(2)` in the child before exec.
+
+use anyhow::Result;
+
+/// Resource names accepted by `--ulimit`.
+pub const RLIMIT_NAMES: [&str; 7] = ["NOFILE", "NPROC", "CORE", "AS", "DATA", "STACK", "CPU"];
+
+/// Value spelling for an infinite limit.
+pub const UNLIMITED: &str = "unlimited";
+
+/// A malformed or unsupported `--ulimit` spec, rejected before any job is created.
+#[derive(Debug)]
+pub struct InvalidRlimit(pub String);
+
+impl std::fmt::Display for InvalidRlimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidRlimit {}
+
+/// A validated limit; `None` values mean unlimited.
+struct RlimitSpec {
+    name: &'static str,
+    soft: Option<u64>,
+    hard: Option<u64>,
+}
+
+fn parse_spec(s: &str) -> Result<RlimitSpec> {
+    let invalid = |reason: &str| {
+        anyhow::anyhow!(InvalidRlimit(format!(
+            "invalid_rlimit: {reason}: {s:?} (expected NAME=SOFT:HARD or NAME=VALUE)"
+        )))
+    };
+    let (name, values) = s.split_once('=').ok_or_else(|| invalid("missing '='"))?;
+    let upper = name.trim().to_ascii_uppercase();
+    let upper = upper.strip_prefix("RLIMIT_").unwrap_or(&upper);
+    let name = RLIMIT_NAMES
+        .iter()
+        .copied()
+        .find(|n| *n == upper)
+        .ok_or_else(|| invalid("unsupported resource"))?;
+    let value = |v: &str| -> Result<Option<u64>> {
+        let v = v.trim();
+        if v.eq_ignore_ascii_case(UNLIMITED) {
+            return Ok(None);
+        }
+        v.parse::<u64>()
+            .map(Some)
+            .map_err(|_| invalid("value must be a non-negative integer or 'unlimited'"))
+    };
+    let (soft, hard) = match values.split_once(':') {
+        Some((soft, hard)) => (value(soft)?, value(hard)?),
+        None => {
+            let v = value(values)?;
+            (v, v)
+        }
+    };
+    let soft_exceeds_hard = match (soft, hard) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(soft), Some(hard)) => soft > hard,
+    };
+    if soft_exceeds_hard {
+        return Err(invalid("soft limit exceeds hard limit"));
+    }
+    Ok(RlimitSpec { name, soft, hard })
+}
+
+/// Validate `s` and return its canonical `NAME=SOFT:HARD` form, as recorded
+/// in `meta.json`.
+pub fn normalize_rlimit(s: &str) -> Result<String> {
+    let spec = parse_spec(s)?;
+    let show = |v: Option<u64>| v.map_or_else(|| UNLIMITED.to_string(), |v| v.to_string());
+    Ok(format!(
+        "{}={}:{}",
+        spec.name,
+        show(spec.soft),
+        show(spec.hard)
+    ))
+}
+
+/// Parse `s` into the `(resource, soft, hard)` arguments of `setrlimit(2)`.
+#[cfg(unix)]
+pub fn parse_rlimit(s: &str) -> Result<(i32, libc::rlim_t, libc::rlim_t)> {
+    let spec = parse_spec(s)?;
+    let resource = match spec.name {
+        "NOFILE" => libc::RLIMIT_NOFILE,
+        "NPROC" => libc::RLIMIT_NPROC,
+        "CORE" => libc::RLIMIT_CORE,
+        "AS" => libc::RLIMIT_AS,
+        "DATA" => libc::RLIMIT_DATA,
+        "STACK" => libc::RLIMIT_STACK,
+        _ => libc::RLIMIT_CPU,
+    } as i32;
+    let limit = |v: Option<u64>| v.map_or(libc::RLIM_INFINITY, |v| v as libc::rlim_t);
+    Ok((resource, limit(spec.soft), limit(spec.hard)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_rlimit_accepts_pairs_single_values_and_unlimited() {
+        assert_eq!(normalize_rlimit("CORE=0:0").unwrap(), "CORE=0:0");
+        assert_eq!(normalize_rlimit("nofile=64").unwrap(), "NOFILE=64:64");
+        assert_eq!(
+            normalize_rlimit("RLIMIT_STACK=8192:unlimited").unwrap(),
+            "STACK=8192:unlimited"
+        );
+    }
+
+    #[test]
+    fn normalize_rlimit_rejects_malformed_specs() {
+        for spec in [
+            "CORE",
+            "FSIZE=1",
+            "NOFILE=-1",
+            "NOFILE=abc",
+            "NOFILE=128:64",
+            "NOFILE=unlimited:64",
+        ] {
+            let err = normalize_rlimit(spec).unwrap_err();
+            assert!(
+                err.downcast_ref::<InvalidRlimit>().is_some(),
+                "{spec}: {err}"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parse_rlimit_maps_names_and_unlimited() {
+        assert_eq!(
+            parse_rlimit("CORE=0:0").unwrap(),
+            (libc::RLIMIT_CORE as i32, 0, 0)
+        );
+        assert_eq!(
+            parse_rlimit("CPU=5:unlimited").unwrap(),
+            (libc::RLIMIT_CPU as i32, 5, libc::RLIM_INFINITY)
+        );
+    }
+}
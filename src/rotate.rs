@@ -0,0 +1,102 @@
+//! Implementation of the `rotate` sub-command.
+//!
+//! `rotate` archives an oversized log file of a job (`stdout.log`, `stderr.log`
+//! or `full.log`) via [`JobDir::rotate_log`].  It is safe to run while the job
+//! is still running: the supervisor notices the replaced log within
+//! 250 ms of further output and reopens it.
+
+use anyhow::Result;
+use tracing::debug;
+
+use crate::jobstore::{JobDir, resolve_root};
+use crate::schema::{Response, RotateData};
+
+/// Log files that may be rotated.
+pub const ROTATABLE_LOGS: [&str; 3] = ["stdout.log", "stderr.log", "full.log"];
+
+/// Options for the `rotate` sub-command.
+#[derive(Debug)]
+pub struct RotateOpts<'a> {
+    pub job_id: &'a str,
+    pub root: Option<&'a str>,
+    /// Log file name inside the job directory (one of [`ROTATABLE_LOGS`]).
+    pub log: &'a str,
+    /// Rotate only when the log is larger than this many bytes.
+    pub max_size_bytes: u64,
+    /// Number of archived generations to keep (`<log>.1` .. `<log>.N`).
+    pub archive_count: u32,
+}
+
+/// Execute `rotate`: rotate a job log if it exceeds the size limit and emit JSON.
+pub fn execute(opts: RotateOpts) -> Result<()> {
+    if !ROTATABLE_LOGS.contains(&opts.log) {
+        anyhow::bail!(
+            "unsupported log for rotate: {} (expected one of {})",
+            opts.log,
+            ROTATABLE_LOGS.join(", ")
+        );
+    }
+
+    let root = resolve_root(opts.root);
+    let job_dir = JobDir::open(&root, opts.job_id)?;
+
+    let log_path = job_dir.path.join(opts.log);
+    let size_bytes = std::fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+    let rotated = size_bytes > opts.max_size_bytes;
+
+    job_dir.rotate_log(opts.log, opts.max_size_bytes, opts.archive_count)?;
+    debug!(job_id = %job_dir.job_id, log = opts.log, size_bytes, rotated, "rotate complete");
+
+    Response::new(
+        "rotate",
+        RotateData {
+            job_id: job_dir.job_id.clone(),
+            log: opts.log.to_string(),
+            log_path: log_path.display().to_string(),
+            size_bytes,
+            max_size_bytes: opts.max_size_bytes,
+            archive_count: opts.archive_count,
+            rotated,
+        },
+    )
+    .print();
+    Ok(())
+}
+
+/// Parse a human-readable byte size: plain bytes or a `k`/`m`/`g` suffix
+/// (binary multiples, case-insensitive), e.g. `512`, `64k`, `10m`, `1g`.
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim().to_ascii_lowercase();
+    let s = s.strip_suffix('b').unwrap_or(&s);
+    let (digits, multiplier) = if let Some(n) = s.strip_suffix('k') {
+        (n, 1024)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 1024 * 1024)
+    } else if let Some(n) = s.strip_suffix('g') {
+        (n, 1024 * 1024 * 1024)
+    } else {
+        (s, 1)
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_units() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("64k"), Some(64 * 1024));
+        assert_eq!(parse_size("10m"), Some(10 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size("10MB"), Some(10 * 1024 * 1024));
+    }
+
+    #[test]
+    fn parse_size_invalid() {
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("ten"), None);
+        assert_eq!(parse_size("10x"), None);
+    }
+}
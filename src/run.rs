@@ -7,14 +7,20 @@
 //!   `status` / `wait` / `tail`.
 
 use anyhow::{Context, Result};
+use std::ffi::OsString;
 use std::io::IsTerminal;
 use std::path::Path;
 use std::process::Command;
 use tracing::{debug, info, warn};
 
-use crate::jobstore::{JobDir, generate_job_id, resolve_root};
+use crate::duration::DurationMs;
+use crate::follow::complete_utf8_prefix;
+use crate::jobstore::{
+    JobDir, cloexec_options, create_with_cloexec, generate_job_id, open_with_cloexec, resolve_root,
+};
 use crate::schema::{
-    JobMeta, JobMetaJob, JobState, JobStateJob, JobStateResult, JobStatus, Response, RunData,
+    JobMeta, JobMetaJob, JobState, JobStateJob, JobStateResult, JobStatus, LogBuffering,
+    ProcessPriority, Response, RunData, RunDryRunData, ValidatedRunOptions,
 };
 
 #[derive(Debug, Clone)]
@@ -69,9 +75,12 @@ pub struct RunOpts<'a> {
     pub max_bytes: u64,
     pub compression_mode: crate::compress::CompressionMode,
     /// Timeout in milliseconds; 0 = no timeout.
-    pub timeout_ms: u64,
-    /// Milliseconds after SIGTERM before SIGKILL; 0 = immediate SIGKILL.
-    pub kill_after_ms: u64,
+    pub timeout_ms: DurationMs,
+    /// Grace period (ms) between the timeout SIGTERM and SIGKILL; 0 = immediate SIGKILL.
+    pub kill_after_ms: DurationMs,
+    /// Extra milliseconds after the kill-after grace period before the process group
+    /// is SIGKILLed; 0 = no extra delay.
+    pub keep_going_ms: DurationMs,
     /// Working directory for the command.
     pub cwd: Option<&'a str>,
     /// Environment variables as KEY=VALUE strings.
@@ -82,6 +91,8 @@ pub struct RunOpts<'a> {
     pub inherit_env: bool,
     /// Keys to mask in JSON output (values replaced with "***").
     pub mask: Vec<String>,
+    /// Files listing further keys to mask, one per line (`#` starts a comment).
+    pub mask_files: Vec<String>,
     /// Optional stdin source definition persisted in meta and materialized into stdin.bin.
     pub stdin: Option<StdinSource>,
     /// Maximum bytes allowed for materialized stdin.bin (default: 64 MiB).
@@ -91,7 +102,7 @@ pub struct RunOpts<'a> {
     /// Override full.log path; None = use job dir.
     pub log: Option<&'a str>,
     /// Interval (ms) for state.json updated_at refresh; 0 = disabled.
-    pub progress_every_ms: u64,
+    pub progress_every_ms: DurationMs,
     /// Shell command string for command notification sink; executed via platform shell.
     /// None = no command sink.
     pub notify_command: Option<String>,
@@ -110,6 +121,142 @@ pub struct RunOpts<'a> {
     /// Resolved shell wrapper argv used to execute command strings.
     /// e.g. `["sh", "-lc"]` or `["bash", "-lc"]`.
     pub shell_wrapper: Vec<String>,
+    /// Launch the supervisor in its own session and process group so that it
+    /// survives the launching shell exiting (e.g. Ctrl+C / SIGHUP on the terminal).
+    pub detach: bool,
+    /// Signal delivered to the child if the supervisor exits before it; None = disabled.
+    pub signal_on_parent_exit: Option<String>,
+    /// Encoding label used by `tail` to decode log bytes (default: "utf-8").
+    pub output_encoding: String,
+    /// Encoding of the inline stdout/stderr snapshot: a text label or
+    /// [`crate::jobstore::BASE64_ENCODING`].
+    pub snapshot_encoding: String,
+    /// Attach the child to a pseudo-terminal so TTY-sensitive programs do not buffer output.
+    pub pty: bool,
+    /// Periodic health check that terminates the job after repeated failures.
+    pub health_check: Option<crate::schema::HealthCheckConfig>,
+    /// Record a structured event stream in `events.ndjson`.
+    pub event_log: bool,
+    /// Periodic JSON status reports written to a FIFO or file.
+    pub status_report: Option<crate::schema::StatusReportConfig>,
+    /// Keep `run` alive after printing the response and proxy caller stdin to the
+    /// child through a FIFO in the job directory (Unix only).
+    pub stdin_forward: bool,
+    /// Pass the caller's own stdin through to the child instead of `/dev/null`.
+    pub stdin_inherit: bool,
+    /// Set RLIMIT_CORE to 0 in the child so it cannot write core dumps (Unix only).
+    pub disable_core_dump: bool,
+    /// Lines per second written to each individual log; 0 = unlimited.
+    pub max_output_lines_per_second: u64,
+    /// Expand `$NAME` / `${NAME}` references between env-file and `--env` values
+    /// in dependency order.
+    pub env_expand: bool,
+    /// Return the most recent successful job with the same `command_hash` that
+    /// finished within this many hours instead of starting a new one.
+    pub deduplicate_hours: Option<u64>,
+    /// Write strategy for stdout.log / stderr.log.
+    pub log_buffering: LogBuffering,
+    /// Scheduling priority of the child process.
+    pub priority: ProcessPriority,
+    /// Nice value for the child; values outside -20..=19 are clamped with a
+    /// warning. Overrides the nice value implied by `priority`.
+    pub nice: Option<i32>,
+    /// Startup probe argv (shell wrapper + command string). When set, `run` polls
+    /// it instead of the inline output wait and reports `startup_probe_passed`.
+    pub startup_probe_command: Option<Vec<String>>,
+    /// Milliseconds between startup probe attempts.
+    pub startup_probe_interval_ms: DurationMs,
+    /// Overall deadline for the startup probe to pass.
+    pub startup_probe_timeout_ms: DurationMs,
+    /// Failed attempts before giving up early; 0 = retry until the timeout.
+    pub startup_probe_retries: u32,
+    /// Write the child's stdout directly to this file instead of stdout.log.
+    pub stdout_file: Option<String>,
+    /// Environment variable names removed from the child environment after
+    /// inheritance, env-files and `--env` are applied.
+    pub env_unset: Vec<String>,
+    /// When inheriting the environment, keep only variables whose key starts
+    /// with one of these prefixes; explicit env values always apply.
+    pub env_inherit_prefix: Vec<String>,
+    /// Resource limits (`NAME=SOFT:HARD`) applied to the child via `setrlimit`.
+    pub rlimits: Vec<String>,
+    /// Start the child as PID 1 of a new PID namespace (Linux only).
+    pub pid_namespace: bool,
+    /// Fail instead of warning when `pid_namespace` cannot be applied.
+    pub require_pid_namespace: bool,
+    /// Do not put the child in a Job Object (Windows only); for hosts that
+    /// already run inside a Job Object that forbids nesting.
+    pub windows_no_job_object: bool,
+    /// Record a non-zero exit as `failed` instead of `exited`.
+    pub require_clean_exit: bool,
+    /// Write a manual snapshot when the supervisor receives SIGUSR1 (Unix only).
+    pub snapshot_on_sigusr1: bool,
+    /// Maximum bytes per full.log line; 0 = unlimited.
+    pub max_line_length: u64,
+    /// Rotate `stdout.log` / `stderr.log` while the job runs once either
+    /// exceeds this size; 0 = never.
+    pub log_rotate_size_bytes: u64,
+    /// Archived generations kept by `log_rotate_size_bytes` rotation.
+    pub log_rotate_count: u32,
+    /// Do not launch until this path exists.
+    pub wait_for_file: Option<String>,
+    /// Give up waiting for `wait_for_file` after this many ms; 0 = no limit.
+    pub wait_for_file_timeout_ms: DurationMs,
+    /// Flat JSON object of string values merged into the environment before
+    /// the `--env` entries.
+    pub env_from_json: Option<String>,
+    /// Job ID whose recorded `env_vars` are replayed ahead of
+    /// `env_from_json` and `--env`; masked values become empty.
+    pub read_env_from_state_job_id: Option<String>,
+    /// Write a JSON summary report to this path once the job finishes.
+    pub report_file: Option<String>,
+    /// Write a folded-stack sampling profile of the child to this path once
+    /// the job finishes.
+    pub profile_output: Option<String>,
+    /// Directory receiving periodic `checkpoint-<job_id>-<ms>.json` files.
+    pub checkpoint_dir: Option<String>,
+    /// Interval between checkpoints, in milliseconds.
+    pub checkpoint_interval_ms: DurationMs,
+    /// Most periodic checkpoints kept per job; older ones are deleted.
+    pub checkpoint_count: u64,
+    /// POST the completion event as JSON to this URL once the job finishes.
+    pub completion_webhook_url: Option<String>,
+    /// Per-attempt timeout for the completion webhook request.
+    pub completion_webhook_timeout_ms: DurationMs,
+    /// Kill a `--notify-command` still running after this many milliseconds.
+    pub notify_timeout_ms: DurationMs,
+    /// Append `--notify-file` events as NDJSON lines (`false` = replace the file).
+    pub notify_file_append: bool,
+    /// Write the final state plus meta to `--notify-file` instead of the event.
+    pub notify_file_snapshot: bool,
+    /// Store the final stdout in `state.json` as `captured_stdout`.
+    pub capture_output: bool,
+    /// Most stdout bytes kept by `capture_output`; longer output keeps the tail.
+    pub capture_output_max_bytes: u64,
+    /// Report per-phase launch timings as `benchmark` in the response.
+    pub benchmark_mode: bool,
+    /// Record the machine's hostname and the invoking user in `meta.json`.
+    pub record_host_info: bool,
+    /// Shell template the command is substituted into at `{CMD}`; the result
+    /// runs through the shell wrapper.
+    pub wrap_command: Option<String>,
+    /// Run the command on this host through `ssh` instead of locally.
+    pub ssh_host: Option<String>,
+    /// Remote login name for `ssh_host`.
+    pub ssh_user: Option<String>,
+    /// Identity file passed to `ssh -i`.
+    pub ssh_key: Option<String>,
+    pub ssh_port: u16,
+    /// Pass `-o StrictHostKeyChecking=no` to `ssh`.
+    pub ssh_no_host_key_check: bool,
+    /// How long `run` waits for the supervisor to spawn the child (or record
+    /// `failed`) before responding; 0 = do not wait.
+    pub startup_timeout_ms: DurationMs,
+    /// Refuse to start when the job directories under the root already hold
+    /// more than this many bytes; 0 = unlimited.
+    pub output_quota_bytes: u64,
+    /// Validate the options and report what would run without creating a job.
+    pub dry_run: bool,
 }
 
 impl<'a> Default for RunOpts<'a> {
@@ -127,18 +274,20 @@ impl<'a> Default for RunOpts<'a> {
             forever: false,
             max_bytes: 65536,
             compression_mode: crate::compress::CompressionMode::default(),
-            timeout_ms: 0,
-            kill_after_ms: 0,
+            timeout_ms: DurationMs::ZERO,
+            kill_after_ms: DurationMs::ZERO,
+            keep_going_ms: DurationMs::ZERO,
             cwd: None,
             env_vars: vec![],
             env_files: vec![],
             inherit_env: true,
             mask: vec![],
+            mask_files: vec![],
             stdin: None,
             stdin_max_bytes: DEFAULT_STDIN_MAX_BYTES,
             tags: vec![],
             log: None,
-            progress_every_ms: 0,
+            progress_every_ms: DurationMs::ZERO,
             notify_command: None,
             notify_file: None,
             output_pattern: None,
@@ -147,6 +296,66 @@ impl<'a> Default for RunOpts<'a> {
             output_command: None,
             output_file: None,
             shell_wrapper: crate::config::default_shell_wrapper(),
+            detach: false,
+            signal_on_parent_exit: None,
+            output_encoding: crate::jobstore::DEFAULT_OUTPUT_ENCODING.to_string(),
+            snapshot_encoding: crate::jobstore::DEFAULT_OUTPUT_ENCODING.to_string(),
+            pty: false,
+            health_check: None,
+            stdin_forward: false,
+            stdin_inherit: false,
+            event_log: false,
+            status_report: None,
+            disable_core_dump: false,
+            max_output_lines_per_second: 0,
+            env_expand: false,
+            deduplicate_hours: None,
+            log_buffering: Default::default(),
+            priority: Default::default(),
+            nice: None,
+            startup_probe_command: None,
+            startup_probe_interval_ms: DurationMs::from_millis(500),
+            startup_probe_timeout_ms: DurationMs::from_millis(30_000),
+            startup_probe_retries: 0,
+            stdout_file: None,
+            env_unset: vec![],
+            env_inherit_prefix: vec![],
+            rlimits: vec![],
+            pid_namespace: false,
+            require_pid_namespace: false,
+            windows_no_job_object: false,
+            require_clean_exit: false,
+            snapshot_on_sigusr1: false,
+            max_line_length: 0,
+            log_rotate_size_bytes: 0,
+            log_rotate_count: DEFAULT_LOG_ROTATE_COUNT,
+            wait_for_file: None,
+            wait_for_file_timeout_ms: DurationMs::from_millis(60_000),
+            env_from_json: None,
+            read_env_from_state_job_id: None,
+            report_file: None,
+            profile_output: None,
+            checkpoint_dir: None,
+            checkpoint_interval_ms: DEFAULT_CHECKPOINT_INTERVAL_MS,
+            checkpoint_count: DEFAULT_CHECKPOINT_COUNT,
+            completion_webhook_url: None,
+            completion_webhook_timeout_ms: DEFAULT_COMPLETION_WEBHOOK_TIMEOUT_MS,
+            notify_timeout_ms: DEFAULT_NOTIFY_TIMEOUT_MS,
+            notify_file_append: true,
+            notify_file_snapshot: false,
+            capture_output: false,
+            capture_output_max_bytes: 4096,
+            benchmark_mode: false,
+            record_host_info: false,
+            wrap_command: None,
+            ssh_host: None,
+            ssh_user: None,
+            ssh_key: None,
+            ssh_port: 22,
+            ssh_no_host_key_check: false,
+            startup_timeout_ms: DEFAULT_STARTUP_TIMEOUT_MS,
+            output_quota_bytes: 0,
+            dry_run: false,
         }
     }
 }
@@ -165,19 +374,59 @@ pub struct SpawnSupervisorParams {
     pub job_id: String,
     pub root: std::path::PathBuf,
     pub full_log_path: String,
-    pub timeout_ms: u64,
-    pub kill_after_ms: u64,
+    /// `meta.created_at`, recorded in the initial `state.json`.
+    pub created_at: String,
+    pub timeout_ms: DurationMs,
+    pub kill_after_ms: DurationMs,
+    pub keep_going_ms: DurationMs,
     pub cwd: Option<String>,
     /// Real (unmasked) KEY=VALUE env var pairs.
     pub env_vars: Vec<String>,
     pub env_files: Vec<String>,
     pub inherit_env: bool,
     pub stdin_file: Option<String>,
-    pub progress_every_ms: u64,
+    /// Hand the caller's stdin to the supervisor, which passes it to the child.
+    pub stdin_inherit: bool,
+    pub progress_every_ms: DurationMs,
     pub notify_command: Option<String>,
     pub notify_file: Option<String>,
     pub shell_wrapper: Vec<String>,
     pub command: Vec<String>,
+    /// Place the supervisor in its own session/process group so it survives the
+    /// launching terminal (`run --detach`).
+    pub detach: bool,
+    pub signal_on_parent_exit: Option<String>,
+    pub pty: bool,
+    pub health_check: Option<crate::schema::HealthCheckConfig>,
+    pub event_log: bool,
+    pub status_report: Option<crate::schema::StatusReportConfig>,
+    pub disable_core_dump: bool,
+    pub max_output_lines_per_second: u64,
+    pub env_expand: bool,
+    pub log_buffering: LogBuffering,
+    pub priority: ProcessPriority,
+    pub nice: Option<i8>,
+    pub stdout_file: Option<String>,
+    pub env_unset: Vec<String>,
+    pub env_inherit_prefix: Vec<String>,
+    pub rlimits: Vec<String>,
+    pub pid_namespace: bool,
+    pub require_pid_namespace: bool,
+    pub windows_no_job_object: bool,
+    pub require_clean_exit: bool,
+    pub snapshot_on_sigusr1: bool,
+    pub max_line_length: u64,
+    pub log_rotate_size_bytes: u64,
+    pub log_rotate_count: u32,
+    pub report_file: Option<String>,
+    pub profile_output: Option<String>,
+    pub checkpoint_dir: Option<String>,
+    pub checkpoint_interval_ms: DurationMs,
+    pub checkpoint_count: u64,
+    pub completion_webhook_url: Option<String>,
+    pub completion_webhook_timeout_ms: DurationMs,
+    /// Byte limit for `--capture-output`; `None` disables capturing.
+    pub capture_output_max_bytes: Option<u64>,
 }
 
 pub fn resolve_stdin_source(
@@ -212,7 +461,7 @@ fn create_stdin_file(path: &std::path::Path) -> Result<std::fs::File> {
     #[cfg(unix)]
     {
         use std::os::unix::fs::OpenOptionsExt;
-        std::fs::OpenOptions::new()
+        cloexec_options()
             .write(true)
             .create(true)
             .truncate(true)
@@ -222,7 +471,7 @@ fn create_stdin_file(path: &std::path::Path) -> Result<std::fs::File> {
     }
     #[cfg(not(unix))]
     {
-        std::fs::File::create(path)
+        create_with_cloexec(path)
             .with_context(|| format!("create materialized stdin {}", path.display()))
     }
 }
@@ -279,7 +528,7 @@ fn materialize_stdin(
                 .context("write inline stdin to stdin.bin")
         }
         StdinSource::File(path) => {
-            let mut input = std::fs::File::open(path)
+            let mut input = open_with_cloexec(Path::new(path))
                 .with_context(|| format!("open --stdin-file source {}", path))?;
             std::io::copy(&mut input, &mut target)
                 .with_context(|| format!("copy --stdin-file source {} to stdin.bin", path))
@@ -336,18 +585,152 @@ impl std::fmt::Display for StdinTooLarge {
 
 impl std::error::Error for StdinTooLarge {}
 
-pub fn open_child_stdin(job_dir: &JobDir, stdin_file: Option<&str>) -> Result<std::process::Stdio> {
-    if let Some(path) = resolve_stdin_path(job_dir, stdin_file) {
-        let file = std::fs::File::open(&path)
-            .with_context(|| format!("open materialized stdin {}", path.display()))?;
-        Ok(std::process::Stdio::from(file))
-    } else {
-        Ok(std::process::Stdio::null())
+#[derive(Debug)]
+pub struct QuotaExceeded(pub String);
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// A `--mask-from-file` path that does not exist, rejected before any job is
+/// created.
+#[derive(Debug)]
+pub struct MaskFileNotFound(pub String);
+
+impl std::fmt::Display for MaskFileNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for MaskFileNotFound {}
+
+/// A `run --stdin-file` path that does not exist, rejected before any job is
+/// created.
+#[derive(Debug)]
+pub struct StdinFileNotFound(pub String);
+
+impl std::fmt::Display for StdinFileNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for StdinFileNotFound {}
+
+/// Absolute path of a `run --stdin-file` source. The supervisor opens it and
+/// hands the handle to the child, so the file is never copied and a FIFO is
+/// read until its writer closes it.
+fn resolve_direct_stdin_file(path: &str) -> Result<String> {
+    match std::fs::canonicalize(path) {
+        Ok(path) => Ok(path.display().to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(anyhow::Error::new(
+            StdinFileNotFound(format!("stdin file not found: {path}")),
+        )),
+        Err(e) => Err(e).with_context(|| format!("resolve --stdin-file {path}")),
+    }
+}
+
+/// Read the key names listed in a `--mask-from-file` file: one per line,
+/// blank lines and `#` comments ignored.
+fn read_mask_file(path: &str) -> Result<Vec<String>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow::Error::new(MaskFileNotFound(format!(
+                "mask file not found: {path}"
+            ))));
+        }
+        Err(e) => return Err(e).with_context(|| format!("read mask file {path}")),
+    };
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Fail with [`QuotaExceeded`] when the job directories under `root` hold
+/// more than `quota_bytes` in total. Best-effort: entries that are not
+/// readable job directories are skipped.
+fn check_output_quota(root: &std::path::Path, quota_bytes: u64) -> Result<()> {
+    if quota_bytes == 0 {
+        return Ok(());
+    }
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Ok(());
+    };
+    let used: u64 = entries
+        .flatten()
+        .filter_map(|entry| JobDir::from_path(&entry.path()).ok())
+        .map(|job_dir| job_dir.total_dir_bytes())
+        .sum();
+    if used > quota_bytes {
+        return Err(anyhow::Error::new(QuotaExceeded(format!(
+            "jobs under {} use {used} bytes, over the output quota of {quota_bytes} bytes",
+            root.display()
+        ))));
+    }
+    Ok(())
+}
+
+pub fn open_child_stdin(job_dir: &JobDir, stdin_mode: &StdinMode) -> Result<std::process::Stdio> {
+    match stdin_mode {
+        StdinMode::Null => Ok(std::process::Stdio::null()),
+        StdinMode::Inherit => Ok(std::process::Stdio::inherit()),
+        StdinMode::File(stdin_file) => {
+            let path = resolve_stdin_path(job_dir, Some(stdin_file))
+                .expect("stdin path resolves for Some");
+            let file = open_with_cloexec(&path)
+                .with_context(|| format!("open stdin file {}", path.display()))?;
+            Ok(std::process::Stdio::from(file))
+        }
     }
 }
 
+/// How the supervised child's stdin is connected.
+///
+/// Inline `--stdin` data and `--stdin -` are materialized into `stdin.bin`
+/// before the supervisor starts, and `run --stdin-file` passes its source
+/// path through, so all of them arrive here as [`StdinMode::File`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StdinMode {
+    /// `/dev/null` (the default; `run --stdin-null` spells it out).
+    #[default]
+    Null,
+    /// The supervisor's own stdin, which `run --stdin-inherit` connects to the
+    /// caller's stdin.
+    Inherit,
+    /// A materialized stdin file, a `run --stdin-file` source, or the
+    /// `--stdin-forward` FIFO, relative to the job directory or absolute.
+    File(String),
+}
+
 pub const DEFAULT_STDIN_MAX_BYTES: u64 = 64 * 1024 * 1024; // 64 MiB
 
+/// Default `--checkpoint-interval-ms`.
+pub const DEFAULT_CHECKPOINT_INTERVAL_MS: DurationMs = DurationMs::from_millis(5000);
+
+/// Default `--checkpoint-count`.
+pub const DEFAULT_CHECKPOINT_COUNT: u64 = 5;
+
+/// Default `--completion-webhook-timeout-ms`.
+pub const DEFAULT_COMPLETION_WEBHOOK_TIMEOUT_MS: DurationMs = DurationMs::from_millis(10_000);
+
+/// Default deadline for a `--notify-command` (and output-match command) to exit.
+pub const DEFAULT_NOTIFY_TIMEOUT_MS: DurationMs = DurationMs::from_millis(30_000);
+
+/// Default `--log-rotate-count`.
+pub const DEFAULT_LOG_ROTATE_COUNT: u32 = 5;
+
+/// Default `--startup-timeout-ms`.
+pub const DEFAULT_STARTUP_TIMEOUT_MS: DurationMs = DurationMs::from_millis(5000);
+
 pub fn materialize_stdin_for_job(
     job_dir: &JobDir,
     stdin: Option<&StdinSource>,
@@ -377,18 +760,23 @@ pub fn spawn_supervisor_process(
         .arg("--full-log")
         .arg(&params.full_log_path);
 
-    if params.timeout_ms > 0 {
-        let timeout_seconds = params.timeout_ms.saturating_add(999) / 1000;
+    if !params.timeout_ms.is_zero() {
+        let timeout_seconds = params.timeout_ms.as_millis().saturating_add(999) / 1000;
         supervisor_cmd
             .arg("--timeout")
             .arg(timeout_seconds.to_string());
     }
-    if params.kill_after_ms > 0 {
-        let kill_after_seconds = params.kill_after_ms.saturating_add(999) / 1000;
+    if !params.kill_after_ms.is_zero() {
+        let kill_after_seconds = params.kill_after_ms.as_millis().saturating_add(999) / 1000;
         supervisor_cmd
             .arg("--kill-after")
             .arg(kill_after_seconds.to_string());
     }
+    if !params.keep_going_ms.is_zero() {
+        supervisor_cmd
+            .arg("--keep-going")
+            .arg(params.keep_going_ms.to_string());
+    }
     if let Some(ref cwd) = params.cwd {
         supervisor_cmd.arg("--cwd").arg(cwd);
     }
@@ -401,11 +789,15 @@ pub fn spawn_supervisor_process(
     if !params.inherit_env {
         supervisor_cmd.arg("--no-inherit-env");
     }
+    if params.stdin_inherit {
+        supervisor_cmd.arg("--stdin-inherit");
+    }
     if let Some(ref stdin_file) = params.stdin_file {
         supervisor_cmd.arg("--stdin-file").arg(stdin_file);
     }
-    if params.progress_every_ms > 0 {
-        let progress_every_seconds = params.progress_every_ms.saturating_add(999) / 1000;
+    if !params.progress_every_ms.is_zero() {
+        let progress_every_seconds =
+            params.progress_every_ms.as_millis().saturating_add(999) / 1000;
         supervisor_cmd
             .arg("--progress-every")
             .arg(progress_every_seconds.to_string());
@@ -416,6 +808,122 @@ pub fn spawn_supervisor_process(
     if let Some(ref nf) = params.notify_file {
         supervisor_cmd.arg("--notify-file").arg(nf);
     }
+    if let Some(ref sig) = params.signal_on_parent_exit {
+        supervisor_cmd.arg("--signal-on-parent-exit").arg(sig);
+    }
+    if params.pty {
+        supervisor_cmd.arg("--pty");
+    }
+    if params.event_log {
+        supervisor_cmd.arg("--event-log");
+    }
+    if params.disable_core_dump {
+        supervisor_cmd.arg("--disable-core-dump");
+    }
+    if params.env_expand {
+        supervisor_cmd.arg("--env-expand");
+    }
+    if let Some(ref report_file) = params.report_file {
+        supervisor_cmd.arg("--report-file").arg(report_file);
+    }
+    if let Some(ref profile_output) = params.profile_output {
+        supervisor_cmd.arg("--profile-output").arg(profile_output);
+    }
+    if let Some(ref checkpoint_dir) = params.checkpoint_dir {
+        supervisor_cmd
+            .arg("--checkpoint-dir")
+            .arg(checkpoint_dir)
+            .arg("--checkpoint-interval-ms")
+            .arg(params.checkpoint_interval_ms.to_string())
+            .arg("--checkpoint-count")
+            .arg(params.checkpoint_count.to_string());
+    }
+    if let Some(ref url) = params.completion_webhook_url {
+        supervisor_cmd
+            .arg("--completion-webhook")
+            .arg(url)
+            .arg("--completion-webhook-timeout-ms")
+            .arg(params.completion_webhook_timeout_ms.to_string());
+    }
+    if let Some(max_bytes) = params.capture_output_max_bytes {
+        supervisor_cmd
+            .arg("--capture-output")
+            .arg("--capture-output-max-bytes")
+            .arg(max_bytes.to_string());
+    }
+    if params.max_line_length > 0 {
+        supervisor_cmd
+            .arg("--max-line-length")
+            .arg(params.max_line_length.to_string());
+    }
+    if params.log_rotate_size_bytes > 0 {
+        supervisor_cmd
+            .arg("--log-rotate-size-bytes")
+            .arg(params.log_rotate_size_bytes.to_string())
+            .arg("--log-rotate-count")
+            .arg(params.log_rotate_count.to_string());
+    }
+    for key in &params.env_unset {
+        supervisor_cmd.arg("--env-unset").arg(key);
+    }
+    for prefix in &params.env_inherit_prefix {
+        supervisor_cmd.arg("--env-prefix").arg(prefix);
+    }
+    for limit in &params.rlimits {
+        supervisor_cmd.arg("--ulimit").arg(limit);
+    }
+    if params.pid_namespace {
+        supervisor_cmd.arg("--pid-namespace");
+    }
+    if params.require_pid_namespace {
+        supervisor_cmd.arg("--require-pid-namespace");
+    }
+    if params.windows_no_job_object {
+        supervisor_cmd.arg("--windows-no-job-object");
+    }
+    if params.require_clean_exit {
+        supervisor_cmd.arg("--require-clean-exit");
+    }
+    if params.snapshot_on_sigusr1 {
+        supervisor_cmd.arg("--snapshot-on-sigusr1");
+    }
+    if let Some(ref stdout_file) = params.stdout_file {
+        supervisor_cmd.arg("--stdout-file").arg(stdout_file);
+    }
+    if params.log_buffering != LogBuffering::None {
+        supervisor_cmd
+            .arg("--log-buffering")
+            .arg(params.log_buffering.as_str());
+    }
+    if params.priority != ProcessPriority::Normal {
+        supervisor_cmd
+            .arg("--priority")
+            .arg(params.priority.as_str());
+    }
+    if let Some(nice) = params.nice {
+        supervisor_cmd.arg(format!("--nice={nice}"));
+    }
+    if params.max_output_lines_per_second > 0 {
+        supervisor_cmd
+            .arg("--max-output-lines-per-second")
+            .arg(params.max_output_lines_per_second.to_string());
+    }
+    if let Some(ref report) = params.status_report {
+        supervisor_cmd
+            .arg("--report-pipe")
+            .arg(&report.path)
+            .arg("--report-interval-ms")
+            .arg(report.interval_ms.to_string());
+    }
+    if let Some(ref hc) = params.health_check {
+        supervisor_cmd
+            .arg("--health-check")
+            .arg(&hc.command)
+            .arg("--health-check-interval-ms")
+            .arg(hc.interval_ms.to_string())
+            .arg("--health-check-retries")
+            .arg(hc.retries.to_string());
+    }
     let wrapper_json =
         serde_json::to_string(&params.shell_wrapper).context("serialize shell wrapper")?;
     supervisor_cmd
@@ -425,16 +933,29 @@ pub fn spawn_supervisor_process(
     supervisor_cmd
         .arg("--")
         .args(&params.command)
-        .stdin(std::process::Stdio::null())
+        .stdin(if params.stdin_inherit {
+            std::process::Stdio::inherit()
+        } else {
+            std::process::Stdio::null()
+        })
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null());
 
+    if params.detach {
+        detach_supervisor_command(&mut supervisor_cmd);
+    }
+
     let supervisor = supervisor_cmd.spawn().context("spawn supervisor")?;
     let supervisor_pid = supervisor.id();
     debug!(supervisor_pid, "supervisor spawned");
 
     // Write initial running state.
-    job_dir.init_state(supervisor_pid, &started_at)?;
+    job_dir.init_state(
+        supervisor_pid,
+        &params.created_at,
+        &started_at,
+        !params.windows_no_job_object,
+    )?;
 
     // Windows Job Object handshake.
     #[cfg(windows)]
@@ -469,6 +990,255 @@ pub fn spawn_supervisor_process(
     Ok((supervisor_pid, started_at))
 }
 
+/// Make the calling thread's future children start in a new PID namespace.
+///
+/// Returns a handle on the thread's current namespace for
+/// [`leave_pid_namespace`]. Needs `CAP_SYS_ADMIN`.
+#[cfg(target_os = "linux")]
+fn enter_pid_namespace() -> std::io::Result<std::fs::File> {
+    let previous = cloexec_options()
+        .read(true)
+        .open("/proc/thread-self/ns/pid")?;
+    // SAFETY: unshare takes no pointers; CLONE_NEWPID only affects processes
+    // this thread forks afterwards.
+    if unsafe { libc::unshare(libc::CLONE_NEWPID) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(previous)
+}
+
+/// Return the calling thread's future children to the namespace saved by
+/// [`enter_pid_namespace`].
+#[cfg(target_os = "linux")]
+fn leave_pid_namespace(previous: &std::fs::File) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: the descriptor is a valid, open namespace file.
+    if unsafe { libc::setns(previous.as_raw_fd(), libc::CLONE_NEWPID) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Wait up to `timeout_ms` for the supervisor to replace its own PID in
+/// `state.json` with the child's, or to leave the `running` state (a failed
+/// spawn), so the `run` response does not report `running` for a job that
+/// never started.
+fn wait_for_child_spawn(job_dir: &JobDir, supervisor_pid: u32, timeout_ms: DurationMs) {
+    let deadline = std::time::Instant::now() + timeout_ms.as_duration();
+    while std::time::Instant::now() < deadline {
+        if let Ok(state) = job_dir.read_state()
+            && (state.pid != Some(supervisor_pid) || *state.status() != JobStatus::Running)
+        {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    debug!(
+        timeout_ms = timeout_ms.as_millis(),
+        "child spawn not confirmed before --startup-timeout-ms"
+    );
+}
+
+/// Set by the supervisor's SIGUSR1 handler (`--snapshot-on-sigusr1`) and
+/// cleared by the watcher once it has written the snapshot.
+#[cfg(unix)]
+static SNAPSHOT_REQUESTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn request_snapshot(_signum: libc::c_int) {
+    SNAPSHOT_REQUESTED.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Route SIGUSR1 to [`request_snapshot`]. The child does not inherit the
+/// handler: exec resets caught signals to their default action.
+#[cfg(unix)]
+fn install_snapshot_signal_handler() -> std::io::Result<()> {
+    let handler: extern "C" fn(libc::c_int) = request_snapshot;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    if unsafe { libc::signal(libc::SIGUSR1, handler as libc::sighandler_t) } == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Best-effort: record a job that could not be launched as `failed`.
+fn mark_job_failed(job_dir: &JobDir) {
+    if let Ok(mut state) = job_dir.read_state() {
+        let now = now_rfc3339();
+        state.job.status = JobStatus::Failed;
+        state.finished_at = Some(now.clone());
+        state.updated_at = now;
+        let _ = job_dir.write_state(&state);
+    }
+}
+
+/// Signal names understood by [`signal_number`], as listed in `--help`.
+pub const SIGNAL_NAMES: &[&str] = &[
+    "TERM", "INT", "KILL", "HUP", "QUIT", "USR1", "USR2", "STOP", "CONT",
+];
+
+/// Map a signal name (`TERM`, `SIGTERM`, ...) to its POSIX signal number.
+///
+/// This is the only signal-name table: `kill --signal` and
+/// `--signal-on-parent-exit` both resolve names through it.
+#[cfg(unix)]
+pub fn signal_number(name: &str) -> Option<i32> {
+    let upper = name.to_ascii_uppercase();
+    match upper.strip_prefix("SIG").unwrap_or(&upper) {
+        "TERM" => Some(libc::SIGTERM),
+        "INT" => Some(libc::SIGINT),
+        "KILL" => Some(libc::SIGKILL),
+        "HUP" => Some(libc::SIGHUP),
+        "QUIT" => Some(libc::SIGQUIT),
+        "USR1" => Some(libc::SIGUSR1),
+        "USR2" => Some(libc::SIGUSR2),
+        "STOP" => Some(libc::SIGSTOP),
+        "CONT" => Some(libc::SIGCONT),
+        _ => None,
+    }
+}
+
+/// Configure the supervisor command to run in its own session and process group.
+///
+/// On Unix the supervisor calls `setsid(2)` before exec, which makes it the leader
+/// of a new session and a new process group; terminal signals (SIGINT/SIGHUP)
+/// delivered to the launching shell's process group no longer reach it.
+/// `process_group(0)` is not combined with `setsid` because a process group
+/// leader cannot create a new session; it is only used as a fallback when
+/// `setsid` fails.
+/// On Windows the supervisor is created with `CREATE_NEW_PROCESS_GROUP`.
+fn detach_supervisor_command(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid/setpgid are async-signal-safe and called before exec.
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 && libc::setpgid(0, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+}
+
+/// Lines of each stream included in a `--report-file` summary.
+const REPORT_TAIL_LINES: u64 = 20;
+
+/// Summarize a finished job for `--report-file`.
+fn build_job_report(
+    job_dir: &JobDir,
+    meta: &JobMeta,
+    state: &crate::schema::JobState,
+) -> crate::schema::JobReport {
+    let tail = |name: &str| {
+        job_dir.read_tail_metrics(name, REPORT_TAIL_LINES, u64::MAX, &meta.output_encoding)
+    };
+    let stdout = tail("stdout.log");
+    let stderr = tail("stderr.log");
+    crate::schema::JobReport {
+        job_id: job_dir.job_id.clone(),
+        command: meta.command.clone(),
+        cwd: meta.cwd.clone(),
+        exit_code: state.result.exit_code,
+        signal: state.result.signal.clone(),
+        started_at: state.job.started_at.clone().unwrap_or_default(),
+        finished_at: state.finished_at.clone().unwrap_or_default(),
+        duration_ms: state.result.duration_ms.unwrap_or_default(),
+        stdout_observed_bytes: stdout.observed_bytes,
+        stderr_observed_bytes: stderr.observed_bytes,
+        stdout_tail: stdout.tail,
+        stderr_tail: stderr.tail,
+    }
+}
+
+/// Atomically write `report` as pretty JSON to `path`.
+fn write_job_report(path: &std::path::Path, report: &crate::schema::JobReport) -> Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    let contents = serde_json::to_string_pretty(report)?;
+    crate::jobstore::write_atomic(dir, path, contents.as_bytes())
+}
+
+/// Whether `key` starts with one of `prefixes`; an empty list matches every key.
+fn has_env_prefix(key: &str, prefixes: &[String]) -> bool {
+    prefixes.is_empty() || prefixes.iter().any(|p| key.starts_with(p.as_str()))
+}
+
+/// Keep the inherited variables whose key matches `--env-prefix`.
+pub fn filter_inherited_env<I>(parent: I, prefixes: &[String]) -> Vec<(OsString, OsString)>
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    parent
+        .into_iter()
+        .filter(|(k, _)| has_env_prefix(&k.to_string_lossy(), prefixes))
+        .collect()
+}
+
+/// Apply the job environment to `cmd`: the inherited environment (narrowed by
+/// `--env-prefix`, or dropped by `--no-inherit-env`), then env files and `--env`
+/// values, then `--env-unset`. The child, its health check and the startup probe
+/// all go through here so they see the same variables.
+fn apply_job_env(
+    cmd: &mut Command,
+    inherit_env: bool,
+    inherit_prefixes: &[String],
+    env_files: &[String],
+    env_vars: &[String],
+    env_expand: bool,
+    env_unset: &[String],
+) -> Result<()> {
+    if !inherit_env {
+        cmd.env_clear();
+    } else if !inherit_prefixes.is_empty() {
+        cmd.env_clear()
+            .envs(filter_inherited_env(std::env::vars_os(), inherit_prefixes));
+    }
+
+    // Apply env files in order, then --env KEY=VALUE overrides (applied after
+    // env-files). With --env-expand, references between them are resolved first;
+    // names they do not define fall back to the inherited environment.
+    let mut env_overrides = collect_env_vars(env_files, env_vars)?;
+    if env_expand {
+        env_overrides = expand_env_vars(&env_overrides, &|name| {
+            (inherit_env && has_env_prefix(name, inherit_prefixes))
+                .then(|| std::env::var(name).ok())
+                .flatten()
+        })?;
+    }
+    for (k, v) in env_overrides {
+        cmd.env(&k, &v);
+    }
+    // --env-unset wins over inherited, env-file and --env values alike.
+    for key in env_unset {
+        cmd.env_remove(key);
+    }
+    Ok(())
+}
+
+/// Best-effort `sync_all` of finished log files; missing files are skipped.
+fn sync_log_files(paths: &[std::path::PathBuf]) {
+    for path in paths {
+        if let Ok(file) = open_with_cloexec(path)
+            && let Err(e) = file.sync_all()
+        {
+            warn!(path = %path.display(), error = %e, "failed to sync log file");
+        }
+    }
+}
+
 /// Pre-create empty log files (stdout.log, stderr.log, full.log) so they exist
 /// immediately after job creation, before the supervisor starts writing.
 pub fn pre_create_log_files(job_dir: &JobDir) -> Result<()> {
@@ -477,7 +1247,7 @@ pub fn pre_create_log_files(job_dir: &JobDir) -> Result<()> {
         job_dir.stderr_path(),
         job_dir.full_log_path(),
     ] {
-        std::fs::OpenOptions::new()
+        cloexec_options()
             .create(true)
             .append(true)
             .open(&log_path)
@@ -486,22 +1256,298 @@ pub fn pre_create_log_files(job_dir: &JobDir) -> Result<()> {
     Ok(())
 }
 
+/// Absolute path of the job's stdout log: the `--stdout-file` target when one
+/// is configured, otherwise stdout.log in the job directory.
+pub fn stdout_log_path(job_dir: &JobDir, meta: &JobMeta) -> String {
+    match meta.stdout_file {
+        Some(ref path) => path.clone(),
+        None => job_dir.stdout_path().display().to_string(),
+    }
+}
+
+/// Path of the job's combined log: the `--log` target when one is
+/// configured, otherwise full.log in the job directory.
+pub fn full_log_path(job_dir: &JobDir, meta: &JobMeta) -> std::path::PathBuf {
+    match meta.full_log_file {
+        Some(ref path) => std::path::PathBuf::from(path),
+        None => job_dir.full_log_path(),
+    }
+}
+
+/// Create (truncating) the `--stdout-file` target for the child's stdout.
+///
+/// On Unix, stdout.log in the job directory is replaced by a symlink to the
+/// file so `tail`, `wait` and inline snapshots keep reading the output.
+fn open_stdout_file(job_dir: &JobDir, path: &str) -> Result<std::fs::File> {
+    let file =
+        create_with_cloexec(Path::new(path)).with_context(|| format!("open stdout file {path}"))?;
+    #[cfg(unix)]
+    {
+        let link = job_dir.stdout_path();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(path, &link)
+            .with_context(|| format!("link {} to stdout file {path}", link.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = job_dir;
+    Ok(file)
+}
+
 /// Execute `run`: spawn job and return launch metadata immediately.
 pub fn execute(opts: RunOpts) -> Result<()> {
-    run_response(opts)?.print();
+    let args: Vec<String> = std::env::args().collect();
+    let stdin_forward = opts.stdin_forward;
+    let root = resolve_root(opts.root);
+    let (mut response, mask_keys) = match run_outcome(opts)? {
+        RunOutcome::Job { response, mask } => (*response, mask),
+        RunOutcome::DryRun(response) => {
+            response.print();
+            return Ok(());
+        }
+    };
+    let (args, args_masked) = mask_invocation_args(&args, &mask_keys);
+    response.data.args = args;
+    response.data.args_masked = args_masked;
+    response.print();
+    if stdin_forward {
+        let job_dir = JobDir::open(&root, &response.data.job_id)?;
+        forward_stdin(&job_dir)?;
+    }
+    Ok(())
+}
+
+/// File name of the FIFO that carries forwarded stdin (`run --stdin-forward`).
+pub const STDIN_FIFO_NAME: &str = "stdin.fifo";
+
+/// Create the stdin FIFO inside the job directory and return its name relative
+/// to the job directory, suitable for `--stdin-file`.
+#[cfg(unix)]
+fn create_stdin_fifo(job_dir: &JobDir) -> Result<String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = job_dir.path.join(STDIN_FIFO_NAME);
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .context("stdin FIFO path contains NUL")?;
+    // SAFETY: c_path is a valid NUL-terminated path.
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("create stdin FIFO {}", path.display()));
+    }
+    Ok(STDIN_FIFO_NAME.to_string())
+}
+
+#[cfg(not(unix))]
+fn create_stdin_fifo(_job_dir: &JobDir) -> Result<String> {
+    anyhow::bail!("--stdin-forward is only supported on Unix")
+}
+
+/// Proxy caller stdin into the job's stdin FIFO until stdin reaches EOF or the
+/// job reaches a terminal state.
+///
+/// Closing the write end on EOF lets the child observe end-of-input.
+#[cfg(unix)]
+fn forward_stdin(job_dir: &JobDir) -> Result<()> {
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let fifo_path = job_dir.path.join(STDIN_FIFO_NAME);
+    let poll = std::time::Duration::from_millis(100);
+    let job_finished = || {
+        job_dir
+            .read_state()
+            .map(|s| !s.status().is_non_terminal())
+            .unwrap_or(false)
+    };
+
+    // A non-blocking open of the write end fails with ENXIO until the supervisor
+    // has opened the read end; retry so a supervisor that dies early cannot hang us.
+    let mut fifo = loop {
+        match cloexec_options()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(&fifo_path)
+        {
+            Ok(f) => break f,
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                if job_finished() {
+                    return Ok(());
+                }
+                std::thread::sleep(poll);
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("open {}", fifo_path.display()));
+            }
+        }
+    };
+    // Switch back to blocking writes so a slow reader applies back-pressure.
+    // SAFETY: fcntl on a descriptor owned by `fifo`.
+    unsafe {
+        let fd = fifo.as_raw_fd();
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK);
+    }
+
+    // Read stdin on a helper thread so the job state can be polled while the
+    // caller is idle; the thread is abandoned when `run` exits.
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<u8>>(4);
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
+        let mut buf = vec![0u8; 8192];
+        while let Ok(n @ 1..) = stdin.read(&mut buf) {
+            if tx.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        match rx.recv_timeout(poll) {
+            Ok(chunk) => {
+                // EPIPE: the child closed its stdin or exited.
+                if fifo.write_all(&chunk).is_err() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if job_finished() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn forward_stdin(_job_dir: &JobDir) -> Result<()> {
     Ok(())
 }
 
 pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
+    match run_outcome(opts)? {
+        RunOutcome::Job { response, .. } => Ok(*response),
+        RunOutcome::DryRun(_) => anyhow::bail!("run_response does not support dry_run"),
+    }
+}
+
+/// Result of [`run_outcome`]: a started (or deduplicated) job, or the options
+/// `run --dry-run` validated.
+pub enum RunOutcome {
+    /// `mask` is the merged `--mask`/`--mask-from-file` list recorded in
+    /// `meta.mask`.
+    Job {
+        response: Box<Response<RunData>>,
+        mask: Vec<String>,
+    },
+    DryRun(Box<Response<RunDryRunData>>),
+}
+
+/// A `run --dry-run` working directory that does not exist.
+#[derive(Debug)]
+pub struct CwdNotFound(pub String);
+
+impl std::fmt::Display for CwdNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CwdNotFound {}
+
+pub fn run_outcome(mut opts: RunOpts) -> Result<RunOutcome> {
     if opts.command.is_empty() {
         anyhow::bail!("no command specified for run");
     }
+    let original_command = opts.wrap_command.as_deref().map(|template| {
+        let wrapped = wrap_command(template, &opts.command);
+        std::mem::replace(&mut opts.command, vec![wrapped])
+    });
+    if let Some(host) = opts.ssh_host.as_deref() {
+        opts.command = ssh_command(
+            host,
+            opts.ssh_user.as_deref(),
+            opts.ssh_key.as_deref(),
+            opts.ssh_port,
+            opts.ssh_no_host_key_check,
+            std::mem::take(&mut opts.command),
+        );
+    }
+    // Keys from --mask-from-file join --mask; the merged list is what masks
+    // values and what meta.json records.
+    for path in &opts.mask_files {
+        for key in read_mask_file(path)? {
+            if !opts.mask.contains(&key) {
+                opts.mask.push(key);
+            }
+        }
+    }
+    // --env-from-json entries become ordinary KEY=VALUE pairs placed ahead of
+    // --env, so masking applies to them and explicit --env values win.
+    let mut env_from_json_keys = Vec::new();
+    if let Some(json) = opts.env_from_json.as_deref() {
+        let pairs = parse_env_json(json)?;
+        env_from_json_keys = pairs.iter().map(|(k, _)| k.clone()).collect();
+        let mut env_vars: Vec<String> =
+            pairs.into_iter().map(|(k, v)| format!("{k}={v}")).collect();
+        env_vars.append(&mut opts.env_vars);
+        opts.env_vars = env_vars;
+    }
+    // --read-env-from-state replays another job's recorded environment with
+    // the lowest precedence. Only masked values were persisted, so those
+    // cannot be recovered and are passed as empty strings.
+    let mut env_source_job_id = None;
+    if let Some(source) = opts.read_env_from_state_job_id.as_deref() {
+        let source_dir = JobDir::open(&resolve_root(opts.root), source)?;
+        let mut env_vars: Vec<String> = source_dir
+            .read_meta()?
+            .env_vars
+            .iter()
+            .map(|kv| match parse_env_var(kv) {
+                (key, value) if value == "***" => format!("{key}="),
+                _ => kv.clone(),
+            })
+            .collect();
+        env_vars.append(&mut opts.env_vars);
+        opts.env_vars = env_vars;
+        env_source_job_id = Some(source_dir.job_id.clone());
+    }
+    opts.env_vars = dedup_env_vars(&opts.env_vars);
+    validate_env_vars(&opts.env_vars)?;
+    let warnings = validate_run_opts(&opts);
+    let rlimits = opts
+        .rlimits
+        .iter()
+        .map(|s| crate::rlimit::normalize_rlimit(s))
+        .collect::<Result<Vec<_>>>()?;
 
     let elapsed_start = std::time::Instant::now();
 
+    // Reject reference cycles before a job is created; the supervisor performs
+    // the actual expansion.
+    if opts.env_expand {
+        expand_env_vars(&collect_env_vars(&opts.env_files, &opts.env_vars)?, &|_| {
+            None
+        })?;
+    }
+
+    let startup_wait_ms = match opts.wait_for_file.as_deref() {
+        Some(_) if opts.dry_run => None,
+        Some(path) => Some(wait_for_file(
+            std::path::Path::new(path),
+            opts.wait_for_file_timeout_ms,
+        )?),
+        None => None,
+    };
+
     let root = resolve_root(opts.root);
-    std::fs::create_dir_all(&root)
-        .with_context(|| format!("create jobs root {}", root.display()))?;
+    if !opts.dry_run {
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("create jobs root {}", root.display()))?;
+    }
+    check_output_quota(&root, opts.output_quota_bytes)?;
 
     let job_id = generate_job_id(&root)?;
     let created_at = now_rfc3339();
@@ -520,10 +1566,44 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
     // If --cwd was specified, use that path; otherwise use the current process's working directory.
     // Canonicalize the path for consistent comparison; fall back to absolute path on failure.
     let effective_cwd = resolve_effective_cwd(opts.cwd);
-
-    // Build output-match config from definition-time options (same logic as `create` and `notify set`).
-    let on_output_match = crate::notify::build_output_match_config(
-        opts.output_pattern,
+    let command_hash = command_hash(&opts.command, Some(&effective_cwd), &env_keys);
+
+    // --deduplicate: reuse the most recent successful run of the same command.
+    if let Some(hours) = opts.deduplicate_hours.filter(|_| !opts.dry_run) {
+        let cutoff = format_rfc3339(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .saturating_sub(hours.saturating_mul(3600)),
+        );
+        if let Some((job_dir, meta)) = find_deduplicated_job(&root, &command_hash, &cutoff) {
+            info!(job_id = %job_dir.job_id, "run --deduplicate: reusing completed job");
+            return deduplicated_response(&job_dir, &meta, &opts, elapsed_start).map(|response| {
+                RunOutcome::Job {
+                    response: Box::new(response),
+                    mask: opts.mask,
+                }
+            });
+        }
+    }
+
+    // Status reports are written by the supervisor, whose cwd may differ from
+    // ours; persist an absolute report path.
+    let status_report = match opts.status_report.clone() {
+        Some(mut report) => {
+            report.path = std::path::absolute(&report.path)
+                .with_context(|| format!("resolve --report-pipe path {}", report.path))?
+                .display()
+                .to_string();
+            Some(report)
+        }
+        None => None,
+    };
+
+    // Build output-match config from definition-time options (same logic as `create` and `notify set`).
+    let on_output_match = crate::notify::build_output_match_config(
+        opts.output_pattern,
         opts.output_match_type,
         opts.output_stream,
         opts.output_command,
@@ -546,9 +1626,37 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
     // Validate and deduplicate tags (preserving first-seen order).
     let tags = dedup_tags(opts.tags)?;
 
+    #[cfg(unix)]
+    if let Some(ref sig) = opts.signal_on_parent_exit
+        && signal_number(sig).is_none()
+    {
+        anyhow::bail!("unknown signal for --signal-on-parent-exit: {sig}");
+    }
+
+    // --stdout-file and --report-file are resolved against the caller's cwd,
+    // not the child's.
+    let stdout_file = opts
+        .stdout_file
+        .as_deref()
+        .map(|path| resolve_effective_cwd(Some(path)));
+    let full_log_file = opts.log.map(|path| resolve_effective_cwd(Some(path)));
+    let report_file = opts
+        .report_file
+        .as_deref()
+        .map(|path| resolve_effective_cwd(Some(path)));
+    let profile_output = opts
+        .profile_output
+        .as_deref()
+        .map(|path| resolve_effective_cwd(Some(path)));
+    let checkpoint_dir = opts
+        .checkpoint_dir
+        .as_deref()
+        .map(|path| resolve_effective_cwd(Some(path)));
+
     let meta = JobMeta {
         job: JobMetaJob { id: job_id.clone() },
         schema_version: crate::schema::SCHEMA_VERSION.to_string(),
+        agent_exec_version: crate::schema::AGENT_EXEC_VERSION.to_string(),
         command: opts.command.clone(),
         created_at: created_at.clone(),
         root: root.display().to_string(),
@@ -559,24 +1667,123 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
         env_vars_runtime: vec![],
         mask: opts.mask.clone(),
         cwd: Some(effective_cwd),
+        command_hash,
         notification,
         // Execution-definition fields (used by start if ever applicable).
         inherit_env: opts.inherit_env,
         env_files: opts.env_files.clone(),
-        timeout_ms: opts.timeout_ms,
-        kill_after_ms: opts.kill_after_ms,
-        progress_every_ms: opts.progress_every_ms,
+        timeout_ms: opts.timeout_ms.as_millis(),
+        kill_after_ms: opts.kill_after_ms.as_millis(),
+        keep_going_ms: opts.keep_going_ms.as_millis(),
+        progress_every_ms: opts.progress_every_ms.as_millis(),
         shell_wrapper: Some(opts.shell_wrapper.clone()),
         stdin_file: None,
+        detached: opts.detach,
+        signal_on_parent_exit: opts.signal_on_parent_exit.clone(),
+        output_encoding: opts.output_encoding.clone(),
+        pty: opts.pty,
+        health_check: opts.health_check.clone(),
+        event_log: opts.event_log,
+        status_report: status_report.clone(),
+        core_dump_disabled: opts.disable_core_dump,
+        max_output_lines_per_second: opts.max_output_lines_per_second,
+        env_expand: opts.env_expand,
+        log_buffering: opts.log_buffering,
+        priority: opts.priority,
+        nice: opts.nice.map(clamp_nice),
+        stdout_file: stdout_file.clone(),
+        full_log_file: full_log_file.clone(),
+        env_unset: opts.env_unset.clone(),
+        env_inherit_prefix: opts.env_inherit_prefix.clone(),
+        rlimits: rlimits.clone(),
+        pid_namespace: opts.pid_namespace,
+        require_pid_namespace: opts.require_pid_namespace,
+        windows_job_object: !opts.windows_no_job_object,
+        require_clean_exit: opts.require_clean_exit,
+        snapshot_on_sigusr1: opts.snapshot_on_sigusr1,
+        max_line_length: opts.max_line_length,
+        log_rotate_size_bytes: opts.log_rotate_size_bytes,
+        log_rotate_count: opts.log_rotate_count,
+        startup_wait_ms,
+        env_from_json_keys,
+        env_source_job_id,
+        ssh_host: opts.ssh_host.clone(),
+        original_command,
+        hostname: opts.record_host_info.then(local_hostname).flatten(),
+        username: opts.record_host_info.then(local_username).flatten(),
+        report_file: report_file.clone(),
+        profile_output: profile_output.clone(),
+        checkpoint_dir: checkpoint_dir.clone(),
+        checkpoint_interval_ms: checkpoint_dir
+            .is_some()
+            .then_some(opts.checkpoint_interval_ms.as_millis()),
+        checkpoint_count: checkpoint_dir.is_some().then_some(opts.checkpoint_count),
+        completion_webhook_url: opts.completion_webhook_url.clone(),
+        notify_file_append: opts.notify_file_append,
+        notify_file_snapshot: opts.notify_file_snapshot,
+        completion_webhook_timeout_ms: opts
+            .completion_webhook_url
+            .is_some()
+            .then_some(opts.completion_webhook_timeout_ms.as_millis()),
+        notify_timeout_ms: opts
+            .notify_command
+            .is_some()
+            .then_some(opts.notify_timeout_ms.as_millis()),
+        capture_output_max_bytes: opts.capture_output.then_some(opts.capture_output_max_bytes),
         tags: tags.clone(),
     };
 
     validate_stdin_source(opts.stdin.as_ref())?;
+    let direct_stdin_file = match opts.stdin.as_ref() {
+        Some(StdinSource::File(path)) => Some(resolve_direct_stdin_file(path)?),
+        _ => None,
+    };
+
+    if opts.dry_run {
+        let cwd = meta.cwd.clone().unwrap_or_default();
+        if !Path::new(&cwd).is_dir() {
+            return Err(anyhow::Error::new(CwdNotFound(format!(
+                "working directory not found: {cwd}"
+            ))));
+        }
+        return Ok(RunOutcome::DryRun(Box::new(Response::new(
+            "run",
+            RunDryRunData {
+                dry_run: true,
+                job_id: None,
+                state: "dry_run".to_string(),
+                validated_options: ValidatedRunOptions {
+                    command: meta.command,
+                    cwd,
+                    env_keys: meta.env_keys,
+                    root: std::path::absolute(&root)
+                        .unwrap_or(root)
+                        .display()
+                        .to_string(),
+                    tags: meta.tags,
+                    command_hash: meta.command_hash,
+                },
+                warnings,
+            },
+        ))));
+    }
 
-    let job_dir = JobDir::create(&root, &job_id, &meta)?;
-    let stdin_file =
-        materialize_stdin_for_job(&job_dir, opts.stdin.as_ref(), opts.stdin_max_bytes)?;
-    if stdin_file.is_some() {
+    let dir_create_start = std::time::Instant::now();
+    let job_dir = JobDir::create_dir(&root, &job_id)?;
+    let dir_create_ms = dir_create_start.elapsed().as_millis() as u64;
+    let write_meta_start = std::time::Instant::now();
+    job_dir.write_meta_atomic(&meta)?;
+    let write_meta_ms = write_meta_start.elapsed().as_millis() as u64;
+    let stdin_file = if opts.stdin_forward {
+        // The FIFO is only meaningful while this `run` process is forwarding, so
+        // it is passed to the supervisor but not persisted for start/restart.
+        Some(create_stdin_fifo(&job_dir)?)
+    } else if direct_stdin_file.is_some() {
+        direct_stdin_file
+    } else {
+        materialize_stdin_for_job(&job_dir, opts.stdin.as_ref(), opts.stdin_max_bytes)?
+    };
+    if stdin_file.is_some() && !opts.stdin_forward {
         let mut meta_with_stdin = meta.clone();
         meta_with_stdin.stdin_file = stdin_file.clone();
         job_dir.write_meta_atomic(&meta_with_stdin)?;
@@ -584,11 +1791,8 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
     info!(job_id = %job_id, "created job directory");
 
     // Determine the full.log path (may be overridden by --log).
-    let full_log_path = if let Some(log) = opts.log {
-        log.to_string()
-    } else {
-        job_dir.full_log_path().display().to_string()
-    };
+    let full_log_path =
+        full_log_file.unwrap_or_else(|| job_dir.full_log_path().display().to_string());
 
     // Pre-create empty log files so they exist before the supervisor starts.
     pre_create_log_files(&job_dir)?;
@@ -596,37 +1800,111 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
     // Spawn the supervisor using the shared helper.
     // Note: masking is handled by `run` (meta.json + JSON response). The supervisor
     // receives the real env var values so the child process can use them as intended.
-    let (_supervisor_pid, _started_at) = spawn_supervisor_process(
+    let spawn_start = std::time::Instant::now();
+    let (supervisor_pid, _started_at) = spawn_supervisor_process(
         &job_dir,
         SpawnSupervisorParams {
             job_id: job_id.clone(),
             root: root.clone(),
             full_log_path: full_log_path.clone(),
+            created_at: created_at.clone(),
             timeout_ms: opts.timeout_ms,
             kill_after_ms: opts.kill_after_ms,
+            keep_going_ms: opts.keep_going_ms,
             cwd: opts.cwd.map(|s| s.to_string()),
             env_vars: opts.env_vars.clone(),
             env_files: opts.env_files.clone(),
             inherit_env: opts.inherit_env,
             stdin_file: stdin_file.clone(),
+            stdin_inherit: opts.stdin_inherit,
             progress_every_ms: opts.progress_every_ms,
             notify_command: opts.notify_command.clone(),
             notify_file: opts.notify_file.clone(),
             shell_wrapper: opts.shell_wrapper.clone(),
             command: opts.command.clone(),
+            detach: opts.detach,
+            signal_on_parent_exit: opts.signal_on_parent_exit.clone(),
+            pty: opts.pty,
+            event_log: opts.event_log,
+            status_report: status_report.clone(),
+            health_check: opts.health_check.clone(),
+            disable_core_dump: opts.disable_core_dump,
+            max_output_lines_per_second: opts.max_output_lines_per_second,
+            env_expand: opts.env_expand,
+            log_buffering: opts.log_buffering,
+            priority: opts.priority,
+            nice: opts.nice.map(clamp_nice),
+            stdout_file,
+            env_unset: opts.env_unset.clone(),
+            env_inherit_prefix: opts.env_inherit_prefix.clone(),
+            rlimits: rlimits.clone(),
+            pid_namespace: opts.pid_namespace,
+            require_pid_namespace: opts.require_pid_namespace,
+            windows_no_job_object: opts.windows_no_job_object,
+            require_clean_exit: opts.require_clean_exit,
+            snapshot_on_sigusr1: opts.snapshot_on_sigusr1,
+            max_line_length: opts.max_line_length,
+            log_rotate_size_bytes: opts.log_rotate_size_bytes,
+            log_rotate_count: opts.log_rotate_count,
+            report_file: report_file.clone(),
+            profile_output,
+            checkpoint_dir,
+            checkpoint_interval_ms: opts.checkpoint_interval_ms,
+            checkpoint_count: opts.checkpoint_count,
+            completion_webhook_url: opts.completion_webhook_url.clone(),
+            completion_webhook_timeout_ms: opts.completion_webhook_timeout_ms,
+            capture_output_max_bytes: meta.capture_output_max_bytes,
         },
     )?;
+    let spawn_ms = spawn_start.elapsed().as_millis() as u64;
+    wait_for_child_spawn(&job_dir, supervisor_pid, opts.startup_timeout_ms);
 
     // Compute absolute paths for stdout.log and stderr.log.
-    let stdout_log_path = job_dir.stdout_path().display().to_string();
+    let stdout_log_path = stdout_log_path(&job_dir, &meta);
     let stderr_log_path = job_dir.stderr_path().display().to_string();
 
+    // A startup probe replaces the inline output wait: the response is returned
+    // as soon as the probe passes (or gives up).
+    let startup_probe_passed = match opts.startup_probe_command.as_deref() {
+        Some(argv) => {
+            let mut probe = Command::new(&argv[0]);
+            probe
+                .args(&argv[1..])
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null());
+            if let Some(cwd) = opts.cwd {
+                probe.current_dir(cwd);
+            }
+            apply_job_env(
+                &mut probe,
+                opts.inherit_env,
+                &opts.env_inherit_prefix,
+                &opts.env_files,
+                &opts.env_vars,
+                opts.env_expand,
+                &opts.env_unset,
+            )?;
+            Some(run_startup_probe(
+                &mut probe,
+                opts.startup_probe_interval_ms,
+                opts.startup_probe_timeout_ms,
+                opts.startup_probe_retries,
+            ))
+        }
+        None => None,
+    };
+
+    // With --stdin-forward the response is printed immediately: the child may be
+    // waiting for the very input that is forwarded after the response.
+    let inline_wait = !opts.stdin_forward && startup_probe_passed.is_none();
     let observation = observe_inline_output(
         &job_dir,
-        opts.wait,
+        opts.wait && inline_wait,
         opts.until_seconds,
-        opts.forever,
+        opts.forever && inline_wait,
         opts.max_bytes,
+        &opts.snapshot_encoding,
     )?;
     let elapsed_ms = elapsed_start.elapsed().as_millis() as u64;
     let compression = crate::compress::compress(crate::compress::CompressionInput {
@@ -635,7 +1913,7 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
         stderr: &observation.stderr,
         stdout_original_bytes: observation.stdout_total_bytes,
         stderr_original_bytes: observation.stderr_total_bytes,
-        mode: opts.compression_mode,
+        mode: snapshot_compression_mode(opts.compression_mode, &opts.snapshot_encoding),
     });
 
     if !opts.no_auto_gc {
@@ -657,10 +1935,13 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
         RunData {
             job_id,
             state: observation.state,
+            command: meta.command.clone(),
             tags,
             // Include masked env_vars in the JSON response so callers can inspect
             // which variables were set (with secret values replaced by "***").
             env_vars: masked_env_vars,
+            root: job_dir.absolute_root().display().to_string(),
+            job_dir: job_dir.absolute_path().display().to_string(),
             stdout_log_path,
             stderr_log_path,
             elapsed_ms,
@@ -677,9 +1958,24 @@ pub fn run_response(opts: RunOpts) -> Result<Response<RunData>> {
             signal: observation.signal,
             duration_ms: observation.duration_ms,
             compression,
+            deduplicated: false,
+            startup_probe_passed,
+            report_file_path: report_file,
+            warnings,
+            args: vec![],
+            args_masked: false,
+            replayed_from: None,
+            benchmark: opts.benchmark_mode.then_some(crate::schema::RunBenchmark {
+                dir_create_ms,
+                spawn_ms,
+                write_meta_ms,
+            }),
         },
     );
-    Ok(response)
+    Ok(RunOutcome::Job {
+        response: Box::new(response),
+        mask: opts.mask,
+    })
 }
 
 /// Options for the `_supervise` internal sub-command.
@@ -695,9 +1991,12 @@ pub struct SuperviseOpts<'a> {
     /// Override full.log path; None = use job dir default.
     pub full_log: Option<&'a str>,
     /// Timeout in milliseconds; 0 = no timeout.
-    pub timeout_ms: u64,
-    /// Milliseconds after SIGTERM before SIGKILL; 0 = immediate SIGKILL.
-    pub kill_after_ms: u64,
+    pub timeout_ms: DurationMs,
+    /// Grace period (ms) between the timeout SIGTERM and SIGKILL; 0 = immediate SIGKILL.
+    pub kill_after_ms: DurationMs,
+    /// Extra milliseconds after the kill-after grace period before the process group
+    /// is SIGKILLed; 0 = no extra delay.
+    pub keep_going_ms: DurationMs,
     /// Working directory for the child process.
     pub cwd: Option<&'a str>,
     /// Environment variables as KEY=VALUE strings (real values, not masked).
@@ -706,10 +2005,10 @@ pub struct SuperviseOpts<'a> {
     pub env_files: Vec<String>,
     /// Whether to inherit the current process environment.
     pub inherit_env: bool,
-    /// Materialized stdin file path (relative to job dir) to feed child stdin.
-    pub stdin_file: Option<String>,
+    /// Where the child's stdin comes from.
+    pub stdin_mode: StdinMode,
     /// Interval (ms) for state.json updated_at refresh; 0 = disabled.
-    pub progress_every_ms: u64,
+    pub progress_every_ms: DurationMs,
     /// Shell command string for command notification sink; executed via platform shell.
     /// None = no command sink.
     pub notify_command: Option<String>,
@@ -717,6 +2016,91 @@ pub struct SuperviseOpts<'a> {
     pub notify_file: Option<String>,
     /// Resolved shell wrapper argv used to execute command strings.
     pub shell_wrapper: Vec<String>,
+    /// Signal delivered to the child when the supervisor exits before it
+    /// (Linux `PR_SET_PDEATHSIG`); None = disabled.
+    pub signal_on_parent_exit: Option<String>,
+    /// Attach the child to a pseudo-terminal instead of pipes (Unix only).
+    pub pty: bool,
+    /// Health-check argv (shell wrapper + command string); None = disabled.
+    pub health_check_command: Option<Vec<String>>,
+    /// Milliseconds between health checks.
+    pub health_check_interval_ms: DurationMs,
+    /// Consecutive failed health checks before the job is terminated.
+    pub health_check_retries: u32,
+    /// Record a structured event stream in `events.ndjson`.
+    pub event_log: bool,
+    /// Interval (ms) between status reports written to `report_pipe`.
+    pub report_interval_ms: DurationMs,
+    /// FIFO or regular file receiving one JSON status line per report interval.
+    pub report_pipe: Option<String>,
+    /// Set RLIMIT_CORE to 0 in the child so it cannot write core dumps.
+    pub disable_core_dump: bool,
+    /// Lines per second written to each individual log; 0 = unlimited.
+    pub max_output_lines_per_second: u64,
+    /// Expand `$NAME` / `${NAME}` references between env-file and `--env` values.
+    pub env_expand: bool,
+    /// Write strategy for stdout.log / stderr.log.
+    pub log_buffering: LogBuffering,
+    /// Scheduling priority of the child process.
+    pub priority: ProcessPriority,
+    /// Nice value set in the child before exec; overrides `priority`'s.
+    pub nice: Option<i8>,
+    /// Write the child's stdout directly to this file instead of piping it into stdout.log.
+    pub stdout_file: Option<String>,
+    /// Environment variable names removed from the child environment.
+    pub env_unset: Vec<String>,
+    /// Key prefixes that inherited environment variables must match.
+    pub env_inherit_prefix: Vec<String>,
+    /// Resource limits (`NAME=SOFT:HARD`) set in the child before exec; Unix only.
+    pub rlimits: Vec<String>,
+    /// Spawn the child as PID 1 of a new PID namespace (Linux only).
+    pub pid_namespace: bool,
+    /// Treat a failure to create the PID namespace as fatal.
+    pub require_pid_namespace: bool,
+    /// Skip Job Object assignment on Windows; `kill` then terminates the
+    /// process tree by enumeration, which is best-effort.
+    pub windows_no_job_object: bool,
+    /// Write `failed` instead of `exited` when the child exits non-zero.
+    pub require_clean_exit: bool,
+    /// On Unix, write `snapshots/manual-<epoch-ms>.json` whenever the
+    /// supervisor receives SIGUSR1.
+    pub snapshot_on_sigusr1: bool,
+    /// Maximum bytes per full.log line; longer lines are cut with a marker.
+    pub max_line_length: u64,
+    /// Rotate stream logs past this size while the job runs; 0 = never.
+    pub log_rotate_size_bytes: u64,
+    pub log_rotate_count: u32,
+    /// Path receiving a [`crate::schema::JobReport`] once the job finishes.
+    pub report_file: Option<String>,
+    /// Path receiving the folded sampling profile of the child.
+    pub profile_output: Option<String>,
+    /// Directory receiving [`crate::schema::Checkpoint`] files while the job runs.
+    pub checkpoint_dir: Option<String>,
+    /// Interval between periodic checkpoints, in milliseconds.
+    pub checkpoint_interval_ms: DurationMs,
+    /// Most periodic checkpoints kept; the final checkpoint is not counted.
+    pub checkpoint_count: u64,
+    /// URL receiving the completion event as an HTTP POST.
+    pub completion_webhook_url: Option<String>,
+    /// Per-attempt timeout for the webhook request, in milliseconds.
+    pub completion_webhook_timeout_ms: DurationMs,
+    /// Record the final stdout in `state.json` once the job finishes.
+    pub capture_output: bool,
+    /// Most stdout bytes recorded by `capture_output`.
+    pub capture_output_max_bytes: u64,
+}
+
+/// Compression mode for an output snapshot: base64 content is never
+/// compressed, since summarizing encoded bytes is meaningless.
+pub fn snapshot_compression_mode(
+    mode: crate::compress::CompressionMode,
+    encoding: &str,
+) -> crate::compress::CompressionMode {
+    if encoding.eq_ignore_ascii_case(crate::jobstore::BASE64_ENCODING) {
+        crate::compress::CompressionMode::Off
+    } else {
+        mode
+    }
 }
 
 /// Resolve the effective working directory for a job.
@@ -731,6 +2115,7 @@ pub fn observe_inline_output(
     until_seconds: u64,
     forever: bool,
     max_bytes: u64,
+    encoding: &str,
 ) -> Result<InlineObservation> {
     let started = std::time::Instant::now();
     let deadline = if wait {
@@ -753,7 +2138,9 @@ pub fn observe_inline_output(
                     .map(|m| m.len() > 0)
                     .unwrap_or(false);
 
-            if !state.status().is_non_terminal() || has_output {
+            // A terminal job may still be draining (and, with --log-buffering,
+            // flushing) its logs; the supervisor bounds that window.
+            if (!state.status().is_non_terminal() && state.logs_drained) || has_output {
                 break;
             }
 
@@ -768,8 +2155,8 @@ pub fn observe_inline_output(
     }
 
     let state = job_dir.read_state()?;
-    let stdout = job_dir.read_head_metrics("stdout.log", max_bytes);
-    let stderr = job_dir.read_head_metrics("stderr.log", max_bytes);
+    let stdout = job_dir.read_head_metrics("stdout.log", max_bytes, encoding);
+    let stderr = job_dir.read_head_metrics("stderr.log", max_bytes, encoding);
 
     Ok(InlineObservation {
         waited_ms: if wait {
@@ -783,7 +2170,7 @@ pub fn observe_inline_output(
         stderr_range: stderr.range,
         stdout_total_bytes: stdout.observed_bytes,
         stderr_total_bytes: stderr.observed_bytes,
-        encoding: "utf-8-lossy".to_string(),
+        encoding: crate::jobstore::response_encoding_name(encoding),
         state: state.status().as_str().to_string(),
         exit_code: state.exit_code(),
         signal: state.signal().map(|s| s.to_string()),
@@ -792,6 +2179,125 @@ pub fn observe_inline_output(
     })
 }
 
+/// Content fingerprint persisted as `meta.json` `command_hash`: the lowercase hex
+/// SHA-256 of the JSON array `[command, cwd, sorted env keys]`. Independent of the
+/// job ID, so resubmitting the same command yields the same hash.
+pub fn command_hash(command: &[String], cwd: Option<&str>, env_keys: &[String]) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut keys = env_keys.to_vec();
+    keys.sort();
+    let bytes = serde_json::to_vec(&serde_json::json!([command, cwd, keys]))
+        .expect("serialize command fingerprint");
+    Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Find the most recent job under `root` with `command_hash` that exited with
+/// code 0 and finished at or after `cutoff` (RFC 3339), for `run --deduplicate`.
+fn find_deduplicated_job(
+    root: &Path,
+    command_hash: &str,
+    cutoff: &str,
+) -> Option<(JobDir, JobMeta)> {
+    let mut best: Option<(String, JobDir, JobMeta)> = None;
+    for entry in std::fs::read_dir(root).ok()?.flatten() {
+        let path = entry.path();
+        let Ok(meta_bytes) = std::fs::read(path.join("meta.json")) else {
+            continue;
+        };
+        let Ok(meta) = serde_json::from_slice::<JobMeta>(&meta_bytes) else {
+            continue;
+        };
+        if meta.command_hash != command_hash {
+            continue;
+        }
+        let job_dir = JobDir {
+            path,
+            job_id: meta.job.id.clone(),
+        };
+        let Ok(state) = job_dir.read_state() else {
+            continue;
+        };
+        let Some(finished_at) = state.finished_at.clone() else {
+            continue;
+        };
+        if *state.status() != JobStatus::Exited
+            || state.exit_code() != Some(0)
+            || finished_at[..finished_at.len().min(19)] < cutoff[..cutoff.len().min(19)]
+        {
+            continue;
+        }
+        if best.as_ref().is_none_or(|(t, _, _)| finished_at > *t) {
+            best = Some((finished_at, job_dir, meta));
+        }
+    }
+    best.map(|(_, job_dir, meta)| (job_dir, meta))
+}
+
+/// Build the `run` response for a job reused by `--deduplicate`.
+fn deduplicated_response(
+    job_dir: &JobDir,
+    meta: &JobMeta,
+    opts: &RunOpts,
+    elapsed_start: std::time::Instant,
+) -> Result<Response<RunData>> {
+    let observation = observe_inline_output(
+        job_dir,
+        false,
+        0,
+        false,
+        opts.max_bytes,
+        &opts.snapshot_encoding,
+    )?;
+    let compression = crate::compress::compress(crate::compress::CompressionInput {
+        command: &meta.command,
+        stdout: &observation.stdout,
+        stderr: &observation.stderr,
+        stdout_original_bytes: observation.stdout_total_bytes,
+        stderr_original_bytes: observation.stderr_total_bytes,
+        mode: snapshot_compression_mode(opts.compression_mode, &opts.snapshot_encoding),
+    });
+    Ok(Response::new(
+        "run",
+        RunData {
+            job_id: job_dir.job_id.clone(),
+            command: meta.command.clone(),
+            state: observation.state,
+            tags: meta.tags.clone(),
+            env_vars: meta.env_vars.clone(),
+            root: job_dir.absolute_root().display().to_string(),
+            job_dir: job_dir.absolute_path().display().to_string(),
+            stdout_log_path: stdout_log_path(job_dir, meta),
+            stderr_log_path: job_dir.stderr_path().display().to_string(),
+            elapsed_ms: elapsed_start.elapsed().as_millis() as u64,
+            waited_ms: observation.waited_ms,
+            stdout: observation.stdout,
+            stderr: observation.stderr,
+            stdout_range: observation.stdout_range,
+            stderr_range: observation.stderr_range,
+            stdout_total_bytes: observation.stdout_total_bytes,
+            stderr_total_bytes: observation.stderr_total_bytes,
+            encoding: observation.encoding,
+            exit_code: observation.exit_code,
+            finished_at: observation.finished_at,
+            signal: observation.signal,
+            duration_ms: observation.duration_ms,
+            compression,
+            deduplicated: true,
+            startup_probe_passed: None,
+            report_file_path: meta.report_file.clone(),
+            benchmark: None,
+            warnings: validate_run_opts(opts),
+            args: vec![],
+            args_masked: false,
+            replayed_from: None,
+        },
+    ))
+}
+
 pub fn resolve_effective_cwd(cwd_override: Option<&str>) -> String {
     let base = match cwd_override {
         Some(p) => std::path::PathBuf::from(p),
@@ -815,7 +2321,10 @@ pub fn resolve_effective_cwd(cwd_override: Option<&str>) -> String {
 }
 
 /// Mask the values of specified keys in a list of KEY=VALUE strings.
-/// Keys listed in `mask_keys` will have their value replaced with "***".
+/// Keys matching an entry of `mask_keys` will have their value replaced with
+/// "***". An entry containing `*` (any run of characters) or `?` (exactly one
+/// character) is a case-sensitive glob pattern; any other entry must match
+/// the key exactly.
 pub fn mask_env_vars(env_vars: &[String], mask_keys: &[String]) -> Vec<String> {
     if mask_keys.is_empty() {
         return env_vars.to_vec();
@@ -824,7 +2333,7 @@ pub fn mask_env_vars(env_vars: &[String], mask_keys: &[String]) -> Vec<String> {
         .iter()
         .map(|s| {
             let (key, _val) = parse_env_var(s);
-            if mask_keys.iter().any(|k| k == &key) {
+            if mask_keys.iter().any(|k| mask_key_matches(k, &key)) {
                 format!("{key}=***")
             } else {
                 s.clone()
@@ -833,6 +2342,120 @@ pub fn mask_env_vars(env_vars: &[String], mask_keys: &[String]) -> Vec<String> {
         .collect()
 }
 
+/// Mask `--env KEY=VALUE` and `--env=KEY=VALUE` values in a CLI invocation
+/// the same way [`mask_env_vars`] masks `env_vars`, and the values of masked
+/// keys inside `--env-from-json`. Arguments after `--` belong to the command
+/// and are left alone. Returns the masked arguments and whether any value was
+/// replaced.
+fn mask_invocation_args(args: &[String], mask_keys: &[String]) -> (Vec<String>, bool) {
+    let mask_one = |env_var: &str| mask_env_vars(&[env_var.to_string()], mask_keys).remove(0);
+    let mask_json = |json: &str| mask_env_json(json, mask_keys);
+    let mut masked = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            masked.push(arg.clone());
+            masked.extend(iter.by_ref().cloned());
+        } else if arg == "--env" {
+            masked.push(arg.clone());
+            if let Some(value) = iter.next() {
+                masked.push(mask_one(value));
+            }
+        } else if let Some(value) = arg.strip_prefix("--env=") {
+            masked.push(format!("--env={}", mask_one(value)));
+        } else if arg == "--env-from-json" {
+            masked.push(arg.clone());
+            if let Some(value) = iter.next() {
+                masked.push(mask_json(value));
+            }
+        } else if let Some(value) = arg.strip_prefix("--env-from-json=") {
+            masked.push(format!("--env-from-json={}", mask_json(value)));
+        } else {
+            masked.push(arg.clone());
+        }
+    }
+    let changed = masked != args;
+    (masked, changed)
+}
+
+/// Replace the values of masked keys in a `--env-from-json` object with
+/// "***". The object is re-serialized only when a key is masked; input that
+/// does not parse is returned unchanged because `run` rejects it anyway.
+fn mask_env_json(json: &str, mask_keys: &[String]) -> String {
+    let Ok(pairs) = parse_env_json(json) else {
+        return json.to_string();
+    };
+    let is_masked = |key: &str| mask_keys.iter().any(|k| mask_key_matches(k, key));
+    if !pairs.iter().any(|(key, _)| is_masked(key)) {
+        return json.to_string();
+    }
+    let map: serde_json::Map<String, serde_json::Value> = pairs
+        .into_iter()
+        .map(|(key, value)| {
+            let value = if is_masked(&key) {
+                "***".to_string()
+            } else {
+                value
+            };
+            (key, serde_json::Value::String(value))
+        })
+        .collect();
+    serde_json::Value::Object(map).to_string()
+}
+
+fn mask_key_matches(mask_key: &str, key: &str) -> bool {
+    if mask_key.contains(['*', '?']) {
+        glob_match(mask_key, key)
+    } else {
+        mask_key == key
+    }
+}
+
+/// Match `text` against a pattern of literal characters, `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it is currently absorbing up to.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` absorb one more character and retry.
+                Some((sp, st)) => {
+                    star = Some((sp, st + 1));
+                    p = sp + 1;
+                    t = st + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Drop repeated KEY=VALUE entries, keeping the last value given for each key
+/// (the one the child would see) in the position of that last occurrence.
+pub fn dedup_env_vars(vars: &[String]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut deduped: Vec<String> = vars
+        .iter()
+        .rev()
+        .filter(|s| seen.insert(parse_env_var(s).0))
+        .cloned()
+        .collect();
+    deduped.reverse();
+    deduped
+}
+
 /// Parse a single KEY=VALUE or KEY= string into (key, value).
 fn parse_env_var(s: &str) -> (String, String) {
     if let Some(pos) = s.find('=') {
@@ -842,6 +2465,139 @@ fn parse_env_var(s: &str) -> (String, String) {
     }
 }
 
+/// `--wait-for-file` gave up before the path appeared.
+#[derive(Debug)]
+pub struct WaitForFileTimeout(pub String);
+
+impl std::fmt::Display for WaitForFileTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for WaitForFileTimeout {}
+
+/// Interval between existence checks for `--wait-for-file`.
+const WAIT_FOR_FILE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Block until `path` exists and return the milliseconds waited, or fail with
+/// [`WaitForFileTimeout`] after `timeout_ms` (0 = no limit).
+///
+/// The wait happens before any job directory is created, so interrupting
+/// `run` here (e.g. Ctrl-C) leaves nothing behind.
+fn wait_for_file(path: &std::path::Path, timeout_ms: DurationMs) -> Result<u64> {
+    let started = std::time::Instant::now();
+    let timeout = timeout_ms.as_duration();
+    loop {
+        if path.exists() {
+            return Ok(started.elapsed().as_millis() as u64);
+        }
+        let elapsed = started.elapsed();
+        if !timeout_ms.is_zero() && elapsed >= timeout {
+            return Err(anyhow::anyhow!(WaitForFileTimeout(format!(
+                "wait_for_file_timeout: {} did not appear within {timeout_ms}ms",
+                path.display()
+            ))));
+        }
+        let remaining = if !timeout_ms.is_zero() {
+            timeout - elapsed
+        } else {
+            WAIT_FOR_FILE_POLL_INTERVAL
+        };
+        std::thread::sleep(WAIT_FOR_FILE_POLL_INTERVAL.min(remaining));
+    }
+}
+
+/// Flag combinations that are accepted but probably not what the caller meant;
+/// reported in the `run` response as `warnings`.
+pub fn validate_run_opts(opts: &RunOpts) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !opts.inherit_env && opts.env_vars.is_empty() && opts.env_files.is_empty() {
+        warnings.push(
+            "--no-inherit-env without --env or --env-file: the child starts with an empty \
+             environment (no PATH), so commands may not be found"
+                .to_string(),
+        );
+    }
+    if opts.timeout_ms.is_zero() && !opts.kill_after_ms.is_zero() {
+        warnings.push("--kill-after has no effect without --timeout".to_string());
+    }
+    if let Some(nice) = opts.nice.filter(|&n| i32::from(clamp_nice(n)) != n) {
+        warnings.push(format!(
+            "--nice {nice} is outside -20..19; clamped to {}",
+            clamp_nice(nice)
+        ));
+    }
+    warnings
+}
+
+/// Clamp a `--nice` value to the range accepted by `setpriority(2)`.
+fn clamp_nice(nice: i32) -> i8 {
+    nice.clamp(-20, 19) as i8
+}
+
+/// A malformed `--env KEY=VALUE` entry, rejected before any job is created.
+#[derive(Debug)]
+pub struct InvalidEnvVar(pub String);
+
+impl std::fmt::Display for InvalidEnvVar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidEnvVar {}
+
+/// Parse a `--env-from-json` object into KEY/VALUE pairs (sorted by key).
+/// Only a flat object with string values is accepted.
+pub fn parse_env_json(json: &str) -> Result<Vec<(String, String)>> {
+    let invalid =
+        |reason: String| anyhow::anyhow!(InvalidEnvVar(format!("invalid_env_var: {reason}")));
+    let value: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| invalid(format!("--env-from-json is not valid JSON: {e}")))?;
+    let serde_json::Value::Object(map) = value else {
+        return Err(invalid("--env-from-json must be a JSON object".to_string()));
+    };
+    map.into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => Ok((key, s)),
+            other => Err(invalid(format!(
+                "--env-from-json value for {key:?} must be a string, got {other}"
+            ))),
+        })
+        .collect()
+}
+
+/// Validate `--env` entries: the key (text before the first `=`) must be
+/// non-empty and free of whitespace, and no part of the entry may contain a
+/// NUL byte. Non-ASCII keys are accepted with a warning.
+pub fn validate_env_vars(env_vars: &[String]) -> Result<()> {
+    for entry in env_vars {
+        let key = entry.split_once('=').map_or(entry.as_str(), |(k, _)| k);
+        let reason = if key.is_empty() {
+            Some("empty key")
+        } else if entry.contains('\0') {
+            Some("contains a NUL byte")
+        } else if key.chars().any(char::is_whitespace) {
+            Some("key contains whitespace")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(anyhow::anyhow!(InvalidEnvVar(format!(
+                "invalid_env_var: {entry:?}: {reason}"
+            ))));
+        }
+        if !key.is_ascii() {
+            warn!(
+                key,
+                "environment variable key contains non-ASCII characters"
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Load environment variables from a .env-style file.
 /// Supports KEY=VALUE lines; lines starting with '#' and empty lines are ignored.
 fn load_env_file(path: &str) -> Result<Vec<(String, String)>> {
@@ -858,6 +2614,178 @@ fn load_env_file(path: &str) -> Result<Vec<(String, String)>> {
     Ok(vars)
 }
 
+/// Load one variable per `*.env` file in `dir`, in alphabetical file order.
+/// `FOO.env` sets `FOO` to the file's content minus one trailing newline.
+fn load_env_dir(dir: &str) -> Result<Vec<(String, String)>> {
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("read env-file directory {dir}"))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "env"))
+        .collect();
+    paths.sort();
+    let mut vars = Vec::new();
+    for path in paths {
+        let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("read env-file {}", path.display()))?;
+        let value = contents
+            .strip_suffix('\n')
+            .map(|v| v.strip_suffix('\r').unwrap_or(v))
+            .unwrap_or(&contents);
+        vars.push((key.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+/// Collect env-file entries followed by `--env` overrides, in the order they are
+/// applied to the child (later entries win). An env-file path that is a
+/// directory is read with [`load_env_dir`].
+fn collect_env_vars(env_files: &[String], env_vars: &[String]) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for env_file in env_files {
+        if std::path::Path::new(env_file).is_dir() {
+            vars.extend(load_env_dir(env_file)?);
+        } else {
+            vars.extend(load_env_file(env_file)?);
+        }
+    }
+    vars.extend(env_vars.iter().map(|s| parse_env_var(s)));
+    Ok(vars)
+}
+
+/// `--env-expand` found variables whose values reference each other in a cycle.
+/// Holds the cycle path, starting and ending with the same key.
+#[derive(Debug)]
+pub struct EnvCycle(pub Vec<String>);
+
+impl std::fmt::Display for EnvCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "environment variable reference cycle: {}",
+            self.0.join(" -> ")
+        )
+    }
+}
+
+impl std::error::Error for EnvCycle {}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// Replace every `$NAME` / `${NAME}` reference in `value` with `lookup(NAME)`.
+/// A `$` that does not start a valid reference is kept literally.
+fn replace_env_refs(value: &str, mut lookup: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) if is_env_name(&braced[..end]) => (&braced[..end], end + 2),
+                _ => ("", 0),
+            },
+            None => {
+                let len = after
+                    .char_indices()
+                    .find(|&(i, c)| {
+                        !(c == '_' || c.is_ascii_alphanumeric()) || (i == 0 && c.is_ascii_digit())
+                    })
+                    .map_or(after.len(), |(i, _)| i);
+                (&after[..len], len)
+            }
+        };
+        if name.is_empty() {
+            out.push('$');
+            rest = after;
+        } else {
+            out.push_str(&lookup(name));
+            rest = &after[consumed..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Expand `$NAME` / `${NAME}` references between `vars` (`--env-expand`).
+///
+/// Later definitions of a key override earlier ones, as when they are applied to
+/// the child. Variables are expanded in dependency order, so forward references
+/// resolve regardless of position; references to names not defined in `vars`
+/// resolve through `fallback` (empty when it returns `None`). Returns one entry
+/// per key, in first-definition order, or [`EnvCycle`] when references loop.
+pub fn expand_env_vars(
+    vars: &[(String, String)],
+    fallback: &dyn Fn(&str) -> Option<String>,
+) -> std::result::Result<Vec<(String, String)>, EnvCycle> {
+    let mut order: Vec<&str> = Vec::new();
+    let mut raw: std::collections::HashMap<&str, &str> = std::collections::HashMap::new();
+    for (key, value) in vars {
+        if raw.insert(key, value).is_none() {
+            order.push(key);
+        }
+    }
+    let mut expanded = std::collections::HashMap::new();
+    for &key in &order {
+        expand_env_var(key, &raw, fallback, &mut expanded, &mut Vec::new())?;
+    }
+    Ok(order
+        .into_iter()
+        .map(|key| {
+            let value = expanded.remove(key).unwrap_or_default();
+            (key.to_string(), value)
+        })
+        .collect())
+}
+
+/// Depth-first step of [`expand_env_vars`]: expand `key`'s dependencies, then `key`.
+fn expand_env_var<'a>(
+    key: &'a str,
+    raw: &std::collections::HashMap<&'a str, &'a str>,
+    fallback: &dyn Fn(&str) -> Option<String>,
+    expanded: &mut std::collections::HashMap<&'a str, String>,
+    stack: &mut Vec<&'a str>,
+) -> std::result::Result<(), EnvCycle> {
+    if expanded.contains_key(key) {
+        return Ok(());
+    }
+    if let Some(pos) = stack.iter().position(|k| *k == key) {
+        let mut cycle: Vec<String> = stack[pos..].iter().map(|k| k.to_string()).collect();
+        cycle.push(key.to_string());
+        return Err(EnvCycle(cycle));
+    }
+    let value = raw[key];
+    let mut deps = Vec::new();
+    replace_env_refs(value, |name| {
+        if let Some((&dep, _)) = raw.get_key_value(name) {
+            deps.push(dep);
+        }
+        String::new()
+    });
+    stack.push(key);
+    for dep in deps {
+        expand_env_var(dep, raw, fallback, expanded, stack)?;
+    }
+    stack.pop();
+    let resolved = replace_env_refs(value, |name| {
+        expanded
+            .get(name)
+            .cloned()
+            .or_else(|| fallback(name))
+            .unwrap_or_default()
+    });
+    expanded.insert(key, resolved);
+    Ok(())
+}
+
 /// Shared state for output-match checking, used by streaming threads in `supervise`.
 ///
 /// Reloads `meta.json` on every observed line so that a `notify set` update is
@@ -998,10 +2926,11 @@ impl OutputMatchChecker {
                 &events_path_str,
                 &self.shell_wrapper,
                 "job.output.matched",
+                DEFAULT_NOTIFY_TIMEOUT_MS,
             ));
         }
         if let Some(ref file_path) = match_cfg.file {
-            delivery_results.push(dispatch_file_sink(file_path, &event_json));
+            delivery_results.push(dispatch_file_sink(file_path, &event_json, true));
         }
 
         // Append delivery record to notification_events.ndjson.
@@ -1010,7 +2939,7 @@ impl OutputMatchChecker {
             delivery_results,
         };
         if let Ok(record_json) = serde_json::to_string(&record)
-            && let Ok(mut f) = std::fs::OpenOptions::new()
+            && let Ok(mut f) = cloexec_options()
                 .create(true)
                 .append(true)
                 .open(&events_path)
@@ -1033,63 +2962,902 @@ impl OutputMatchChecker {
 /// The optional `on_line` callback is invoked for each complete line (without
 /// the trailing newline) and is used to drive output-match checking.
 ///
+/// When `events` is set, every chunk read is also recorded in `events.ndjson`
+/// as a `stdout_chunk` / `stderr_chunk` event (derived from `label`).
+///
+/// Both log files are reopened when `agent-exec rotate` has replaced them on
+/// disk (see [`reopen_if_rotated`]); that check runs at most once per
+/// [`ROTATION_CHECK_INTERVAL`] or [`ROTATION_CHECK_BYTES`] written, and once
+/// more at EOF. With `rotation` enabled the individual log is also rotated
+/// here once it grows past the configured size.
+///
+/// Writes to the individual log go through [`BufferedLog`] according to
+/// `log_buffering`; in `full` mode a scoped thread flushes it every
+/// [`FULL_LOG_FLUSH_INTERVAL`] so a quiet stream is not held back indefinitely.
+///
 /// This helper is used by both the stdout and stderr monitoring threads inside
 /// [`supervise`], replacing the previously duplicated per-stream implementations.
 /// Buffer size (8192 bytes) and newline-split logic are preserved unchanged.
+#[allow(clippy::too_many_arguments)]
 fn stream_to_logs<R, F>(
     stream: R,
     log_path: &std::path::Path,
     full_log: std::sync::Arc<std::sync::Mutex<std::fs::File>>,
+    full_log_path: &std::path::Path,
     label: &str,
     on_line: Option<F>,
+    events: Option<&JobDir>,
+    max_lines_per_second: u64,
+    log_buffering: LogBuffering,
+    max_line_length: u64,
+    rotation: LogRotation,
 ) where
     R: std::io::Read,
     F: Fn(&str),
 {
     use std::io::Write;
-    let mut log_file = std::fs::File::create(log_path).expect("create stream log file in thread");
-    let mut stream = stream;
-    let mut buf = [0u8; 8192];
-    // Incomplete-line buffer for full.log formatting.
-    let mut line_buf: Vec<u8> = Vec::new();
-    loop {
-        match stream.read(&mut buf) {
-            Ok(0) => break, // EOF
-            Ok(n) => {
-                let chunk = &buf[..n];
-                // Write raw bytes to the individual log (captures partial lines too).
-                let _ = log_file.write_all(chunk);
-                // Accumulate bytes for full.log line formatting.
-                for &b in chunk {
-                    if b == b'\n' {
-                        let line = String::from_utf8_lossy(&line_buf);
-                        if let Ok(mut fl) = full_log.lock() {
-                            let ts = now_rfc3339();
-                            let _ = writeln!(fl, "{ts} [{label}] {line}");
-                        }
-                        if let Some(ref f) = on_line {
-                            f(&line);
-                        }
-                        line_buf.clear();
-                    } else {
-                        line_buf.push(b);
+    let chunk_event = format!("{}_chunk", label.to_ascii_lowercase());
+    let log_file = create_with_cloexec(log_path).expect("create stream log file in thread");
+    let log = std::sync::Mutex::new(BufferedLog::new(log_file, log_buffering));
+    let (stop_flusher, flusher_stopped) = std::sync::mpsc::channel::<()>();
+    std::thread::scope(|scope| {
+        if log_buffering == LogBuffering::Full {
+            let log = &log;
+            scope.spawn(move || {
+                while let Err(std::sync::mpsc::RecvTimeoutError::Timeout) =
+                    flusher_stopped.recv_timeout(FULL_LOG_FLUSH_INTERVAL)
+                {
+                    if let Ok(mut log) = log.lock() {
+                        let _ = log.flush();
                     }
                 }
-            }
-            Err(_) => break,
+            });
+        }
+        stream_chunks_to_logs(
+            stream,
+            &log,
+            log_path,
+            &full_log,
+            full_log_path,
+            label,
+            &chunk_event,
+            on_line,
+            events,
+            max_lines_per_second,
+            max_line_length,
+            rotation,
+        );
+        drop(stop_flusher);
+    });
+}
+
+/// Passes reads through, recording the instant the first byte arrives.
+struct FirstOutputReader<R> {
+    inner: R,
+    first_output_at: std::sync::Arc<std::sync::OnceLock<std::time::Instant>>,
+}
+
+impl<R: std::io::Read> std::io::Read for FirstOutputReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.first_output_at.get_or_init(std::time::Instant::now);
         }
+        Ok(n)
     }
-    // Flush any remaining incomplete line to full.log and trigger callback.
+}
+
+/// Monotonic clock anchored to wall time at supervisor start, so phase
+/// timestamps in `timing.json` keep sub-millisecond precision and order.
+struct PhaseClock {
+    start: std::time::Instant,
+    start_wall: std::time::SystemTime,
+}
+
+impl PhaseClock {
+    fn start() -> Self {
+        PhaseClock {
+            start: std::time::Instant::now(),
+            start_wall: std::time::SystemTime::now(),
+        }
+    }
+
+    /// `at` as an RFC 3339 timestamp with microseconds.
+    fn rfc3339(&self, at: std::time::Instant) -> String {
+        let since_epoch = (self.start_wall + at.saturating_duration_since(self.start))
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let secs = format_rfc3339(since_epoch.as_secs());
+        format!(
+            "{}.{:06}Z",
+            secs.trim_end_matches('Z'),
+            since_epoch.subsec_micros()
+        )
+    }
+}
+
+/// Suffix appended to a full.log line cut by `--max-line-length`.
+const TRUNCATED_LINE_MARKER: &str = "... [truncated]";
+
+/// Longest time a log is written to between checks for an external rotation.
+const ROTATION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Most bytes written to a log between checks for an external rotation.
+const ROTATION_CHECK_BYTES: u64 = 1024 * 1024;
+
+/// Rate limit for [`reopen_if_rotated`], which costs an `fstat` plus a `stat`.
+struct RotationCheck {
+    last: std::time::Instant,
+    bytes: u64,
+}
+
+impl RotationCheck {
+    fn new() -> Self {
+        RotationCheck {
+            last: std::time::Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Count `written` bytes; true once a check is due, which starts the next
+    /// interval.
+    fn due(&mut self, written: usize) -> bool {
+        self.bytes = self.bytes.saturating_add(written as u64);
+        if self.bytes < ROTATION_CHECK_BYTES && self.last.elapsed() < ROTATION_CHECK_INTERVAL {
+            return false;
+        }
+        self.last = std::time::Instant::now();
+        self.bytes = 0;
+        true
+    }
+}
+
+/// In-run rotation of an individual stream log (`run --log-rotate-size-mb`).
+#[derive(Debug, Clone, Copy, Default)]
+struct LogRotation {
+    /// Rotate once the log exceeds this many bytes; 0 = never.
+    size_bytes: u64,
+    /// Archived generations to keep (`<log>.1` .. `<log>.N`).
+    count: u32,
+}
+
+/// Rotate the log behind `log` when its on-disk size plus any buffered bytes
+/// exceeds `rotation.size_bytes`, then continue writing to the fresh file.
+fn rotate_stream_log(
+    log: &mut BufferedLog<std::fs::File>,
+    log_path: &std::path::Path,
+    rotation: LogRotation,
+) {
+    use std::io::Write;
+    if rotation.size_bytes == 0 {
+        return;
+    }
+    let on_disk = log.inner.metadata().map(|m| m.len()).unwrap_or(0);
+    if on_disk + log.pending.len() as u64 <= rotation.size_bytes {
+        return;
+    }
+    let (Some(dir), Some(filename)) = (
+        log_path.parent(),
+        log_path.file_name().and_then(|name| name.to_str()),
+    ) else {
+        return;
+    };
+    let _ = log.flush();
+    if let Err(e) = crate::jobstore::rotate_log_file(dir, filename, 0, rotation.count) {
+        debug!(path = %log_path.display(), error = %e, "in-run log rotation failed");
+        return;
+    }
+    reopen_if_rotated(&mut log.inner, log_path);
+}
+
+/// Read loop of [`stream_to_logs`]; returns at EOF after flushing `log`.
+#[allow(clippy::too_many_arguments)]
+fn stream_chunks_to_logs<R, F>(
+    mut stream: R,
+    log: &std::sync::Mutex<BufferedLog<std::fs::File>>,
+    log_path: &std::path::Path,
+    full_log: &std::sync::Mutex<std::fs::File>,
+    full_log_path: &std::path::Path,
+    label: &str,
+    chunk_event: &str,
+    on_line: Option<F>,
+    events: Option<&JobDir>,
+    max_lines_per_second: u64,
+    max_line_length: u64,
+    rotation: LogRotation,
+) where
+    R: std::io::Read,
+    F: Fn(&str),
+{
+    use std::io::Write;
+    let mut limiter =
+        (max_lines_per_second > 0).then(|| LineRateLimiter::new(max_lines_per_second));
+    let mut buf = [0u8; 8192];
+    // Incomplete-line buffer for full.log formatting and `on_line`.
+    let mut line_buf: Vec<u8> = Vec::new();
+    // Bytes of `line_buf` already written to full.log as cut fragments.
+    let mut line_written = 0usize;
+    let mut log_check = RotationCheck::new();
+    let mut full_log_check = RotationCheck::new();
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break, // EOF
+            Ok(n) => {
+                let chunk = &buf[..n];
+                // Write raw bytes to the individual log (captures partial lines too).
+                if let Ok(mut log_file) = log.lock() {
+                    if log_check.due(n) {
+                        reopen_if_rotated(&mut log_file.inner, log_path);
+                    }
+                    match limiter {
+                        Some(ref mut limiter) => limiter.write(&mut *log_file, chunk),
+                        None => {
+                            let _ = log_file.write_all(chunk);
+                        }
+                    }
+                    rotate_stream_log(&mut log_file, log_path, rotation);
+                }
+                emit_event(
+                    events,
+                    chunk_event,
+                    serde_json::json!({ "text": String::from_utf8_lossy(chunk) }),
+                );
+                // Accumulate bytes for full.log line formatting.
+                for &b in chunk {
+                    if b == b'\n' {
+                        let rest = String::from_utf8_lossy(&line_buf[line_written..]);
+                        if let Ok(mut fl) = full_log.lock() {
+                            if full_log_check.due(rest.len()) {
+                                reopen_if_rotated(&mut fl, full_log_path);
+                            }
+                            let ts = now_rfc3339();
+                            let _ = writeln!(fl, "{ts} [{label}] {rest}");
+                        }
+                        if let Some(ref f) = on_line {
+                            f(&String::from_utf8_lossy(&line_buf));
+                        }
+                        line_buf.clear();
+                        line_written = 0;
+                    } else {
+                        // A line exceeding --max-line-length is cut in full.log
+                        // only, at a character boundary; the rest continues as
+                        // a new full.log line. `on_line` still sees the whole
+                        // line.
+                        let pending = &line_buf[line_written..];
+                        if max_line_length > 0 && pending.len() as u64 >= max_line_length {
+                            let cut = match complete_utf8_prefix(pending) {
+                                0 => pending.len(),
+                                n => n,
+                            };
+                            let fragment = String::from_utf8_lossy(&pending[..cut]);
+                            if let Ok(mut fl) = full_log.lock() {
+                                if full_log_check.due(fragment.len()) {
+                                    reopen_if_rotated(&mut fl, full_log_path);
+                                }
+                                let ts = now_rfc3339();
+                                let _ = writeln!(
+                                    fl,
+                                    "{ts} [{label}] {fragment}{TRUNCATED_LINE_MARKER}"
+                                );
+                            }
+                            line_written += cut;
+                            // Without a line consumer the written part is no
+                            // longer needed.
+                            if on_line.is_none() {
+                                line_buf.drain(..line_written);
+                                line_written = 0;
+                            }
+                        }
+                        line_buf.push(b);
+                    }
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    if let Ok(mut log_file) = log.lock() {
+        reopen_if_rotated(&mut log_file.inner, log_path);
+        if let Some(ref mut limiter) = limiter {
+            limiter.flush_marker(&mut *log_file);
+        }
+        let _ = log_file.flush();
+    }
+    // Flush any remaining incomplete line to full.log and trigger callback.
     if !line_buf.is_empty() {
-        let line = String::from_utf8_lossy(&line_buf);
+        let rest = String::from_utf8_lossy(&line_buf[line_written..]);
         if let Ok(mut fl) = full_log.lock() {
+            reopen_if_rotated(&mut fl, full_log_path);
             let ts = now_rfc3339();
-            let _ = writeln!(fl, "{ts} [{label}] {line}");
+            let _ = writeln!(fl, "{ts} [{label}] {rest}");
         }
         if let Some(ref f) = on_line {
-            f(&line);
+            f(&String::from_utf8_lossy(&line_buf));
+        }
+    }
+}
+
+/// CPU time and peak memory of the reaped child, recorded in `state.json`.
+#[derive(Debug, Default, Clone, Copy)]
+struct ChildUsage {
+    cpu_user_ms: Option<u64>,
+    cpu_sys_ms: Option<u64>,
+    max_rss_kb: Option<u64>,
+}
+
+/// Wait for `child` to exit and collect its own resource usage via `wait4`
+/// (`RUSAGE_CHILDREN` would also count health-check probes).
+#[cfg(unix)]
+fn wait_with_usage(
+    child: std::process::Child,
+    mut on_pause: impl FnMut(bool),
+) -> Result<(std::process::ExitStatus, ChildUsage)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child.id() as libc::pid_t;
+    let mut status: libc::c_int = 0;
+    // SAFETY: rusage is plain old data and is filled in by wait4.
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: pid is our unreaped child; status and rusage are valid out-pointers.
+        let waited = unsafe {
+            libc::wait4(
+                pid,
+                &mut status,
+                libc::WUNTRACED | libc::WCONTINUED,
+                &mut rusage,
+            )
+        };
+        if waited == pid {
+            if libc::WIFSTOPPED(status) {
+                on_pause(true);
+                continue;
+            }
+            if libc::WIFCONTINUED(status) {
+                on_pause(false);
+                continue;
+            }
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err).context("wait for child");
         }
     }
+    // The child is reaped; dropping the handle neither waits nor signals it.
+    drop(child);
+
+    let millis = |tv: libc::timeval| tv.tv_sec as u64 * 1000 + tv.tv_usec as u64 / 1000;
+    // ru_maxrss is reported in bytes on macOS and in KiB elsewhere.
+    let max_rss = rusage.ru_maxrss as u64;
+    let max_rss_kb = if cfg!(target_os = "macos") {
+        max_rss / 1024
+    } else {
+        max_rss
+    };
+    Ok((
+        std::process::ExitStatus::from_raw(status),
+        ChildUsage {
+            cpu_user_ms: Some(millis(rusage.ru_utime)),
+            cpu_sys_ms: Some(millis(rusage.ru_stime)),
+            max_rss_kb: Some(max_rss_kb),
+        },
+    ))
+}
+
+/// Wait for `child` to exit, then read its CPU times and peak working set.
+#[cfg(windows)]
+fn wait_with_usage(
+    mut child: std::process::Child,
+    _on_pause: impl FnMut(bool),
+) -> Result<(std::process::ExitStatus, ChildUsage)> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{FILETIME, HANDLE};
+    use windows::Win32::System::ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::GetProcessTimes;
+
+    let exit_status = child.wait().context("wait for child")?;
+    let handle = HANDLE(child.as_raw_handle());
+    // FILETIME counts 100 ns intervals.
+    let millis =
+        |t: FILETIME| ((u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime)) / 10_000;
+    let mut usage = ChildUsage::default();
+    let mut created = FILETIME::default();
+    let mut exited = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+    // SAFETY: the handle stays open until `child` drops; out-pointers are valid.
+    unsafe {
+        if GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user).is_ok() {
+            usage.cpu_user_ms = Some(millis(user));
+            usage.cpu_sys_ms = Some(millis(kernel));
+        }
+        if K32GetProcessMemoryInfo(
+            handle,
+            &mut counters,
+            std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        )
+        .as_bool()
+        {
+            usage.max_rss_kb = Some(counters.PeakWorkingSetSize as u64 / 1024);
+        }
+    }
+    Ok((exit_status, usage))
+}
+
+/// Apply `run --priority` / `run --nice` to the spawned child (best effort).
+/// A nice value wins and maps onto the closest priority class.
+#[cfg(windows)]
+fn set_priority_class(child: &std::process::Child, priority: ProcessPriority, nice: Option<i8>) {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Threading::{
+        ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+        IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS, SetPriorityClass,
+    };
+
+    let class = match (nice, priority) {
+        (Some(n), _) if n <= -10 => HIGH_PRIORITY_CLASS,
+        (Some(n), _) if n <= 0 => NORMAL_PRIORITY_CLASS,
+        (Some(n), _) if n <= 10 => BELOW_NORMAL_PRIORITY_CLASS,
+        (Some(_), _) => IDLE_PRIORITY_CLASS,
+        (None, ProcessPriority::Low) => BELOW_NORMAL_PRIORITY_CLASS,
+        (None, ProcessPriority::Normal) => return,
+        (None, ProcessPriority::High) => ABOVE_NORMAL_PRIORITY_CLASS,
+    };
+    // SAFETY: the handle is owned by `child` and stays open for this call.
+    unsafe {
+        let _ = SetPriorityClass(HANDLE(child.as_raw_handle()), class);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn wait_with_usage(
+    mut child: std::process::Child,
+    _on_pause: impl FnMut(bool),
+) -> Result<(std::process::ExitStatus, ChildUsage)> {
+    let exit_status = child.wait().context("wait for child")?;
+    Ok((exit_status, ChildUsage::default()))
+}
+
+/// Capacity of the `--log-buffering full` buffer.
+const FULL_LOG_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Interval at which a `--log-buffering full` buffer is flushed even when not full.
+const FULL_LOG_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1000);
+
+/// Individual stream log writer implementing `--log-buffering`.
+///
+/// `none` passes every write through, `line` holds bytes until a newline
+/// completes the line (or [`FULL_LOG_BUFFER_BYTES`] accumulate), and `full`
+/// holds up to [`FULL_LOG_BUFFER_BYTES`].
+/// Pending bytes are written by [`std::io::Write::flush`].
+struct BufferedLog<W: std::io::Write> {
+    inner: W,
+    mode: LogBuffering,
+    pending: Vec<u8>,
+}
+
+impl<W: std::io::Write> BufferedLog<W> {
+    fn new(inner: W, mode: LogBuffering) -> Self {
+        BufferedLog {
+            inner,
+            mode,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for BufferedLog<W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        match self.mode {
+            LogBuffering::None => return self.inner.write(data),
+            LogBuffering::Line => match data.iter().rposition(|&b| b == b'\n') {
+                Some(end) => {
+                    self.pending.extend_from_slice(&data[..=end]);
+                    self.flush()?;
+                    self.pending.extend_from_slice(&data[end + 1..]);
+                }
+                // A line longer than the full-mode buffer is written in pieces.
+                None if self.pending.len() + data.len() >= FULL_LOG_BUFFER_BYTES => {
+                    self.pending.extend_from_slice(data);
+                    self.flush()?;
+                }
+                None => self.pending.extend_from_slice(data),
+            },
+            LogBuffering::Full => {
+                if self.pending.len() + data.len() > FULL_LOG_BUFFER_BYTES {
+                    self.flush()?;
+                }
+                if data.len() >= FULL_LOG_BUFFER_BYTES {
+                    self.inner.write_all(data)?;
+                } else {
+                    self.pending.extend_from_slice(data);
+                }
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.pending.is_empty() {
+            self.inner.write_all(&self.pending)?;
+            self.pending.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+/// Token bucket for `--max-output-lines-per-second`.
+///
+/// Refills `rate` tokens per second up to a burst of `rate` and spends one token
+/// per line written to an individual log. Lines arriving while the bucket is
+/// empty are drained but not written; once writing resumes (or at EOF) a single
+/// marker line records how many were dropped.
+struct LineRateLimiter {
+    rate: f64,
+    tokens: f64,
+    last_refill: std::time::Instant,
+    /// True when the next byte begins a new line.
+    at_line_start: bool,
+    /// Whether the current line was admitted by the bucket.
+    admitting: bool,
+    dropped: u64,
+}
+
+impl LineRateLimiter {
+    fn new(lines_per_second: u64) -> Self {
+        let rate = lines_per_second as f64;
+        Self {
+            rate,
+            tokens: rate,
+            last_refill: std::time::Instant::now(),
+            at_line_start: true,
+            admitting: true,
+            dropped: 0,
+        }
+    }
+
+    fn admit_line(&mut self) -> bool {
+        let now = std::time::Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.rate);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Write the admitted lines of `chunk`; partial lines inherit the decision
+    /// made when the line started.
+    fn write(&mut self, log: &mut impl std::io::Write, chunk: &[u8]) {
+        for segment in chunk.split_inclusive(|&b| b == b'\n') {
+            if self.at_line_start {
+                self.admitting = self.admit_line();
+                if self.admitting {
+                    self.flush_marker(log);
+                } else {
+                    self.dropped += 1;
+                }
+            }
+            if self.admitting {
+                let _ = log.write_all(segment);
+            }
+            self.at_line_start = segment.ends_with(b"\n");
+        }
+    }
+
+    fn flush_marker(&mut self, log: &mut impl std::io::Write) {
+        if self.dropped > 0 {
+            let _ = writeln!(
+                log,
+                "... rate limit exceeded, {} lines dropped ...",
+                self.dropped
+            );
+            self.dropped = 0;
+        }
+    }
+}
+
+/// Periodic status reporter for `--report-pipe` / `--report-interval-ms`.
+///
+/// When the report path is an existing FIFO, each report is written to it with a
+/// non-blocking handle (reports are skipped while no reader has the FIFO open);
+/// otherwise a regular file is created (truncated) and reports are appended so
+/// readers can `tail -f` it.
+struct StatusReporter {
+    path: std::path::PathBuf,
+    fifo: bool,
+    file: std::sync::Mutex<Option<std::fs::File>>,
+}
+
+impl StatusReporter {
+    fn create(path: &Path) -> Result<Self> {
+        #[cfg(unix)]
+        let fifo = {
+            use std::os::unix::fs::FileTypeExt;
+            std::fs::metadata(path)
+                .map(|m| m.file_type().is_fifo())
+                .unwrap_or(false)
+        };
+        #[cfg(not(unix))]
+        let fifo = false;
+        let file = if fifo {
+            None
+        } else {
+            Some(
+                create_with_cloexec(path)
+                    .with_context(|| format!("create report file {}", path.display()))?,
+            )
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            fifo,
+            file: std::sync::Mutex::new(file),
+        })
+    }
+
+    /// Write one report as a single JSON line; failures never affect the job.
+    fn report(&self, report: serde_json::Value) {
+        use std::io::Write;
+
+        let Ok(mut guard) = self.file.lock() else {
+            return;
+        };
+        if guard.is_none() && self.fifo {
+            *guard = open_fifo_nonblocking(&self.path).ok();
+        }
+        let Some(file) = guard.as_mut() else {
+            return;
+        };
+        let mut line = report.to_string().into_bytes();
+        line.push(b'\n');
+        if let Err(e) = file.write_all(&line)
+            && e.kind() != std::io::ErrorKind::WouldBlock
+        {
+            // The FIFO reader went away; reopen on the next report.
+            *guard = None;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn open_fifo_nonblocking(path: &Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    cloexec_options()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn open_fifo_nonblocking(_path: &Path) -> std::io::Result<std::fs::File> {
+    Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+}
+
+/// Record a supervisor event in `events.ndjson` when the event log is enabled.
+///
+/// Failures are logged and otherwise ignored: the event log must never affect
+/// the job itself.
+fn emit_event(events: Option<&JobDir>, kind: &str, data: serde_json::Value) {
+    if let Some(dir) = events
+        && let Err(e) = dir.append_event(kind, data)
+    {
+        warn!(job_id = %dir.job_id, kind, error = %e, "failed to append event");
+    }
+}
+
+/// Send SIGTERM to the child's process group, then SIGKILL once the
+/// `kill_after_ms` grace period plus `keep_going_ms` have elapsed (immediately
+/// when both are 0).
+///
+/// The child was placed in its own session/group via setsid, so signalling the
+/// negative PID reaches the whole process tree.
+fn terminate_process_group(
+    job_id: &str,
+    pid: u32,
+    kill_after_ms: DurationMs,
+    keep_going_ms: DurationMs,
+) {
+    #[cfg(unix)]
+    {
+        unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGTERM) };
+    }
+    // If kill_after > 0, wait kill_after ms then SIGKILL.
+    if !kill_after_ms.is_zero() {
+        std::thread::sleep(kill_after_ms.as_duration());
+        info!(job_id, "kill-after elapsed");
+    }
+    // --keep-going: give SIGTERM handlers extra time to finish their teardown.
+    if !keep_going_ms.is_zero() {
+        std::thread::sleep(keep_going_ms.as_duration());
+        info!(
+            job_id,
+            keep_going_ms = keep_going_ms.as_millis(),
+            "keep-going elapsed"
+        );
+    }
+    if !kill_after_ms.is_zero() || !keep_going_ms.is_zero() {
+        info!(job_id, "sending SIGKILL to process group");
+    }
+    #[cfg(unix)]
+    {
+        unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// Poll the `run --startup-probe` command until it exits 0, `retries` attempts
+/// have failed (0 = unlimited), or `timeout_ms` elapses; a probe still running at
+/// the deadline is killed. Returns whether the probe passed.
+fn run_startup_probe(
+    probe: &mut Command,
+    interval_ms: DurationMs,
+    timeout_ms: DurationMs,
+    retries: u32,
+) -> bool {
+    let deadline = std::time::Instant::now() + timeout_ms.as_duration();
+    let interval = interval_ms.as_duration();
+    let mut attempts = 0u32;
+    loop {
+        attempts += 1;
+        if let Ok(mut child) = probe.spawn() {
+            loop {
+                match child.try_wait() {
+                    Ok(Some(status)) if status.success() => return true,
+                    Ok(Some(_)) => break,
+                    Ok(None) if std::time::Instant::now() < deadline => {
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                    }
+                    _ => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return false;
+                    }
+                }
+            }
+        }
+        if (retries > 0 && attempts >= retries) || std::time::Instant::now() + interval >= deadline
+        {
+            return false;
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Shortest time a health-check probe may run before it is killed and counted
+/// as failed, however short the interval.
+const HEALTH_CHECK_MIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A `run --health-check` probe driven from the watcher loop without blocking
+/// it: [`HealthProbe::start`] spawns the probe and [`HealthProbe::poll`] reaps
+/// it, so timeouts and progress ticks keep running while a probe hangs.
+struct HealthProbe {
+    command: Command,
+    running: Option<(std::process::Child, std::time::Instant)>,
+}
+
+impl HealthProbe {
+    fn new(command: Command) -> Self {
+        HealthProbe {
+            command,
+            running: None,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Spawn the probe; `Some(false)` when it cannot be started at all.
+    fn start(&mut self) -> Option<bool> {
+        match self.command.spawn() {
+            Ok(child) => {
+                self.running = Some((child, std::time::Instant::now()));
+                None
+            }
+            Err(_) => Some(false),
+        }
+    }
+
+    /// The outcome once the running probe has exited (true on exit 0), or
+    /// false once it has run for `timeout` and was killed; `None` while it is
+    /// still running or when no probe is running.
+    fn poll(&mut self, timeout: std::time::Duration) -> Option<bool> {
+        let (child, started) = self.running.as_mut()?;
+        let passed = match child.try_wait() {
+            Ok(Some(status)) => status.success(),
+            Ok(None) if started.elapsed() < timeout => return None,
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                false
+            }
+        };
+        self.running = None;
+        Some(passed)
+    }
+}
+
+impl Drop for HealthProbe {
+    fn drop(&mut self) {
+        if let Some((mut child, _)) = self.running.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Open a pseudo-terminal pair sized 80x24 and return `(master, slave)`.
+///
+/// Output post-processing (`ONLCR`) is disabled on the slave so that logs keep
+/// plain `\n` line endings instead of `\r\n`.
+#[cfg(unix)]
+fn open_pty() -> Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd)> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let mut winsize = libc::winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: openpty writes two valid descriptors on success; name/termios are optional.
+    let rc = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            // Raw pointer: the winsize parameter is `*const` on Linux but `*mut` on macOS.
+            &raw mut winsize,
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error()).context("openpty");
+    }
+    // SAFETY: both descriptors were just returned by openpty and are owned here.
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+    // SAFETY: termios is plain data; tcgetattr initializes it before use.
+    unsafe {
+        use std::os::fd::AsRawFd;
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(slave.as_raw_fd(), &mut termios) == 0 {
+            termios.c_oflag &= !libc::ONLCR;
+            libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios);
+        }
+    }
+    Ok((master, slave))
+}
+
+/// Reopen `file` in append mode when `path` no longer refers to it, e.g. after
+/// `agent-exec rotate` renamed the log away and put an empty file in its place.
+fn reopen_if_rotated(file: &mut std::fs::File, path: &std::path::Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let open_id = file.metadata().map(|m| (m.dev(), m.ino()));
+        let disk_id = std::fs::metadata(path).map(|m| (m.dev(), m.ino()));
+        if let (Ok(open_id), Ok(disk_id)) = (open_id, disk_id)
+            && open_id == disk_id
+        {
+            return;
+        }
+        if let Ok(reopened) = cloexec_options().create(true).append(true).open(path) {
+            *file = reopened;
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (file, path);
+    }
 }
 
 /// Internal supervisor sub-command.
@@ -1104,6 +3872,7 @@ fn stream_to_logs<R, F>(
 pub fn supervise(opts: SuperviseOpts) -> Result<()> {
     use std::sync::{Arc, Mutex};
 
+    let phase_clock = PhaseClock::start();
     let job_id = opts.job_id;
     let root = opts.root;
     let command = opts.command;
@@ -1134,7 +3903,7 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("create dir for full.log: {}", parent.display()))?;
     }
-    let full_log_file = std::fs::File::create(&full_log_path).context("create full.log")?;
+    let full_log_file = create_with_cloexec(&full_log_path).context("create full.log")?;
     let full_log = Arc::new(Mutex::new(full_log_file));
 
     // Execute command through the shell wrapper.
@@ -1199,67 +3968,336 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
         }
     }
 
-    if opts.inherit_env {
-        // Start with the current environment (default).
-    } else {
-        child_cmd.env_clear();
-    }
-
-    // Apply env files in order.
-    for env_file in &opts.env_files {
-        let vars = load_env_file(env_file)?;
-        for (k, v) in vars {
-            child_cmd.env(&k, &v);
-        }
-    }
-
-    // Apply --env KEY=VALUE overrides (applied after env-files).
-    for env_var in &opts.env_vars {
-        let (k, v) = parse_env_var(env_var);
-        child_cmd.env(&k, &v);
-    }
+    apply_job_env(
+        &mut child_cmd,
+        opts.inherit_env,
+        &opts.env_inherit_prefix,
+        &opts.env_files,
+        &opts.env_vars,
+        opts.env_expand,
+        &opts.env_unset,
+    )?;
 
     // Set working directory if specified.
     if let Some(cwd) = opts.cwd {
         child_cmd.current_dir(cwd);
     }
 
+    // The health-check probe sees the same environment and working directory as
+    // the child, but its stdio is detached so it never mixes into the job logs.
+    let mut health_check_cmd = match opts.health_check_command.as_deref() {
+        Some([program, args @ ..]) => {
+            let mut probe = Command::new(program);
+            probe.args(args);
+            apply_job_env(
+                &mut probe,
+                opts.inherit_env,
+                &opts.env_inherit_prefix,
+                &opts.env_files,
+                &opts.env_vars,
+                opts.env_expand,
+                &opts.env_unset,
+            )?;
+            if let Some(cwd) = opts.cwd {
+                probe.current_dir(cwd);
+            }
+            probe
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null());
+            Some(HealthProbe::new(probe))
+        }
+        _ => None,
+    };
+
     // Put the child in its own process group so that timeout signals
     // (SIGTERM / SIGKILL) reach the entire process tree, not just the
     // shell wrapper.  Without this, `sh -lc "sleep 60"` would absorb
     // the signal while the grandchild (`sleep`) keeps running.
+    //
+    // With --signal-on-parent-exit the child additionally asks the kernel to
+    // deliver the configured signal when its parent (this supervisor) exits,
+    // so an unexpectedly killed supervisor does not leave an orphaned workload.
     #[cfg(unix)]
     {
         use std::os::unix::process::CommandExt;
-        // SAFETY: setsid is async-signal-safe and called before exec.
+        let parent_death_signal = opts.signal_on_parent_exit.as_deref().and_then(|name| {
+            let signum = signal_number(name);
+            if signum.is_none() {
+                warn!(
+                    job_id,
+                    signal = name,
+                    "unknown --signal-on-parent-exit signal; ignoring"
+                );
+            } else if !cfg!(target_os = "linux") {
+                warn!(
+                    job_id,
+                    signal = name,
+                    "--signal-on-parent-exit is not supported on this platform; ignoring"
+                );
+                return None;
+            }
+            signum
+        });
+        #[cfg(target_os = "linux")]
+        let supervisor_pid = std::process::id() as libc::pid_t;
+        let pty_mode = opts.pty;
+        let disable_core_dump = opts.disable_core_dump;
+        let rlimits = opts
+            .rlimits
+            .iter()
+            .map(|s| crate::rlimit::parse_rlimit(s))
+            .collect::<Result<Vec<_>>>()?;
+        let priority = opts.priority;
+        let nice = opts.nice;
+        // SAFETY: setsid, setpriority, ioprio_set, ioctl, setrlimit, prctl, getppid
+        // and raise are async-signal-safe and called before exec.
         unsafe {
-            child_cmd.pre_exec(|| {
+            child_cmd.pre_exec(move || {
                 libc::setsid();
+                // Best effort: raising priority needs privilege, and a refused
+                // request must not keep the job from starting.
+                if priority != ProcessPriority::Normal {
+                    let (nice, io_level) = match priority {
+                        ProcessPriority::High => (-10, 0),
+                        _ => (10, 7),
+                    };
+                    libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+                    #[cfg(target_os = "linux")]
+                    {
+                        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                        const IOPRIO_CLASS_BE: libc::c_int = 2;
+                        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+                        libc::syscall(
+                            libc::SYS_ioprio_set,
+                            IOPRIO_WHO_PROCESS,
+                            0,
+                            (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | io_level,
+                        );
+                    }
+                    #[cfg(not(target_os = "linux"))]
+                    let _ = io_level;
+                }
+                if let Some(nice) = nice {
+                    libc::setpriority(libc::PRIO_PROCESS, 0, nice.into());
+                }
+                if disable_core_dump {
+                    let no_core = libc::rlimit {
+                        rlim_cur: 0,
+                        rlim_max: 0,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_CORE, &no_core) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                for &(resource, soft, hard) in &rlimits {
+                    let limit = libc::rlimit {
+                        rlim_cur: soft,
+                        rlim_max: hard,
+                    };
+                    if libc::setrlimit(resource as _, &limit) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                // Make the PTY slave (already dup'ed onto stdout) the controlling terminal.
+                if pty_mode {
+                    libc::ioctl(1, libc::TIOCSCTTY as _, 0);
+                }
+                #[cfg(target_os = "linux")]
+                if let Some(signum) = parent_death_signal {
+                    if libc::prctl(libc::PR_SET_PDEATHSIG, signum as libc::c_ulong) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    // The supervisor may have exited between fork and prctl.
+                    if libc::getppid() != supervisor_pid {
+                        libc::raise(signum);
+                    }
+                }
+                #[cfg(not(target_os = "linux"))]
+                let _ = parent_death_signal;
                 Ok(())
             });
         }
     }
+    #[cfg(not(unix))]
+    if !opts.rlimits.is_empty() {
+        warn!(
+            job_id,
+            "--ulimit is not supported on this platform; ignoring"
+        );
+    }
+    #[cfg(not(unix))]
+    if opts.signal_on_parent_exit.is_some() {
+        warn!(
+            job_id,
+            "--signal-on-parent-exit is not supported on this platform; ignoring"
+        );
+    }
 
     // Spawn the child with piped stdout/stderr so we can tee to logs.
-    let child_stdin = open_child_stdin(&job_dir, opts.stdin_file.as_deref())?;
-    let mut child = child_cmd
-        .stdin(child_stdin)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .context("supervisor: spawn child")?;
+    //
+    // With --pty the child's stdout/stderr (and stdin, unless materialized stdin
+    // is configured) are attached to the slave side of a pseudo-terminal instead,
+    // so programs that buffer when not on a TTY flush line by line. The combined
+    // output is read from the master side and logged as stdout.
+    #[cfg(unix)]
+    let pty_master = if opts.pty {
+        let (master, slave) = open_pty()?;
+        let child_stdin = if opts.stdin_mode != StdinMode::Null {
+            open_child_stdin(&job_dir, &opts.stdin_mode)?
+        } else {
+            std::process::Stdio::from(slave.try_clone().context("dup pty slave")?)
+        };
+        child_cmd
+            .stdin(child_stdin)
+            .stdout(std::process::Stdio::from(
+                slave.try_clone().context("dup pty slave")?,
+            ))
+            .stderr(std::process::Stdio::from(slave));
+        Some(master)
+    } else {
+        None
+    };
+    #[cfg(not(unix))]
+    let pty_master: Option<std::fs::File> = {
+        if opts.pty {
+            warn!(
+                job_id,
+                "--pty is not supported on this platform; using pipes"
+            );
+        }
+        None
+    };
+    // With --stdout-file the child writes stdout straight into that file; no
+    // pipe or log thread copies it (and it does not appear in full.log).
+    let stdout_file = match opts.stdout_file {
+        Some(ref path) if pty_master.is_none() => Some(open_stdout_file(&job_dir, path)?),
+        _ => None,
+    };
+    if pty_master.is_none() {
+        let child_stdin = open_child_stdin(&job_dir, &opts.stdin_mode)?;
+        let child_stdout = match stdout_file {
+            Some(ref file) => {
+                std::process::Stdio::from(file.try_clone().context("dup stdout file")?)
+            }
+            None => std::process::Stdio::piped(),
+        };
+        child_cmd
+            .stdin(child_stdin)
+            .stdout(child_stdout)
+            .stderr(std::process::Stdio::piped());
+    }
+    // --pid-namespace: unshare(CLONE_NEWPID) only moves processes forked
+    // afterwards, so this thread enters the namespace for the one spawn below
+    // (making the child its PID 1) and then restores its own.
+    #[cfg(target_os = "linux")]
+    let previous_pid_ns = if opts.pid_namespace {
+        match enter_pid_namespace() {
+            Ok(previous) => Some(previous),
+            Err(e) if opts.require_pid_namespace => {
+                mark_job_failed(&job_dir);
+                return Err(anyhow::Error::new(e)
+                    .context("supervisor: --require-pid-namespace: unshare(CLONE_NEWPID)"));
+            }
+            Err(e) => {
+                warn!(
+                    job_id,
+                    error = %e,
+                    "--pid-namespace unavailable; running without PID isolation"
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    #[cfg(not(target_os = "linux"))]
+    if opts.pid_namespace {
+        if opts.require_pid_namespace {
+            mark_job_failed(&job_dir);
+            anyhow::bail!("supervisor: --require-pid-namespace is only supported on Linux");
+        }
+        warn!(
+            job_id,
+            "--pid-namespace is not supported on this platform; ignoring"
+        );
+    }
+    let child_spawn_at = std::time::Instant::now();
+    let spawned = child_cmd.spawn();
+    #[cfg(target_os = "linux")]
+    if let Some(previous) = previous_pid_ns
+        && let Err(e) = leave_pid_namespace(&previous)
+    {
+        warn!(job_id, error = %e, "failed to restore supervisor PID namespace");
+    }
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            mark_job_failed(&job_dir);
+            return Err(e).context("supervisor: spawn child");
+        }
+    };
+    // Release the parent's copies of the child stdio (notably the PTY slave) so
+    // that reading the PTY master reaches EOF once the child side closes.
+    drop(child_cmd);
 
     let pid = child.id();
+    let sampler = opts.profile_output.as_ref().map(|_| {
+        if !cfg!(target_os = "linux") {
+            warn!(
+                job_id,
+                "--profile-output sampling is only supported on Linux; the profile will be empty"
+            );
+        }
+        crate::profile::Sampler::start(pid)
+    });
     info!(job_id, pid, "child process started");
 
+    // Structured event log (--event-log). Each thread gets its own handle; the
+    // file itself is opened in append mode per event.
+    let event_log = || {
+        opts.event_log.then(|| JobDir {
+            path: job_dir.path.clone(),
+            job_id: job_id.to_string(),
+        })
+    };
+    if let Some(ref events) = event_log() {
+        // Start every supervision with a fresh event log (e.g. after restart).
+        let _ = std::fs::remove_file(events.events_path());
+    }
+    let events = event_log();
+    emit_event(events.as_ref(), "spawn", serde_json::json!({ "pid": pid }));
+
+    // Status reports (--report-pipe): failure to set up the report target is
+    // logged but never prevents the job from running.
+    let reporter = match opts.report_pipe.as_deref() {
+        Some(path) if !opts.report_interval_ms.is_zero() => {
+            match StatusReporter::create(Path::new(path)) {
+                Ok(r) => Some(Arc::new(r)),
+                Err(e) => {
+                    warn!(job_id, error = %e, "status reports disabled");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    #[cfg(windows)]
+    set_priority_class(&child, opts.priority, opts.nice);
+
     // On Windows, assign child to a named Job Object for process-tree management.
     // The job name is derived from the job_id so that `kill` can look it up.
     // Assignment is a MUST requirement on Windows: if it fails, the supervisor
     // kills the child process and updates state.json to "failed" before returning
     // an error, so that the run front-end (which may have already returned) can
-    // detect the failure via state.json on next poll.
+    // detect the failure via state.json on next poll. `--windows-no-job-object`
+    // opts out entirely; process-tree management is then best-effort.
     #[cfg(windows)]
-    let windows_job_name = {
+    let windows_job_name = if opts.windows_no_job_object {
+        info!(job_id, "supervisor: Job Object assignment disabled");
+        None
+    } else {
         match assign_to_job_object(job_id, pid) {
             Ok(name) => Some(name),
             Err(e) => {
@@ -1279,12 +4317,22 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
                         exit_code: None,
                         signal: None,
                         duration_ms: None,
+                        cpu_user_ms: None,
+                        cpu_sys_ms: None,
+                        max_rss_kb: None,
                     },
                     pid: Some(pid),
+                    created_at: Some(meta.created_at.clone()),
                     finished_at: Some(now_rfc3339()),
                     updated_at: now_rfc3339(),
                     logs_drained: true,
                     windows_job_name: None,
+                    killed_reason: None,
+                    captured_stdout: None,
+                    captured_stdout_truncated: false,
+                    agent_version: Some(crate::schema::AGENT_EXEC_VERSION.to_string()),
+                    webhook_status_code: None,
+                    resource_usage: None,
                 };
                 // Best-effort: if writing state fails, we still propagate the
                 // original assignment error.
@@ -1296,7 +4344,7 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
                 if opts.notify_command.is_some() || opts.notify_file.is_some() {
                     let finished_at_ts =
                         failed_state.finished_at.clone().unwrap_or_else(now_rfc3339);
-                    let stdout_log = job_dir.stdout_path().display().to_string();
+                    let stdout_log = stdout_log_path(&job_dir, &meta);
                     let stderr_log = job_dir.stderr_path().display().to_string();
                     let fail_event = crate::schema::CompletionEvent {
                         schema_version: crate::schema::SCHEMA_VERSION.to_string(),
@@ -1337,10 +4385,16 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
                             &fail_event_path,
                             &opts.shell_wrapper,
                             "job.finished",
+                            meta.notify_timeout_ms
+                                .map_or(DEFAULT_NOTIFY_TIMEOUT_MS, DurationMs::from_millis),
                         ));
                     }
                     if let Some(ref file_path) = opts.notify_file {
-                        fail_delivery_results.push(dispatch_file_sink(file_path, &fail_event_json));
+                        fail_delivery_results.push(if meta.notify_file_snapshot {
+                            dispatch_snapshot_sink(file_path, &failed_state, &meta)
+                        } else {
+                            dispatch_file_sink(file_path, &fail_event_json, meta.notify_file_append)
+                        });
                     }
                     if let Err(we) = job_dir.write_completion_event_atomic(
                         &crate::schema::CompletionEventRecord {
@@ -1374,9 +4428,10 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
     let windows_job_name: Option<String> = None;
 
     // Update state.json with real child PID and Windows Job Object name.
-    // On Windows, windows_job_name is always Some at this point (guaranteed
-    // by the MUST requirement above), so state.json will always contain the
-    // Job Object identifier while the job is running.
+    // On Windows, windows_job_name is Some at this point unless
+    // `--windows-no-job-object` was given (guaranteed by the MUST requirement
+    // above), so state.json contains the Job Object identifier while the job
+    // is running.
     let state = JobState {
         job: JobStateJob {
             id: job_id.to_string(),
@@ -1387,20 +4442,56 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
             exit_code: None,
             signal: None,
             duration_ms: None,
+            cpu_user_ms: None,
+            cpu_sys_ms: None,
+            max_rss_kb: None,
         },
         pid: Some(pid),
+        created_at: Some(meta.created_at.clone()),
         finished_at: None,
         updated_at: now_rfc3339(),
         logs_drained: true,
         windows_job_name,
+        killed_reason: None,
+        captured_stdout: None,
+        captured_stdout_truncated: false,
+        agent_version: Some(crate::schema::AGENT_EXEC_VERSION.to_string()),
+        webhook_status_code: None,
+        resource_usage: None,
     };
     job_dir.write_state(&state)?;
+    if let Err(e) = job_dir.write_child_pid(pid) {
+        warn!(job_id, error = %e, "failed to write child.pid");
+    }
 
     let child_start_time = std::time::Instant::now();
 
     // Take stdout/stderr handles before moving child.
-    let child_stdout = child.stdout.take().expect("child stdout piped");
-    let child_stderr = child.stderr.take().expect("child stderr piped");
+    type LogSource = Box<dyn std::io::Read + Send>;
+    let (child_stdout, child_stderr): (LogSource, LogSource) = match pty_master {
+        Some(master) => (
+            Box::new(std::fs::File::from(master)),
+            Box::new(std::io::empty()),
+        ),
+        None if stdout_file.is_some() => (
+            Box::new(std::io::empty()),
+            Box::new(child.stderr.take().expect("child stderr piped")),
+        ),
+        None => (
+            Box::new(child.stdout.take().expect("child stdout piped")),
+            Box::new(child.stderr.take().expect("child stderr piped")),
+        ),
+    };
+    // Record when the first output byte arrives on either stream (timing.json).
+    let first_output_at = Arc::new(std::sync::OnceLock::new());
+    let child_stdout: LogSource = Box::new(FirstOutputReader {
+        inner: child_stdout,
+        first_output_at: Arc::clone(&first_output_at),
+    });
+    let child_stderr: LogSource = Box::new(FirstOutputReader {
+        inner: child_stderr,
+        first_output_at: Arc::clone(&first_output_at),
+    });
 
     // Create shared output-match checker from the initial meta notification config.
     let match_checker = std::sync::Arc::new(OutputMatchChecker::new(
@@ -1418,14 +4509,29 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
     // Thread: read stdout, write to stdout.log and full.log.
     let stdout_log_path = job_dir.stdout_path();
     let full_log_stdout = Arc::clone(&full_log);
+    let full_log_path_stdout = full_log_path.clone();
     let match_checker_stdout = std::sync::Arc::clone(&match_checker);
+    let max_output_lines_per_second = opts.max_output_lines_per_second;
+    let log_buffering = opts.log_buffering;
+    let max_line_length = opts.max_line_length;
+    let rotation = LogRotation {
+        size_bytes: opts.log_rotate_size_bytes,
+        count: opts.log_rotate_count,
+    };
+    let events_stdout = event_log();
     let t_stdout = std::thread::spawn(move || {
         stream_to_logs(
             child_stdout,
             &stdout_log_path,
             full_log_stdout,
+            &full_log_path_stdout,
             "STDOUT",
             Some(move |line: &str| match_checker_stdout.check_line(line, "stdout")),
+            events_stdout.as_ref(),
+            max_output_lines_per_second,
+            log_buffering,
+            max_line_length,
+            rotation,
         );
         let _ = tx_stdout_done.send(());
     });
@@ -1433,14 +4539,22 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
     // Thread: read stderr, write to stderr.log and full.log.
     let stderr_log_path = job_dir.stderr_path();
     let full_log_stderr = Arc::clone(&full_log);
+    let full_log_path_stderr = full_log_path.clone();
     let match_checker_stderr = std::sync::Arc::clone(&match_checker);
+    let events_stderr = event_log();
     let t_stderr = std::thread::spawn(move || {
         stream_to_logs(
             child_stderr,
             &stderr_log_path,
             full_log_stderr,
+            &full_log_path_stderr,
             "STDERR",
             Some(move |line: &str| match_checker_stderr.check_line(line, "stderr")),
+            events_stderr.as_ref(),
+            max_output_lines_per_second,
+            log_buffering,
+            max_line_length,
+            rotation,
         );
         let _ = tx_stderr_done.send(());
     });
@@ -1449,33 +4563,72 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
     // We spawn a watcher thread to handle timeout and periodic state.json updates.
     let timeout_ms = opts.timeout_ms;
     let kill_after_ms = opts.kill_after_ms;
+    let keep_going_ms = opts.keep_going_ms;
     let progress_every_ms = opts.progress_every_ms;
+    let health_check_interval_ms = opts.health_check_interval_ms;
+    let health_check_retries = opts.health_check_retries;
+    let report_interval_ms = opts.report_interval_ms;
+    let reporter_watcher = reporter.clone();
+    let checkpoint_dir = opts.checkpoint_dir.as_deref().map(std::path::PathBuf::from);
+    if let Some(ref dir) = checkpoint_dir
+        && let Err(e) = std::fs::create_dir_all(dir)
+    {
+        warn!(job_id, error = %e, "failed to create checkpoint directory");
+    }
+    let checkpoint_watcher = checkpoint_dir.clone();
+    let checkpoint_interval_ms = opts.checkpoint_interval_ms;
+    let checkpoint_count = opts.checkpoint_count;
+    let checkpoint_encoding = meta.output_encoding.clone();
     let watcher_job_dir = JobDir {
         path: job_dir.path.clone(),
         job_id: job_id.to_string(),
     };
     let job_id_str = job_id.to_string();
+    let events_watcher = event_log();
 
     // Use an atomic flag to signal the watcher thread when the child has exited.
     use std::sync::atomic::{AtomicBool, Ordering};
     let child_done = Arc::new(AtomicBool::new(false));
+    // Set by the watcher when it terminates the job after failed health checks.
+    let health_check_failed = Arc::new(AtomicBool::new(false));
+    // Whether the child is stopped (SIGSTOP), as last reported by wait4.
+    let child_stopped = Arc::new(AtomicBool::new(false));
+    // Serializes the read-modify-write updates of state.json made while the
+    // child runs (progress ticks, stop/continue) with the terminal write.
+    let state_lock = Arc::new(Mutex::new(()));
+
+    // SIGUSR1 only sets a flag; the watcher writes the snapshot.
+    let snapshot_on_sigusr1 = cfg!(unix) && opts.snapshot_on_sigusr1;
+    #[cfg(unix)]
+    if snapshot_on_sigusr1 && let Err(e) = install_snapshot_signal_handler() {
+        warn!(job_id, error = %e, "failed to install SIGUSR1 snapshot handler");
+    }
 
-    let watcher = if timeout_ms > 0 || progress_every_ms > 0 {
+    let watcher = if !timeout_ms.is_zero()
+        || !progress_every_ms.is_zero()
+        || health_check_cmd.is_some()
+        || reporter_watcher.is_some()
+        || checkpoint_watcher.is_some()
+        || snapshot_on_sigusr1
+    {
         let child_done_clone = Arc::clone(&child_done);
+        let health_check_failed_clone = Arc::clone(&health_check_failed);
+        let child_stopped_clone = Arc::clone(&child_stopped);
+        let state_lock_watcher = Arc::clone(&state_lock);
         Some(std::thread::spawn(move || {
             let start = std::time::Instant::now();
-            let timeout_dur = if timeout_ms > 0 {
-                Some(std::time::Duration::from_millis(timeout_ms))
-            } else {
-                None
-            };
-            let progress_dur = if progress_every_ms > 0 {
-                Some(std::time::Duration::from_millis(progress_every_ms))
-            } else {
-                None
-            };
+            let timeout_dur = (!timeout_ms.is_zero()).then(|| timeout_ms.as_duration());
+            let progress_dur =
+                (!progress_every_ms.is_zero()).then(|| progress_every_ms.as_duration());
 
             let poll_interval = std::time::Duration::from_millis(100);
+            let health_check_dur = health_check_interval_ms.as_duration();
+            let mut last_health_check = std::time::Instant::now();
+            let mut consecutive_failures: u32 = 0;
+            let report_dur = report_interval_ms.as_duration();
+            let mut last_report = std::time::Instant::now();
+            let checkpoint_dur = checkpoint_interval_ms.as_duration();
+            let mut last_checkpoint = std::time::Instant::now();
 
             loop {
                 std::thread::sleep(poll_interval);
@@ -1492,28 +4645,48 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
                     && elapsed >= td
                 {
                     info!(job_id = %job_id_str, "timeout reached, sending SIGTERM to process group");
-                    // Send SIGTERM to the entire process group (negative PID).
-                    // The child was placed in its own session/group via setsid.
-                    #[cfg(unix)]
-                    {
-                        unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGTERM) };
+                    emit_event(
+                        events_watcher.as_ref(),
+                        "timeout_signal",
+                        serde_json::json!({ "signal": "SIGTERM", "elapsed_ms": elapsed.as_millis() as u64 }),
+                    );
+                    terminate_process_group(&job_id_str, pid, kill_after_ms, keep_going_ms);
+                    break;
+                }
+
+                // Health check: start the probe every interval and reap it on later
+                // ticks; a probe still running after the interval (at least
+                // HEALTH_CHECK_MIN_TIMEOUT) is killed and counts as failed. The job is
+                // terminated once `health_check_retries` checks failed in a row.
+                let health_check_outcome = match health_check_cmd {
+                    Some(ref mut probe) if probe.is_running() => {
+                        probe.poll(health_check_dur.max(HEALTH_CHECK_MIN_TIMEOUT))
                     }
-                    // If kill_after > 0, wait kill_after ms then SIGKILL.
-                    if kill_after_ms > 0 {
-                        std::thread::sleep(std::time::Duration::from_millis(kill_after_ms));
-                        info!(job_id = %job_id_str, "kill-after elapsed, sending SIGKILL to process group");
-                        #[cfg(unix)]
-                        {
-                            unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
-                        }
+                    Some(ref mut probe) if last_health_check.elapsed() >= health_check_dur => {
+                        last_health_check = std::time::Instant::now();
+                        probe.start()
+                    }
+                    _ => None,
+                };
+                if let Some(passed) = health_check_outcome {
+                    if passed {
+                        consecutive_failures = 0;
                     } else {
-                        // Immediate SIGKILL to the process group.
-                        #[cfg(unix)]
+                        consecutive_failures += 1;
+                        warn!(
+                            job_id = %job_id_str,
+                            consecutive_failures,
+                            "health check failed"
+                        );
+                        if consecutive_failures >= health_check_retries
+                            && !child_done_clone.load(Ordering::Relaxed)
                         {
-                            unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
+                            info!(job_id = %job_id_str, "health check retries exhausted, sending SIGTERM to process group");
+                            health_check_failed_clone.store(true, Ordering::Relaxed);
+                            terminate_process_group(&job_id_str, pid, kill_after_ms, keep_going_ms);
+                            break;
                         }
                     }
-                    break;
                 }
 
                 // Progress-every: update updated_at periodically.
@@ -1524,10 +4697,68 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
                     if elapsed_ms % pd_ms < poll_ms {
                         // Read, update updated_at, write back atomically so concurrent
                         // status/wait/run observers never parse a truncated state file.
-                        if let Ok(mut st) = watcher_job_dir.read_state() {
+                        // Under the state lock, and never once the terminal state may
+                        // have been written.
+                        let _guard = state_lock_watcher.lock();
+                        if !child_done_clone.load(Ordering::Relaxed)
+                            && let Ok(mut st) = watcher_job_dir.read_state()
+                        {
                             st.updated_at = now_rfc3339();
                             let _ = watcher_job_dir.write_state(&st);
                         }
+                        emit_event(
+                            events_watcher.as_ref(),
+                            "progress_tick",
+                            serde_json::json!({ "elapsed_ms": elapsed_ms }),
+                        );
+                    }
+                }
+
+                // Status reports: one JSON line per report interval.
+                if let Some(ref reporter) = reporter_watcher
+                    && last_report.elapsed() >= report_dur
+                {
+                    last_report = std::time::Instant::now();
+                    let log_len =
+                        |p: std::path::PathBuf| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+                    let status = if child_stopped_clone.load(Ordering::Relaxed) {
+                        JobStatus::Stopped
+                    } else {
+                        JobStatus::Running
+                    };
+                    reporter.report(serde_json::json!({
+                        "ts": now_rfc3339(),
+                        "state": status.as_str(),
+                        "stdout_bytes": log_len(watcher_job_dir.stdout_path()),
+                        "stderr_bytes": log_len(watcher_job_dir.stderr_path()),
+                    }));
+                }
+
+                // Checkpoints: state + log snapshot every interval, oldest pruned.
+                if let Some(ref dir) = checkpoint_watcher
+                    && last_checkpoint.elapsed() >= checkpoint_dur
+                {
+                    last_checkpoint = std::time::Instant::now();
+                    let label = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_millis());
+                    let written = watcher_job_dir
+                        .write_checkpoint(dir, &label.to_string(), &checkpoint_encoding)
+                        .and_then(|_| watcher_job_dir.prune_checkpoints(dir, checkpoint_count));
+                    if let Err(e) = written {
+                        warn!(job_id = %job_id_str, error = %e, "failed to write checkpoint");
+                    }
+                }
+
+                #[cfg(unix)]
+                if snapshot_on_sigusr1 && SNAPSHOT_REQUESTED.swap(false, Ordering::Relaxed) {
+                    match watcher_job_dir.write_manual_snapshot(&checkpoint_encoding) {
+                        Ok(path) => {
+                            info!(job_id = %job_id_str, path = %path.display(), "manual snapshot written")
+                        }
+                        Err(e) => {
+                            warn!(job_id = %job_id_str, error = %e, "failed to write manual snapshot")
+                        }
                     }
                 }
             }
@@ -1536,8 +4767,41 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
         None
     };
 
-    // Wait for child to finish.
-    let exit_status = child.wait().context("wait for child")?;
+    // Wait for child to finish. `kill --signal STOP/CONT` only signals the job;
+    // the supervisor is the one that records each stop and resume it observes.
+    let (exit_status, usage) = wait_with_usage(child, |stopped| {
+        child_stopped.store(stopped, Ordering::Relaxed);
+        let status = if stopped {
+            JobStatus::Stopped
+        } else {
+            JobStatus::Running
+        };
+        let _guard = state_lock.lock();
+        match job_dir.read_state() {
+            Ok(mut st) if st.status().is_active() => {
+                st.job.status = status.clone();
+                st.updated_at = now_rfc3339();
+                if let Err(e) = job_dir.write_state(&st) {
+                    warn!(job_id, error = %e, "failed to record stop/continue");
+                }
+            }
+            _ => {}
+        }
+        info!(job_id, state = status.as_str(), "child stop state changed");
+    })?;
+    let child_exited_at = std::time::Instant::now();
+    if let Err(e) = job_dir.remove_child_pid() {
+        warn!(job_id, error = %e, "failed to remove child.pid");
+    }
+    let resource_usage = sampler.map(|sampler| {
+        let profile = sampler.finish();
+        if let Some(ref path) = opts.profile_output
+            && let Err(e) = profile.write_folded(std::path::Path::new(path))
+        {
+            warn!(job_id, error = %e, "failed to write profile output");
+        }
+        profile.resource_usage()
+    });
 
     // Signal the watcher that the child has finished so it can exit its loop.
     child_done.store(true, Ordering::Relaxed);
@@ -1564,6 +4828,15 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
     };
     #[cfg(not(unix))]
     let (terminal_status, signal_name) = (JobStatus::Exited, None::<String>);
+    // `--require-clean-exit`: a non-zero exit is a failure; the code is kept.
+    let terminal_status = if opts.require_clean_exit
+        && terminal_status == JobStatus::Exited
+        && exit_code != Some(0)
+    {
+        JobStatus::Failed
+    } else {
+        terminal_status
+    };
 
     let mut state = JobState {
         job: JobStateJob {
@@ -1575,14 +4848,39 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
             exit_code,
             signal: signal_name.clone(),
             duration_ms: Some(duration_ms),
+            cpu_user_ms: usage.cpu_user_ms,
+            cpu_sys_ms: usage.cpu_sys_ms,
+            max_rss_kb: usage.max_rss_kb,
         },
         pid: Some(pid),
+        created_at: Some(meta.created_at.clone()),
         finished_at: Some(finished_at.clone()),
         updated_at: now_rfc3339(),
         logs_drained: false,
         windows_job_name: None, // not needed after process exits
+        killed_reason: health_check_failed
+            .load(Ordering::Relaxed)
+            .then(|| "health_check_failed".to_string()),
+        captured_stdout: None,
+        captured_stdout_truncated: false,
+        agent_version: Some(crate::schema::AGENT_EXEC_VERSION.to_string()),
+        webhook_status_code: None,
+        resource_usage,
     };
-    job_dir.write_state(&state)?;
+    // Record the exit event first so it is present once state.json turns terminal.
+    emit_event(
+        events.as_ref(),
+        "exit",
+        serde_json::json!({
+            "exit_code": exit_code,
+            "signal": signal_name,
+            "duration_ms": duration_ms,
+        }),
+    );
+    {
+        let _guard = state_lock.lock();
+        job_dir.write_state(&state)?;
+    }
     info!(job_id, ?exit_code, "child process finished");
 
     // Bounded join for log-reader threads.
@@ -1616,6 +4914,73 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
         drop(t_stderr); // detach: descendant holds the pipe open
     }
 
+    // Persist the drained logs before declaring them drained, so a caller that
+    // sees `logs_drained` and immediately tails the job reads the final output.
+    sync_log_files(&[
+        job_dir.stdout_path(),
+        job_dir.stderr_path(),
+        full_log_path.clone(),
+    ]);
+
+    let timing = crate::schema::JobTiming {
+        supervisor_start: phase_clock.rfc3339(phase_clock.start),
+        child_spawn: phase_clock.rfc3339(child_spawn_at),
+        child_started: first_output_at.get().map(|&at| phase_clock.rfc3339(at)),
+        child_exited: phase_clock.rfc3339(child_exited_at),
+        logs_flushed: phase_clock.rfc3339(std::time::Instant::now()),
+    };
+    if let Err(e) = job_dir.write_timing(&timing) {
+        warn!(job_id, error = %e, "failed to write timing.json");
+    }
+
+    // Written before logs_drained flips so `wait` returning implies the report exists.
+    if let Some(ref report_file) = opts.report_file {
+        let report = build_job_report(&job_dir, &meta, &state);
+        if let Err(e) = write_job_report(std::path::Path::new(report_file), &report) {
+            warn!(job_id, error = %e, "failed to write report file");
+        }
+    }
+
+    if opts.capture_output {
+        let captured = job_dir.read_tail_metrics(
+            "stdout.log",
+            0,
+            opts.capture_output_max_bytes,
+            &meta.output_encoding,
+        );
+        state.captured_stdout_truncated = captured.observed_bytes > opts.capture_output_max_bytes;
+        state.captured_stdout = Some(captured.tail);
+    }
+
+    let event = crate::schema::CompletionEvent {
+        schema_version: crate::schema::SCHEMA_VERSION.to_string(),
+        event_type: "job.finished".to_string(),
+        job_id: job_id.to_string(),
+        state: terminal_status.as_str().to_string(),
+        command: meta.command.clone(),
+        cwd: meta.cwd.clone(),
+        started_at,
+        finished_at,
+        duration_ms: Some(duration_ms),
+        exit_code,
+        signal: signal_name,
+        stdout_log_path: self::stdout_log_path(&job_dir, &meta),
+        stderr_log_path: job_dir.stderr_path().display().to_string(),
+    };
+    let event_json = serde_json::to_string(&event).unwrap_or_default();
+
+    // Delivered before logs_drained flips so `wait` returning implies
+    // `webhook_status_code` is recorded; the webhook timeout bounds the delay.
+    let webhook_result = opts.completion_webhook_url.as_ref().map(|url| {
+        let (result, status_code) =
+            dispatch_webhook_sink(url, &event_json, job_id, opts.completion_webhook_timeout_ms);
+        if let Some(ref e) = result.error {
+            warn!(job_id, url = %url, error = %e, "completion webhook failed");
+        }
+        state.webhook_status_code = status_code;
+        result
+    });
+
     state.logs_drained = true;
     state.updated_at = now_rfc3339();
     job_dir.write_state(&state)?;
@@ -1625,6 +4990,21 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
         let _ = w.join();
     }
 
+    if let Some(ref dir) = checkpoint_dir
+        && let Err(e) = job_dir.write_checkpoint(dir, "final", &meta.output_encoding)
+    {
+        warn!(job_id, error = %e, "failed to write final checkpoint");
+    }
+
+    // Final status report; the report target is closed when `reporter` drops.
+    if let Some(ref reporter) = reporter {
+        reporter.report(serde_json::json!({
+            "ts": now_rfc3339(),
+            "state": terminal_status.as_str(),
+            "exit_code": exit_code,
+        }));
+    }
+
     // Reload the latest notification config from meta.json to pick up any post-creation
     // updates (e.g. from `notify set` invoked after the job was launched).
     let latest_notification = job_dir.read_meta().ok().and_then(|m| m.notification);
@@ -1635,27 +5015,10 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
 
     // Dispatch completion event to configured notification sinks.
     // Failure here must not alter job state (delivery result is recorded separately).
-    let has_notification = current_notify_command.is_some() || current_notify_file.is_some();
+    let has_notification = current_notify_command.is_some()
+        || current_notify_file.is_some()
+        || opts.completion_webhook_url.is_some();
     if has_notification {
-        let stdout_log = job_dir.stdout_path().display().to_string();
-        let stderr_log = job_dir.stderr_path().display().to_string();
-        let event = crate::schema::CompletionEvent {
-            schema_version: crate::schema::SCHEMA_VERSION.to_string(),
-            event_type: "job.finished".to_string(),
-            job_id: job_id.to_string(),
-            state: terminal_status.as_str().to_string(),
-            command: meta.command.clone(),
-            cwd: meta.cwd.clone(),
-            started_at,
-            finished_at,
-            duration_ms: Some(duration_ms),
-            exit_code,
-            signal: signal_name,
-            stdout_log_path: stdout_log,
-            stderr_log_path: stderr_log,
-        };
-
-        let event_json = serde_json::to_string(&event).unwrap_or_default();
         let event_path = job_dir.completion_event_path().display().to_string();
         let mut delivery_results: Vec<crate::schema::SinkDeliveryResult> = Vec::new();
 
@@ -1677,10 +5040,19 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
                 &event_path,
                 &opts.shell_wrapper,
                 "job.finished",
+                meta.notify_timeout_ms
+                    .map_or(DEFAULT_NOTIFY_TIMEOUT_MS, DurationMs::from_millis),
             ));
         }
+        delivery_results.extend(webhook_result);
+        // The file sink goes last so a snapshot carries every field the other
+        // sinks record in `state.json` (e.g. `webhook_status_code`).
         if let Some(ref file_path) = current_notify_file {
-            delivery_results.push(dispatch_file_sink(file_path, &event_json));
+            delivery_results.push(if meta.notify_file_snapshot {
+                dispatch_snapshot_sink(file_path, &state, &meta)
+            } else {
+                dispatch_file_sink(file_path, &event_json, meta.notify_file_append)
+            });
         }
 
         // Update completion_event.json with delivery results.
@@ -1702,7 +5074,8 @@ pub fn supervise(opts: SuperviseOpts) -> Result<()> {
 /// AGENT_EXEC_EVENT_TYPE env vars.
 ///
 /// The shell wrapper argv (e.g. `["sh", "-lc"]`) is provided by the caller.
-/// The command string is appended as the final argument to the wrapper.
+/// The command string is appended as the final argument to the wrapper. A
+/// command still running after `timeout_ms` is killed and recorded as failed.
 fn dispatch_command_sink(
     shell_cmd: &str,
     event_json: &str,
@@ -1710,6 +5083,7 @@ fn dispatch_command_sink(
     event_path: &str,
     shell_wrapper: &[String],
     event_type: &str,
+    timeout_ms: DurationMs,
 ) -> crate::schema::SinkDeliveryResult {
     use std::io::Write;
     let attempted_at = now_rfc3339();
@@ -1722,6 +5096,7 @@ fn dispatch_command_sink(
             success: false,
             error: Some("empty shell command".to_string()),
             attempted_at,
+            exit_code: None,
         };
     }
 
@@ -1732,6 +5107,7 @@ fn dispatch_command_sink(
             success: false,
             error: Some("shell wrapper must not be empty".to_string()),
             attempted_at,
+            exit_code: None,
         };
     }
 
@@ -1750,13 +5126,29 @@ fn dispatch_command_sink(
             if let Some(mut stdin) = child.stdin.take() {
                 let _ = stdin.write_all(event_json.as_bytes());
             }
-            match child.wait() {
+            let deadline = std::time::Instant::now() + timeout_ms.as_duration();
+            let waited = loop {
+                match child.try_wait() {
+                    Ok(Some(status)) => break Ok(status),
+                    Ok(None) if std::time::Instant::now() < deadline => {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                    }
+                    Ok(None) => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        break Err(format!("timed out after {timeout_ms} ms"));
+                    }
+                    Err(e) => break Err(format!("wait error: {e}")),
+                }
+            };
+            match waited {
                 Ok(status) if status.success() => crate::schema::SinkDeliveryResult {
                     sink_type: "command".to_string(),
                     target,
                     success: true,
                     error: None,
                     attempted_at,
+                    exit_code: status.code(),
                 },
                 Ok(status) => crate::schema::SinkDeliveryResult {
                     sink_type: "command".to_string(),
@@ -1764,13 +5156,15 @@ fn dispatch_command_sink(
                     success: false,
                     error: Some(format!("exited with status {status}")),
                     attempted_at,
+                    exit_code: status.code(),
                 },
-                Err(e) => crate::schema::SinkDeliveryResult {
+                Err(error) => crate::schema::SinkDeliveryResult {
                     sink_type: "command".to_string(),
                     target,
                     success: false,
-                    error: Some(format!("wait error: {e}")),
+                    error: Some(error),
                     attempted_at,
+                    exit_code: None,
                 },
             }
         }
@@ -1780,13 +5174,100 @@ fn dispatch_command_sink(
             success: false,
             error: Some(format!("spawn error: {e}")),
             attempted_at,
+            exit_code: None,
         },
     }
 }
 
-/// Dispatch the file sink: append event JSON as a single NDJSON line.
-/// Creates parent directories automatically.
-fn dispatch_file_sink(file_path: &str, event_json: &str) -> crate::schema::SinkDeliveryResult {
+/// Dispatch the snapshot file sink: atomically replace `file_path` with the
+/// final `state` plus `meta`, so a watcher never reads a partial file.
+fn dispatch_snapshot_sink(
+    file_path: &str,
+    state: &JobState,
+    meta: &JobMeta,
+) -> crate::schema::SinkDeliveryResult {
+    let attempted_at = now_rfc3339();
+    let path = std::path::Path::new(file_path);
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    let snapshot = crate::schema::CompletionSnapshot {
+        state: state.clone(),
+        meta: meta.clone(),
+    };
+    let written = std::fs::create_dir_all(dir)
+        .with_context(|| format!("create {}", dir.display()))
+        .and_then(|_| serde_json::to_vec_pretty(&snapshot).context("serialize snapshot"))
+        .and_then(|bytes| crate::jobstore::write_atomic(dir, path, &bytes));
+    crate::schema::SinkDeliveryResult {
+        sink_type: "file".to_string(),
+        target: file_path.to_string(),
+        success: written.is_ok(),
+        error: written.err().map(|e| format!("{e:#}")),
+        attempted_at,
+        exit_code: None,
+    }
+}
+
+/// Dispatch the webhook sink: POST the event JSON to `url`, retrying once when
+/// the connection cannot be established. Returns the delivery result and the
+/// HTTP status of the response, if one was received. Non-2xx is a failure.
+fn dispatch_webhook_sink(
+    url: &str,
+    event_json: &str,
+    job_id: &str,
+    timeout_ms: DurationMs,
+) -> (crate::schema::SinkDeliveryResult, Option<u16>) {
+    let attempted_at = now_rfc3339();
+    let result = |success: bool, error: Option<String>| crate::schema::SinkDeliveryResult {
+        sink_type: "webhook".to_string(),
+        target: url.to_string(),
+        success,
+        error,
+        attempted_at: attempted_at.clone(),
+        exit_code: None,
+    };
+    // reqwest is built without a bundled crypto provider; install ring once per process.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(timeout_ms.as_duration())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return (result(false, Some(format!("client error: {e}"))), None),
+    };
+    let send = || {
+        client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-Agent-Exec-Job-Id", job_id)
+            .header("X-Agent-Exec-Version", crate::schema::AGENT_EXEC_VERSION)
+            .body(event_json.to_string())
+            .send()
+    };
+    let response = match send() {
+        Err(e) if e.is_connect() => send(),
+        other => other,
+    };
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            let error = (!status.is_success()).then(|| format!("HTTP {status}"));
+            (result(error.is_none(), error), Some(status.as_u16()))
+        }
+        Err(e) => (result(false, Some(format!("request error: {e}"))), None),
+    }
+}
+
+/// Dispatch the file sink: append one NDJSON line (holding an exclusive
+/// `flock` so concurrent supervisors sharing the file do not interleave), or
+/// with `append` false replace the file with the event.
+fn dispatch_file_sink(
+    file_path: &str,
+    event_json: &str,
+    append: bool,
+) -> crate::schema::SinkDeliveryResult {
     use std::io::Write;
     let attempted_at = now_rfc3339();
     let path = std::path::Path::new(file_path);
@@ -1800,21 +5281,31 @@ fn dispatch_file_sink(file_path: &str, event_json: &str) -> crate::schema::SinkD
             success: false,
             error: Some(format!("create parent dir: {e}")),
             attempted_at,
+            exit_code: None,
         };
     }
 
-    match std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-    {
-        Ok(mut f) => match writeln!(f, "{event_json}") {
+    let mut options = cloexec_options();
+    if append {
+        options.append(true);
+    } else {
+        options.write(true);
+    }
+    match options.create(true).open(path) {
+        // Truncate only once the lock is held so a concurrent writer's event
+        // is replaced whole; the lock is released when `f` drops.
+        Ok(mut f) => match f
+            .lock()
+            .and_then(|_| if append { Ok(()) } else { f.set_len(0) })
+            .and_then(|_| f.write_all(format!("{event_json}\n").as_bytes()))
+        {
             Ok(_) => crate::schema::SinkDeliveryResult {
                 sink_type: "file".to_string(),
                 target: file_path.to_string(),
                 success: true,
                 error: None,
                 attempted_at,
+                exit_code: None,
             },
             Err(e) => crate::schema::SinkDeliveryResult {
                 sink_type: "file".to_string(),
@@ -1822,6 +5313,7 @@ fn dispatch_file_sink(file_path: &str, event_json: &str) -> crate::schema::SinkD
                 success: false,
                 error: Some(format!("write error: {e}")),
                 attempted_at,
+                exit_code: None,
             },
         },
         Err(e) => crate::schema::SinkDeliveryResult {
@@ -1830,11 +5322,88 @@ fn dispatch_file_sink(file_path: &str, event_json: &str) -> crate::schema::SinkD
             success: false,
             error: Some(format!("open error: {e}")),
             attempted_at,
+            exit_code: None,
         },
     }
 }
 
+/// Substitute `command` into a `--wrap-command` template at every `{CMD}`.
+/// A single-element command is already a shell string and is inserted as-is;
+/// an argv is inserted as a command line with each argument shell-quoted.
+fn wrap_command(template: &str, command: &[String]) -> String {
+    let command_line = match command {
+        [shell_string] => shell_string.clone(),
+        argv => argv
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+    template.replace("{CMD}", &command_line)
+}
+
+/// Quote `arg` for a POSIX shell; arguments made only of safe characters
+/// are left bare.
+fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
 /// Public alias so other modules can call the timestamp helper.
+/// Wrap `command` so it runs on `host` through `ssh`. The supervisor, logs
+/// and state stay local; only the workload runs remotely.
+fn ssh_command(
+    host: &str,
+    user: Option<&str>,
+    key: Option<&str>,
+    port: u16,
+    no_host_key_check: bool,
+    command: Vec<String>,
+) -> Vec<String> {
+    let mut ssh = vec!["ssh".to_string(), "-p".to_string(), port.to_string()];
+    if let Some(key) = key {
+        ssh.extend(["-i".to_string(), key.to_string()]);
+    }
+    if no_host_key_check {
+        ssh.extend(["-o".to_string(), "StrictHostKeyChecking=no".to_string()]);
+    }
+    ssh.push(match user {
+        Some(user) => format!("{user}@{host}"),
+        None => host.to_string(),
+    });
+    ssh.push("--".to_string());
+    ssh.extend(command);
+    ssh
+}
+
+/// The machine's hostname, or `None` when it cannot be determined.
+#[cfg(unix)]
+fn local_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let name = String::from_utf8_lossy(&buf[..len]).into_owned();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(not(unix))]
+fn local_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok().filter(|s| !s.is_empty())
+}
+
+/// The invoking user's login name from `USER` (or `USERNAME` on Windows).
+fn local_username() -> Option<String> {
+    ["USER", "USERNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|s| !s.is_empty()))
+}
+
 pub fn now_rfc3339_pub() -> String {
     now_rfc3339()
 }
@@ -1905,6 +5474,45 @@ fn format_rfc3339(secs: u64) -> String {
     )
 }
 
+/// Parse a `YYYY-MM-DDTHH:MM:SSZ` timestamp (as written by [`now_rfc3339`])
+/// into Unix seconds; the inverse of [`format_rfc3339`]. Fractional seconds
+/// and offsets are not supported.
+pub fn parse_rfc3339_secs(ts: &str) -> Option<u64> {
+    let b = ts.as_bytes();
+    if b.len() != 20 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' || b[19] != b'Z' {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| ts.get(range)?.parse::<u64>().ok();
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hours, minutes, seconds) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if year < 1970 || !(1..=12).contains(&month) || day == 0 || hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    let mut days: u64 = (1970..year)
+        .map(|y| if is_leap(y) { 366 } else { 365 })
+        .sum();
+    let month_days: [u64; 12] = [
+        31,
+        if is_leap(year) { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    if day > month_days[month as usize - 1] {
+        return None;
+    }
+    days += month_days[..month as usize - 1].iter().sum::<u64>() + day - 1;
+    Some(((days * 24 + hours) * 60 + minutes) * 60 + seconds)
+}
+
 fn is_leap(year: u64) -> bool {
     (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
 }
@@ -1989,4 +5597,496 @@ mod tests {
         // 2024-01-01T00:00:00Z = 1704067200
         assert_eq!(format_rfc3339(1704067200), "2024-01-01T00:00:00Z");
     }
+
+    #[test]
+    fn wrap_command_substitutes_quoted_command_line() {
+        let argv = vec!["echo".to_string(), "it's ok".to_string()];
+        assert_eq!(
+            wrap_command("bash -c \"{CMD}\"", &argv),
+            "bash -c \"echo 'it'\\''s ok'\""
+        );
+        assert_eq!(
+            wrap_command("set -e; {CMD}", &["make test && make".to_string()]),
+            "set -e; make test && make"
+        );
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a/b.c=1"), "a/b.c=1");
+    }
+
+    #[test]
+    fn ssh_command_prefixes_ssh_invocation() {
+        let command = vec!["echo".to_string(), "hi".to_string()];
+        assert_eq!(
+            ssh_command("build01", None, None, 22, false, command.clone()),
+            ["ssh", "-p", "22", "build01", "--", "echo", "hi"]
+        );
+        assert_eq!(
+            ssh_command("build01", Some("ci"), Some("/k/id"), 2222, true, command),
+            [
+                "ssh",
+                "-p",
+                "2222",
+                "-i",
+                "/k/id",
+                "-o",
+                "StrictHostKeyChecking=no",
+                "ci@build01",
+                "--",
+                "echo",
+                "hi"
+            ]
+        );
+    }
+
+    #[test]
+    fn rotation_check_is_due_after_byte_threshold() {
+        let mut check = RotationCheck::new();
+        assert!(!check.due(1));
+        assert!(check.due(ROTATION_CHECK_BYTES as usize));
+        assert!(!check.due(1));
+    }
+
+    #[test]
+    fn phase_clock_formats_microseconds() {
+        let clock = PhaseClock {
+            start: std::time::Instant::now(),
+            start_wall: std::time::UNIX_EPOCH
+                + std::time::Duration::from_micros(1_704_067_200_000_042),
+        };
+        assert_eq!(clock.rfc3339(clock.start), "2024-01-01T00:00:00.000042Z");
+        assert_eq!(
+            clock.rfc3339(clock.start + std::time::Duration::from_millis(1_500)),
+            "2024-01-01T00:00:01.500042Z"
+        );
+    }
+
+    #[test]
+    fn parse_rfc3339_secs_inverts_format() {
+        for secs in [0, 951_782_400, 1_704_067_200, 1_709_251_199] {
+            assert_eq!(parse_rfc3339_secs(&format_rfc3339(secs)), Some(secs));
+        }
+        assert_eq!(parse_rfc3339_secs("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339_secs("2024-01-01T00:00:00.5Z"), None);
+        assert_eq!(parse_rfc3339_secs("2024-01-01 00:00:00Z"), None);
+    }
+
+    #[test]
+    fn command_hash_ignores_env_key_order_and_tracks_cwd() {
+        let command = vec!["make".to_string(), "test".to_string()];
+        let keys = |ks: &[&str]| ks.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        let hash = command_hash(&command, Some("/work"), &keys(&["B", "A"]));
+        assert_eq!(hash.len(), 64);
+        assert!(
+            hash.chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+        );
+        assert_eq!(
+            hash,
+            command_hash(&command, Some("/work"), &keys(&["A", "B"]))
+        );
+        assert_ne!(
+            hash,
+            command_hash(&command, Some("/other"), &keys(&["A", "B"]))
+        );
+    }
+
+    fn env_vars(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_env_json_accepts_flat_string_object() {
+        assert_eq!(
+            parse_env_json(r#"{"FOO":"bar","A":""}"#).unwrap(),
+            vec![
+                ("A".to_string(), String::new()),
+                ("FOO".to_string(), "bar".to_string())
+            ]
+        );
+        assert!(parse_env_json("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_env_json_rejects_non_string_values_and_non_objects() {
+        for json in [r#"{"N":1}"#, r#"{"O":{"x":"y"}}"#, r#"["FOO"]"#, "not json"] {
+            let err = parse_env_json(json).unwrap_err();
+            assert!(
+                err.downcast_ref::<InvalidEnvVar>().is_some(),
+                "{json}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn filter_inherited_env_keeps_only_prefixed_keys() {
+        let parent = || {
+            [("FOO", "1"), ("BAR_X", "2"), ("BAR_Y", "3")]
+                .map(|(k, v)| (OsString::from(k), OsString::from(v)))
+        };
+        let kept: Vec<String> = filter_inherited_env(parent(), &env_vars(&["BAR_"]))
+            .into_iter()
+            .map(|(k, _)| k.into_string().unwrap())
+            .collect();
+        assert_eq!(kept, ["BAR_X", "BAR_Y"]);
+        assert_eq!(filter_inherited_env(parent(), &[]).len(), 3);
+    }
+
+    #[test]
+    fn validate_run_opts_flags_suspicious_combinations() {
+        assert!(validate_run_opts(&RunOpts::default()).is_empty());
+
+        let empty_env = RunOpts {
+            inherit_env: false,
+            ..Default::default()
+        };
+        assert_eq!(validate_run_opts(&empty_env).len(), 1);
+        let with_env = RunOpts {
+            inherit_env: false,
+            env_vars: env_vars(&["PATH=/bin"]),
+            ..Default::default()
+        };
+        assert!(validate_run_opts(&with_env).is_empty());
+
+        let kill_after_only = RunOpts {
+            kill_after_ms: DurationMs::from_millis(5_000),
+            ..Default::default()
+        };
+        assert!(validate_run_opts(&kill_after_only)[0].contains("--kill-after"));
+        let with_timeout = RunOpts {
+            timeout_ms: DurationMs::from_millis(1_000),
+            ..kill_after_only
+        };
+        assert!(validate_run_opts(&with_timeout).is_empty());
+
+        let nice_in_range = RunOpts {
+            nice: Some(-20),
+            ..Default::default()
+        };
+        assert!(validate_run_opts(&nice_in_range).is_empty());
+        let nice_out_of_range = RunOpts {
+            nice: Some(-25),
+            ..Default::default()
+        };
+        assert!(validate_run_opts(&nice_out_of_range)[0].contains("clamped to -20"));
+    }
+
+    #[test]
+    fn dedup_env_vars_keeps_last_value_per_key() {
+        assert_eq!(dedup_env_vars(&env_vars(&["FOO=a", "FOO=b"])), ["FOO=b"]);
+        assert_eq!(
+            dedup_env_vars(&env_vars(&["A=1", "B=2", "A=3", "C", "C=4"])),
+            ["B=2", "A=3", "C=4"]
+        );
+        assert!(dedup_env_vars(&[]).is_empty());
+    }
+
+    #[test]
+    fn mask_invocation_args_masks_env_values_before_separator() {
+        let args: Vec<String> = [
+            "agent-exec",
+            "run",
+            "--env",
+            "TOKEN=s3cret",
+            "--env=USER=me",
+            "--env=API_KEY=k",
+            "--",
+            "env",
+            "--env",
+            "TOKEN=literal",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let (masked, changed) =
+            mask_invocation_args(&args, &["TOKEN".to_string(), "*_KEY".to_string()]);
+        assert!(changed);
+        assert_eq!(masked[3], "TOKEN=***");
+        assert_eq!(masked[4], "--env=USER=me");
+        assert_eq!(masked[5], "--env=API_KEY=***");
+        assert_eq!(masked[9], "TOKEN=literal");
+
+        let (unmasked, changed) = mask_invocation_args(&args, &[]);
+        assert!(!changed);
+        assert_eq!(unmasked, args);
+    }
+
+    #[test]
+    fn mask_invocation_args_masks_env_from_json_values() {
+        let args: Vec<String> = [
+            "agent-exec",
+            "run",
+            "--env-from-json",
+            r#"{"API_KEY":"s3cret","USER":"me"}"#,
+            r#"--env-from-json={"API_KEY":"s3cret"}"#,
+            "--",
+            "true",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let (masked, changed) = mask_invocation_args(&args, &["API_KEY".to_string()]);
+        assert!(changed);
+        assert_eq!(masked[3], r#"{"API_KEY":"***","USER":"me"}"#);
+        assert_eq!(masked[4], r#"--env-from-json={"API_KEY":"***"}"#);
+        assert!(masked.iter().all(|a| !a.contains("s3cret")));
+
+        let (unmasked, changed) = mask_invocation_args(&args, &["OTHER".to_string()]);
+        assert!(!changed);
+        assert_eq!(unmasked, args);
+    }
+
+    #[test]
+    fn mask_env_vars_matches_suffix_glob() {
+        assert_eq!(
+            mask_env_vars(
+                &env_vars(&["GITHUB_TOKEN=a", "NPM_TOKEN=b", "TOKEN_URL=c"]),
+                &env_vars(&["*_TOKEN"])
+            ),
+            ["GITHUB_TOKEN=***", "NPM_TOKEN=***", "TOKEN_URL=c"]
+        );
+    }
+
+    #[test]
+    fn mask_env_vars_matches_prefix_glob_case_sensitively() {
+        assert_eq!(
+            mask_env_vars(
+                &env_vars(&["AWS_SECRET_KEY=a", "aws_region=b", "PATH=c"]),
+                &env_vars(&["AWS_*"])
+            ),
+            ["AWS_SECRET_KEY=***", "aws_region=b", "PATH=c"]
+        );
+    }
+
+    #[test]
+    fn mask_env_vars_exact_key_still_matches_only_itself() {
+        assert_eq!(
+            mask_env_vars(
+                &env_vars(&["SECRET=a", "SECRET_2=b"]),
+                &env_vars(&["SECRET"])
+            ),
+            ["SECRET=***", "SECRET_2=b"]
+        );
+    }
+
+    #[test]
+    fn mask_env_vars_question_mark_matches_one_character() {
+        assert_eq!(
+            mask_env_vars(
+                &env_vars(&["KEY1=a", "KEY12=b", "KEY=c"]),
+                &env_vars(&["KEY?"])
+            ),
+            ["KEY1=***", "KEY12=b", "KEY=c"]
+        );
+        assert!(glob_match("A*B*C", "AxxBCyyC"));
+        assert!(!glob_match("A*B?", "AB"));
+    }
+
+    #[test]
+    fn validate_env_vars_accepts_well_formed_entries() {
+        validate_env_vars(&env_vars(&["A=1", "B=", "C", "D=x=y", "E=has space"])).unwrap();
+        validate_env_vars(&env_vars(&["CAFÉ=1"])).unwrap();
+    }
+
+    #[test]
+    fn validate_env_vars_rejects_empty_key() {
+        let err = validate_env_vars(&env_vars(&["A=1", "=value"])).unwrap_err();
+        assert!(err.downcast_ref::<InvalidEnvVar>().is_some());
+        assert!(err.to_string().contains("\"=value\""), "{err}");
+        assert!(validate_env_vars(&env_vars(&[""])).is_err());
+    }
+
+    #[test]
+    fn validate_env_vars_rejects_nul_bytes() {
+        assert!(validate_env_vars(&env_vars(&["A\0B=1"])).is_err());
+        assert!(validate_env_vars(&env_vars(&["A=1\u{0}2"])).is_err());
+    }
+
+    #[test]
+    fn validate_env_vars_rejects_whitespace_in_key() {
+        let err = validate_env_vars(&env_vars(&["KEY WITH SPACES=V"])).unwrap_err();
+        assert!(err.downcast_ref::<InvalidEnvVar>().is_some());
+        assert!(validate_env_vars(&env_vars(&["TAB\tKEY=V"])).is_err());
+    }
+
+    fn expand(vars: &[(&str, &str)]) -> std::result::Result<Vec<(String, String)>, EnvCycle> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        expand_env_vars(&vars, &|_| None)
+    }
+
+    #[test]
+    fn expand_env_vars_resolves_forward_chain() {
+        let out = expand(&[("A", "a-$B"), ("B", "b-${C}"), ("C", "c")]).unwrap();
+        assert_eq!(
+            out,
+            vec![
+                ("A".to_string(), "a-b-c".to_string()),
+                ("B".to_string(), "b-c".to_string()),
+                ("C".to_string(), "c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_env_vars_resolves_diamond() {
+        let out = expand(&[("A", "$B+$C"), ("B", "b$D"), ("C", "c$D"), ("D", "d")]).unwrap();
+        assert_eq!(out[0], ("A".to_string(), "bd+cd".to_string()));
+    }
+
+    #[test]
+    fn expand_env_vars_rejects_self_reference() {
+        let err = expand(&[("A", "x$A")]).unwrap_err();
+        assert_eq!(err.0, vec!["A", "A"]);
+        assert!(err.to_string().contains("A -> A"), "{err}");
+    }
+
+    #[test]
+    fn collect_env_vars_reads_env_directory_in_alphabetical_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let env_d = dir.path().join("env.d");
+        std::fs::create_dir(&env_d).unwrap();
+        std::fs::write(env_d.join("FOO.env"), "foo-value\n").unwrap();
+        std::fs::write(env_d.join("BAR.env"), "bar=value").unwrap();
+        std::fs::write(env_d.join("README.txt"), "ignored").unwrap();
+
+        let vars =
+            collect_env_vars(&[env_d.display().to_string()], &["FOO=override".into()]).unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("BAR".to_string(), "bar=value".to_string()),
+                ("FOO".to_string(), "foo-value".to_string()),
+                ("FOO".to_string(), "override".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_env_vars_crosses_env_file_and_env_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let env_file = dir.path().join("app.env");
+        std::fs::write(&env_file, "URL=http://$HOST:8080\nHOST=file-host\n").unwrap();
+        let vars = collect_env_vars(
+            &[env_file.display().to_string()],
+            &["HOST=flag-host".to_string(), "API=${URL}/api".to_string()],
+        )
+        .unwrap();
+        let out = expand_env_vars(&vars, &|_| None).unwrap();
+        assert_eq!(
+            out,
+            vec![
+                ("URL".to_string(), "http://flag-host:8080".to_string()),
+                ("HOST".to_string(), "flag-host".to_string()),
+                ("API".to_string(), "http://flag-host:8080/api".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_rate_limiter_drops_lines_past_burst_and_reports_count() {
+        let mut limiter = LineRateLimiter::new(2);
+        let mut log = Vec::new();
+        limiter.write(&mut log, b"a\nb");
+        limiter.write(&mut log, b"b\nc\nd\n");
+        limiter.flush_marker(&mut log);
+        assert_eq!(
+            String::from_utf8(log).unwrap(),
+            "a\nbb\n... rate limit exceeded, 2 lines dropped ...\n"
+        );
+    }
+
+    /// Sink that counts `write` calls, standing in for write syscalls.
+    #[derive(Default)]
+    struct CountingSink {
+        writes: usize,
+        bytes: Vec<u8>,
+    }
+
+    impl std::io::Write for CountingSink {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.writes += 1;
+            self.bytes.extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn max_line_length_cuts_full_log_only_at_char_boundaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let full_log_path = dir.path().join("full.log");
+        let full_log = std::sync::Arc::new(std::sync::Mutex::new(
+            std::fs::File::create(&full_log_path).unwrap(),
+        ));
+        let seen = std::sync::Mutex::new(Vec::new());
+        // "é" is two bytes; a 5-byte cut would fall inside the third one.
+        let input = "ééééé\nend".as_bytes();
+        stream_to_logs(
+            input,
+            &dir.path().join("stdout.log"),
+            full_log,
+            &full_log_path,
+            "STDOUT",
+            Some(|line: &str| seen.lock().unwrap().push(line.to_string())),
+            None,
+            0,
+            LogBuffering::None,
+            5,
+            LogRotation::default(),
+        );
+        assert_eq!(*seen.lock().unwrap(), ["ééééé", "end"]);
+        let full = std::fs::read_to_string(&full_log_path).unwrap();
+        let bodies: Vec<&str> = full
+            .lines()
+            .map(|l| l.split_once("[STDOUT] ").unwrap().1)
+            .collect();
+        assert_eq!(
+            bodies,
+            ["éé... [truncated]", "éé... [truncated]", "é", "end"]
+        );
+        assert!(!full.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn buffered_log_line_mode_holds_partial_lines_until_newline() {
+        use std::io::Write;
+        let mut log = BufferedLog::new(CountingSink::default(), LogBuffering::Line);
+        log.write_all(b"ab").unwrap();
+        assert!(log.inner.bytes.is_empty());
+        log.write_all(b"c\nd").unwrap();
+        assert_eq!(log.inner.bytes, b"abc\n");
+        log.flush().unwrap();
+        assert_eq!(log.inner.bytes, b"abc\nd");
+    }
+
+    #[test]
+    fn buffered_log_full_mode_cuts_writes_tenfold_without_losing_data() {
+        use std::io::Write;
+        // One second of 10 MB/s output arriving as 1 KiB reads.
+        let chunk: Vec<u8> = (0..1024).map(|i| b'a' + (i % 26) as u8).collect();
+        let mut expected = Vec::new();
+        let mut counts = Vec::new();
+        for mode in [LogBuffering::None, LogBuffering::Line, LogBuffering::Full] {
+            let mut log = BufferedLog::new(CountingSink::default(), mode);
+            for _ in 0..10 * 1024 {
+                log.write_all(&chunk).unwrap();
+            }
+            log.flush().unwrap();
+            if expected.is_empty() {
+                expected = log.inner.bytes.clone();
+            }
+            assert_eq!(log.inner.bytes, expected, "{mode:?} lost data");
+            counts.push(log.inner.writes);
+        }
+        assert_eq!(counts[0], 10 * 1024);
+        assert!(
+            counts[0] >= 10 * counts[2],
+            "full buffering should cut writes 10x: {counts:?}"
+        );
+    }
 }
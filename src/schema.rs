@@ -1,6 +1,7 @@
 //! Shared output schema types for agent-exec v0.1.
 //!
-//! Stdout output is JSON by default; YAML when --yaml is set.
+//! Stdout output is JSON by default; YAML when --yaml is set. `--color`
+//! turns JSON into indented, ANSI-colored text for people reading a terminal.
 //! Tracing logs go to stderr.
 //! Schema version is fixed at "0.1".
 
@@ -10,23 +11,98 @@ use std::sync::atomic::{AtomicBool, Ordering};
 /// Global flag: when true, print YAML instead of JSON on stdout.
 static YAML_OUTPUT: AtomicBool = AtomicBool::new(false);
 
+/// Global flag: when true, pretty-print JSON with ANSI colors.
+static COLOR_OUTPUT: AtomicBool = AtomicBool::new(false);
+
 /// Set the output format.  Call once from `main` before running any subcommand.
 pub fn set_yaml_output(yaml: bool) {
     YAML_OUTPUT.store(yaml, Ordering::Relaxed);
 }
 
+/// Enable colored JSON output.  Call once from `main`, like [`set_yaml_output`].
+pub fn set_color_output(color: bool) {
+    COLOR_OUTPUT.store(color, Ordering::Relaxed);
+}
+
+const KEY_COLOR: &str = "\x1b[34m";
+const STRING_COLOR: &str = "\x1b[32m";
+const NUMBER_COLOR: &str = "\x1b[33m";
+const LITERAL_COLOR: &str = "\x1b[35m";
+const RESET: &str = "\x1b[0m";
+
+/// Wrap the tokens of serialized JSON in ANSI color codes: keys blue, strings
+/// green, numbers yellow, `true`/`false`/`null` magenta. Punctuation and
+/// whitespace are copied unchanged, so key order and layout are preserved.
+pub fn colorize_json(json: &str) -> String {
+    let mut out = String::with_capacity(json.len() * 2);
+    let mut chars = json.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let (end, color) = match c {
+            '"' => {
+                let mut escaped = false;
+                let mut end = json.len();
+                for (i, c) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == '"' {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                let is_key = json[end..].trim_start().starts_with(':');
+                (end, if is_key { KEY_COLOR } else { STRING_COLOR })
+            }
+            '-' | '0'..='9' | 't' | 'f' | 'n' => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')) {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                let color = if c.is_ascii_alphabetic() {
+                    LITERAL_COLOR
+                } else {
+                    NUMBER_COLOR
+                };
+                (end, color)
+            }
+            _ => {
+                out.push(c);
+                continue;
+            }
+        };
+        out.push_str(color);
+        out.push_str(&json[start..end]);
+        out.push_str(RESET);
+    }
+    out
+}
+
 pub const SCHEMA_VERSION: &str = "0.1";
 
+/// Version of this agent-exec binary, recorded in every job's meta.json.
+pub const AGENT_EXEC_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// Serialize `value` and print to stdout in the selected format (JSON default, YAML with --yaml).
 ///
 /// This is the single place where stdout output is written, ensuring the
-/// stdout-is-machine-readable contract is enforced uniformly across all response types.
-fn print_to_stdout(value: &impl Serialize) {
+/// stdout-is-machine-readable contract is enforced uniformly across all response types,
+/// including the per-line records streamed before a final envelope (`list --ndjson`).
+pub(crate) fn print_to_stdout(value: &impl Serialize) {
     if YAML_OUTPUT.load(Ordering::Relaxed) {
         print!(
             "{}",
             serde_yaml::to_string(value).expect("YAML serialization failed")
         );
+    } else if COLOR_OUTPUT.load(Ordering::Relaxed) {
+        println!(
+            "{}",
+            colorize_json(&serde_json::to_string_pretty(value).expect("JSON serialization failed"))
+        );
     } else {
         println!(
             "{}",
@@ -148,6 +224,8 @@ pub struct CompressionData {
 pub struct RunData {
     pub job_id: String,
     pub state: String,
+    /// Command argv of the job, as persisted in meta.json.
+    pub command: Vec<String>,
     /// Tags assigned to this job (always present; empty array when none).
     #[serde(default)]
     pub tags: Vec<String>,
@@ -155,6 +233,10 @@ pub struct RunData {
     /// Omitted from JSON when empty.
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub env_vars: Vec<String>,
+    /// Absolute path to the jobs root.
+    pub root: String,
+    /// Absolute path to this job's directory (`root/<job_id>`).
+    pub job_dir: String,
     /// Absolute path to stdout.log for this job.
     pub stdout_log_path: String,
     /// Absolute path to stderr.log for this job.
@@ -191,6 +273,76 @@ pub struct RunData {
     pub duration_ms: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub compression: Option<CompressionData>,
+    /// True when `run --deduplicate` returned an earlier job instead of starting one.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub deduplicated: bool,
+    /// Outcome of `run --startup-probe`; absent when no probe was configured.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub startup_probe_passed: Option<bool>,
+    /// Absolute path of the `run --report-file` summary, written once the job finishes.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub report_file_path: Option<String>,
+    /// Launch phase timings; present only with `run --benchmark-mode`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub benchmark: Option<RunBenchmark>,
+    /// Flag combinations that were accepted but are likely mistakes.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub warnings: Vec<String>,
+    /// Full `agent-exec run ...` invocation, with masked `--env` values
+    /// replaced by "***". Omitted when not invoked from the CLI.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub args: Vec<String>,
+    /// True when at least one value in `args` was masked.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub args_masked: bool,
+    /// Job this one re-ran with the same definition (`replay`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub replayed_from: Option<String>,
+}
+
+/// Response for `run --dry-run`: the options passed validation, but no job
+/// directory was created and nothing was started.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunDryRunData {
+    /// Always true.
+    pub dry_run: bool,
+    /// Always null; no job was created.
+    pub job_id: Option<String>,
+    /// Always "dry_run".
+    pub state: String,
+    /// The options as `run` resolved them.
+    pub validated_options: ValidatedRunOptions,
+    /// Flag combinations that were accepted but are likely mistakes.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub warnings: Vec<String>,
+}
+
+/// Resolved options reported by `run --dry-run`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidatedRunOptions {
+    /// Command argv as it would be persisted in meta.json.
+    pub command: Vec<String>,
+    /// Absolute working directory of the command.
+    pub cwd: String,
+    /// Names of the environment variables passed with `--env` and related flags.
+    pub env_keys: Vec<String>,
+    /// Absolute path to the jobs root.
+    pub root: String,
+    /// Validated, deduplicated tags.
+    pub tags: Vec<String>,
+    /// Hash the job would be recorded under in meta.json.
+    pub command_hash: String,
+}
+
+/// Per-phase launch timings reported by `run --benchmark-mode`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunBenchmark {
+    /// Time to create the job directory.
+    pub dir_create_ms: u64,
+    /// Time to spawn the supervisor and write the initial `state.json`.
+    pub spawn_ms: u64,
+    /// Time to write `meta.json`.
+    pub write_meta_ms: u64,
 }
 
 /// Response for `status` command.
@@ -207,6 +359,50 @@ pub struct StatusData {
     pub started_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finished_at: Option<String>,
+    /// Absolute path to the combined full.log, or the `run --log` target when one was given; absent when the file does not exist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_log_path: Option<String>,
+    /// Current size of full.log in bytes; present together with `full_log_path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_log_size_bytes: Option<u64>,
+    /// User-mode CPU time of the finished child in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_user_ms: Option<u64>,
+    /// Kernel-mode CPU time of the finished child in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_sys_ms: Option<u64>,
+    /// Peak resident set size of the finished child in KiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rss_kb: Option<u64>,
+    /// Stdout recorded by `run --capture-output`; only with `status --captured`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captured_stdout: Option<String>,
+    /// Whether `captured_stdout` is only the tail of a longer output; only
+    /// with `status --captured`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captured_stdout_truncated: Option<bool>,
+    /// How long the job has been running: time since `started_at` while it is
+    /// active, `result.duration_ms` once it has finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    /// Contents of `timing.json`; present only with `status --timing` once
+    /// the supervisor has written it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<JobTiming>,
+    /// Current sizes of the job's log files (always present).
+    pub log_sizes: LogSizes,
+    /// Problems noticed while reading the job, such as a `state.json` written
+    /// by a different agent-exec version.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Log file sizes reported by `status`; a missing file counts as 0 bytes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogSizes {
+    pub stdout_bytes: u64,
+    pub stderr_bytes: u64,
+    pub full_log_bytes: u64,
 }
 
 /// Response for `tail` command.
@@ -228,8 +424,19 @@ pub struct TailData {
     pub stdout_total_bytes: u64,
     /// Total bytes currently observed in stderr.log.
     pub stderr_total_bytes: u64,
+    /// Tail of the interleaved full.log; only with `tail --full-log` when
+    /// full.log exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_log_tail: Option<String>,
+    /// Total bytes currently observed in full.log; present with `full_log_tail`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_log_observed_bytes: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub compression: Option<CompressionData>,
+    /// Whether the snapshot omits earlier bytes of either log; only in the
+    /// first line of `tail --stream`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub truncated: Option<bool>,
 }
 
 /// Response for `wait` command.
@@ -252,6 +459,15 @@ pub struct WaitData {
     pub stderr_total_bytes: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<String>,
+    /// User-mode CPU time of the finished child in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cpu_user_ms: Option<u64>,
+    /// Kernel-mode CPU time of the finished child in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cpu_sys_ms: Option<u64>,
+    /// Peak resident set size of the finished child in KiB.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_rss_kb: Option<u64>,
 }
 
 /// Response for `kill` command.
@@ -267,6 +483,9 @@ pub struct KillData {
     pub terminated_signal: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub observed_within_ms: Option<u64>,
+    /// True when the recorded PID no longer exists, so no signal was sent.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub stale_pid: bool,
 }
 
 /// Response for `schema` command.
@@ -280,16 +499,50 @@ pub struct SchemaData {
     pub generated_at: String,
 }
 
-/// Summary of a single job, included in `list` responses.
+/// Response for the `version` command.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionData {
+    /// Version of the agent-exec binary.
+    pub version: String,
+}
+
+/// Final summary written to the `run --report-file` path once the job finishes.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct JobReport {
+    pub job_id: String,
+    pub command: Vec<String>,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub signal: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_ms: u64,
+    /// Total bytes in stdout.log when the report was written.
+    pub stdout_observed_bytes: u64,
+    /// Total bytes in stderr.log when the report was written.
+    pub stderr_observed_bytes: u64,
+    /// Last 20 lines of stdout (UTF-8 lossy).
+    pub stdout_tail: String,
+    /// Last 20 lines of stderr (UTF-8 lossy).
+    pub stderr_tail: String,
+}
+
+/// Summary of a single job, included in `list` responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobSummary {
     pub job_id: String,
     /// Human-facing short identifier (first 7 characters of job_id).
     pub short_job_id: String,
-    /// Job state: created | running | exited | killed | failed | unknown
+    /// Job state: created | running | stopped | exited | killed | failed | unknown
     pub state: String,
     /// Original command argv persisted in meta.json.
     pub command: Vec<String>,
+    /// The command joined by spaces, cut to 80 characters (`...` appended when truncated).
+    pub command_preview: String,
+    /// Effective working directory persisted in meta.json; absent for jobs created
+    /// before cwd tracking.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exit_code: Option<i32>,
     /// Creation timestamp from meta.json (RFC 3339).
@@ -301,9 +554,82 @@ pub struct JobSummary {
     pub finished_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<String>,
+    /// Elapsed run time from state.json `result.duration_ms`; absent until the job finishes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
     /// Tags assigned to this job (always present; empty array when none).
     #[serde(default)]
     pub tags: Vec<String>,
+    /// The job's full `meta.json`; present only with `list --full`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+    /// Combined size of `stdout.log` and `stderr.log`; present only while running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_output_bytes: Option<u64>,
+    /// Machine that ran the job; present when it was started with
+    /// `run --record-host-info`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+/// One entry of the structured event log (`events.ndjson`, `run --event-log`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JobEvent {
+    /// RFC 3339 timestamp when the event was recorded.
+    pub ts: String,
+    /// Event type: spawn | stdout_chunk | stderr_chunk | timeout_signal | exit | progress_tick
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Type-specific payload (e.g. `{"text": "..."}` for output chunks).
+    pub data: serde_json::Value,
+}
+
+/// Response for `events` command.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventsData {
+    pub job_id: String,
+    /// Events in the order they were recorded, after `--type` filtering.
+    pub events: Vec<JobEvent>,
+}
+
+/// One NDJSON line printed by `follow` and `tail --stream`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FollowEvent {
+    /// New bytes of `stream` ("stdout" or "stderr"), decoded with the job's
+    /// output encoding; `offset` is the byte offset of the first one in the
+    /// log file.
+    Chunk {
+        stream: String,
+        data: String,
+        offset: u64,
+    },
+    /// The job reached a terminal state and its logs were drained; always the
+    /// last line.
+    Finished {
+        state: String,
+        exit_code: Option<i32>,
+    },
+    /// Like `Finished`, but printed by `tail --stream`.
+    Eof { state: String },
+}
+
+/// Response for `rotate` command.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateData {
+    pub job_id: String,
+    /// Log file name inside the job directory (e.g. "stdout.log").
+    pub log: String,
+    /// Absolute path of the (new) active log file.
+    pub log_path: String,
+    /// Size of the log before rotation, in bytes.
+    pub size_bytes: u64,
+    /// Rotation threshold in bytes.
+    pub max_size_bytes: u64,
+    /// Number of archived generations kept.
+    pub archive_count: u32,
+    /// True when the log exceeded the threshold and was rotated.
+    pub rotated: bool,
 }
 
 /// Response for `tag set` command.
@@ -325,6 +651,54 @@ pub struct ListData {
     pub truncated: bool,
     /// Number of directories skipped because they could not be read as jobs.
     pub skipped: u64,
+    /// Effective cwd filter applied to the listing; null when no cwd filter was
+    /// used (`--all`, or a bare `--command-hash` lookup).
+    pub filter_cwd: Option<String>,
+    /// Job counts by state; present only for `list --stats-only` and as the
+    /// closing line of `list --format ndjson`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub stats: Option<ListStats>,
+    /// Jobs whose summary came from `.list-cache.json`; present only with
+    /// `list --cache`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cache_hits: Option<u64>,
+    /// Jobs read from their job directory; present only with `list --cache`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cache_misses: Option<u64>,
+    /// Last returned job when older jobs remain; pass it to `--before` for
+    /// the next page.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub next_cursor: Option<String>,
+    /// First returned job when newer jobs remain; pass it to `--after` for
+    /// the previous page.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub prev_cursor: Option<String>,
+}
+
+/// Job counts reported by `list --stats-only`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListStats {
+    /// Number of jobs counted.
+    pub total: u64,
+    /// Jobs whose effective state is `running`.
+    pub running: u64,
+    /// Jobs in a terminal state (`exited`, `killed`, or `failed`).
+    pub finished: u64,
+    /// Count per effective state (same values as `JobSummary.state`).
+    pub by_state: std::collections::BTreeMap<String, u64>,
+}
+
+impl ListStats {
+    /// Count one job with the given effective state.
+    pub fn record(&mut self, state: &str) {
+        self.total += 1;
+        match state {
+            "running" => self.running += 1,
+            "exited" | "killed" | "failed" => self.finished += 1,
+            _ => {}
+        }
+        *self.by_state.entry(state.to_string()).or_default() += 1;
+    }
 }
 
 /// Response for the `gc` command.
@@ -357,11 +731,33 @@ pub struct GcData {
     pub candidate_count: u64,
 }
 
+/// Response for the `purge` command.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurgeData {
+    /// Resolved root directory path.
+    pub root: String,
+    /// Whether this was a dry-run (no deletions performed).
+    pub dry_run: bool,
+    /// Minimum age of `finished_at`, in seconds, for a job to be purged.
+    pub older_than_secs: u64,
+    /// Job IDs that would be deleted, oldest first; present only for a dry run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub would_delete: Option<Vec<String>>,
+    /// Number of job directories actually deleted (0 when dry_run=true).
+    pub deleted_count: u64,
+    /// Number of job directories left in place: not terminal, too recent,
+    /// filtered out by `--state` or `--limit`, changed before deletion, or
+    /// failed to delete.
+    pub skipped_count: u64,
+    /// Total bytes freed (or would be freed in dry-run mode).
+    pub freed_bytes: u64,
+}
+
 /// Per-job result entry in a `delete` response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteJobResult {
     pub job_id: String,
-    /// Job state as reported from state.json: created | running | exited | killed | failed | unknown
+    /// Job state as reported from state.json: created | running | stopped | exited | killed | failed | unknown
     pub state: String,
     /// What delete did: "deleted" | "would_delete" | "skipped"
     pub action: String,
@@ -408,6 +804,8 @@ pub struct InstalledSkillSummary {
     pub source_type: String,
     /// Absolute path to the installed skill directory.
     pub path: String,
+    /// True when an existing lock entry for this skill was overwritten.
+    pub updated: bool,
 }
 
 /// Response for `notify set` command.
@@ -429,6 +827,28 @@ pub struct InstallSkillsData {
     pub lock_file_path: String,
 }
 
+/// Verification result for a single skill, included in `verify_skills` responses.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifiedSkillSummary {
+    /// Skill name (directory name under `.agents/skills/`).
+    pub name: String,
+    /// True when every installed file still matches its recorded checksum.
+    pub verified: bool,
+    /// Relative paths that were modified or removed since installation.
+    pub mismatches: Vec<String>,
+}
+
+/// Response for `verify-skills` command.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifySkillsData {
+    /// True when every recorded skill verified.
+    pub verified: bool,
+    /// Per-skill results, in lock file order.
+    pub skills: Vec<VerifiedSkillSummary>,
+    /// Absolute path to the `.skill-lock.json` file that was read.
+    pub lock_file_path: String,
+}
+
 /// Snapshot of stdout/stderr tail at a point in time.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Snapshot {
@@ -447,6 +867,34 @@ pub struct Snapshot {
     pub stderr_included_bytes: u64,
 }
 
+/// Contents of a `run --checkpoint-dir` file: the job state plus a log
+/// snapshot at the time it was written.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// RFC 3339 timestamp when the checkpoint was written.
+    pub ts: String,
+    pub state: JobState,
+    pub snapshot: Snapshot,
+}
+
+/// Contents of `timing.json`: when the supervisor reached each phase of a
+/// run, as RFC 3339 timestamps with microsecond precision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobTiming {
+    /// The supervisor process started.
+    pub supervisor_start: String,
+    /// The child was about to be spawned.
+    pub child_spawn: String,
+    /// The first output byte arrived from the child; absent when it printed
+    /// nothing.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub child_started: Option<String>,
+    /// Waiting on the child returned.
+    pub child_exited: String,
+    /// The log reader threads finished and the logs were synced.
+    pub logs_flushed: String,
+}
+
 // ---------- Notification / completion event models ----------
 
 /// Match type for output-match notification.
@@ -487,6 +935,26 @@ pub struct OutputMatchConfig {
     pub file: Option<String>,
 }
 
+/// Periodic health-check configuration persisted in meta.json (`run --health-check`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HealthCheckConfig {
+    /// Shell command string executed through the job's shell wrapper.
+    pub command: String,
+    /// Milliseconds between consecutive checks.
+    pub interval_ms: u64,
+    /// Consecutive failed checks after which the job is terminated.
+    pub retries: u32,
+}
+
+/// Periodic status-report configuration persisted in meta.json (`run --report-pipe`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusReportConfig {
+    /// Absolute path of the FIFO or regular file receiving reports.
+    pub path: String,
+    /// Milliseconds between consecutive reports.
+    pub interval_ms: u64,
+}
+
 /// Notification configuration persisted in meta.json.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NotificationConfig {
@@ -532,6 +1000,18 @@ pub struct SinkDeliveryResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub attempted_at: String,
+    /// Exit code of a command sink that ran to completion.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub exit_code: Option<i32>,
+}
+
+/// Written to the notify file by `run --notify-file-format snapshot`: the
+/// final `state.json` with the job's `meta.json` under `meta`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompletionSnapshot {
+    #[serde(flatten)]
+    pub state: JobState,
+    pub meta: JobMeta,
 }
 
 /// Persisted in `completion_event.json` after terminal state is reached.
@@ -579,6 +1059,7 @@ pub struct JobMetaJob {
 /// {
 ///   "job": { "id": "..." },
 ///   "schema_version": "0.1",
+///   "agent_exec_version": "x.y.z",
 ///   "command": [...],
 ///   "created_at": "...",
 ///   "root": "...",
@@ -603,6 +1084,10 @@ pub struct JobMetaJob {
 pub struct JobMeta {
     pub job: JobMetaJob,
     pub schema_version: String,
+    /// Version of the agent-exec binary that created the job; empty for jobs
+    /// created before it was recorded.
+    #[serde(default)]
+    pub agent_exec_version: String,
     pub command: Vec<String>,
     pub created_at: String,
     pub root: String,
@@ -626,6 +1111,10 @@ pub struct JobMeta {
     /// Used by `list` to filter jobs by cwd. Absent for jobs created before this feature.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub cwd: Option<String>,
+    /// Lowercase hex SHA-256 fingerprint of `[command, cwd, sorted env_keys]`.
+    /// Used by `list --command-hash` and `run --deduplicate`; empty for older jobs.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub command_hash: String,
     /// Notification configuration (present only when --notify-command or --notify-file was used).
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub notification: Option<NotificationConfig>,
@@ -643,9 +1132,12 @@ pub struct JobMeta {
     /// Timeout in milliseconds; 0 = no timeout.
     #[serde(default)]
     pub timeout_ms: u64,
-    /// Milliseconds after SIGTERM before SIGKILL; 0 = immediate SIGKILL.
+    /// Grace period (ms) between the timeout SIGTERM and SIGKILL; 0 = immediate SIGKILL.
     #[serde(default)]
     pub kill_after_ms: u64,
+    /// Extra milliseconds after the kill-after grace period before the final SIGKILL.
+    #[serde(default)]
+    pub keep_going_ms: u64,
     /// Interval (ms) for state.json updated_at refresh; 0 = disabled.
     #[serde(default)]
     pub progress_every_ms: u64,
@@ -655,16 +1147,191 @@ pub struct JobMeta {
     /// Relative path (from job directory) to materialized stdin content.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub stdin_file: Option<String>,
+    /// Whether the supervisor was launched in its own session and process group (`run --detach`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub detached: bool,
+    /// Signal delivered to the child if its supervisor exits first (`--signal-on-parent-exit`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub signal_on_parent_exit: Option<String>,
+    /// Encoding label used by `tail` to decode log bytes (e.g. "utf-8", "latin1").
+    #[serde(default = "default_output_encoding")]
+    pub output_encoding: String,
+    /// Whether the child is attached to a pseudo-terminal (`run --pty`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub pty: bool,
+    /// Periodic health check that terminates the job after repeated failures.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub health_check: Option<HealthCheckConfig>,
+    /// Whether the supervisor records `events.ndjson` (`run --event-log`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub event_log: bool,
+    /// Periodic JSON status reports written to a FIFO or file (`run --report-pipe`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub status_report: Option<StatusReportConfig>,
+    /// Whether the child runs with RLIMIT_CORE = 0 (`run --disable-core-dump`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub core_dump_disabled: bool,
+    /// Lines per second written to each individual log; 0 = unlimited
+    /// (`run --max-output-lines-per-second`).
+    #[serde(default)]
+    pub max_output_lines_per_second: u64,
+    /// Whether env-file and `--env` values are expanded in dependency order (`run --env-expand`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub env_expand: bool,
+    /// Write strategy for stdout.log / stderr.log (`run --log-buffering`).
+    #[serde(skip_serializing_if = "LogBuffering::is_none", default)]
+    pub log_buffering: LogBuffering,
+    /// Scheduling priority of the child process (`run --priority`).
+    #[serde(skip_serializing_if = "ProcessPriority::is_normal", default)]
+    pub priority: ProcessPriority,
+    /// Nice value applied to the child (`run --nice`), already clamped to
+    /// -20..=19; takes precedence over the nice value implied by `priority`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nice: Option<i8>,
+    /// Absolute path the child's stdout is written to directly, used as the job's
+    /// stdout log in place of stdout.log (`run --stdout-file`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub stdout_file: Option<String>,
+    /// Absolute path the combined log is written to in place of full.log in
+    /// the job directory (`run --log`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub full_log_file: Option<String>,
+    /// Environment variable names removed from the child environment (`run --env-unset`).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub env_unset: Vec<String>,
+    /// Key prefixes that inherited variables must match (`run --env-prefix`).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub env_inherit_prefix: Vec<String>,
+    /// Resource limits set on the child, normalized to `NAME=SOFT:HARD`
+    /// (`run --ulimit`).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub rlimits: Vec<String>,
+    /// Whether the child was asked to run as PID 1 of a new PID namespace
+    /// (`run --pid-namespace`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub pid_namespace: bool,
+    /// Whether a missing PID namespace is fatal (`run --require-pid-namespace`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub require_pid_namespace: bool,
+    /// Whether the child was placed in a Windows Job Object; `false` only with
+    /// `run --windows-no-job-object`.
+    #[serde(
+        default = "default_windows_job_object",
+        skip_serializing_if = "is_windows_job_object_default"
+    )]
+    pub windows_job_object: bool,
+    /// Whether a non-zero exit is recorded as `failed` (`run --require-clean-exit`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub require_clean_exit: bool,
+    /// Whether SIGUSR1 to the supervisor writes a manual snapshot
+    /// (`run --snapshot-on-sigusr1`).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub snapshot_on_sigusr1: bool,
+    /// Maximum bytes per full.log line before it is cut with a `... [truncated]`
+    /// marker; 0 = unlimited (`run --max-line-length`).
+    #[serde(default)]
+    pub max_line_length: u64,
+    /// Size at which the supervisor rotates `stdout.log` / `stderr.log` while
+    /// the job runs; 0 = never (`run --log-rotate-size-mb`).
+    #[serde(default)]
+    pub log_rotate_size_bytes: u64,
+    /// Archived generations kept by in-run rotation (`run --log-rotate-count`).
+    #[serde(default)]
+    pub log_rotate_count: u32,
+    /// Milliseconds `run` waited for the `--wait-for-file` path before launching.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub startup_wait_ms: Option<u64>,
+    /// Keys supplied through `run --env-from-json` (values are not persisted here).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub env_from_json_keys: Vec<String>,
+    /// Job whose environment `run --read-env-from-state` replayed.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub env_source_job_id: Option<String>,
+    /// Remote host the command ran on (`run --ssh-host`); `command` already
+    /// holds the full `ssh` invocation.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ssh_host: Option<String>,
+    /// The command as given to `run --wrap-command`; `command` holds the
+    /// wrapped shell string.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub original_command: Option<Vec<String>>,
+    /// Hostname of the machine that ran `run --record-host-info`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub hostname: Option<String>,
+    /// Login name of the user who ran `run --record-host-info`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub username: Option<String>,
+    /// Absolute path the supervisor writes a [`JobReport`] to when the job
+    /// finishes (`run --report-file`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub report_file: Option<String>,
+    /// Absolute path the supervisor writes the folded sampling profile to
+    /// (`run --profile-output`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub profile_output: Option<String>,
+    /// Absolute directory receiving checkpoint files (`run --checkpoint-dir`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub checkpoint_dir: Option<String>,
+    /// Checkpoint interval in milliseconds; present with `checkpoint_dir`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub checkpoint_interval_ms: Option<u64>,
+    /// Most periodic checkpoints kept; present with `checkpoint_dir`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub checkpoint_count: Option<u64>,
+    /// URL the completion event is POSTed to (`run --completion-webhook`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub completion_webhook_url: Option<String>,
+    /// Webhook request timeout in milliseconds; present with the URL.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub completion_webhook_timeout_ms: Option<u64>,
+    /// `--notify-command` deadline in milliseconds; present with a notify
+    /// command set by `run`. Absent means the 30 s default.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub notify_timeout_ms: Option<u64>,
+    /// Whether `notify_file` events are appended as NDJSON lines; `false` only
+    /// with `run --notify-file-format replace`.
+    #[serde(
+        default = "default_notify_file_append",
+        skip_serializing_if = "is_notify_file_append_default"
+    )]
+    pub notify_file_append: bool,
+    /// Whether `notify_file` receives a [`CompletionSnapshot`] instead of the
+    /// event; set by `run --notify-file-format snapshot`.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub notify_file_snapshot: bool,
+    /// Stdout byte limit for `run --capture-output`; absent when not capturing.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub capture_output_max_bytes: Option<u64>,
 }
 
 fn default_inherit_env() -> bool {
     true
 }
 
+fn default_output_encoding() -> String {
+    crate::jobstore::DEFAULT_OUTPUT_ENCODING.to_string()
+}
+
 fn default_logs_drained() -> bool {
     true
 }
 
+fn default_windows_job_object() -> bool {
+    true
+}
+
+fn is_windows_job_object_default(v: &bool) -> bool {
+    *v
+}
+
+fn default_notify_file_append() -> bool {
+    true
+}
+
+fn is_notify_file_append_default(v: &bool) -> bool {
+    *v
+}
+
 impl JobMeta {
     /// Convenience accessor: returns the job ID.
     pub fn job_id(&self) -> &str {
@@ -694,6 +1361,15 @@ pub struct JobStateResult {
     pub signal: Option<String>,
     /// `null` while running; set to elapsed milliseconds when process ends.
     pub duration_ms: Option<u64>,
+    /// User-mode CPU time of the child in milliseconds; `null` until it ends.
+    #[serde(default)]
+    pub cpu_user_ms: Option<u64>,
+    /// Kernel-mode CPU time of the child in milliseconds; `null` until it ends.
+    #[serde(default)]
+    pub cpu_sys_ms: Option<u64>,
+    /// Peak resident set size of the child in KiB; `null` until it ends.
+    #[serde(default)]
+    pub max_rss_kb: Option<u64>,
 }
 
 /// Persisted in `state.json`, updated as the job progresses.
@@ -718,6 +1394,10 @@ pub struct JobState {
     /// Process ID (not part of the public spec; omitted when not available).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pid: Option<u32>,
+    /// When `run` or `create` registered the job (RFC 3339), copied from
+    /// `meta.json`; absent in files written by older versions.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub created_at: Option<String>,
     /// Finish time (not part of the nested result block; kept for internal use).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finished_at: Option<String>,
@@ -732,6 +1412,38 @@ pub struct JobState {
     /// fails (in which case tree management falls back to snapshot enumeration).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub windows_job_name: Option<String>,
+    /// Why the supervisor terminated the job, when it did so on its own
+    /// initiative (e.g. `"health_check_failed"`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub killed_reason: Option<String>,
+    /// Final stdout recorded by `run --capture-output` (the last
+    /// `capture_output_max_bytes` bytes when the output was longer).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub captured_stdout: Option<String>,
+    /// True when `captured_stdout` holds only the tail of a longer stdout.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub captured_stdout_truncated: bool,
+    /// Version of the agent-exec binary that wrote this file; absent in
+    /// files written by older versions and by `create`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub agent_version: Option<String>,
+    /// HTTP status returned by the `run --completion-webhook` endpoint;
+    /// absent when no webhook is configured or no response was received.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub webhook_status_code: Option<u16>,
+    /// Sampling summary from `run --profile-output`; present once a profiled
+    /// job has finished.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub resource_usage: Option<ResourceUsage>,
+}
+
+/// Summary of the sampling profile taken by `run --profile-output`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResourceUsage {
+    /// Wall-clock time the child was sampled for, in milliseconds.
+    pub wall_clock_ms: u64,
+    /// Thread samples written to the profile.
+    pub samples: u64,
 }
 
 impl JobState {
@@ -774,6 +1486,8 @@ pub enum JobStatus {
     Exited,
     Killed,
     Failed,
+    /// Paused by `kill --signal STOP`; `kill --signal CONT` resumes it.
+    Stopped,
 }
 
 impl JobStatus {
@@ -784,12 +1498,93 @@ impl JobStatus {
             JobStatus::Exited => "exited",
             JobStatus::Killed => "killed",
             JobStatus::Failed => "failed",
+            JobStatus::Stopped => "stopped",
         }
     }
 
-    /// Returns true when the status is a non-terminal state (created or running).
+    /// Returns true when the status is a non-terminal state (created, running, or stopped).
     pub fn is_non_terminal(&self) -> bool {
-        matches!(self, JobStatus::Created | JobStatus::Running)
+        matches!(
+            self,
+            JobStatus::Created | JobStatus::Running | JobStatus::Stopped
+        )
+    }
+
+    /// Returns true when the job has a live process (running or stopped).
+    pub fn is_active(&self) -> bool {
+        matches!(self, JobStatus::Running | JobStatus::Stopped)
+    }
+}
+
+/// Write strategy for the individual stream logs (`run --log-buffering`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogBuffering {
+    /// Write every chunk as soon as it is read.
+    #[default]
+    None,
+    /// Hold bytes until a newline completes the line.
+    Line,
+    /// Hold up to 64 KiB, flushed when full, every second, and at end of stream.
+    Full,
+}
+
+impl LogBuffering {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogBuffering::None => "none",
+            LogBuffering::Line => "line",
+            LogBuffering::Full => "full",
+        }
+    }
+
+    /// Parse a CLI value; unknown names fall back to `none`.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "line" => LogBuffering::Line,
+            "full" => LogBuffering::Full,
+            _ => LogBuffering::None,
+        }
+    }
+
+    fn is_none(&self) -> bool {
+        *self == LogBuffering::None
+    }
+}
+
+/// Scheduling priority of the child process (`run --priority`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessPriority {
+    /// nice +10 and lowest best-effort I/O priority.
+    Low,
+    /// Inherit the supervisor's priority.
+    #[default]
+    Normal,
+    /// nice -10 and highest best-effort I/O priority; needs privilege on Unix.
+    High,
+}
+
+impl ProcessPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessPriority::Low => "low",
+            ProcessPriority::Normal => "normal",
+            ProcessPriority::High => "high",
+        }
+    }
+
+    /// Parse a CLI value case-insensitively; unknown names fall back to `normal`.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "low" => ProcessPriority::Low,
+            "high" => ProcessPriority::High,
+            _ => ProcessPriority::Normal,
+        }
+    }
+
+    fn is_normal(&self) -> bool {
+        *self == ProcessPriority::Normal
     }
 }
 
@@ -797,6 +1592,26 @@ impl JobStatus {
 mod tests {
     use super::*;
 
+    #[test]
+    fn colorize_json_colors_tokens_and_keeps_text() {
+        let json = r#"{"k": "v \" x", "n": -1.5e3, "b": [true, null]}"#;
+        let colored = colorize_json(json);
+        assert!(colored.contains("\x1b[34m\"k\"\x1b[0m"), "{colored:?}");
+        assert!(
+            colored.contains("\x1b[32m\"v \\\" x\"\x1b[0m"),
+            "{colored:?}"
+        );
+        assert!(colored.contains("\x1b[33m-1.5e3\x1b[0m"), "{colored:?}");
+        assert!(colored.contains("\x1b[35mnull\x1b[0m"), "{colored:?}");
+        let stripped = colored
+            .replace(KEY_COLOR, "")
+            .replace(STRING_COLOR, "")
+            .replace(NUMBER_COLOR, "")
+            .replace(LITERAL_COLOR, "")
+            .replace(RESET, "");
+        assert_eq!(stripped, json);
+    }
+
     fn sample_run_data(
         exit_code: Option<i32>,
         finished_at: Option<&str>,
@@ -806,8 +1621,11 @@ mod tests {
         RunData {
             job_id: "abc123".into(),
             state: "exited".into(),
+            command: vec!["echo".into(), "hello".into()],
             tags: vec![],
             env_vars: vec![],
+            root: "/tmp/root".into(),
+            job_dir: "/tmp/root/abc123".into(),
             stdout_log_path: "/tmp/stdout.log".into(),
             stderr_log_path: "/tmp/stderr.log".into(),
             elapsed_ms: 50,
@@ -824,6 +1642,14 @@ mod tests {
             signal: signal.map(|s| s.to_string()),
             duration_ms,
             compression: None,
+            deduplicated: false,
+            startup_probe_passed: None,
+            report_file_path: None,
+            benchmark: None,
+            warnings: vec![],
+            args: vec![],
+            args_masked: false,
+            replayed_from: None,
         }
     }
 
@@ -885,6 +1711,9 @@ mod tests {
             stdout_total_bytes: 18,
             stderr_total_bytes: 14,
             updated_at: Some("2025-01-01T00:00:00Z".into()),
+            cpu_user_ms: None,
+            cpu_sys_ms: None,
+            max_rss_kb: None,
         };
         let json = serde_json::to_value(&data).unwrap();
         assert_eq!(json["stdout"], "partial stdout");
@@ -912,6 +1741,9 @@ mod tests {
             stdout_total_bytes: 0,
             stderr_total_bytes: 0,
             updated_at: None,
+            cpu_user_ms: None,
+            cpu_sys_ms: None,
+            max_rss_kb: None,
         };
         let json = serde_json::to_value(&data).unwrap();
         assert_eq!(json["stdout"], "");
@@ -938,6 +1770,9 @@ mod tests {
             stdout_total_bytes: 5,
             stderr_total_bytes: 0,
             updated_at: Some("2025-01-01T00:00:02Z".into()),
+            cpu_user_ms: None,
+            cpu_sys_ms: None,
+            max_rss_kb: None,
         };
         let json = serde_json::to_value(&data).unwrap();
         assert_eq!(json["exit_code"], 0);
@@ -960,6 +1795,9 @@ mod tests {
             stdout_total_bytes: 100,
             stderr_total_bytes: 200,
             updated_at: Some("2025-06-01T12:00:00Z".into()),
+            cpu_user_ms: None,
+            cpu_sys_ms: None,
+            max_rss_kb: None,
         };
         let serialized = serde_json::to_string(&data).unwrap();
         let deserialized: WaitData = serde_json::from_str(&serialized).unwrap();
@@ -19,8 +19,11 @@ use std::collections::HashMap;
 use std::net::IpAddr;
 use std::sync::Arc;
 
-use crate::jobstore::{JobDir, JobNotFound, generate_job_id, resolve_root};
-use crate::schema::{JobMeta, JobMetaJob, Response, RunData, SCHEMA_VERSION, StatusData, TailData};
+use crate::duration::DurationMs;
+use crate::jobstore::{
+    DEFAULT_OUTPUT_ENCODING, JobDir, JobNotFound, generate_job_id, resolve_root,
+};
+use crate::schema::{JobMeta, JobMetaJob, Response, RunData, SCHEMA_VERSION, TailData};
 
 /// Options for the `serve` sub-command.
 pub struct ServeOpts {
@@ -299,7 +302,7 @@ async fn exec_handler(State(state): State<Arc<AppState>>, request: Request) -> A
         .map(|(k, v)| format!("{k}={v}"))
         .collect();
     let cwd = req.cwd;
-    let timeout_ms = req.timeout.map(|s| (s * 1000.0) as u64).unwrap_or(0);
+    let timeout_ms = req.timeout.map_or(DurationMs::ZERO, DurationMs::from_secs);
     let wait = req.wait.unwrap_or(true);
     let until = req.until.unwrap_or(10);
     let max_bytes = req.max_bytes.unwrap_or(65536);
@@ -334,7 +337,7 @@ struct ExecParams {
     command: Vec<String>,
     cwd: Option<String>,
     env_vars: Vec<String>,
-    timeout_ms: u64,
+    timeout_ms: DurationMs,
     wait: bool,
     until: u64,
     max_bytes: u64,
@@ -342,8 +345,8 @@ struct ExecParams {
 
 fn run_exec_inner(p: ExecParams) -> Result<serde_json::Value> {
     use crate::run::{
-        SpawnSupervisorParams, now_rfc3339_pub, observe_inline_output, pre_create_log_files,
-        resolve_effective_cwd, spawn_supervisor_process,
+        SpawnSupervisorParams, command_hash, now_rfc3339_pub, observe_inline_output,
+        pre_create_log_files, resolve_effective_cwd, spawn_supervisor_process,
     };
 
     let elapsed_start = std::time::Instant::now();
@@ -361,10 +364,12 @@ fn run_exec_inner(p: ExecParams) -> Result<serde_json::Value> {
         .iter()
         .map(|kv| kv.split('=').next().unwrap_or(kv).to_string())
         .collect();
+    let command_hash = command_hash(&p.command, Some(&effective_cwd), &env_keys);
 
     let meta = JobMeta {
         job: JobMetaJob { id: job_id.clone() },
         schema_version: SCHEMA_VERSION.to_string(),
+        agent_exec_version: crate::schema::AGENT_EXEC_VERSION.to_string(),
         command: p.command.clone(),
         created_at,
         root: resolved_root.display().to_string(),
@@ -373,14 +378,60 @@ fn run_exec_inner(p: ExecParams) -> Result<serde_json::Value> {
         env_vars_runtime: vec![],
         mask: vec![],
         cwd: Some(effective_cwd),
+        command_hash,
         notification: None,
         inherit_env: true,
         env_files: vec![],
-        timeout_ms: p.timeout_ms,
+        timeout_ms: p.timeout_ms.as_millis(),
         kill_after_ms: 0,
+        keep_going_ms: 0,
         progress_every_ms: 0,
         shell_wrapper: Some(shell_wrapper.clone()),
         stdin_file: None,
+        detached: false,
+        signal_on_parent_exit: None,
+        output_encoding: DEFAULT_OUTPUT_ENCODING.to_string(),
+        pty: false,
+        health_check: None,
+        event_log: false,
+        status_report: None,
+        core_dump_disabled: false,
+        max_output_lines_per_second: 0,
+        env_expand: false,
+        log_buffering: Default::default(),
+        priority: Default::default(),
+        nice: None,
+        stdout_file: None,
+        full_log_file: None,
+        env_unset: vec![],
+        env_inherit_prefix: vec![],
+        rlimits: vec![],
+        pid_namespace: false,
+        require_pid_namespace: false,
+        windows_job_object: true,
+        require_clean_exit: false,
+        snapshot_on_sigusr1: false,
+        max_line_length: 0,
+        log_rotate_size_bytes: 0,
+        log_rotate_count: 0,
+        startup_wait_ms: None,
+        env_from_json_keys: vec![],
+        env_source_job_id: None,
+        ssh_host: None,
+        original_command: None,
+        hostname: None,
+        username: None,
+        report_file: None,
+        profile_output: None,
+        checkpoint_dir: None,
+        checkpoint_interval_ms: None,
+        checkpoint_count: None,
+        completion_webhook_url: None,
+        completion_webhook_timeout_ms: None,
+        notify_timeout_ms: None,
+        notify_file_append: true,
+        notify_file_snapshot: false,
+        capture_output_max_bytes: None,
         tags: vec![],
     };
 
@@ -393,25 +444,67 @@ fn run_exec_inner(p: ExecParams) -> Result<serde_json::Value> {
             job_id: job_id.clone(),
             root: resolved_root.clone(),
             full_log_path: job_dir.full_log_path().display().to_string(),
+            created_at: meta.created_at.clone(),
             timeout_ms: p.timeout_ms,
-            kill_after_ms: 0,
+            kill_after_ms: DurationMs::ZERO,
+            keep_going_ms: DurationMs::ZERO,
             cwd: p.cwd.clone(),
             env_vars: p.env_vars.clone(),
             env_files: vec![],
             inherit_env: true,
             stdin_file: None,
-            progress_every_ms: 0,
+            stdin_inherit: false,
+            progress_every_ms: DurationMs::ZERO,
             notify_command: None,
             notify_file: None,
             shell_wrapper: shell_wrapper.clone(),
             command: p.command.clone(),
+            detach: false,
+            signal_on_parent_exit: None,
+            pty: false,
+            health_check: None,
+            event_log: false,
+            status_report: None,
+            disable_core_dump: false,
+            max_output_lines_per_second: 0,
+            env_expand: false,
+            log_buffering: Default::default(),
+            priority: Default::default(),
+            nice: None,
+            stdout_file: None,
+            env_unset: vec![],
+            env_inherit_prefix: vec![],
+            rlimits: vec![],
+            pid_namespace: false,
+            require_pid_namespace: false,
+            windows_no_job_object: false,
+            require_clean_exit: false,
+            snapshot_on_sigusr1: false,
+            max_line_length: 0,
+            log_rotate_size_bytes: 0,
+            log_rotate_count: 0,
+            report_file: None,
+            profile_output: None,
+            checkpoint_dir: None,
+            checkpoint_interval_ms: DurationMs::ZERO,
+            checkpoint_count: 0,
+            completion_webhook_url: None,
+            completion_webhook_timeout_ms: DurationMs::ZERO,
+            capture_output_max_bytes: None,
         },
     )?;
 
     let stdout_log_path = job_dir.stdout_path().display().to_string();
     let stderr_log_path = job_dir.stderr_path().display().to_string();
 
-    let observation = observe_inline_output(&job_dir, p.wait, p.until, false, p.max_bytes)?;
+    let observation = observe_inline_output(
+        &job_dir,
+        p.wait,
+        p.until,
+        false,
+        p.max_bytes,
+        &meta.output_encoding,
+    )?;
 
     let elapsed_ms = elapsed_start.elapsed().as_millis() as u64;
 
@@ -420,8 +513,11 @@ fn run_exec_inner(p: ExecParams) -> Result<serde_json::Value> {
         RunData {
             job_id,
             state: observation.state,
+            command: meta.command.clone(),
             tags: vec![],
             env_vars: vec![],
+            root: job_dir.absolute_root().display().to_string(),
+            job_dir: job_dir.absolute_path().display().to_string(),
             stdout_log_path,
             stderr_log_path,
             elapsed_ms,
@@ -438,6 +534,14 @@ fn run_exec_inner(p: ExecParams) -> Result<serde_json::Value> {
             signal: observation.signal,
             duration_ms: observation.duration_ms,
             compression: None,
+            deduplicated: false,
+            startup_probe_passed: None,
+            report_file_path: None,
+            benchmark: None,
+            warnings: vec![],
+            args: vec![],
+            args_masked: false,
+            replayed_from: None,
         },
     );
 
@@ -452,21 +556,15 @@ async fn status_handler(
 ) -> AxumResponse {
     let root_opt = state.root.clone();
     let result = tokio::task::spawn_blocking(move || {
-        let root = resolve_root(root_opt.as_deref());
-        let job_dir = JobDir::open(&root, &id)?;
-        let meta = job_dir.read_meta()?;
-        let st = job_dir.read_state()?;
-        let response = Response::new(
-            "status",
-            StatusData {
-                job_id: job_dir.job_id.clone(),
-                state: st.status().as_str().to_string(),
-                exit_code: st.exit_code(),
-                created_at: meta.created_at,
-                started_at: st.started_at().map(|s| s.to_string()),
-                finished_at: st.finished_at,
-            },
-        );
+        let response = crate::status::status_response(crate::status::StatusOpts {
+            job_id: &id,
+            root: root_opt.as_deref(),
+            captured: false,
+            timing: false,
+            poll: false,
+            poll_ms: DurationMs::ZERO,
+            timeout_ms: None,
+        })?;
         Ok::<_, anyhow::Error>(serde_json::to_value(&response)?)
     })
     .await;
@@ -491,8 +589,9 @@ async fn tail_handler(State(state): State<Arc<AppState>>, Path(id): Path<String>
         let job_dir = JobDir::open(&root, &id)?;
         let stdout_log_path = job_dir.stdout_path();
         let stderr_log_path = job_dir.stderr_path();
-        let stdout = job_dir.read_tail_metrics("stdout.log", 50, 65536);
-        let stderr = job_dir.read_tail_metrics("stderr.log", 50, 65536);
+        let encoding = job_dir.read_meta()?.output_encoding;
+        let stdout = job_dir.read_tail_metrics("stdout.log", 50, 65536, &encoding);
+        let stderr = job_dir.read_tail_metrics("stderr.log", 50, 65536, &encoding);
         let response = Response::new(
             "tail",
             TailData {
@@ -506,7 +605,10 @@ async fn tail_handler(State(state): State<Arc<AppState>>, Path(id): Path<String>
                 stderr_range: stderr.range,
                 stdout_total_bytes: stdout.observed_bytes,
                 stderr_total_bytes: stderr.observed_bytes,
+                full_log_tail: None,
+                full_log_observed_bytes: None,
                 compression: None,
+                truncated: None,
             },
         );
         Ok::<_, anyhow::Error>(serde_json::to_value(&response)?)
@@ -535,6 +637,7 @@ async fn wait_handler(State(state): State<Arc<AppState>>, Path(id): Path<String>
             poll_seconds: 1,
             until_seconds: 0,
             forever: true,
+            poll_file: None,
         })?;
         Ok::<_, anyhow::Error>(serde_json::to_value(&response)?)
     })
@@ -2,8 +2,10 @@
 //!
 //! `install-skills` is intentionally narrow: it installs only the built-in
 //! `agent-exec` skill into `.agents/skills/` or `.claude/skills/` and records
-//! the result in `.skill-lock.json`.
+//! the result in `.skill-lock.json`, including a SHA-256 checksum of every
+//! installed file so `verify-skills` can detect later modification.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
@@ -54,6 +56,13 @@ pub struct LockEntry {
     pub installed_at: String,
     /// Absolute path to the installed skill directory.
     pub path: String,
+    /// `installed_at` of the entry this installation replaced, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_installed_at: Option<String>,
+    /// SHA-256 (lowercase hex) of each installed file, keyed by its path
+    /// relative to `path`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<String, String>,
 }
 
 /// Represents the `.agents/.skill-lock.json` file.
@@ -98,11 +107,17 @@ impl LockFile {
         std::fs::write(path, json).with_context(|| format!("write lock file {}", path.display()))
     }
 
-    pub fn upsert(&mut self, entry: LockEntry) {
+    /// Insert `entry`, replacing (and refreshing every field of) an existing
+    /// entry with the same name. Returns true when an entry was replaced; its
+    /// `installed_at` is kept as `previous_installed_at`.
+    pub fn upsert(&mut self, mut entry: LockEntry) -> bool {
         if let Some(existing) = self.skills.iter_mut().find(|e| e.name == entry.name) {
+            entry.previous_installed_at = Some(existing.installed_at.clone());
             *existing = entry;
+            true
         } else {
             self.skills.push(entry);
+            false
         }
     }
 }
@@ -113,6 +128,29 @@ pub struct InstalledSkill {
     pub name: String,
     pub path: PathBuf,
     pub source_type: String,
+    /// SHA-256 of each written file, keyed by relative path.
+    pub checksums: BTreeMap<String, String>,
+}
+
+/// Result of checking one lock entry against the files on disk.
+#[derive(Debug, Clone)]
+pub struct SkillVerification {
+    pub name: String,
+    /// True when checksums were recorded and every file still matches.
+    pub verified: bool,
+    /// Relative paths whose content differs from the recorded checksum or
+    /// that no longer exist.
+    pub mismatches: Vec<String>,
+}
+
+/// Lowercase hex SHA-256 of `bytes`.
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
 }
 
 /// Install the built-in `agent-exec` skill into `agents_dir/skills/agent-exec/`.
@@ -121,6 +159,7 @@ pub fn install_builtin(agents_dir: &Path) -> Result<InstalledSkill> {
     let dest = agents_dir.join("skills").join(name);
     std::fs::create_dir_all(&dest)
         .with_context(|| format!("create skill dir {}", dest.display()))?;
+    let mut checksums = BTreeMap::new();
     for file in EMBEDDED_AGENT_EXEC_FILES {
         let file_dest = dest.join(file.relative_path);
         if let Some(parent) = file_dest.parent() {
@@ -129,14 +168,46 @@ pub fn install_builtin(agents_dir: &Path) -> Result<InstalledSkill> {
         }
         std::fs::write(&file_dest, file.content)
             .with_context(|| format!("write embedded file {}", file_dest.display()))?;
+        checksums.insert(file.relative_path.to_string(), sha256_hex(file.content));
     }
     Ok(InstalledSkill {
         name: name.to_string(),
         path: dest,
         source_type: "embedded".to_string(),
+        checksums,
     })
 }
 
+/// Re-hash the files of every skill recorded in `agents_dir/.skill-lock.json`
+/// and compare them with the checksums stored at install time.
+///
+/// Entries installed before checksums were recorded report `verified: false`
+/// with no mismatches; reinstalling records them.
+pub fn verify(agents_dir: &Path) -> Result<Vec<SkillVerification>> {
+    let lock = LockFile::read(&agents_dir.join(".skill-lock.json"))?;
+    Ok(lock
+        .skills
+        .iter()
+        .map(|entry| {
+            let root = Path::new(&entry.path);
+            let mismatches: Vec<String> = entry
+                .checksums
+                .iter()
+                .filter(|(relative, expected)| {
+                    std::fs::read(root.join(relative.as_str()))
+                        .map_or(true, |content| sha256_hex(&content) != **expected)
+                })
+                .map(|(relative, _)| relative.clone())
+                .collect();
+            SkillVerification {
+                name: entry.name.clone(),
+                verified: !entry.checksums.is_empty() && mismatches.is_empty(),
+                mismatches,
+            }
+        })
+        .collect())
+}
+
 /// Resolve the root directory for skill installation.
 pub fn resolve_root_dir(global: bool, claude: bool) -> Result<PathBuf> {
     let root_name = if claude { ".claude" } else { ".agents" };
@@ -213,3 +284,59 @@ fn days_to_ymd(mut days: u64) -> (u64, u64, u64) {
 fn is_leap(year: u64) -> bool {
     (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(installed_at: &str, path: &str) -> LockEntry {
+        LockEntry {
+            name: "agent-exec".to_string(),
+            source_type: "embedded".to_string(),
+            installed_at: installed_at.to_string(),
+            path: path.to_string(),
+            previous_installed_at: None,
+            checksums: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn upsert_reinstall_refreshes_entry_and_records_previous_install() {
+        let mut lock = LockFile::default();
+        assert!(!lock.upsert(entry("2026-01-01T00:00:00Z", "/old/agent-exec")));
+        assert!(lock.skills[0].previous_installed_at.is_none());
+
+        assert!(lock.upsert(entry("2026-02-01T00:00:00Z", "/new/agent-exec")));
+        assert_eq!(lock.skills.len(), 1);
+        let updated = &lock.skills[0];
+        assert_eq!(updated.installed_at, "2026-02-01T00:00:00Z");
+        assert_eq!(updated.path, "/new/agent-exec");
+        assert_eq!(
+            updated.previous_installed_at.as_deref(),
+            Some("2026-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn verify_reports_modified_skill_file_as_mismatch() {
+        let agents_dir = tempfile::tempdir().unwrap();
+        let installed = install_builtin(agents_dir.path()).unwrap();
+        let mut lock = LockFile::default();
+        lock.upsert(LockEntry {
+            checksums: installed.checksums.clone(),
+            ..entry("2026-01-01T00:00:00Z", &installed.path.to_string_lossy())
+        });
+        lock.write(&agents_dir.path().join(".skill-lock.json"))
+            .unwrap();
+
+        let intact = verify(agents_dir.path()).unwrap();
+        assert!(intact[0].verified, "{intact:?}");
+        assert!(intact[0].mismatches.is_empty());
+
+        std::fs::write(installed.path.join("SKILL.md"), "tampered").unwrap();
+        let result = verify(agents_dir.path()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert!(!result[0].verified);
+        assert_eq!(result[0].mismatches, ["SKILL.md"]);
+    }
+}
@@ -7,6 +7,7 @@
 use anyhow::Result;
 use tracing::info;
 
+use crate::duration::DurationMs;
 use crate::jobstore::{InvalidJobState, JobDir, resolve_root};
 use crate::run::{
     SpawnSupervisorParams, mask_env_vars, observe_inline_output, spawn_supervisor_process,
@@ -83,14 +84,17 @@ pub fn execute(opts: StartOpts) -> Result<()> {
             job_id: job_dir.job_id.clone(),
             root: root.clone(),
             full_log_path: full_log_path.clone(),
-            timeout_ms: meta.timeout_ms,
-            kill_after_ms: meta.kill_after_ms,
+            created_at: meta.created_at.clone(),
+            timeout_ms: DurationMs::from_millis(meta.timeout_ms),
+            kill_after_ms: DurationMs::from_millis(meta.kill_after_ms),
+            keep_going_ms: DurationMs::from_millis(meta.keep_going_ms),
             cwd: meta.cwd.clone(),
             env_vars: meta.env_vars_runtime.clone(),
             env_files: meta.env_files.clone(),
             inherit_env: meta.inherit_env,
             stdin_file: meta.stdin_file.clone(),
-            progress_every_ms: meta.progress_every_ms,
+            stdin_inherit: false,
+            progress_every_ms: DurationMs::from_millis(meta.progress_every_ms),
             notify_command: meta
                 .notification
                 .as_ref()
@@ -101,6 +105,46 @@ pub fn execute(opts: StartOpts) -> Result<()> {
                 .and_then(|n| n.notify_file.clone()),
             shell_wrapper,
             command: meta.command.clone(),
+            detach: meta.detached,
+            signal_on_parent_exit: meta.signal_on_parent_exit.clone(),
+            pty: meta.pty,
+            health_check: meta.health_check.clone(),
+            event_log: meta.event_log,
+            status_report: meta.status_report.clone(),
+            disable_core_dump: meta.core_dump_disabled,
+            max_output_lines_per_second: meta.max_output_lines_per_second,
+            env_expand: meta.env_expand,
+            log_buffering: meta.log_buffering,
+            priority: meta.priority,
+            nice: meta.nice,
+            stdout_file: meta.stdout_file.clone(),
+            env_unset: meta.env_unset.clone(),
+            env_inherit_prefix: meta.env_inherit_prefix.clone(),
+            rlimits: meta.rlimits.clone(),
+            pid_namespace: meta.pid_namespace,
+            require_pid_namespace: meta.require_pid_namespace,
+            windows_no_job_object: !meta.windows_job_object,
+            require_clean_exit: meta.require_clean_exit,
+            snapshot_on_sigusr1: meta.snapshot_on_sigusr1,
+            max_line_length: meta.max_line_length,
+            log_rotate_size_bytes: meta.log_rotate_size_bytes,
+            log_rotate_count: meta.log_rotate_count,
+            report_file: meta.report_file.clone(),
+            profile_output: meta.profile_output.clone(),
+            checkpoint_dir: meta.checkpoint_dir.clone(),
+            checkpoint_interval_ms: meta.checkpoint_interval_ms.map_or(
+                crate::run::DEFAULT_CHECKPOINT_INTERVAL_MS,
+                DurationMs::from_millis,
+            ),
+            checkpoint_count: meta
+                .checkpoint_count
+                .unwrap_or(crate::run::DEFAULT_CHECKPOINT_COUNT),
+            completion_webhook_url: meta.completion_webhook_url.clone(),
+            completion_webhook_timeout_ms: meta.completion_webhook_timeout_ms.map_or(
+                crate::run::DEFAULT_COMPLETION_WEBHOOK_TIMEOUT_MS,
+                DurationMs::from_millis,
+            ),
+            capture_output_max_bytes: meta.capture_output_max_bytes,
         },
     )?;
 
@@ -120,7 +164,7 @@ pub fn execute(opts: StartOpts) -> Result<()> {
         crate::gc::maybe_run_auto_gc(&root, &auto_cfg);
     }
 
-    let stdout_log_path = job_dir.stdout_path().display().to_string();
+    let stdout_log_path = crate::run::stdout_log_path(&job_dir, &meta);
     let stderr_log_path = job_dir.stderr_path().display().to_string();
 
     // The response uses the masked env_vars (display view), not the runtime values.
@@ -131,6 +175,7 @@ pub fn execute(opts: StartOpts) -> Result<()> {
         opts.until_seconds,
         opts.forever,
         opts.max_bytes,
+        &meta.output_encoding,
     )?;
     let compression = crate::compress::compress(crate::compress::CompressionInput {
         command: &meta.command,
@@ -146,8 +191,11 @@ pub fn execute(opts: StartOpts) -> Result<()> {
         RunData {
             job_id: job_dir.job_id.clone(),
             state: observation.state,
+            command: meta.command.clone(),
             tags: meta.tags.clone(),
             env_vars: masked_env_vars,
+            root: job_dir.absolute_root().display().to_string(),
+            job_dir: job_dir.absolute_path().display().to_string(),
             stdout_log_path,
             stderr_log_path,
             elapsed_ms: 0,
@@ -164,6 +212,14 @@ pub fn execute(opts: StartOpts) -> Result<()> {
             signal: observation.signal,
             duration_ms: observation.duration_ms,
             compression,
+            deduplicated: false,
+            startup_probe_passed: None,
+            report_file_path: meta.report_file.clone(),
+            benchmark: None,
+            warnings: vec![],
+            args: vec![],
+            args_masked: false,
+            replayed_from: None,
         },
     )
     .print();
@@ -3,41 +3,136 @@
 use anyhow::Result;
 use tracing::debug;
 
+use crate::duration::DurationMs;
 use crate::jobstore::{JobDir, resolve_root};
-use crate::schema::{Response, StatusData};
+use crate::run::parse_rfc3339_secs;
+use crate::schema::{AGENT_EXEC_VERSION, JobState, JobStatus, LogSizes, Response, StatusData};
 
 /// Options for the `status` sub-command.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct StatusOpts<'a> {
     pub job_id: &'a str,
     pub root: Option<&'a str>,
+    /// Include the stdout recorded by `run --capture-output`.
+    pub captured: bool,
+    /// Include the supervisor's `timing.json`.
+    pub timing: bool,
+    /// Keep emitting one status response per line until the job is terminal.
+    pub poll: bool,
+    /// Interval between polled responses, in milliseconds.
+    pub poll_ms: DurationMs,
+    /// Stop polling after this many milliseconds even if the job is still
+    /// running; `None` polls until the job is terminal.
+    pub timeout_ms: Option<DurationMs>,
+}
+
+/// Warning for a `state.json` written by a different agent-exec version.
+/// Files without a version (older binaries, `create`) are not flagged.
+fn version_mismatch_warning(agent_version: Option<&str>) -> Option<String> {
+    agent_version.filter(|v| *v != AGENT_EXEC_VERSION).map(|v| {
+        format!(
+            "agent_version_mismatch: state.json was written by agent-exec {v}, \
+                 this is {AGENT_EXEC_VERSION}"
+        )
+    })
+}
+
+/// Milliseconds since `started_at` for an active job, or the recorded
+/// `result.duration_ms` once it has finished. `started_at` has one-second
+/// resolution, so a running job's value can be up to a second high.
+fn elapsed_ms(state: &JobState) -> Option<u64> {
+    if !state.status().is_active() {
+        return state.duration_ms();
+    }
+    let started_ms = parse_rfc3339_secs(state.started_at()?)?.saturating_mul(1000);
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_millis() as u64;
+    Some(now_ms.saturating_sub(started_ms))
 }
 
 /// Execute `status`: read job state and emit JSON.
+///
+/// With `poll`, responses are emitted as NDJSON until the job reaches a
+/// terminal state or `timeout_ms` elapses; the last line is always a fresh read.
 pub fn execute(opts: StatusOpts) -> Result<()> {
-    status_response(opts)?.print();
-    Ok(())
+    if !opts.poll {
+        status_response(opts)?.print();
+        return Ok(());
+    }
+    let deadline = opts
+        .timeout_ms
+        .map(|ms| std::time::Instant::now() + ms.as_duration());
+    loop {
+        let (response, status) = read_status(opts)?;
+        response.print();
+        if !status.is_non_terminal() || deadline.is_some_and(|dl| std::time::Instant::now() >= dl) {
+            return Ok(());
+        }
+        std::thread::sleep(opts.poll_ms.as_duration());
+    }
+}
+
+/// Size of `path` in bytes, or 0 when it cannot be read.
+fn file_len(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
 }
 
 pub fn status_response(opts: StatusOpts) -> Result<Response<StatusData>> {
+    read_status(opts).map(|(response, _)| response)
+}
+
+/// The `status` response together with the job status it reports.
+fn read_status(opts: StatusOpts) -> Result<(Response<StatusData>, JobStatus)> {
     let root = resolve_root(opts.root);
-    let job_dir = JobDir::open(&root, opts.job_id)?;
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
 
     let meta = job_dir.read_meta()?;
     let state = job_dir.read_state()?;
 
     debug!(job_id = %opts.job_id, state = ?state.status(), "status query");
 
+    let full_log_path = crate::run::full_log_path(&job_dir, &meta);
+    let full_log_size_bytes = std::fs::metadata(&full_log_path).ok().map(|m| m.len());
+    let elapsed_ms = elapsed_ms(&state);
+    let status = state.status().clone();
+
     let response = Response::new(
         "status",
         StatusData {
             job_id: job_dir.job_id.clone(),
-            state: state.status().as_str().to_string(),
+            state: status.as_str().to_string(),
             exit_code: state.exit_code(),
             created_at: meta.created_at,
             started_at: state.started_at().map(|s| s.to_string()),
             finished_at: state.finished_at,
+            full_log_path: full_log_size_bytes.map(|_| full_log_path.display().to_string()),
+            full_log_size_bytes,
+            cpu_user_ms: state.result.cpu_user_ms,
+            cpu_sys_ms: state.result.cpu_sys_ms,
+            max_rss_kb: state.result.max_rss_kb,
+            captured_stdout: opts
+                .captured
+                .then(|| state.captured_stdout.clone())
+                .flatten(),
+            captured_stdout_truncated: (opts.captured && state.captured_stdout.is_some())
+                .then_some(state.captured_stdout_truncated),
+            elapsed_ms,
+            timing: if opts.timing {
+                job_dir.read_timing()?
+            } else {
+                None
+            },
+            log_sizes: LogSizes {
+                stdout_bytes: file_len(&job_dir.stdout_path()),
+                stderr_bytes: file_len(&job_dir.stderr_path()),
+                full_log_bytes: full_log_size_bytes.unwrap_or(0),
+            },
+            warnings: version_mismatch_warning(state.agent_version.as_deref())
+                .into_iter()
+                .collect(),
         },
     );
-    Ok(response)
+    Ok((response, status))
 }
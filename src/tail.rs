@@ -1,9 +1,16 @@
 //! Implementation of the `tail` sub-command.
+//!
+//! With `--stream`, the tail snapshot is printed as the first NDJSON line and
+//! new output follows as `chunk` lines, read from where the snapshot ended,
+//! until the job finishes; a final `eof` line reports its state. Chunks use
+//! the same encoding as the snapshot.
 
 use anyhow::Result;
 
+use crate::duration::DurationMs;
+use crate::follow::{FollowedLog, stream_until_drained};
 use crate::jobstore::{JobDir, resolve_root};
-use crate::schema::{Response, TailData};
+use crate::schema::{FollowEvent, Response, TailData, print_to_stdout};
 
 /// Options for the `tail` sub-command.
 #[derive(Debug)]
@@ -15,6 +22,16 @@ pub struct TailOpts<'a> {
     /// Maximum bytes to read from the end of each log.
     pub max_bytes: u64,
     pub compression_mode: crate::compress::CompressionMode,
+    /// Encoding used to decode log bytes (or `base64` for the raw bytes);
+    /// None = use `meta.json.output_encoding`.
+    pub encoding: Option<String>,
+    /// Also return the tail of the interleaved `full.log`.
+    pub full_log: bool,
+    /// Keep printing new output as NDJSON `chunk` lines after the snapshot
+    /// until the job finishes.
+    pub stream: bool,
+    /// Interval between checks for new output while streaming.
+    pub stream_poll_ms: DurationMs,
 }
 
 impl<'a> Default for TailOpts<'a> {
@@ -25,34 +42,95 @@ impl<'a> Default for TailOpts<'a> {
             tail_lines: 50,
             max_bytes: 65536,
             compression_mode: crate::compress::CompressionMode::default(),
+            encoding: None,
+            full_log: false,
+            stream: false,
+            stream_poll_ms: DurationMs::from_millis(200),
         }
     }
 }
 
 /// Execute `tail`: read log tails and emit JSON.
 pub fn execute(opts: TailOpts) -> Result<()> {
-    tail_response(opts)?.print();
+    if !opts.stream {
+        tail_response(opts)?.print();
+        return Ok(());
+    }
+
+    let root = resolve_root(opts.root);
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
+    let poll = opts
+        .stream_poll_ms
+        .max(DurationMs::from_millis(1))
+        .as_duration();
+    let encoding = match &opts.encoding {
+        Some(encoding) => encoding.clone(),
+        None => job_dir.read_meta()?.output_encoding,
+    };
+    let mut response = tail_response(opts)?;
+    let data = &mut response.data;
+    data.truncated = Some(data.stdout_range[0] > 0 || data.stderr_range[0] > 0);
+    // Continue exactly where the snapshot stopped reading.
+    let mut logs = [
+        FollowedLog::new(
+            "stdout",
+            job_dir.stdout_path(),
+            data.stdout_total_bytes,
+            &encoding,
+        ),
+        FollowedLog::new(
+            "stderr",
+            job_dir.stderr_path(),
+            data.stderr_total_bytes,
+            &encoding,
+        ),
+    ];
+
+    response.print();
+    let state = stream_until_drained(&job_dir, &mut logs, poll, &mut |event| {
+        print_to_stdout(&event)
+    })?;
+    print_to_stdout(&FollowEvent::Eof {
+        state: state.status().as_str().to_string(),
+    });
     Ok(())
 }
 
 pub fn tail_response(opts: TailOpts) -> Result<Response<TailData>> {
     let root = resolve_root(opts.root);
-    let job_dir = JobDir::open(&root, opts.job_id)?;
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
 
     let stdout_log_path = job_dir.stdout_path();
     let stderr_log_path = job_dir.stderr_path();
 
-    // Use the shared helper so that byte metric calculation is in one place.
-    let stdout = job_dir.read_tail_metrics("stdout.log", opts.tail_lines, opts.max_bytes);
-    let stderr = job_dir.read_tail_metrics("stderr.log", opts.tail_lines, opts.max_bytes);
     let meta = job_dir.read_meta()?;
+    let encoding = opts
+        .encoding
+        .unwrap_or_else(|| meta.output_encoding.clone());
+
+    // Use the shared helper so that byte metric calculation is in one place.
+    let stdout =
+        job_dir.read_tail_metrics("stdout.log", opts.tail_lines, opts.max_bytes, &encoding);
+    let stderr =
+        job_dir.read_tail_metrics("stderr.log", opts.tail_lines, opts.max_bytes, &encoding);
+    // The tail-lines and max-bytes limits apply to full.log on their own;
+    // a `run --log` target is read in its place.
+    let full_log_path = crate::run::full_log_path(&job_dir, &meta);
+    let full_log = (opts.full_log && full_log_path.exists()).then(|| {
+        crate::jobstore::read_tail_metrics_at(
+            &full_log_path,
+            opts.tail_lines,
+            opts.max_bytes,
+            &encoding,
+        )
+    });
     let compression = crate::compress::compress(crate::compress::CompressionInput {
         command: &meta.command,
         stdout: &stdout.tail,
         stderr: &stderr.tail,
         stdout_original_bytes: stdout.observed_bytes,
         stderr_original_bytes: stderr.observed_bytes,
-        mode: opts.compression_mode,
+        mode: crate::run::snapshot_compression_mode(opts.compression_mode, &encoding),
     });
 
     let response = Response::new(
@@ -61,14 +139,17 @@ pub fn tail_response(opts: TailOpts) -> Result<Response<TailData>> {
             job_id: job_dir.job_id.clone(),
             stdout: stdout.tail,
             stderr: stderr.tail,
-            encoding: "utf-8-lossy".to_string(),
+            encoding: crate::jobstore::response_encoding_name(&encoding),
             stdout_log_path: stdout_log_path.display().to_string(),
             stderr_log_path: stderr_log_path.display().to_string(),
             stdout_range: stdout.range,
             stderr_range: stderr.range,
             stdout_total_bytes: stdout.observed_bytes,
             stderr_total_bytes: stderr.observed_bytes,
+            full_log_observed_bytes: full_log.as_ref().map(|f| f.observed_bytes),
+            full_log_tail: full_log.map(|f| f.tail),
             compression,
+            truncated: None,
         },
     );
     Ok(response)
@@ -0,0 +1,42 @@
+//! Implementation of the `verify-skills` subcommand.
+//!
+//! Re-hashes the files of each skill recorded in `.skill-lock.json` and
+//! reports which ones no longer match the checksums stored by `install-skills`.
+
+use anyhow::Result;
+
+use crate::schema::{Response, VerifiedSkillSummary, VerifySkillsData};
+use crate::skills::{resolve_root_dir, verify};
+
+/// Options for the `verify-skills` subcommand.
+pub struct VerifySkillsOpts {
+    /// If true, verify the home-directory installation; otherwise cwd.
+    pub global: bool,
+    /// If true, use `.claude` root instead of `.agents`.
+    pub claude: bool,
+}
+
+/// Execute the `verify-skills` command.
+///
+/// A mismatch is reported in the payload (`verified: false`), not as an error.
+pub fn execute(opts: VerifySkillsOpts) -> Result<()> {
+    let root_dir = resolve_root_dir(opts.global, opts.claude)?;
+    let lock_path = root_dir.join(".skill-lock.json");
+
+    let skills: Vec<VerifiedSkillSummary> = verify(&root_dir)?
+        .into_iter()
+        .map(|s| VerifiedSkillSummary {
+            name: s.name,
+            verified: s.verified,
+            mismatches: s.mismatches,
+        })
+        .collect();
+
+    let data = VerifySkillsData {
+        verified: skills.iter().all(|s| s.verified),
+        skills,
+        lock_file_path: lock_path.to_string_lossy().into_owned(),
+    };
+    Response::new("verify_skills", data).print();
+    Ok(())
+}
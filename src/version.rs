@@ -0,0 +1,22 @@
+//! Implementation of the `version` subcommand.
+//!
+//! Reports the agent-exec binary version alongside the response schema version
+//! (carried by the standard envelope).
+
+use anyhow::Result;
+
+use crate::schema::{AGENT_EXEC_VERSION, Response, VersionData};
+
+pub struct VersionOpts;
+
+/// Execute the `version` subcommand.
+pub fn execute(_opts: VersionOpts) -> Result<()> {
+    Response::new(
+        "version",
+        VersionData {
+            version: AGENT_EXEC_VERSION.to_string(),
+        },
+    )
+    .print();
+    Ok(())
+}
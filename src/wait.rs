@@ -21,6 +21,10 @@ pub struct WaitOpts<'a> {
     pub until_seconds: u64,
     /// Wait indefinitely when true.
     pub forever: bool,
+    /// Watch this file's mtime/size and re-read `state.json` only when it
+    /// changes. Relative paths resolve against the job directory, so
+    /// `"state.json"` watches the state file itself.
+    pub poll_file: Option<String>,
 }
 
 impl<'a> Default for WaitOpts<'a> {
@@ -31,18 +35,35 @@ impl<'a> Default for WaitOpts<'a> {
             poll_seconds: 1,
             until_seconds: 30,
             forever: false,
+            poll_file: None,
         }
     }
 }
 
+/// With `--poll-file`, `state.json` is still re-read every this many polls in
+/// case a change went unnoticed (e.g. coarse mtime resolution on network mounts).
+const POLL_FILE_REFRESH_EVERY: u32 = 30;
+
 const INLINE_OUTPUT_LINES: u64 = 50;
 const INLINE_OUTPUT_MAX_BYTES: u64 = 65_536;
 
 pub fn build_wait_data(job_dir: &JobDir, state: &crate::schema::JobState) -> WaitData {
-    let stdout =
-        job_dir.read_tail_metrics("stdout.log", INLINE_OUTPUT_LINES, INLINE_OUTPUT_MAX_BYTES);
-    let stderr =
-        job_dir.read_tail_metrics("stderr.log", INLINE_OUTPUT_LINES, INLINE_OUTPUT_MAX_BYTES);
+    let encoding = job_dir
+        .read_meta()
+        .map(|meta| meta.output_encoding)
+        .unwrap_or_else(|_| crate::jobstore::DEFAULT_OUTPUT_ENCODING.to_string());
+    let stdout = job_dir.read_tail_metrics(
+        "stdout.log",
+        INLINE_OUTPUT_LINES,
+        INLINE_OUTPUT_MAX_BYTES,
+        &encoding,
+    );
+    let stderr = job_dir.read_tail_metrics(
+        "stderr.log",
+        INLINE_OUTPUT_LINES,
+        INLINE_OUTPUT_MAX_BYTES,
+        &encoding,
+    );
 
     WaitData {
         job_id: job_dir.job_id.clone(),
@@ -56,9 +77,18 @@ pub fn build_wait_data(job_dir: &JobDir, state: &crate::schema::JobState) -> Wai
         stdout_total_bytes: stdout.observed_bytes,
         stderr_total_bytes: stderr.observed_bytes,
         updated_at: Some(state.updated_at.clone()),
+        cpu_user_ms: state.result.cpu_user_ms,
+        cpu_sys_ms: state.result.cpu_sys_ms,
+        max_rss_kb: state.result.max_rss_kb,
     }
 }
 
+/// Modification time and size of the `--poll-file`, if it can be read.
+fn poll_file_signature(path: &std::path::Path) -> Option<(std::time::SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
 /// Execute `wait`: poll until done, then emit JSON.
 pub fn execute(opts: WaitOpts) -> Result<()> {
     wait_response(opts)?.print();
@@ -67,7 +97,7 @@ pub fn execute(opts: WaitOpts) -> Result<()> {
 
 pub fn wait_response(opts: WaitOpts) -> Result<Response<WaitData>> {
     let root = resolve_root(opts.root);
-    let job_dir = JobDir::open(&root, opts.job_id)?;
+    let job_dir = JobDir::open_id_or_path(&root, opts.job_id)?;
 
     let poll = std::time::Duration::from_secs(opts.poll_seconds.max(1));
     let deadline = if opts.forever {
@@ -76,8 +106,30 @@ pub fn wait_response(opts: WaitOpts) -> Result<Response<WaitData>> {
         Some(std::time::Instant::now() + std::time::Duration::from_secs(opts.until_seconds))
     };
 
+    let poll_file = opts.poll_file.as_ref().map(|p| job_dir.path.join(p));
+    let mut last_seen = None;
+    let mut cached_state = None;
+    let mut polls_since_read = 0u32;
+
     loop {
-        let state = job_dir.read_state()?;
+        // With --poll-file the cached state is reused while the poll file's
+        // signature is unchanged; an unreadable poll file always re-reads.
+        let seen = poll_file.as_deref().and_then(poll_file_signature);
+        polls_since_read += 1;
+        let state = match cached_state.take() {
+            Some(cached)
+                if seen.is_some()
+                    && seen == last_seen
+                    && polls_since_read < POLL_FILE_REFRESH_EVERY =>
+            {
+                cached
+            }
+            _ => {
+                last_seen = seen;
+                polls_since_read = 0;
+                job_dir.read_state()?
+            }
+        };
         debug!(job_id = %opts.job_id, state = ?state.status(), "wait poll");
 
         if !state.status().is_non_terminal() && state.logs_drained {
@@ -93,6 +145,11 @@ pub fn wait_response(opts: WaitOpts) -> Result<Response<WaitData>> {
             return Ok(Response::new("wait", data));
         }
 
+        // A finished job whose logs are still draining is re-read every poll:
+        // the poll file may not change again once the last output is written.
+        if poll_file.is_some() && state.status().is_non_terminal() {
+            cached_state = Some(state);
+        }
         std::thread::sleep(poll);
     }
 }
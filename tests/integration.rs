@@ -39,6 +39,15 @@ fn run_returns_json_with_job_id() {
     assert!(v.get("stderr").is_some(), "stderr missing: {v}");
 }
 
+/// `run` reports the command argv that was launched.
+#[test]
+fn run_response_includes_command() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "echo", "hello"]);
+    assert_envelope(&v, "run", true);
+    assert_eq!(v["command"], serde_json::json!(["echo", "hello"]), "{v}");
+}
+
 #[test]
 fn run_returns_inline_payload_without_legacy_snapshot_fields() {
     let h = TestHarness::new();
@@ -76,6 +85,458 @@ fn status_returns_json_for_existing_job() {
     assert!(v.get("started_at").is_some(), "started_at missing");
 }
 
+#[test]
+fn status_reports_full_log_path_and_size() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "echo", "hello"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["status", &job_id]);
+    assert_envelope(&v, "status", true);
+    let path = v["full_log_path"].as_str().unwrap_or("");
+    assert!(path.ends_with("full.log"), "full_log_path: {v}");
+    assert!(
+        v["full_log_size_bytes"].as_u64().unwrap_or(0) > 0,
+        "full_log_size_bytes must be positive: {v}"
+    );
+}
+
+/// Spec: for a `run --log` job, `status` reports the file the combined log is
+/// written to, not the unused full.log in the job directory.
+#[test]
+fn status_reports_log_override_as_full_log() {
+    let h = TestHarness::new();
+    let log_path = std::path::Path::new(h.root()).join("custom_full.log");
+    let run_v = h.run(&["run", "--log", log_path.to_str().unwrap(), "echo", "hello"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["status", &job_id]);
+    assert_envelope(&v, "status", true);
+    let reported = std::path::Path::new(v["full_log_path"].as_str().expect("full_log_path"));
+    assert_eq!(
+        reported.canonicalize().unwrap(),
+        log_path.canonicalize().unwrap(),
+        "{v}"
+    );
+    let size = std::fs::metadata(&log_path).unwrap().len();
+    assert!(size > 0);
+    assert_eq!(v["full_log_size_bytes"], size, "{v}");
+    assert_eq!(v["log_sizes"]["full_log_bytes"], size, "{v}");
+}
+
+/// Spec: `tail --full-log`, `restart` and `replay` keep using a `run --log`
+/// target instead of the job directory's full.log.
+#[test]
+fn log_override_is_kept_by_tail_restart_and_replay() {
+    let h = TestHarness::new();
+    let log_path = std::path::Path::new(h.root()).join("custom_full.log");
+    let run_v = h.run(&["run", "--log", log_path.to_str().unwrap(), "echo", "first"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let tail_v = h.run(&["tail", "--full-log", &job_id]);
+    assert_envelope(&tail_v, "tail", true);
+    let full_tail = tail_v["full_log_tail"].as_str().expect("full_log_tail");
+    assert!(full_tail.contains("first"), "{tail_v}");
+
+    let restart_v = h.run(&["restart", "--wait", &job_id]);
+    assert_envelope(&restart_v, "restart", true);
+    wait_until_terminal(&h, &job_id);
+    let restarted = std::fs::read_to_string(&log_path).unwrap();
+    assert!(restarted.contains("first"), "{restarted}");
+    let job_full_log = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("full.log");
+    assert_eq!(std::fs::metadata(&job_full_log).map_or(0, |m| m.len()), 0);
+
+    let replay_v = h.run(&["replay", &job_id]);
+    assert_envelope(&replay_v, "replay", true);
+    let replay_id = replay_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &replay_id);
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&replay_id)
+                .join("meta.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let recorded = std::path::Path::new(meta["full_log_file"].as_str().expect("full_log_file"));
+    assert_eq!(
+        recorded.canonicalize().unwrap(),
+        log_path.canonicalize().unwrap(),
+        "{meta}"
+    );
+}
+
+/// Spec: `status` reports `elapsed_ms` for a running job.
+#[test]
+fn status_reports_elapsed_ms_for_running_job() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--no-wait", "--", "sleep", "5"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    let v = h.run(&["status", &job_id]);
+    assert_envelope(&v, "status", true);
+    assert_eq!(v["state"], "running", "{v}");
+    let elapsed = v["elapsed_ms"].as_u64().expect("elapsed_ms");
+    assert!((300..5000).contains(&elapsed), "{v}");
+    h.run(&["kill", "--signal", "KILL", &job_id]);
+}
+
+/// Spec: `follow` streams new output as NDJSON chunks and ends with a
+/// `finished` line.
+#[cfg(unix)]
+#[test]
+fn follow_streams_chunks_until_finished() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--",
+        "sh",
+        "-c",
+        "sleep 1; echo one; echo err >&2; sleep 0.3; echo two; exit 3",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    let output = Command::new(binary())
+        .args(["--root", h.root(), "follow", "--poll-ms", "50", &job_id])
+        .output()
+        .expect("run follow");
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("NDJSON line"))
+        .collect();
+    let stream_text = |stream: &str| -> String {
+        lines
+            .iter()
+            .filter(|l| l["type"] == "chunk" && l["stream"] == stream)
+            .map(|l| l["data"].as_str().unwrap())
+            .collect()
+    };
+    assert_eq!(stream_text("stdout"), "one\ntwo\n", "{lines:?}");
+    assert_eq!(stream_text("stderr"), "err\n", "{lines:?}");
+    let first_stdout = lines
+        .iter()
+        .find(|l| l["stream"] == "stdout")
+        .expect("stdout chunk");
+    assert_eq!(first_stdout["offset"], 0);
+    let last = lines.last().expect("finished line");
+    assert_eq!(last["type"], "finished", "{lines:?}");
+    assert_eq!(last["state"], "exited");
+    assert_eq!(last["exit_code"], 3);
+}
+
+/// Spec: `follow` keeps every byte when `--log-rotate-size-mb` rotates
+/// stdout.log between polls.
+#[cfg(unix)]
+#[test]
+fn follow_keeps_output_across_log_rotation() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--log-rotate-size-mb",
+        "0.01",
+        "--log-rotate-count",
+        "100",
+        "--",
+        "sh",
+        "-c",
+        "sleep 1; for i in $(seq 1 20); do seq 1 2000; sleep 0.1; done",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    let output = Command::new(binary())
+        .args([
+            "--root",
+            h.root(),
+            "follow",
+            "--stdout-only",
+            "--poll-ms",
+            "300",
+            &job_id,
+        ])
+        .output()
+        .expect("run follow");
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("NDJSON line"))
+        .collect();
+    let stdout: String = lines
+        .iter()
+        .filter(|l| l["type"] == "chunk")
+        .map(|l| l["data"].as_str().unwrap())
+        .collect();
+    let expected: String = (0..20)
+        .flat_map(|_| 1..=2000)
+        .map(|n| format!("{n}\n"))
+        .collect();
+    assert!(stdout == expected, "follow lost output across rotation");
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    assert!(job_path.join("stdout.log.2").exists(), "log never rotated");
+    assert_eq!(lines.last().unwrap()["type"], "finished");
+}
+
+/// Spec: `tail --stream` prints the snapshot first, then the remaining output
+/// as chunks, and ends with an `eof` line.
+#[cfg(unix)]
+#[test]
+fn tail_stream_continues_after_snapshot() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--wait",
+        "--until",
+        "1",
+        "--",
+        "sh",
+        "-c",
+        "echo one; sleep 1; echo two; exit 3",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    let output = Command::new(binary())
+        .args([
+            "--root",
+            h.root(),
+            "tail",
+            "--stream",
+            "--stream-poll-ms",
+            "50",
+            &job_id,
+        ])
+        .output()
+        .expect("run tail --stream");
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("NDJSON line"))
+        .collect();
+    let snapshot = &lines[0];
+    assert_envelope(snapshot, "tail", true);
+    assert_eq!(snapshot["truncated"], false, "{snapshot}");
+    let mut stdout = snapshot["stdout"].as_str().unwrap().to_string();
+    for chunk in lines[1..].iter().filter(|l| l["type"] == "chunk") {
+        assert!(chunk.get("truncated").is_none(), "{chunk}");
+        if chunk["stream"] == "stdout" {
+            stdout.push_str(chunk["data"].as_str().unwrap());
+        }
+    }
+    assert_eq!(stdout, "one\ntwo\n", "{lines:?}");
+    let last = lines.last().unwrap();
+    assert_eq!(last["type"], "eof", "{lines:?}");
+    assert_eq!(last["state"], "exited");
+}
+
+/// Spec: `tail --stream` keeps every chunk when `--log-rotate-size-mb`
+/// rotates stdout.log between polls.
+#[cfg(unix)]
+#[test]
+fn tail_stream_keeps_output_across_log_rotation() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--wait",
+        "--until",
+        "1",
+        "--log-rotate-size-mb",
+        "0.01",
+        "--log-rotate-count",
+        "100",
+        "--",
+        "sh",
+        "-c",
+        "echo start; sleep 1.5; for i in $(seq 1 20); do seq 1 2000; sleep 0.1; done",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    let output = Command::new(binary())
+        .args([
+            "--root",
+            h.root(),
+            "tail",
+            "--stream",
+            "--stream-poll-ms",
+            "300",
+            &job_id,
+        ])
+        .output()
+        .expect("run tail --stream");
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("NDJSON line"))
+        .collect();
+    let mut stdout = lines[0]["stdout"].as_str().unwrap().to_string();
+    for chunk in lines[1..]
+        .iter()
+        .filter(|l| l["type"] == "chunk" && l["stream"] == "stdout")
+    {
+        stdout.push_str(chunk["data"].as_str().unwrap());
+    }
+    let expected: String = std::iter::once("start\n".to_string())
+        .chain((0..20).flat_map(|_| 1..=2000).map(|n| format!("{n}\n")))
+        .collect();
+    assert!(
+        stdout == expected,
+        "tail --stream lost output across rotation"
+    );
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    assert!(job_path.join("stdout.log.2").exists(), "log never rotated");
+    assert_eq!(lines.last().unwrap()["type"], "eof");
+}
+
+/// Spec: `tail --stream --encoding base64` also base64-encodes the chunks.
+#[cfg(unix)]
+#[test]
+fn tail_stream_chunks_use_snapshot_encoding() {
+    use base64::Engine;
+
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--wait",
+        "--until",
+        "1",
+        "--",
+        "sh",
+        "-c",
+        "echo one; sleep 2; printf '\\377\\376'",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    let output = Command::new(binary())
+        .args([
+            "--root",
+            h.root(),
+            "tail",
+            "--stream",
+            "--encoding",
+            "base64",
+            "--stream-poll-ms",
+            "50",
+            &job_id,
+        ])
+        .output()
+        .expect("run tail --stream");
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("NDJSON line"))
+        .collect();
+    assert_eq!(lines[0]["encoding"], "base64", "{lines:?}");
+    let decode = |v: &serde_json::Value| {
+        base64::engine::general_purpose::STANDARD
+            .decode(v.as_str().unwrap())
+            .unwrap()
+    };
+    let mut stdout = decode(&lines[0]["stdout"]);
+    for chunk in lines[1..]
+        .iter()
+        .filter(|l| l["type"] == "chunk" && l["stream"] == "stdout")
+    {
+        stdout.extend(decode(&chunk["data"]));
+    }
+    assert_eq!(stdout, b"one\n\xFF\xFE", "{lines:?}");
+}
+
+/// Spec: `status` always reports `log_sizes` for the job's log files.
+#[test]
+fn status_reports_log_sizes() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "echo", "hello"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["status", &job_id]);
+    assert_envelope(&v, "status", true);
+    let sizes = &v["log_sizes"];
+    assert_eq!(sizes["stdout_bytes"].as_u64(), Some(6), "{v}");
+    assert_eq!(sizes["stderr_bytes"].as_u64(), Some(0), "{v}");
+    assert!(sizes["full_log_bytes"].as_u64().is_some(), "{v}");
+}
+
+/// Spec: the supervisor writes `timing.json` and `status --timing` reports
+/// its five phase timestamps in chronological order.
+#[test]
+fn status_timing_reports_supervisor_phases_in_order() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "echo", "timed"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["status", "--timing", &job_id]);
+    assert_envelope(&v, "status", true);
+    let phases: Vec<&str> = [
+        "supervisor_start",
+        "child_spawn",
+        "child_started",
+        "child_exited",
+        "logs_flushed",
+    ]
+    .iter()
+    .map(|phase| {
+        v["timing"][phase]
+            .as_str()
+            .unwrap_or_else(|| panic!("missing {phase}: {v}"))
+    })
+    .collect();
+    // Fixed-width RFC 3339 timestamps sort lexicographically.
+    assert!(phases.windows(2).all(|w| w[0] <= w[1]), "{v}");
+    assert!(phases[0].ends_with('Z') && phases[0].contains('.'), "{v}");
+
+    let plain = h.run(&["status", &job_id]);
+    assert!(plain.get("timing").is_none(), "{plain}");
+}
+
+/// Spec: `state.json` records `created_at` next to `job.started_at`, and the
+/// two are at most a second apart for `run` (timestamps have 1 s resolution).
+#[test]
+fn state_json_records_created_at_and_started_at() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "echo", "hello"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let state: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("state.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let created_at = state["created_at"].as_str().expect("created_at");
+    let started_at = state["job"]["started_at"].as_str().expect("started_at");
+    assert!(created_at <= started_at, "{state}");
+
+    let time_of_day = |ts: &str| -> i64 {
+        ts[11..19]
+            .split(':')
+            .fold(0, |acc, part| acc * 60 + part.parse::<i64>().unwrap())
+    };
+    let gap = (time_of_day(started_at) - time_of_day(created_at)).rem_euclid(86_400);
+    assert!(gap <= 1, "created_at and started_at {gap}s apart: {state}");
+
+    let v = h.run(&["status", &job_id]);
+    assert_eq!(v["created_at"], created_at, "{v}");
+    assert_eq!(v["started_at"], started_at, "{v}");
+}
+
 #[test]
 fn status_error_for_unknown_job() {
     let h = TestHarness::new();
@@ -139,6 +600,33 @@ fn tail_returns_json_with_encoding() {
     assert!(v.get("stderr").is_some(), "stderr missing");
 }
 
+/// Once `wait` reports the job finished, `tail` includes its last output line,
+/// even when the logs are written with full buffering.
+#[test]
+fn tail_after_finish_includes_final_line() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--log-buffering",
+        "full",
+        "--",
+        "sh",
+        "-c",
+        "i=0; while [ $i -lt 200 ]; do echo line$i; i=$((i+1)); done; echo final_line",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["tail", "--tail-lines", "1", &job_id]);
+    assert_envelope(&v, "tail", true);
+    assert_eq!(
+        v["stdout"].as_str().unwrap().trim_end(),
+        "final_line",
+        "{v}"
+    );
+}
+
 fn wait_until_terminal(h: &TestHarness, job_id: &str) -> serde_json::Value {
     for _ in 0..20 {
         let wait_v = h.run(&["wait", "--until", "1000", job_id]);
@@ -203,34 +691,159 @@ fn run_stdin_inline_preserves_exact_bytes() {
     assert_eq!(stdout, "abc", "inline stdin should not append newline");
 }
 
+/// Spec: run --dry-run validates without creating a job directory
 #[test]
-fn run_stdin_file_uses_materialized_copy() {
+fn run_dry_run_creates_no_job() {
     let h = TestHarness::new();
-    let src_path = std::path::Path::new(h.root()).join("stdin-source.txt");
-    std::fs::write(&src_path, b"file-input").expect("write stdin source file");
-
-    let v = h.run(&[
-        "run",
-        "--stdin-file",
-        src_path.to_str().expect("utf8 path"),
-        "--",
-        "cat",
-    ]);
+    let v = h.run(&["run", "--dry-run", "--env", "A=1", "--", "echo", "hi"]);
     assert_envelope(&v, "run", true);
-    let job_id = v["job_id"].as_str().expect("job_id missing").to_string();
+    assert_eq!(v["dry_run"], true);
+    assert!(v["job_id"].is_null(), "{v}");
+    assert_eq!(v["state"], "dry_run");
+    let options = &v["validated_options"];
+    assert_eq!(options["command"], serde_json::json!(["echo", "hi"]));
+    assert_eq!(options["env_keys"], serde_json::json!(["A"]));
+    assert!(options["cwd"].as_str().is_some_and(|c| !c.is_empty()));
+    let entries = std::fs::read_dir(h.root())
+        .map(|dir| dir.count())
+        .unwrap_or(0);
+    assert_eq!(entries, 0, "dry run must not create job directories");
 
-    let wait_v = wait_until_terminal(&h, &job_id);
+    let missing = std::path::Path::new(h.root()).join("no-such-dir");
+    let output = run_raw_with_root_and_stdin(
+        &[
+            "run",
+            "--dry-run",
+            "--cwd",
+            missing.to_str().expect("utf8 path"),
+            "--",
+            "echo",
+            "hi",
+        ],
+        Some(h.root()),
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let v: serde_json::Value = serde_json::from_str(stdout.trim()).expect("stdout should be JSON");
+    assert_eq!(v["error"]["code"].as_str(), Some("cwd_not_found"));
+}
+
+/// Spec: run reports its own invocation in args, with masked --env values
+#[test]
+fn run_response_includes_masked_invocation_args() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--env",
+        "TOKEN=s3cret",
+        "--mask",
+        "TOKEN",
+        "--",
+        "echo",
+        "done",
+    ]);
+    assert_envelope(&v, "run", true);
+    let args: Vec<&str> = v["args"]
+        .as_array()
+        .expect("args array")
+        .iter()
+        .map(|a| a.as_str().expect("string arg"))
+        .collect();
+    assert!(
+        args[0].ends_with("agent-exec") || args[0].ends_with("agent-exec.exe"),
+        "args[0]: {}",
+        args[0]
+    );
+    assert_eq!(&args[args.len() - 2..], ["echo", "done"]);
+    assert!(args.contains(&"TOKEN=***"), "args: {args:?}");
+    assert!(!args.iter().any(|a| a.contains("s3cret")));
+    assert_eq!(v["args_masked"], true);
+}
+
+/// Spec: run --stdin-file connects the source file without copying it
+#[test]
+fn run_stdin_file_is_passed_without_copy() {
+    let h = TestHarness::new();
+    let src_path = std::path::Path::new(h.root()).join("stdin-source.txt");
+    std::fs::write(&src_path, b"file-input").expect("write stdin source file");
+
+    let v = h.run(&[
+        "run",
+        "--stdin-file",
+        src_path.to_str().expect("utf8 path"),
+        "--",
+        "cat",
+    ]);
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().expect("job_id missing").to_string();
+
+    let wait_v = wait_until_terminal(&h, &job_id);
     assert_eq!(wait_v["state"].as_str().unwrap_or(""), "exited");
 
     let tail_v = h.run(&["tail", &job_id]);
     let stdout = tail_v["stdout"].as_str().unwrap_or("");
     assert_eq!(stdout, "file-input");
 
-    let stdin_path = std::path::Path::new(h.root())
-        .join(&job_id)
-        .join("stdin.bin");
-    let materialized = std::fs::read(&stdin_path).expect("read stdin.bin");
-    assert_eq!(materialized, b"file-input");
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    assert!(!job_path.join("stdin.bin").exists());
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(job_path.join("meta.json")).expect("read meta.json"),
+    )
+    .expect("meta.json is JSON");
+    let canonical = std::fs::canonicalize(&src_path).expect("canonicalize source");
+    assert_eq!(
+        meta["stdin_file"].as_str(),
+        Some(canonical.to_str().expect("utf8 path"))
+    );
+}
+
+/// Spec: a 1 MiB --stdin-file reaches the command in full
+#[test]
+fn run_stdin_file_streams_large_file() {
+    let h = TestHarness::new();
+    let src_path = std::path::Path::new(h.root()).join("one-mib.bin");
+    std::fs::write(&src_path, vec![b'x'; 1024 * 1024]).expect("write stdin source file");
+
+    let v = h.run(&[
+        "run",
+        "--stdin-file",
+        src_path.to_str().expect("utf8 path"),
+        "--",
+        "wc",
+        "-c",
+    ]);
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().expect("job_id missing").to_string();
+
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["state"].as_str().unwrap_or(""), "exited");
+
+    let tail_v = h.run(&["tail", &job_id]);
+    let stdout = tail_v["stdout"].as_str().unwrap_or("");
+    assert!(stdout.contains("1048576"), "stdout: {stdout:?}");
+}
+
+/// Spec: a missing --stdin-file fails with stdin_file_not_found
+#[test]
+fn run_stdin_file_missing_is_rejected() {
+    let h = TestHarness::new();
+    let src_path = std::path::Path::new(h.root()).join("missing.txt");
+
+    let output = run_raw_with_root_and_stdin(
+        &[
+            "run",
+            "--stdin-file",
+            src_path.to_str().expect("utf8 path"),
+            "--",
+            "cat",
+        ],
+        Some(h.root()),
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let v: serde_json::Value = serde_json::from_str(stdout.trim()).expect("stdout should be JSON");
+    assert_eq!(v["ok"].as_bool(), Some(false));
+    assert_eq!(v["error"]["code"].as_str(), Some("stdin_file_not_found"));
 }
 
 #[test]
@@ -418,6 +1031,28 @@ fn wait_returns_json_after_job_finishes() {
     assert_eq!(v["stderr_total_bytes"].as_u64(), Some(0));
 }
 
+/// `wait --poll-file` re-reads state.json only on mtime/size changes yet still
+/// sees completion within a couple of poll intervals.
+#[test]
+fn wait_poll_file_detects_completion() {
+    let h = TestHarness::new();
+    for poll_file in ["--poll-file", "--poll-file=full.log"] {
+        let run_v = h.run(&["run", "--no-wait", "--", "sh", "-c", "sleep 2; echo done"]);
+        let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+        let started = std::time::Instant::now();
+        let v = h.run(&["wait", "--until", "10", poll_file, &job_id]);
+        let elapsed = started.elapsed();
+        assert_envelope(&v, "wait", true);
+        assert_eq!(v["state"], "exited", "{poll_file}: {v}");
+        assert_eq!(v["stdout"], "done\n", "{poll_file}: {v}");
+        assert!(
+            elapsed < std::time::Duration::from_millis(4500),
+            "{poll_file}: completion noticed after {elapsed:?}"
+        );
+    }
+}
+
 #[test]
 fn wait_bounds_large_output_and_preserves_full_log() {
     let h = TestHarness::new();
@@ -642,18 +1277,18 @@ fn kill_returns_json() {
 
 #[test]
 fn kill_signal_non_listed_value_accepted_by_clap() {
-    // Verifies that a signal name not in the suggested list (e.g., QUIT) is
+    // Verifies that a signal name not in the suggested list (e.g., WINCH) is
     // accepted by clap (exit code != 2) and reaches the runtime error path
     // (job not found), rather than being rejected as a usage error.
     let bin = binary();
     let output = std::process::Command::new(&bin)
-        .args(["kill", "--signal", "QUIT", "NONEXISTENT_JOB_ID_XYZ"])
+        .args(["kill", "--signal", "WINCH", "NONEXISTENT_JOB_ID_XYZ"])
         .output()
         .expect("run binary");
     let code = output.status.code().unwrap_or(-1);
     assert_ne!(
         code, 2,
-        "exit code 2 means clap rejected 'QUIT' as a usage error; it should be accepted"
+        "exit code 2 means clap rejected 'WINCH' as a usage error; it should be accepted"
     );
     // Exit code should be non-zero (job not found runtime error) but not 2.
     assert_ne!(code, 0, "expected non-zero exit code for unknown job id");
@@ -686,6 +1321,141 @@ fn kill_observes_terminal_state() {
     );
 }
 
+/// Spec: the supervisor's job files (logs, events, `--stdout-file`) are opened
+/// close-on-exec, so the child's fd table holds nothing from the job directory
+/// beyond stdin/stdout/stderr.
+#[cfg(target_os = "linux")]
+#[test]
+fn run_child_inherits_no_job_file_descriptors() {
+    let h = TestHarness::new();
+    let tmp = tempfile::tempdir().unwrap();
+    let stdout_file = tmp.path().join("out.log");
+    let run_v = h.run(&[
+        "run",
+        "--event-log",
+        "--stdout-file",
+        stdout_file.to_str().unwrap(),
+        "--",
+        "/bin/sh",
+        "-c",
+        "for fd in /proc/$$/fd/*; do n=${fd##*/}; [ \"$n\" -gt 2 ] && echo \"$n -> $(readlink \"$fd\")\"; done; true",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["state"], "exited", "{wait_v}");
+
+    let listing = std::fs::read_to_string(&stdout_file).unwrap();
+    assert!(
+        !listing.contains(h.root()) && !listing.contains(tmp.path().to_str().unwrap()),
+        "job file descriptor leaked into child:\n{listing}"
+    );
+}
+
+/// Spec: `kill --signal USR1` delivers SIGUSR1 (not SIGKILL), so a job that
+/// traps it can handle the signal and exit on its own terms.
+#[cfg(unix)]
+#[test]
+fn kill_delivers_usr1_to_the_job() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--",
+        "/bin/sh",
+        "-c",
+        "trap 'echo got-usr1; exit 3' USR1; echo ready; while :; do sleep 0.1; done",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let stdout_path = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("stdout.log");
+    for _ in 0..100 {
+        if std::fs::read_to_string(&stdout_path).is_ok_and(|s| s.contains("ready")) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    let v = h.run(&["kill", "--signal", "USR1", &job_id]);
+    assert_envelope(&v, "kill", true);
+    assert_eq!(v["signal"], "USR1", "{v}");
+
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["state"], "exited", "{wait_v}");
+    assert_eq!(wait_v["exit_code"], 3, "{wait_v}");
+    let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+    assert!(stdout.contains("got-usr1"), "{stdout}");
+}
+
+/// Spec: `kill --signal STOP` pauses a job (`stopped`), `CONT` resumes it, and a
+/// terminating signal still ends a stopped job.
+#[cfg(unix)]
+#[test]
+fn kill_stop_and_cont_pause_and_resume_job() {
+    let h = TestHarness::new();
+
+    let run_v = h.run(&["run", "sleep", "60"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    let v = h.run(&["kill", "--signal", "STOP", &job_id]);
+    assert_envelope(&v, "kill", true);
+    assert_eq!(v["state"], "stopped", "{v}");
+    assert_eq!(h.run(&["status", &job_id])["state"], "stopped");
+    let list_v = h.run(&["list", "--all", "--state", "stopped"]);
+    let listed = list_v["jobs"].as_array().unwrap();
+    assert!(
+        listed.iter().any(|j| j["job_id"] == job_id.as_str()),
+        "{list_v}"
+    );
+
+    let v = h.run(&["kill", "--signal", "cont", &job_id]);
+    assert_eq!(v["state"], "running", "{v}");
+    assert_eq!(h.run(&["status", &job_id])["state"], "running");
+
+    h.run(&["kill", "--signal", "STOP", &job_id]);
+    let v = h.run(&["kill", "--signal", "TERM", &job_id]);
+    assert_eq!(v["state"], "killed", "{v}");
+}
+
+/// Spec: the supervisor records STOP/CONT itself, so progress ticks never
+/// overwrite `stopped` and status reports carry the actual state.
+#[cfg(unix)]
+#[test]
+fn kill_stop_is_recorded_by_the_supervisor() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().unwrap();
+    let report = dir.path().join("report.ndjson");
+    let run_v = h.run(&[
+        "run",
+        "--progress-every",
+        "1",
+        "--report-pipe",
+        report.to_str().unwrap(),
+        "--report-interval-ms",
+        "200",
+        "--",
+        "sleep",
+        "60",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    let v = h.run(&["kill", "--signal", "STOP", &job_id]);
+    assert_eq!(v["state"], "stopped", "{v}");
+    // Several progress ticks and reports happen while the job is stopped.
+    std::thread::sleep(std::time::Duration::from_millis(2200));
+    assert_eq!(h.run(&["status", &job_id])["state"], "stopped");
+    let contents = std::fs::read_to_string(&report).unwrap();
+    let last: serde_json::Value =
+        serde_json::from_str(contents.lines().last().expect("report lines")).unwrap();
+    assert_eq!(last["state"], "stopped", "{contents}");
+
+    let v = h.run(&["kill", "--signal", "KILL", &job_id]);
+    assert_eq!(v["state"], "killed", "{v}");
+}
+
 #[test]
 fn kill_no_wait_returns_legacy_shape() {
     let h = TestHarness::new();
@@ -713,6 +1483,110 @@ fn kill_no_wait_returns_legacy_shape() {
     );
 }
 
+/// Spec: with `state.json` gone, `kill` signals the PID recorded in `child.pid`.
+#[test]
+fn kill_falls_back_to_child_pid_without_state_json() {
+    let h = TestHarness::new();
+
+    let run_v = h.run(&["run", "sleep", "60"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let job_dir = std::path::Path::new(h.root()).join(&job_id);
+    let pid: u32 = std::fs::read_to_string(job_dir.join("child.pid"))
+        .expect("child.pid written by supervisor")
+        .trim()
+        .parse()
+        .unwrap();
+    std::fs::remove_file(job_dir.join("state.json")).unwrap();
+
+    let v = h.run(&["kill", "--signal", "KILL", &job_id]);
+    assert_envelope(&v, "kill", true);
+    assert_eq!(v["signal"], "KILL", "{v}");
+
+    // The supervisor sees the child die and rewrites state.json.
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["state"], "killed", "{wait_v}");
+    let state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_dir.join("state.json")).unwrap())
+            .unwrap();
+    assert_eq!(state["pid"], pid, "{state}");
+}
+
+/// Spec: the supervisor removes `child.pid` once the child is reaped, and the
+/// `child.pid` fallback reports `stale_pid: true` instead of signalling a PID
+/// that no longer exists.
+#[cfg(unix)]
+#[test]
+fn kill_child_pid_fallback_reports_stale_pid_when_process_is_gone() {
+    let h = TestHarness::new();
+
+    let run_v = h.run(&["run", "true"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let job_dir = std::path::Path::new(h.root()).join(&job_id);
+    assert!(
+        !job_dir.join("child.pid").exists(),
+        "child.pid must be removed after the child is reaped"
+    );
+
+    let mut gone = Command::new("true").spawn().unwrap();
+    let dead_pid = gone.id();
+    gone.wait().unwrap();
+    std::fs::write(job_dir.join("child.pid"), dead_pid.to_string()).unwrap();
+    std::fs::remove_file(job_dir.join("state.json")).unwrap();
+
+    let v = h.run(&["kill", "--signal", "KILL", &job_id]);
+    assert_envelope(&v, "kill", true);
+    assert_eq!(v["signal"], "KILL", "{v}");
+    assert_eq!(v["stale_pid"], true, "{v}");
+}
+
+/// Spec: a successful signal reports `state: "killed"` without `stale_pid`.
+#[test]
+fn kill_running_job_reports_killed_state() {
+    let h = TestHarness::new();
+
+    let run_v = h.run(&["run", "sleep", "60"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    let v = h.run(&["kill", &job_id]);
+    assert_envelope(&v, "kill", true);
+    assert_eq!(v["state"], "killed", "{v}");
+    assert!(v.get("stale_pid").is_none(), "{v}");
+}
+
+/// Spec: when the recorded PID no longer exists, `kill` reports
+/// `state: "killed"` with `stale_pid: true` instead of failing.
+#[cfg(unix)]
+#[test]
+fn kill_reports_stale_pid_when_process_is_gone() {
+    let h = TestHarness::new();
+
+    let run_v = h.run(&["run", "true"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    // Simulate a supervisor that died before recording a final state: the
+    // job still claims to be running under a PID that has since exited.
+    let mut gone = Command::new("true").spawn().unwrap();
+    let dead_pid = gone.id();
+    gone.wait().unwrap();
+    let state_path = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("state.json");
+    let mut state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+    state["job"]["status"] = serde_json::json!("running");
+    state["pid"] = serde_json::json!(dead_pid);
+    std::fs::write(&state_path, serde_json::to_string(&state).unwrap()).unwrap();
+
+    let v = h.run(&["kill", &job_id]);
+    assert_envelope(&v, "kill", true);
+    assert_eq!(v["state"], "killed", "{v}");
+    assert_eq!(v["stale_pid"], true, "{v}");
+}
+
 // ── full.log ───────────────────────────────────────────────────────────────────
 
 #[test]
@@ -1050,55 +1924,1648 @@ fn run_no_inherit_env_clears_env() {
     // PATH is typically set; with --no-inherit-env it should not be in child env.
     let run_v = h.run(&[
         "run",
-        "--no-inherit-env",
+        "--no-inherit-env",
+        "--",
+        "/bin/sh",
+        "-c",
+        "echo INHERITED=$HOME",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    // Wait for the child to finish.
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let stdout_log = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("stdout.log");
+    if stdout_log.exists() {
+        let contents = std::fs::read_to_string(&stdout_log).unwrap_or_default();
+        // $HOME should be empty when env is cleared.
+        assert!(
+            contents.contains("INHERITED=\n") || contents.contains("INHERITED="),
+            "expected HOME to be empty with --no-inherit-env; stdout.log: {contents}"
+        );
+    }
+}
+
+/// Spec: --env-unset removes a variable from the inherited environment, even
+/// one also set with --env, and records the key in meta.json.
+#[cfg(unix)]
+#[test]
+fn run_env_unset_removes_inherited_variable() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--env-unset",
+        "HOME",
+        "--env-unset",
+        "AGENT_EXEC_UNSET_TEST",
+        "--env",
+        "AGENT_EXEC_UNSET_TEST=1",
+        "--",
+        "/bin/sh",
+        "-c",
+        "env | grep -e ^HOME= -e ^AGENT_EXEC_UNSET_TEST=; echo done",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "done\n", "expected no HOME in child env");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        meta["env_unset"],
+        serde_json::json!(["HOME", "AGENT_EXEC_UNSET_TEST"]),
+        "{meta}"
+    );
+}
+
+/// Spec: `--ulimit` sets the child's soft and hard resource limits.
+#[cfg(unix)]
+#[test]
+fn run_ulimit_sets_child_resource_limits() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--ulimit",
+        "CORE=0:0",
+        "--ulimit",
+        "NOFILE=32:64",
+        "--",
+        "/bin/sh",
+        "-c",
+        "ulimit -c; ulimit -Hc; ulimit -n; ulimit -Hn",
+    ]);
+    assert_envelope(&run_v, "run", true);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "0\n0\n32\n64\n");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        meta["rlimits"],
+        serde_json::json!(["CORE=0:0", "NOFILE=32:64"]),
+        "{meta}"
+    );
+}
+
+/// Spec: `--pid-namespace` runs the child as PID 1 where namespaces are
+/// permitted, and still runs it (unisolated) where they are not.
+#[cfg(target_os = "linux")]
+#[test]
+fn run_pid_namespace_makes_child_pid_1() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--pid-namespace", "--", "/bin/sh", "-c", "echo $$"]);
+    assert_envelope(&run_v, "run", true);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["exit_code"], 0, "{wait_v}");
+
+    let permitted = std::process::Command::new("unshare")
+        .args(["--pid", "--fork", "true"])
+        .status()
+        .is_ok_and(|s| s.success());
+    if permitted {
+        let job_path = std::path::Path::new(h.root()).join(&job_id);
+        let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+        assert_eq!(stdout, "1\n");
+    }
+}
+
+/// Spec: `--windows-no-job-object` is recorded in meta.json and the job still
+/// completes normally (the flag only changes Windows process-tree handling).
+#[test]
+fn run_windows_no_job_object_completes_and_is_recorded() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--windows-no-job-object", "--", "echo", "ok"]);
+    assert_envelope(&run_v, "run", true);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["state"], "exited", "{wait_v}");
+    assert_eq!(wait_v["exit_code"], 0, "{wait_v}");
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["windows_job_object"], false, "{meta}");
+
+    let plain = h.run(&["run", "--", "true"]);
+    let plain_meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(plain["job_id"].as_str().unwrap())
+                .join("meta.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(
+        plain_meta.get("windows_job_object").is_none(),
+        "{plain_meta}"
+    );
+}
+
+/// Spec: `--require-clean-exit` records a non-zero exit as `failed` and keeps the code.
+#[test]
+fn run_require_clean_exit_marks_nonzero_exit_failed() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--require-clean-exit", "--", "sh", "-c", "exit 1"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let s = h.run(&["status", &job_id]);
+    assert_eq!(s["state"], "failed", "{s}");
+    assert_eq!(s["exit_code"], 1, "{s}");
+
+    let run_v = h.run(&["run", "--require-clean-exit", "--", "true"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let s = h.run(&["status", &job_id]);
+    assert_eq!(s["state"], "exited", "{s}");
+}
+
+/// Spec: `--checkpoint-dir` writes periodic checkpoints, prunes to
+/// `--checkpoint-count`, and adds a final checkpoint.
+#[test]
+fn run_checkpoint_dir_writes_rotating_checkpoints() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().unwrap();
+    let dir_str = dir.path().to_str().unwrap();
+    let run_v = h.run(&[
+        "run",
+        "--checkpoint-dir",
+        dir_str,
+        "--checkpoint-interval-ms",
+        "200",
+        "--",
+        "sleep",
+        "1",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    // The final checkpoint is written just after the terminal state.
+    let final_name = format!("checkpoint-{job_id}-final.json");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !dir.path().join(&final_name).exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "final checkpoint missing"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert!((2..=6).contains(&names.len()), "{names:?}");
+    assert_eq!(names.last().unwrap(), &final_name, "{names:?}");
+    let checkpoint: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join(&final_name)).unwrap())
+            .unwrap();
+    assert_eq!(
+        checkpoint["state"]["job"]["status"], "exited",
+        "{checkpoint}"
+    );
+    assert!(
+        checkpoint["snapshot"]["stdout_tail"].is_string(),
+        "{checkpoint}"
+    );
+}
+
+/// Spec: `--completion-webhook` POSTs the completion event with identifying
+/// headers and records the response status in state.json.
+#[test]
+fn run_completion_webhook_posts_event() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let h = TestHarness::new();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = String::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap();
+            }
+            head.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        tx.send((head, String::from_utf8(body).unwrap())).unwrap();
+    });
+
+    let run_v = h.run(&["run", "--completion-webhook", &url, "--", "echo", "hook"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let (head, body) = rx
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("webhook must receive a request");
+    let head_lower = head.to_ascii_lowercase();
+    assert!(head.starts_with("POST /hook "), "{head}");
+    assert!(
+        head_lower.contains(&format!("x-agent-exec-job-id: {job_id}")),
+        "{head}"
+    );
+    assert!(head_lower.contains("x-agent-exec-version: "), "{head}");
+    let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(event["event_type"], "job.finished", "{event}");
+    assert_eq!(event["job_id"], job_id.as_str(), "{event}");
+
+    // The status code is recorded before logs_drained, so `wait` returning
+    // implies it is in state.json.
+    wait_until_terminal(&h, &job_id);
+    let state_path = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("state.json");
+    let state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+    assert_eq!(state["webhook_status_code"], 204, "{state}");
+}
+
+/// Spec: the `run` response carries the absolute jobs root and job directory.
+#[test]
+fn run_response_includes_root_and_job_dir() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "true"]);
+    let job_id = run_v["job_id"].as_str().unwrap();
+    let root = std::path::Path::new(run_v["root"].as_str().expect("root"));
+    let job_dir = std::path::Path::new(run_v["job_dir"].as_str().expect("job_dir"));
+    assert!(root.is_absolute() && job_dir.is_absolute(), "{run_v}");
+    assert_eq!(job_dir, root.join(job_id), "{run_v}");
+    assert!(job_dir.join("meta.json").is_file(), "{run_v}");
+}
+
+/// Spec: `status`, `tail`, `wait`, and `kill` accept the job directory path
+/// from a `run` response without the matching root.
+#[test]
+fn job_commands_accept_job_dir_path() {
+    let h = TestHarness::new();
+    let other = TestHarness::new();
+    let run_v = h.run(&["run", "--", "echo", "by-path"]);
+    let job_id = run_v["job_id"].as_str().unwrap();
+    let job_dir = run_v["job_dir"].as_str().unwrap();
+
+    let wait_v = other.run(&["wait", job_dir]);
+    assert_envelope(&wait_v, "wait", true);
+    assert_eq!(wait_v["job_id"], job_id, "{wait_v}");
+    let status_v = other.run(&["status", job_dir]);
+    assert_eq!(status_v["job_id"], job_id, "{status_v}");
+    assert_eq!(status_v["state"], "exited", "{status_v}");
+    let tail_v = other.run(&["tail", job_dir]);
+    assert_eq!(tail_v["stdout"], "by-path\n", "{tail_v}");
+    let kill_v = other.run(&["kill", job_dir]);
+    assert_envelope(&kill_v, "kill", true);
+}
+
+/// Spec: with `--snapshot-on-sigusr1`, SIGUSR1 to the supervisor writes
+/// `snapshots/manual-<epoch-ms>.json` while the job keeps running.
+#[cfg(target_os = "linux")]
+#[test]
+fn run_snapshot_on_sigusr1_writes_manual_snapshot() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--snapshot-on-sigusr1",
+        "--",
+        "sh",
+        "-c",
+        "echo before-signal; sleep 30",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let job_dir = std::path::Path::new(h.root()).join(&job_id);
+
+    // The supervisor is the parent of the child recorded in child.pid.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let child_pid = loop {
+        if let Ok(raw) = std::fs::read_to_string(job_dir.join("child.pid")) {
+            break raw.trim().to_string();
+        }
+        assert!(std::time::Instant::now() < deadline, "child.pid missing");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    let stat = std::fs::read_to_string(format!("/proc/{child_pid}/stat")).unwrap();
+    let supervisor_pid = stat
+        .rsplit(')')
+        .next()
+        .unwrap()
+        .split_whitespace()
+        .nth(1)
+        .unwrap();
+    let status = Command::new("kill")
+        .args(["-USR1", supervisor_pid])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let snapshots = job_dir.join("snapshots");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+    let snapshot_path = loop {
+        if let Some(entry) = std::fs::read_dir(&snapshots)
+            .ok()
+            .and_then(|mut d| d.next())
+        {
+            break entry.unwrap().path();
+        }
+        assert!(std::time::Instant::now() < deadline, "no manual snapshot");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    let name = snapshot_path.file_name().unwrap().to_str().unwrap();
+    assert!(
+        name.starts_with("manual-") && name.ends_with(".json"),
+        "{name}"
+    );
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+    assert_eq!(snapshot["state"]["job"]["status"], "running", "{snapshot}");
+
+    let status_v = h.run(&["status", &job_id]);
+    assert_eq!(status_v["state"], "running", "{status_v}");
+    h.run(&["kill", &job_id]);
+}
+
+/// Spec: when the supervisor cannot spawn the child, `run --no-wait` waits
+/// for the startup handshake and reports `failed` instead of `running`.
+#[test]
+fn run_startup_timeout_reports_failed_spawn() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--startup-timeout-ms",
+        "2000",
+        "--shell-wrapper",
+        "/nonexistent/agent-exec-test-shell -c",
+        "--",
+        "true",
+    ]);
+    assert_envelope(&run_v, "run", true);
+    assert_eq!(run_v["state"], "failed", "{run_v}");
+
+    let run_v = h.run(&["run", "--no-wait", "--", "sleep", "1"]);
+    assert_eq!(run_v["state"], "running", "{run_v}");
+}
+
+/// Spec: once the jobs under the root exceed `--quota-bytes`, new jobs fail
+/// with `quota_exceeded`.
+#[test]
+fn run_output_quota_rejects_new_jobs_when_exceeded() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "sh", "-c", "head -c 10240 /dev/zero"]);
+    assert_envelope(&run_v, "run", true);
+    wait_until_terminal(&h, run_v["job_id"].as_str().unwrap());
+
+    let output = run_raw_with_root_and_stdin(
+        &["run", "--quota-bytes", "1", "--", "true"],
+        Some(h.root()),
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_envelope(&v, "error", false);
+    assert_eq!(v["error"]["code"], "quota_exceeded", "{v}");
+    assert_eq!(v["error"]["retryable"], false, "{v}");
+
+    let run_v = h.run(&["run", "--output-quota-bytes", "1000000", "--", "true"]);
+    assert_envelope(&run_v, "run", true);
+}
+
+/// Spec: `--record-host-info` stores the hostname in `meta.json` and `list`
+/// reports it; without the flag neither field is recorded.
+#[test]
+fn run_record_host_info_stores_hostname() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--record-host-info", "--", "true"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let plain_v = h.run(&["run", "--", "true"]);
+    let plain_id = plain_v["job_id"].as_str().unwrap().to_string();
+
+    let read_meta = |id: &str| -> serde_json::Value {
+        let path = std::path::Path::new(h.root()).join(id).join("meta.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    };
+    let meta = read_meta(&job_id);
+    let hostname = meta["hostname"].as_str().unwrap_or("");
+    assert!(!hostname.is_empty(), "{meta}");
+    let plain_meta = read_meta(&plain_id);
+    assert!(plain_meta.get("hostname").is_none(), "{plain_meta}");
+    assert!(plain_meta.get("username").is_none(), "{plain_meta}");
+
+    let list_v = h.run(&["list", "--all"]);
+    let jobs = list_v["jobs"].as_array().unwrap();
+    let summary = jobs
+        .iter()
+        .find(|j| j["job_id"] == job_id.as_str())
+        .unwrap();
+    assert_eq!(summary["hostname"], hostname, "{summary}");
+}
+
+/// Spec: `--ssh-host` runs the command through `ssh` and logs its output
+/// locally. Skipped unless `ssh localhost` works without a prompt.
+#[cfg(unix)]
+#[test]
+fn run_ssh_host_executes_remotely() {
+    let ssh_ok = Command::new("ssh")
+        .args([
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ConnectTimeout=2",
+            "localhost",
+            "true",
+        ])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if !ssh_ok {
+        eprintln!("skipping: ssh localhost is not available");
+        return;
+    }
+
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--ssh-host", "localhost", "--", "echo", "via-ssh"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["exit_code"], 0, "{wait_v}");
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "via-ssh\n");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["ssh_host"], "localhost", "{meta}");
+    assert_eq!(meta["command"][0], "ssh", "{meta}");
+}
+
+/// Spec: `--wrap-command` runs the command inside the shell template and
+/// keeps the unwrapped command in meta.json.
+#[cfg(unix)]
+#[test]
+fn run_wrap_command_runs_inside_template() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--wrap-command",
+        "sh -c \"echo wrapper; {CMD}\"",
+        "--",
+        "echo",
+        "hello",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["exit_code"], 0, "{wait_v}");
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "wrapper\nhello\n");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        meta["original_command"],
+        serde_json::json!(["echo", "hello"])
+    );
+    assert_eq!(
+        meta["command"],
+        serde_json::json!(["sh -c \"echo wrapper; echo hello\""])
+    );
+}
+
+/// Spec: `--profile-output` writes a folded-stack profile and records
+/// `resource_usage` next to the run duration in state.json.
+#[cfg(target_os = "linux")]
+#[test]
+fn run_profile_output_writes_folded_profile() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+    let profile = dir.path().canonicalize().unwrap().join("profile.folded");
+    let run_v = h.run(&[
+        "run",
+        "--profile-output",
+        profile.to_str().unwrap(),
+        "--",
+        "sleep",
+        "0.3",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let folded = std::fs::read_to_string(&profile).expect("profile written");
+    assert!(!folded.is_empty());
+    // Samples taken before the shell wrapper execs the command show up as
+    // `sh` stacks; at least one must come from `sleep` itself.
+    let mut sleep_stacks = 0;
+    for line in folded.lines() {
+        let (stack, count) = line.rsplit_once(' ').expect("stack and count");
+        assert!(count.parse::<u64>().unwrap() > 0, "{line}");
+        if stack.starts_with("sleep;sleep;") {
+            sleep_stacks += 1;
+        }
+    }
+    assert!(sleep_stacks > 0, "{folded}");
+
+    let state: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("state.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let wall_clock_ms = state["resource_usage"]["wall_clock_ms"].as_i64().unwrap();
+    let duration_ms = state["result"]["duration_ms"].as_i64().unwrap();
+    assert!((wall_clock_ms - duration_ms).abs() <= 100, "{state}");
+    assert!(
+        state["resource_usage"]["samples"].as_u64().unwrap() > 0,
+        "{state}"
+    );
+}
+
+/// Spec: `--ssh-user` and friends are rejected without `--ssh-host`.
+#[test]
+fn run_ssh_options_require_ssh_host() {
+    let h = TestHarness::new();
+    let output = run_raw_with_root_and_stdin(
+        &["run", "--ssh-user", "ci", "--", "true"],
+        Some(h.root()),
+        None,
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Spec: `--log-rotate-size-mb` rotates `stdout.log` while the job runs.
+#[cfg(unix)]
+#[test]
+fn run_log_rotate_size_rotates_stdout_while_running() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--log-rotate-size-mb",
+        "0.001",
+        "--log-rotate-count",
+        "2",
+        "--",
+        "yes",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let stdout_logs = || {
+        std::fs::read_dir(&job_path)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("stdout.log"))
+            .count()
+    };
+    let count = stdout_logs();
+    h.run(&["kill", "--signal", "KILL", &job_id]);
+
+    assert!(
+        count >= 2,
+        "expected stdout.log plus archives, found {count}"
+    );
+    assert!(
+        !job_path.join("stdout.log.3").exists(),
+        "archive count exceeded"
+    );
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["log_rotate_size_bytes"], 1049, "{meta}");
+}
+
+/// Spec: `--mask-from-file` masks every key listed in the file and records
+/// the merged mask list in meta.json.
+#[test]
+fn run_mask_from_file_masks_listed_keys() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+    let mask_file = dir.path().join("secrets.list");
+    std::fs::write(&mask_file, "# shared secrets\nSECRET_KEY\nAPI_TOKEN\n").unwrap();
+
+    let v = h.run(&[
+        "run",
+        "--env",
+        "SECRET_KEY=s3cr3t",
+        "--env",
+        "API_TOKEN=t0k3n",
+        "--env",
+        "PLAIN=visible",
+        "--mask-from-file",
+        mask_file.to_str().unwrap(),
+        "--",
+        "true",
+    ]);
+    assert_envelope(&v, "run", true);
+    let env_vars: Vec<&str> = v["env_vars"]
+        .as_array()
+        .expect("env_vars")
+        .iter()
+        .map(|e| e.as_str().unwrap())
+        .collect();
+    assert!(env_vars.contains(&"SECRET_KEY=***"), "{v}");
+    assert!(env_vars.contains(&"API_TOKEN=***"), "{v}");
+    assert!(env_vars.contains(&"PLAIN=visible"), "{v}");
+
+    let job_path = std::path::Path::new(h.root()).join(v["job_id"].as_str().unwrap());
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        meta["mask"],
+        serde_json::json!(["SECRET_KEY", "API_TOKEN"]),
+        "{meta}"
+    );
+}
+
+/// Spec: a missing `--mask-from-file` fails with `mask_file_not_found`.
+#[test]
+fn run_mask_from_file_missing_is_rejected() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--mask-from-file",
+        "/nonexistent/secrets.list",
+        "--",
+        "true",
+    ]);
+    assert_eq!(v["type"], "error", "{v}");
+    assert_eq!(v["error"]["code"], "mask_file_not_found", "{v}");
+}
+
+/// Spec: a malformed `--ulimit` is rejected before any job is created.
+#[test]
+fn run_rejects_invalid_ulimit() {
+    let h = TestHarness::new();
+    let output = run_raw_with_root_and_stdin(
+        &["run", "--ulimit", "NOFILE=128:64", "--", "true"],
+        Some(h.root()),
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_envelope(&v, "error", false);
+    assert_eq!(v["error"]["code"], "invalid_rlimit");
+    let jobs = std::fs::read_dir(h.root()).map_or(0, |d| d.count());
+    assert_eq!(jobs, 0, "no job directory should be created");
+}
+
+/// Spec: `--env-prefix` keeps only matching inherited variables; `--env` still applies.
+#[test]
+fn run_env_prefix_filters_inherited_variables() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--env-prefix",
+        "PAT",
+        "--env",
+        "AGENT_EXEC_PREFIX_TEST=1",
+        "--",
+        "/bin/sh",
+        "-c",
+        "echo \"home=$HOME path=${PATH:+set} explicit=$AGENT_EXEC_PREFIX_TEST\"",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "home= path=set explicit=1\n");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        meta["env_inherit_prefix"],
+        serde_json::json!(["PAT"]),
+        "{meta}"
+    );
+}
+
+/// Spec: `--read-env-from-state` replays another job's recorded environment;
+/// masked values arrive empty and `--env` still overrides.
+#[test]
+fn run_read_env_from_state_replays_previous_job_env() {
+    let h = TestHarness::new();
+    let source_v = h.run(&[
+        "run",
+        "--env",
+        "FOO=bar",
+        "--env",
+        "SECRET=s3cret",
+        "--env",
+        "MODE=a",
+        "--mask",
+        "SECRET",
+        "--",
+        "true",
+    ]);
+    let source_id = source_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &source_id);
+
+    let run_v = h.run(&[
+        "run",
+        "--read-env-from-state",
+        &source_id,
+        "--env",
+        "MODE=b",
+        "--",
+        "/bin/sh",
+        "-c",
+        "echo \"foo=$FOO secret=[$SECRET] mode=$MODE\"",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "foo=bar secret=[] mode=b\n");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["env_source_job_id"], source_id, "{meta}");
+}
+
+/// Spec: --timeout causes the child process to be terminated after the deadline.
+#[test]
+fn run_timeout_terminates_child() {
+    let h = TestHarness::new();
+
+    // Start a long sleep with a short timeout.
+    // run returns immediately, so this can be tested directly; timeout is tested via status.
+    let run_v = h.run(&["run", "--timeout", "1", "--kill-after", "1", "sleep", "60"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    // Poll until the job is no longer running (timeout + kill-after should fire).
+    // Use a polling loop instead of a fixed sleep to tolerate slow CI runners.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let v = h.run(&["status", &job_id]);
+        let state = v["state"].as_str().unwrap_or("running");
+        if state != "running" {
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "job should have been terminated by timeout; state={state}"
+        );
+    }
+}
+
+/// Spec: timeout signals reach the child's whole process group, so grandchildren
+/// spawned by a shell wrapper are terminated along with it.
+#[cfg(target_os = "linux")]
+#[test]
+fn run_timeout_terminates_grandchildren_in_process_group() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().unwrap();
+    let pid_file = dir.path().join("grandchild.pid");
+    let script = format!("sleep 60 & echo $! > {}; wait", pid_file.display());
+    let v = h.run(&[
+        "run",
+        "--no-wait",
+        "--timeout",
+        "1",
+        "--kill-after",
+        "1",
+        "--",
+        "sh",
+        "-c",
+        &script,
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let grandchild: u64 = std::fs::read_to_string(&pid_file)
+        .expect("grandchild pid file")
+        .trim()
+        .parse()
+        .unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    loop {
+        // /proc/<pid>/stat: "pid (comm) state ..."; a zombie awaiting reaping counts as gone.
+        let gone = match std::fs::read_to_string(format!("/proc/{grandchild}/stat")) {
+            Err(_) => true,
+            Ok(s) => !s.contains("(sleep)") || s[s.rfind(')').unwrap() + 2..].starts_with('Z'),
+        };
+        if gone {
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "grandchild sleep must be terminated with the process group"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
+/// Spec: `run --stdin-forward` prints the response, then proxies caller stdin to
+/// the child through a FIFO until EOF.
+#[cfg(unix)]
+#[test]
+fn run_stdin_forward_proxies_caller_stdin_to_child() {
+    let h = TestHarness::new();
+    let output = run_raw_with_root_and_stdin(
+        &["run", "--stdin-forward", "cat"],
+        Some(h.root()),
+        Some(b"hello\n"),
+    );
+    assert!(output.status.success(), "run failed: {output:?}");
+    let v: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("run must print one JSON response");
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+
+    wait_until_terminal(&h, &job_id);
+    let stdout = std::fs::read_to_string(
+        std::path::Path::new(h.root())
+            .join(&job_id)
+            .join("stdout.log"),
+    )
+    .unwrap();
+    assert_eq!(stdout, "hello\n");
+}
+
+/// Spec: the child's stdin is `/dev/null` by default (or with `--stdin-null`);
+/// `run --stdin-inherit` hands the caller's stdin to the child instead.
+#[cfg(unix)]
+#[test]
+fn run_stdin_inherit_passes_caller_stdin_to_child() {
+    let h = TestHarness::new();
+    let stdout_of = |args: &[&str]| {
+        let output = run_raw_with_root_and_stdin(args, Some(h.root()), Some(b"hi\n"));
+        assert!(output.status.success(), "run failed: {output:?}");
+        let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_envelope(&v, "run", true);
+        let job_id = v["job_id"].as_str().unwrap().to_string();
+        wait_until_terminal(&h, &job_id);
+        std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("stdout.log"),
+        )
+        .unwrap()
+    };
+
+    assert_eq!(stdout_of(&["run", "--", "cat"]), "");
+    assert_eq!(stdout_of(&["run", "--stdin-null", "--", "cat"]), "");
+    assert_eq!(stdout_of(&["run", "--stdin-inherit", "--", "cat"]), "hi\n");
+}
+
+/// Spec: the same command gets the same `command_hash` regardless of job ID;
+/// `list --command-hash` finds those jobs and `run --deduplicate` reuses the latest.
+#[test]
+fn run_command_hash_is_stable_and_supports_list_and_deduplicate() {
+    let h = TestHarness::new();
+    let cwd = tempfile::tempdir().unwrap();
+    let cwd_str = cwd.path().to_str().unwrap();
+    let run = |extra: &[&str]| {
+        let mut args = vec!["run", "--cwd", cwd_str, "--env", "MODE=ci"];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["--", "echo", "fingerprint"]);
+        h.run(&args)
+    };
+    let hash_of = |job_id: &str| {
+        let meta: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(
+                std::path::Path::new(h.root())
+                    .join(job_id)
+                    .join("meta.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        meta["command_hash"].as_str().unwrap().to_string()
+    };
+
+    let first = run(&[])["job_id"].as_str().unwrap().to_string();
+    let second = run(&[])["job_id"].as_str().unwrap().to_string();
+    assert_ne!(first, second);
+    let hash = hash_of(&first);
+    assert_eq!(hash.len(), 64, "hash: {hash}");
+    assert_eq!(hash, hash_of(&second));
+
+    let listed = h.run(&["list", "--command-hash", &hash]);
+    let mut ids: Vec<&str> = listed["jobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|j| j["job_id"].as_str().unwrap())
+        .collect();
+    ids.sort();
+    let mut expected = vec![first.as_str(), second.as_str()];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    wait_until_terminal(&h, &first);
+    wait_until_terminal(&h, &second);
+    let deduped = run(&["--deduplicate", "1"]);
+    assert_envelope(&deduped, "run", true);
+    assert_eq!(deduped["deduplicated"], true, "{deduped}");
+    let reused = deduped["job_id"].as_str().unwrap();
+    assert!(reused == first || reused == second, "{deduped}");
+    assert_eq!(deduped["stdout"], "fingerprint\n");
+}
+
+/// Spec: `run --env-expand` resolves forward references between `--env` values
+/// and rejects reference cycles with `env_cycle`.
+#[cfg(unix)]
+#[test]
+fn run_env_expand_resolves_references_and_rejects_cycles() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--env-expand",
+        "--env",
+        "A=$B",
+        "--env",
+        "B=hello",
+        "--",
+        "sh",
+        "-c",
+        "printf %s \"$A\"",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let stdout = std::fs::read_to_string(
+        std::path::Path::new(h.root())
+            .join(&job_id)
+            .join("stdout.log"),
+    )
+    .unwrap();
+    assert_eq!(stdout, "hello");
+
+    let output = run_raw_with_root_and_stdin(
+        &[
+            "run",
+            "--env-expand",
+            "--env",
+            "A=$B",
+            "--env",
+            "B=$A",
+            "--",
+            "true",
+        ],
+        Some(h.root()),
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_envelope(&v, "error", false);
+    assert_eq!(v["error"]["code"], "env_cycle");
+    let message = v["error"]["message"].as_str().unwrap();
+    assert!(message.contains("A -> B -> A"), "{message}");
+}
+
+/// Spec: `run --report-file` writes a JSON summary once the job finishes and
+/// reports its path as `report_file_path`.
+#[cfg(unix)]
+#[test]
+fn run_report_file_written_after_completion() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+    let report = dir
+        .path()
+        .canonicalize()
+        .unwrap()
+        .join("reports/summary.json");
+    let report_str = report.display().to_string();
+
+    let v = h.run(&[
+        "run",
+        "--report-file",
+        &report_str,
+        "--",
+        "sh",
+        "-c",
+        "seq 1 30; echo oops >&2; exit 3",
+    ]);
+    assert_envelope(&v, "run", true);
+    assert_eq!(v["report_file_path"], report_str.as_str(), "{v}");
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let r: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report).expect("report file")).unwrap();
+    assert_eq!(r["job_id"], job_id.as_str());
+    assert_eq!(r["exit_code"], 3);
+    assert_eq!(r["command"][0], "sh");
+    let expected_tail: String = (11..=30).map(|i| format!("{i}\n")).collect();
+    assert_eq!(r["stdout_tail"], expected_tail.as_str());
+    assert_eq!(r["stderr_tail"], "oops\n");
+    assert_eq!(r["stderr_observed_bytes"], 5);
+    assert!(
+        r["duration_ms"].is_u64() && r["finished_at"].is_string(),
+        "{r}"
+    );
+}
+
+/// Spec: `run --no-inherit-env` without any `--env` warns about the empty environment.
+#[test]
+fn run_warns_on_no_inherit_env_without_env() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--no-inherit-env", "--", "echo", "hi"]);
+    assert_envelope(&v, "run", true);
+    let warnings = v["warnings"].as_array().expect("warnings array");
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.as_str().unwrap().contains("--no-inherit-env")),
+        "{v}"
+    );
+    let plain = h.run(&["run", "--", "echo", "hi"]);
+    assert!(plain.get("warnings").is_none(), "{plain}");
+}
+
+/// Spec: `run --benchmark-mode` reports per-phase launch timings.
+#[test]
+fn run_benchmark_mode_reports_phase_timings() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--benchmark-mode", "--", "true"]);
+    assert_envelope(&v, "run", true);
+    for phase in ["dir_create_ms", "spawn_ms", "write_meta_ms"] {
+        assert!(v["benchmark"][phase].is_u64(), "{phase}: {v}");
+    }
+    let plain = h.run(&["run", "--", "true"]);
+    assert!(plain.get("benchmark").is_none(), "{plain}");
+}
+
+/// Spec: `run --capture-output` records the final stdout, returned by `status --captured`.
+#[test]
+fn run_capture_output_reported_by_status_captured() {
+    let h = TestHarness::new();
+
+    let v = h.run(&["run", "--capture-output", "--", "echo", "hello"]);
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let s = h.run(&["status", "--captured", &job_id]);
+    assert_envelope(&s, "status", true);
+    assert_eq!(s["captured_stdout"], "hello\n", "{s}");
+    assert_eq!(s["captured_stdout_truncated"], false, "{s}");
+    let plain = h.run(&["status", &job_id]);
+    assert!(plain.get("captured_stdout").is_none(), "{plain}");
+
+    let v = h.run(&[
+        "run",
+        "--capture-output",
+        "--capture-output-max-bytes",
+        "4",
+        "--",
+        "echo",
+        "abcdefgh",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let s = h.run(&["status", "--captured", &job_id]);
+    assert_eq!(s["captured_stdout"], "fgh\n", "{s}");
+    assert_eq!(s["captured_stdout_truncated"], true, "{s}");
+}
+
+/// Spec: a key repeated with `--env` is stored once, with its last value.
+#[test]
+fn run_dedups_repeated_env_keys() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run", "--env", "FOO=a", "--env", "BAR=1", "--env", "FOO=b", "--", "echo",
+    ]);
+    assert_envelope(&v, "run", true);
+    assert_eq!(v["env_vars"], serde_json::json!(["BAR=1", "FOO=b"]), "{v}");
+    let job_id = v["job_id"].as_str().unwrap();
+    let meta_path = std::path::Path::new(h.root())
+        .join(job_id)
+        .join("meta.json");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(meta_path).unwrap()).unwrap();
+    assert_eq!(
+        meta["env_keys"],
+        serde_json::json!(["BAR", "FOO"]),
+        "{meta}"
+    );
+}
+
+/// Spec: `run --env-from-json` passes a flat JSON object to the child as env
+/// vars, honours `--mask`, and records only the keys in meta.json.
+#[cfg(unix)]
+#[test]
+fn run_env_from_json_sets_child_env() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--env-from-json",
+        r#"{"FOO":"bar","SECRET":"hunter2"}"#,
+        "--mask",
+        "SECRET",
+        "--",
+        "/bin/sh",
+        "-c",
+        "echo FOO=$FOO",
+    ]);
+    assert_envelope(&v, "run", true);
+    let env_vars: Vec<&str> = v["env_vars"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e.as_str().unwrap())
+        .collect();
+    assert_eq!(env_vars, ["FOO=bar", "SECRET=***"], "{v}");
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "FOO=bar\n");
+    let meta_raw = std::fs::read_to_string(job_path.join("meta.json")).unwrap();
+    assert!(!meta_raw.contains("hunter2"), "{meta_raw}");
+    let meta: serde_json::Value = serde_json::from_str(&meta_raw).unwrap();
+    assert_eq!(
+        meta["env_from_json_keys"],
+        serde_json::json!(["FOO", "SECRET"])
+    );
+}
+
+/// Spec: `run --wait-for-file` launches only once the path exists and records
+/// the wait in meta.json; a missing path fails with `wait_for_file_timeout`.
+#[test]
+fn run_wait_for_file_delays_launch_until_file_appears() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+    let marker = dir.path().join("ready");
+    let marker_str = marker.display().to_string();
+
+    let toucher = std::thread::spawn({
+        let marker = marker.clone();
+        move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            std::fs::write(&marker, b"").unwrap();
+        }
+    });
+    let v = h.run(&["run", "--wait-for-file", &marker_str, "--", "echo", "go"]);
+    toucher.join().unwrap();
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    let meta_path = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("meta.json");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(meta_path).unwrap()).unwrap();
+    let waited = meta["startup_wait_ms"].as_u64().expect("startup_wait_ms");
+    assert!(waited >= 150, "waited only {waited}ms: {meta}");
+
+    let missing = dir.path().join("never").display().to_string();
+    let output = run_raw_with_root_and_stdin(
+        &[
+            "run",
+            "--wait-for-file",
+            &missing,
+            "--wait-for-file-timeout-ms",
+            "200",
+            "--",
+            "echo",
+        ],
+        Some(h.root()),
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_envelope(&v, "error", false);
+    assert_eq!(v["error"]["code"], "wait_for_file_timeout");
+    let jobs = std::fs::read_dir(h.root()).unwrap().count();
+    assert_eq!(jobs, 1, "a timed-out run must not create a job");
+}
+
+/// Spec: a malformed `--env` entry fails with `invalid_env_var` before any job
+/// directory is created.
+#[test]
+fn run_rejects_invalid_env_var_entry() {
+    let h = TestHarness::new();
+    let output = run_raw_with_root_and_stdin(
+        &["run", "--env", "=value", "--", "echo"],
+        Some(h.root()),
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_envelope(&v, "error", false);
+    assert_eq!(v["error"]["code"], "invalid_env_var");
+    let message = v["error"]["message"].as_str().unwrap();
+    assert!(message.contains("=value"), "{message}");
+    let jobs = std::fs::read_dir(h.root()).map_or(0, |d| d.count());
+    assert_eq!(jobs, 0, "no job directory should be created");
+}
+
+/// Spec: every `run --log-buffering` mode writes the complete output, including
+/// a trailing partial line, and non-default modes are recorded in meta.json.
+#[cfg(unix)]
+#[test]
+fn run_log_buffering_modes_keep_all_output() {
+    let h = TestHarness::new();
+    for mode in ["none", "line", "full"] {
+        let v = h.run(&[
+            "run",
+            "--no-wait",
+            "--log-buffering",
+            mode,
+            "--",
+            "sh",
+            "-c",
+            "seq 1 5000; printf tail",
+        ]);
+        let job_id = v["job_id"].as_str().unwrap().to_string();
+        wait_until_terminal(&h, &job_id);
+        let job_dir = std::path::Path::new(h.root()).join(&job_id);
+        let stdout = std::fs::read_to_string(job_dir.join("stdout.log")).unwrap();
+        let mut expected: String = (1..=5000).map(|i| format!("{i}\n")).collect();
+        expected.push_str("tail");
+        assert_eq!(stdout, expected, "mode {mode} lost output");
+
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(job_dir.join("meta.json")).unwrap())
+                .unwrap();
+        if mode == "none" {
+            assert!(meta.get("log_buffering").is_none(), "{meta}");
+        } else {
+            assert_eq!(meta["log_buffering"], mode, "{meta}");
+        }
+    }
+}
+
+/// Spec: `run --max-line-length` cuts over-long full.log lines with a
+/// `... [truncated]` marker while stdout.log keeps the raw line.
+#[cfg(unix)]
+#[test]
+fn run_max_line_length_truncates_full_log_lines_only() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--no-wait",
+        "--max-line-length",
+        "100",
+        "--",
+        "sh",
+        "-c",
+        "head -c 10000 /dev/zero | tr '\\0' x; echo; echo short",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let job_dir = std::path::Path::new(h.root()).join(&job_id);
+
+    let stdout = std::fs::read_to_string(job_dir.join("stdout.log")).unwrap();
+    assert_eq!(stdout, format!("{}\nshort\n", "x".repeat(10_000)));
+
+    let full = std::fs::read_to_string(job_dir.join("full.log")).unwrap();
+    let bodies: Vec<&str> = full
+        .lines()
+        .map(|l| l.split_once("[STDOUT] ").expect("full.log line prefix").1)
+        .collect();
+    assert_eq!(bodies.len(), 101, "{full}");
+    for body in &bodies[..99] {
+        assert_eq!(*body, format!("{}... [truncated]", "x".repeat(100)));
+    }
+    // The final 100 bytes fit exactly and are not marked.
+    assert_eq!(bodies[99], "x".repeat(100));
+    assert_eq!(bodies[100], "short");
+
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_dir.join("meta.json")).unwrap()).unwrap();
+    assert_eq!(meta["max_line_length"], 100, "{meta}");
+}
+
+/// Spec: `run --max-output-lines-per-second` caps the lines written to the
+/// individual log and records how many were dropped.
+#[cfg(unix)]
+#[test]
+fn run_max_output_lines_per_second_rate_limits_stdout_log() {
+    let h = TestHarness::new();
+    let started = std::time::Instant::now();
+    let v = h.run(&[
+        "run",
+        "--max-output-lines-per-second",
+        "10",
+        "--",
+        "sh",
+        "-c",
+        "yes & sleep 0.5; kill $!",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    // Burst of 10 plus 10 lines/s refill for as long as the pipe is drained,
+    // which on a loaded machine outlasts the 0.5 s `yes` run (~20 lines nominally).
+    let elapsed_ms = started.elapsed().as_millis() as usize;
+    let budget = 10 + (elapsed_ms * 10).div_ceil(1000) + 1;
+
+    let stdout = std::fs::read_to_string(
+        std::path::Path::new(h.root())
+            .join(&job_id)
+            .join("stdout.log"),
+    )
+    .unwrap();
+    let kept = stdout.lines().filter(|l| *l == "y").count();
+    assert!(
+        (1..=budget).contains(&kept),
+        "expected at most {budget} lines over {elapsed_ms} ms, got {kept}"
+    );
+    assert!(
+        stdout.contains("... rate limit exceeded, ") && stdout.contains(" lines dropped ..."),
+        "missing rate-limit marker: {:?}",
+        &stdout[stdout.len().saturating_sub(200)..]
+    );
+}
+
+/// Spec: the supervisor records the child's CPU time and peak RSS in state.json
+/// and exposes them through `status`.
+#[cfg(unix)]
+#[test]
+fn run_records_child_resource_usage() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--", "echo", "hi"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let state: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("state.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let result = &state["result"];
+    assert!(result["max_rss_kb"].as_u64().unwrap() > 0, "state: {state}");
+    assert!(result["cpu_user_ms"].is_u64(), "state: {state}");
+    assert!(result["cpu_sys_ms"].is_u64(), "state: {state}");
+
+    let status = h.run(&["status", &job_id]);
+    assert_eq!(status["max_rss_kb"], result["max_rss_kb"], "{status}");
+}
+
+/// Spec: `run --disable-core-dump` runs the child with a zero core-file limit
+/// and records the setting in meta.json.
+#[cfg(unix)]
+#[test]
+fn run_disable_core_dump_sets_zero_core_limit() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--disable-core-dump",
+        "--",
+        "sh",
+        "-c",
+        "ulimit -c; ulimit -Hc",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_eq!(stdout, "0\n0\n", "soft and hard core limits must be 0");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["core_dump_disabled"], true, "meta: {meta}");
+}
+
+/// Spec: `run --priority low` lowers the child's scheduling priority by 10 nice
+/// levels and records the priority in meta.json; `normal` inherits it.
+#[cfg(unix)]
+#[test]
+fn run_priority_low_renices_child() {
+    let h = TestHarness::new();
+    let niceness = |priority: &str| {
+        let v = h.run(&["run", "--priority", priority, "--", "nice"]);
+        let job_id = v["job_id"].as_str().unwrap().to_string();
+        wait_until_terminal(&h, &job_id);
+        let job_path = std::path::Path::new(h.root()).join(&job_id);
+        let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+                .unwrap();
+        (stdout.trim().parse::<i32>().unwrap(), meta)
+    };
+
+    let (normal, normal_meta) = niceness("normal");
+    assert!(normal_meta.get("priority").is_none(), "{normal_meta}");
+    let (low, low_meta) = niceness("LOW");
+    assert_eq!(low, (normal + 10).min(19));
+    assert_eq!(low_meta["priority"], "low", "{low_meta}");
+}
+
+/// Spec: `run --nice 10` sets the child's nice value and records it in
+/// meta.json.
+#[cfg(unix)]
+#[test]
+fn run_nice_sets_child_niceness() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--nice", "10", "--", "nice"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let stdout = std::fs::read_to_string(job_path.join("stdout.log")).unwrap();
+    assert_ne!(stdout.trim().parse::<i32>().unwrap(), 0, "{stdout}");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["nice"], 10, "{meta}");
+}
+
+/// Spec: an out-of-range `--nice` is clamped and reported in `warnings`.
+#[test]
+fn run_nice_out_of_range_is_clamped_with_warning() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--nice", "40", "--", "true"]);
+    assert_envelope(&v, "run", true);
+    let warnings = v["warnings"].as_array().expect("warnings");
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.as_str().unwrap().contains("clamped to 19")),
+        "{v}"
+    );
+    let job_path = std::path::Path::new(h.root()).join(v["job_id"].as_str().unwrap());
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["nice"], 19, "{meta}");
+}
+
+/// Spec: `run --stdout-file` writes the child's stdout directly to the given
+/// path, reports it as `stdout_log_path` and records it in meta.json.
+#[cfg(unix)]
+#[test]
+fn run_stdout_file_writes_child_stdout_to_path() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+    let out = dir.path().canonicalize().unwrap().join("out.txt");
+    let out_str = out.display().to_string();
+
+    let v = h.run(&["run", "--stdout-file", &out_str, "--", "cat", "/etc/hosts"]);
+    assert_envelope(&v, "run", true);
+    assert_eq!(v["stdout_log_path"], out_str.as_str(), "{v}");
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let expected = std::fs::read_to_string("/etc/hosts").unwrap();
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), expected);
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["stdout_file"], out_str.as_str(), "{meta}");
+    // stdout.log links to the file, so tail still sees the output.
+    assert_eq!(
+        std::fs::read_to_string(job_path.join("stdout.log")).unwrap(),
+        expected
+    );
+}
+
+/// Spec: `run --event-log` records a structured event stream that `events`
+/// reads back, filtered by `--type`.
+#[test]
+fn run_event_log_records_chunks_and_events_filters_by_type() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--event-log", "--", "echo", "hello"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let all = h.run(&["events", &job_id]);
+    assert_envelope(&all, "events", true);
+    let kinds: Vec<&str> = all["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds.first(), Some(&"spawn"), "events: {all}");
+    assert!(kinds.contains(&"exit"), "events: {all}");
+
+    let chunks = h.run(&["events", "--type", "stdout_chunk", &job_id]);
+    let chunks = chunks["events"].as_array().unwrap();
+    assert_eq!(chunks.len(), 1, "chunks: {chunks:?}");
+    assert_eq!(chunks[0]["data"]["text"], "hello\n");
+    assert!(chunks[0]["ts"].is_string());
+}
+
+/// Spec: `run --report-pipe <FILE> --report-interval-ms` appends one JSON status
+/// line per interval and a final terminal line, and records the path in meta.json.
+#[test]
+fn run_report_pipe_writes_periodic_status_lines() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().unwrap();
+    let report = dir.path().join("report.ndjson");
+    let v = h.run(&[
+        "run",
+        "--report-pipe",
+        report.to_str().unwrap(),
+        "--report-interval-ms",
+        "300",
         "--",
-        "/bin/sh",
+        "sh",
         "-c",
-        "echo INHERITED=$HOME",
+        "echo hi; sleep 1.2",
     ]);
-    let job_id = run_v["job_id"].as_str().unwrap().to_string();
-
-    // Wait for the child to finish.
-    std::thread::sleep(std::time::Duration::from_millis(500));
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    // The final line is written after state.json turns terminal.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let contents = loop {
+        let contents = std::fs::read_to_string(&report).unwrap();
+        if contents.contains("\"exited\"") || std::time::Instant::now() > deadline {
+            break contents;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    };
 
-    let stdout_log = std::path::Path::new(h.root())
-        .join(&job_id)
-        .join("stdout.log");
-    if stdout_log.exists() {
-        let contents = std::fs::read_to_string(&stdout_log).unwrap_or_default();
-        // $HOME should be empty when env is cleared.
-        assert!(
-            contents.contains("INHERITED=\n") || contents.contains("INHERITED="),
-            "expected HOME to be empty with --no-inherit-env; stdout.log: {contents}"
-        );
+    let lines: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("report line must be JSON"))
+        .collect();
+    let (last, running) = lines.split_last().expect("report must not be empty");
+    assert!(
+        running.len() >= 3,
+        "expected >= 3 interval reports: {lines:?}"
+    );
+    for line in running {
+        assert_eq!(line["state"], "running");
+        assert!(line["ts"].is_string());
+        assert!(line["stderr_bytes"].is_u64());
     }
+    assert_eq!(running.last().unwrap()["stdout_bytes"], 3);
+    assert_eq!(last["state"], "exited");
+    assert_eq!(last["exit_code"], 0);
+
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("meta.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(meta["status_report"]["path"], report.to_str().unwrap());
 }
 
-/// Spec: --timeout causes the child process to be terminated after the deadline.
+/// Spec: --keep-going defers the final SIGKILL so a SIGTERM handler can finish
+/// its teardown; without it the handler is cut short.
+#[cfg(unix)]
 #[test]
-fn run_timeout_terminates_child() {
+fn run_keep_going_lets_sigterm_handler_finish() {
     let h = TestHarness::new();
+    let script = "trap 'sleep 0.2; echo cleaned > marker; exit 0' TERM; \
+                  while :; do sleep 0.05; done";
 
-    // Start a long sleep with a short timeout.
-    // run returns immediately, so this can be tested directly; timeout is tested via status.
-    let run_v = h.run(&["run", "--timeout", "1", "--kill-after", "1", "sleep", "60"]);
-    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let patient = tempfile::tempdir().unwrap();
+    let hasty = tempfile::tempdir().unwrap();
+    let launch = |dir: &std::path::Path, extra: &[&str]| {
+        let mut args = vec!["run", "--timeout", "1", "--cwd", dir.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["--", "sh", "-c", script]);
+        h.run(&args)["job_id"].as_str().unwrap().to_string()
+    };
+    let patient_id = launch(patient.path(), &["--keep-going", "1000"]);
+    let hasty_id = launch(hasty.path(), &[]);
 
-    // Poll until the job is no longer running (timeout + kill-after should fire).
-    // Use a polling loop instead of a fixed sleep to tolerate slow CI runners.
-    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
-    loop {
-        std::thread::sleep(std::time::Duration::from_millis(200));
-        let v = h.run(&["status", &job_id]);
-        let state = v["state"].as_str().unwrap_or("running");
-        if state != "running" {
-            break;
-        }
-        assert!(
-            std::time::Instant::now() < deadline,
-            "job should have been terminated by timeout; state={state}"
-        );
-    }
+    wait_until_terminal(&h, &patient_id);
+    wait_until_terminal(&h, &hasty_id);
+    assert!(
+        patient.path().join("marker").exists(),
+        "--keep-going must let the SIGTERM handler finish"
+    );
+    assert!(
+        !hasty.path().join("marker").exists(),
+        "without --keep-going the handler is interrupted by SIGKILL"
+    );
 }
 
 /// Spec: --progress-every updates state.json.updated_at within the interval.
@@ -1156,6 +3623,254 @@ fn progress_every_supervise_stops_after_child_exits() {
     );
 }
 
+/// Spec: `run --pty` attaches the child to a pseudo-terminal so `isatty` is true,
+/// records `"pty": true` in meta.json, and logs output with plain `\n` endings.
+#[cfg(unix)]
+#[test]
+fn run_pty_attaches_child_to_terminal() {
+    let h = TestHarness::new();
+    let script = "test -t 1 && echo tty || echo notty; stty size";
+
+    let v = h.run(&["run", "--pty", "--", "sh", "-c", script]);
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let stdout = std::fs::read_to_string(
+        std::path::Path::new(h.root())
+            .join(&job_id)
+            .join("stdout.log"),
+    )
+    .unwrap();
+    assert_eq!(stdout, "tty\n24 80\n");
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("meta.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(meta["pty"], true, "meta.json must record pty: {meta}");
+
+    let piped = h.run(&[
+        "run",
+        "--",
+        "sh",
+        "-c",
+        "test -t 1 && echo tty || echo notty",
+    ]);
+    let piped_id = piped["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &piped_id);
+    let tail = h.run(&["tail", &piped_id]);
+    assert_eq!(tail["stdout"], "notty\n");
+}
+
+/// Spec: `run --health-check` terminates the job once the probe fails
+/// `--health-check-retries` times in a row and records the reason in state.json.
+#[cfg(unix)]
+#[test]
+fn run_health_check_failure_terminates_job() {
+    let h = TestHarness::new();
+    let work = tempfile::tempdir().unwrap();
+    let flag = work.path().join("flagfile");
+    std::fs::write(&flag, "ok").unwrap();
+    let cwd = work.path().to_str().unwrap();
+
+    let v = h.run(&[
+        "run",
+        "--no-wait",
+        "--cwd",
+        cwd,
+        "--health-check",
+        "ls flagfile",
+        "--health-check-interval",
+        "1",
+        "--health-check-retries",
+        "2",
+        "--",
+        "sleep",
+        "60",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+
+    // The probe passes while the flag file exists.
+    std::thread::sleep(std::time::Duration::from_millis(2500));
+    assert_eq!(h.run(&["status", &job_id])["state"], "running");
+
+    std::fs::remove_file(&flag).unwrap();
+    wait_until_terminal(&h, &job_id);
+    let status = h.run(&["status", &job_id]);
+    assert_eq!(
+        status["state"], "killed",
+        "job must be terminated: {status}"
+    );
+
+    let state: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            std::path::Path::new(h.root())
+                .join(&job_id)
+                .join("state.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(state["killed_reason"], "health_check_failed");
+}
+
+/// Spec: a hanging health-check probe does not hold up `--timeout`; the probe
+/// runs beside the watcher and is killed after one interval.
+#[cfg(unix)]
+#[test]
+fn run_health_check_hanging_probe_does_not_block_timeout() {
+    let h = TestHarness::new();
+    let started = std::time::Instant::now();
+    let v = h.run(&[
+        "run",
+        "--no-wait",
+        "--timeout",
+        "2",
+        "--health-check",
+        "sleep 30",
+        "--health-check-interval",
+        "1",
+        "--health-check-retries",
+        "10",
+        "--",
+        "sleep",
+        "60",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(10),
+        "timeout must fire while the probe hangs: {:?}",
+        started.elapsed()
+    );
+    assert_eq!(h.run(&["status", &job_id])["state"], "killed");
+}
+
+/// Spec: `run --detach` places the supervisor in its own session so that a SIGHUP
+/// delivered to the launching process group does not take the job down.
+#[cfg(unix)]
+#[test]
+fn run_detach_supervisor_survives_sighup_to_launching_group() {
+    use std::os::unix::process::CommandExt;
+
+    let h = TestHarness::new();
+    // Launch `run` as the leader of its own process group so the hangup below
+    // only targets the launcher's group, not the test runner.
+    let launcher = Command::new(binary())
+        .args(["run", "--detach", "--no-wait", "--", "sleep", "30"])
+        .env("AGENT_EXEC_ROOT", h.root())
+        .process_group(0)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn run");
+    let launcher_pgid = launcher.id() as libc::pid_t;
+    let output = launcher.wait_with_output().expect("wait run");
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("run stdout is JSON");
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        meta["detached"], true,
+        "meta.json must record detached: {meta}"
+    );
+
+    let state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("state.json")).unwrap())
+            .unwrap();
+    let supervisor_pid = state["pid"].as_u64().expect("pid in state.json") as libc::pid_t;
+    assert_eq!(
+        unsafe { libc::getsid(supervisor_pid) },
+        supervisor_pid,
+        "detached supervisor must lead its own session"
+    );
+
+    // Hang up the launcher's process group, as a closing terminal would.
+    unsafe { libc::killpg(launcher_pgid, libc::SIGHUP) };
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let status = h.run(&["status", &job_id]);
+    assert_eq!(
+        status["state"], "running",
+        "detached job must survive: {status}"
+    );
+
+    h.run(&["kill", "--signal", "KILL", &job_id]);
+}
+
+/// Spec: `--signal-on-parent-exit` delivers the signal to the child when its
+/// supervisor dies, and the configured signal is persisted in meta.json.
+#[cfg(target_os = "linux")]
+#[test]
+fn run_signal_on_parent_exit_signals_child_when_supervisor_dies() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--signal-on-parent-exit",
+        "TERM",
+        "--no-wait",
+        "--",
+        "sleep",
+        "30",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(job_path.join("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["signal_on_parent_exit"], "TERM", "meta: {meta}");
+
+    // Wait until state.json points at the `sleep` child rather than the supervisor.
+    let proc_stat = |pid: u64| std::fs::read_to_string(format!("/proc/{pid}/stat")).ok();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let child_pid = loop {
+        let state: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(job_path.join("state.json")).unwrap())
+                .unwrap();
+        let pid = state["pid"].as_u64().expect("pid in state.json");
+        if proc_stat(pid).is_some_and(|s| s.contains("(sleep)")) {
+            break pid;
+        }
+        assert!(std::time::Instant::now() < deadline, "child never started");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    };
+    // /proc/<pid>/stat: "pid (comm) state ppid ..."
+    let stat = proc_stat(child_pid).unwrap();
+    let after_comm = &stat[stat.rfind(')').unwrap() + 2..];
+    let supervisor_pid: libc::pid_t = after_comm
+        .split_whitespace()
+        .nth(1)
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    unsafe { libc::kill(supervisor_pid, libc::SIGKILL) };
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    loop {
+        let gone = match proc_stat(child_pid) {
+            None => true,
+            Some(s) => s[s.rfind(')').unwrap() + 2..].starts_with('Z'),
+        };
+        if gone {
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "child must be signalled after its supervisor dies"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
 /// Spec: --inherit-env and --no-inherit-env are mutually exclusive (clap rejects both together).
 #[test]
 fn inherit_env_and_no_inherit_env_are_mutually_exclusive() {
@@ -1259,59 +3974,202 @@ fn run_json_response_includes_masked_env_vars() {
 
     assert_envelope(&v, "run", true);
 
-    // env_vars must be present in the run JSON response.
-    let env_vars = v["env_vars"]
-        .as_array()
-        .expect("env_vars missing in run JSON response");
+    // env_vars must be present in the run JSON response.
+    let env_vars = v["env_vars"]
+        .as_array()
+        .expect("env_vars missing in run JSON response");
+
+    // The masked key must appear as "SECRET=***" (not the real value).
+    let has_masked = env_vars.iter().any(|v| v.as_str() == Some("SECRET=***"));
+    assert!(
+        has_masked,
+        "expected SECRET=*** in run JSON env_vars, got: {v}"
+    );
+
+    // The real secret value must NOT appear in the run JSON response.
+    assert!(
+        !stdout.contains("super_secret_run_value"),
+        "real secret value should not appear in run JSON stdout: {stdout}"
+    );
+}
+
+/// Spec: tail range reflects returned window when output exceeds constraints.
+#[test]
+fn tail_range_reflects_slice_when_over_limit() {
+    let h = TestHarness::new();
+
+    // Generate exactly 5 lines; request only 2 lines.
+    let run_v = h.run(&[
+        "run",
+        "--",
+        "sh",
+        "-c",
+        "printf 'line1\\nline2\\nline3\\nline4\\nline5\\n'",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+
+    // Wait for the child to finish.
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    let v = h.run(&["tail", "--tail-lines", "2", &job_id]);
+    assert_envelope(&v, "tail", true);
+    let alias = h.run(&["tail", "--lines", "2", &job_id]);
+    assert_envelope(&alias, "tail", true);
+    assert_eq!(alias["stdout"], v["stdout"]);
+    assert_eq!(alias["stderr"], v["stderr"]);
+
+    let stdout = v["stdout"].as_str().unwrap_or_default();
+    let range = v["stdout_range"].as_array().expect("stdout_range missing");
+    let begin = range.first().and_then(|x| x.as_u64()).unwrap_or(0);
+    let end = range.get(1).and_then(|x| x.as_u64()).unwrap_or(0);
+    assert_eq!(
+        end.saturating_sub(begin),
+        stdout.len() as u64,
+        "stdout_range length must match returned stdout bytes: {v}"
+    );
+}
+
+/// Spec: `run --output-encoding` is persisted and used by `tail` to decode log bytes;
+/// `tail --encoding` overrides it.
+#[test]
+fn tail_decodes_logs_with_output_encoding() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--output-encoding",
+        "latin1",
+        "--",
+        "sh",
+        "-c",
+        "printf '\\351t\\351\\n'",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["tail", &job_id]);
+    assert_envelope(&v, "tail", true);
+    assert_eq!(v["stdout"], "été\n", "latin1 bytes must decode: {v}");
+    assert_eq!(v["encoding"], "windows-1252");
+
+    let utf8 = h.run(&["tail", "--encoding", "utf-8", &job_id]);
+    assert_eq!(utf8["stdout"], "\u{FFFD}t\u{FFFD}\n");
+    assert_eq!(utf8["encoding"], "utf-8-lossy");
+}
+
+/// Spec: `run --output-encoding` also decodes the `run` snapshot, the `wait`
+/// inline output, the `--report-file` tails and the `restart` snapshot.
+#[test]
+fn output_encoding_applies_to_every_snapshot() {
+    let h = TestHarness::new();
+    let report = std::path::Path::new(h.root()).join("report.json");
+    let run_v = h.run(&[
+        "run",
+        "--output-encoding",
+        "latin1",
+        "--report-file",
+        report.to_str().unwrap(),
+        "--",
+        "sh",
+        "-c",
+        "printf '\\351t\\351\\n'",
+    ]);
+    assert_envelope(&run_v, "run", true);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    assert_eq!(run_v["stdout"], "été\n", "{run_v}");
+
+    let wait_v = h.run(&["wait", &job_id]);
+    assert_eq!(wait_v["stdout"], "été\n", "{wait_v}");
 
-    // The masked key must appear as "SECRET=***" (not the real value).
-    let has_masked = env_vars.iter().any(|v| v.as_str() == Some("SECRET=***"));
-    assert!(
-        has_masked,
-        "expected SECRET=*** in run JSON env_vars, got: {v}"
-    );
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(report["stdout_tail"], "été\n", "{report}");
 
-    // The real secret value must NOT appear in the run JSON response.
-    assert!(
-        !stdout.contains("super_secret_run_value"),
-        "real secret value should not appear in run JSON stdout: {stdout}"
-    );
+    let restart_v = h.run(&["restart", "--wait", &job_id]);
+    assert_eq!(restart_v["stdout"], "été\n", "{restart_v}");
 }
 
-/// Spec: tail range reflects returned window when output exceeds constraints.
+/// Spec: `tail --encoding base64` and `run --snapshot-encoding base64` return the
+/// raw (possibly non-UTF-8) log bytes base64-encoded, after the line/byte limits.
 #[test]
-fn tail_range_reflects_slice_when_over_limit() {
+fn tail_and_run_snapshot_support_base64_encoding() {
     let h = TestHarness::new();
-
-    // Generate exactly 5 lines; request only 2 lines.
     let run_v = h.run(&[
         "run",
+        "--snapshot-encoding",
+        "base64",
         "--",
         "sh",
         "-c",
-        "printf 'line1\\nline2\\nline3\\nline4\\nline5\\n'",
+        "printf 'a\\n\\377\\376'",
     ]);
     let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    if run_v["stdout_total_bytes"] == 4 {
+        assert_eq!(run_v["stdout"], "YQr//g==", "{run_v}");
+    }
+    assert_eq!(run_v["encoding"], "base64", "{run_v}");
+    assert!(run_v.get("compression").is_none(), "{run_v}");
 
-    // Wait for the child to finish.
-    std::thread::sleep(std::time::Duration::from_millis(300));
-
-    let v = h.run(&["tail", "--tail-lines", "2", &job_id]);
+    let v = h.run(&["tail", "--encoding", "base64", "--tail-lines", "1", &job_id]);
     assert_envelope(&v, "tail", true);
-    let alias = h.run(&["tail", "--lines", "2", &job_id]);
-    assert_envelope(&alias, "tail", true);
-    assert_eq!(alias["stdout"], v["stdout"]);
-    assert_eq!(alias["stderr"], v["stderr"]);
+    assert_eq!(v["stdout"], "//4=", "{v}");
+    assert_eq!(v["encoding"], "base64");
+    assert_eq!(v["stdout_range"], serde_json::json!([2, 4]));
+}
 
-    let stdout = v["stdout"].as_str().unwrap_or_default();
-    let range = v["stdout_range"].as_array().expect("stdout_range missing");
-    let begin = range.first().and_then(|x| x.as_u64()).unwrap_or(0);
-    let end = range.get(1).and_then(|x| x.as_u64()).unwrap_or(0);
+/// Spec: `rotate` archives an oversized log to `<log>.1`, leaves an empty active
+/// log, and the running supervisor keeps writing to the new file.
+#[test]
+fn rotate_archives_stdout_log_while_job_runs() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--no-wait",
+        "--",
+        "sh",
+        "-c",
+        "echo before; sleep 1; echo after",
+    ]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let job_path = std::path::Path::new(h.root()).join(&job_id);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let v = h.run(&[
+        "rotate",
+        "--job-id",
+        &job_id,
+        "--log",
+        "stdout.log",
+        "--max-size",
+        "1",
+    ]);
+    assert_envelope(&v, "rotate", true);
+    assert_eq!(v["rotated"], true, "rotate response: {v}");
     assert_eq!(
-        end.saturating_sub(begin),
-        stdout.len() as u64,
-        "stdout_range length must match returned stdout bytes: {v}"
+        std::fs::read_to_string(job_path.join("stdout.log.1")).unwrap(),
+        "before\n"
+    );
+
+    wait_until_terminal(&h, &job_id);
+    assert_eq!(
+        std::fs::read_to_string(job_path.join("stdout.log")).unwrap(),
+        "after\n",
+        "supervisor must reopen the rotated log"
     );
+
+    // Below the limit: nothing happens.
+    let noop = h.run(&[
+        "rotate",
+        "--job-id",
+        &job_id,
+        "--log",
+        "stdout.log",
+        "--max-size",
+        "10m",
+    ]);
+    assert_eq!(noop["rotated"], false, "rotate response: {noop}");
+    assert!(!job_path.join("stdout.log.2").exists());
 }
 
 // ── add-run-tail-metrics: new fields ──────────────────────────────────────────
@@ -1697,6 +4555,74 @@ fn restart_with_old_terminal_job_keeps_target_inspectable() {
     assert_eq!(status_v["job_id"].as_str().unwrap_or(""), target_id);
 }
 
+// ── replay ─────────────────────────────────────────────────────────────────────
+
+/// Spec: replay starts a new job from the original definition and carries
+/// masked env values over only as their "***" placeholder.
+#[test]
+fn replay_runs_definition_as_new_job_without_secrets() {
+    let h = TestHarness::new();
+    let run_v = h.run(&[
+        "run",
+        "--env",
+        "SECRET_REPLAY=value42",
+        "--mask",
+        "SECRET_REPLAY",
+        "--stdin",
+        "stdin-ok",
+        "--tag",
+        "replayed",
+        "--",
+        "sh",
+        "-c",
+        "printf '%s:' \"$SECRET_REPLAY\"; cat",
+    ]);
+    let job_id = run_v["job_id"].as_str().expect("job_id").to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["replay", &job_id]);
+    assert_envelope(&v, "replay", true);
+    assert_eq!(v["replayed_from"], job_id.as_str());
+    let new_id = v["job_id"].as_str().expect("new job_id").to_string();
+    assert_ne!(new_id, job_id);
+    assert_eq!(v["tags"], serde_json::json!(["replayed"]));
+    assert_eq!(
+        v["env_vars"],
+        serde_json::json!(["SECRET_REPLAY=***"]),
+        "{v}"
+    );
+    wait_until_terminal(&h, &new_id);
+    let tail_v = h.run(&["tail", &new_id]);
+    assert_eq!(tail_v["stdout"], "***:stdin-ok", "{tail_v}");
+}
+
+/// Spec: replay accepts the job directory path, like status and tail.
+#[test]
+fn replay_accepts_job_directory_path() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "echo", "again"]);
+    let job_id = run_v["job_id"].as_str().expect("job_id").to_string();
+    let job_dir = run_v["job_dir"].as_str().expect("job_dir").to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["replay", &job_dir]);
+    assert_envelope(&v, "replay", true);
+    assert_eq!(v["replayed_from"], job_id.as_str(), "{v}");
+}
+
+/// Spec: replaying a job that has not finished fails with job_still_running.
+#[test]
+fn replay_rejects_running_job() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--no-wait", "--", "sleep", "30"]);
+    let job_id = run_v["job_id"].as_str().expect("job_id").to_string();
+
+    let v = h.run(&["replay", &job_id]);
+    assert_envelope(&v, "error", false);
+    assert_eq!(v["error"]["code"], "job_still_running");
+    let _ = h.run(&["kill", "--signal", "KILL", &job_id]);
+}
+
 // ── list ───────────────────────────────────────────────────────────────────────
 
 /// Spec: `list` on an empty (non-existent) root returns jobs=[].
@@ -1800,6 +4726,141 @@ fn list_jobs_include_command() {
     assert_eq!(actual_command, expected_command);
 }
 
+/// Spec: `list --cache` serves finished jobs from `.list-cache.json` until
+/// their files change, and evicts jobs whose directory is gone.
+#[test]
+fn list_cache_reuses_finished_job_summaries() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "echo", "cached"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    // Let the supervisor finish its last state.json write.
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    let first = h.run(&["list", "--all", "--cache"]);
+    assert_envelope(&first, "list", true);
+    assert_eq!(first["cache_hits"], 0, "{first}");
+    assert_eq!(first["cache_misses"], 1, "{first}");
+    let cache_path = std::path::Path::new(h.root()).join(".list-cache.json");
+    assert!(cache_path.is_file());
+
+    let second = h.run(&["list", "--all", "--cache"]);
+    assert_eq!(second["cache_hits"], 1, "{second}");
+    assert_eq!(second["cache_misses"], 0, "{second}");
+    assert_eq!(second["jobs"], first["jobs"], "{second}");
+
+    // A tag change rewrites meta.json, so the entry is stale.
+    h.run(&["tag", "set", &job_id, "--tag", "fresh"]);
+    let third = h.run(&["list", "--all", "--cache"]);
+    assert_eq!(third["cache_misses"], 1, "{third}");
+    assert_eq!(third["jobs"][0]["tags"][0], "fresh", "{third}");
+
+    // Without --cache the fields are absent.
+    let plain = h.run(&["list", "--all"]);
+    assert!(plain.get("cache_hits").is_none(), "{plain}");
+
+    std::fs::remove_dir_all(std::path::Path::new(h.root()).join(&job_id)).unwrap();
+    let fourth = h.run(&["list", "--all", "--cache"]);
+    assert_eq!(fourth["jobs"].as_array().unwrap().len(), 0, "{fourth}");
+    let cache = std::fs::read_to_string(&cache_path).unwrap();
+    assert!(!cache.contains(&job_id), "{cache}");
+}
+
+/// Spec: `list --before` continues from `next_cursor` and `--after` goes
+/// back from `prev_cursor`.
+#[test]
+fn list_cursor_pagination_pages_through_jobs() {
+    let h = TestHarness::new();
+    for i in 0..5 {
+        let v = h.run(&["run", "--", "echo", &i.to_string()]);
+        assert_envelope(&v, "run", true);
+    }
+    let ids = |v: &serde_json::Value| -> Vec<String> {
+        v["jobs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|j| j["job_id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let all = ids(&h.run(&["list", "--all", "--limit", "0"]));
+    assert_eq!(all.len(), 5);
+
+    let first = h.run(&["list", "--all", "--limit", "2"]);
+    assert_eq!(ids(&first), all[..2]);
+    assert!(first.get("prev_cursor").is_none(), "{first}");
+    let cursor = first["next_cursor"]
+        .as_str()
+        .expect("next_cursor")
+        .to_string();
+    assert_eq!(cursor, all[1]);
+
+    let second = h.run(&["list", "--all", "--limit", "2", "--before", &cursor]);
+    assert_envelope(&second, "list", true);
+    assert_eq!(ids(&second), all[2..4]);
+    assert_eq!(second["prev_cursor"].as_str(), Some(all[2].as_str()));
+
+    let back = h.run(&[
+        "list",
+        "--all",
+        "--limit",
+        "2",
+        "--after",
+        second["prev_cursor"].as_str().unwrap(),
+    ]);
+    assert_eq!(ids(&back), all[..2]);
+
+    let last = h.run(&["list", "--all", "--limit", "2", "--before", &all[3]]);
+    assert_eq!(ids(&last), all[4..]);
+    assert!(last.get("next_cursor").is_none(), "{last}");
+}
+
+/// Spec: the unsorted NDJSON stream rejects `--before`/`--after` instead of
+/// ignoring the cursor.
+#[test]
+fn list_ndjson_rejects_cursor() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--", "true"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    for flag in ["--before", "--after"] {
+        let v = h.run(&["list", "--all", "--format", "ndjson", flag, &job_id]);
+        assert!(!v["ok"].as_bool().unwrap_or(true), "{v}");
+        assert_eq!(
+            v["error"]["code"].as_str(),
+            Some("cursor_with_ndjson"),
+            "{v}"
+        );
+    }
+}
+
+/// Spec: `list --command-prefix` matches the program or the start of the
+/// command line.
+#[test]
+fn list_command_prefix_filters_by_command() {
+    let h = TestHarness::new();
+    h.run(&["run", "--", "echo", "a"]);
+    let sleep_v = h.run(&["run", "--no-wait", "--", "sleep", "10"]);
+    h.run(&["run", "--", "echo", "b"]);
+
+    let count = |prefix: &str| {
+        let v = h.run(&["list", "--all", "--command-prefix", prefix]);
+        assert_envelope(&v, "list", true);
+        v["jobs"].as_array().unwrap().len()
+    };
+    assert_eq!(count("echo"), 2);
+    assert_eq!(count("sleep"), 1);
+    assert_eq!(count("echo b"), 1);
+    assert_eq!(count("ech"), 2);
+    assert_eq!(count("true"), 0);
+
+    h.run(&[
+        "kill",
+        "--signal",
+        "KILL",
+        sleep_v["job_id"].as_str().unwrap(),
+    ]);
+}
+
 /// Spec: `--limit` truncates the result and sets truncated=true.
 #[test]
 fn list_limit_truncates_result() {
@@ -2293,6 +5354,95 @@ fn list_cwd_flag_filters_by_specified_directory() {
     );
 }
 
+/// `list --cwd <PATH> --cwd-recursive` also includes jobs created from
+/// subdirectories of `PATH`, but not from siblings that share its name prefix.
+#[test]
+fn list_cwd_recursive_includes_subdirectory_jobs() {
+    let h = TestHarness::new();
+
+    let base = tempfile::tempdir().expect("create base dir");
+    let dir = base.path().join("dir");
+    let mut job_ids = Vec::new();
+    for sub in ["dir/sub1", "dir/sub2", "dir-other"] {
+        let path = base.path().join(sub);
+        std::fs::create_dir_all(&path).unwrap();
+        let (v, _) = run_cmd_with_root_and_cwd(&["run", "true"], Some(h.root()), Some(&path));
+        job_ids.push(v["job_id"].as_str().expect("job_id missing").to_string());
+    }
+
+    let listed = |extra: &[&str]| -> Vec<String> {
+        let mut args = vec!["list", "--cwd", dir.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        let (v, _) = run_cmd_with_root_and_cwd(&args, Some(h.root()), None);
+        assert_envelope(&v, "list", true);
+        v["jobs"]
+            .as_array()
+            .expect("jobs missing")
+            .iter()
+            .map(|j| j["job_id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let recursive = listed(&["--cwd-recursive"]);
+    assert!(recursive.contains(&job_ids[0]), "{recursive:?}");
+    assert!(recursive.contains(&job_ids[1]), "{recursive:?}");
+    assert!(!recursive.contains(&job_ids[2]), "{recursive:?}");
+    assert!(listed(&[]).is_empty());
+}
+
+/// `list` job summaries expose the persisted cwd of each job.
+#[test]
+fn list_job_summary_includes_cwd() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+
+    let (run_v, _) =
+        run_cmd_with_root_and_cwd(&["run", "echo", "hi"], Some(h.root()), Some(dir.path()));
+    let job_id = run_v["job_id"]
+        .as_str()
+        .expect("job_id missing")
+        .to_string();
+
+    let (list_v, _) = run_cmd_with_root_and_cwd(&["list"], Some(h.root()), Some(dir.path()));
+    assert_envelope(&list_v, "list", true);
+    let job = list_v["jobs"]
+        .as_array()
+        .expect("jobs missing")
+        .iter()
+        .find(|j| j["job_id"].as_str() == Some(&job_id))
+        .unwrap_or_else(|| panic!("job must be listed from its cwd: {list_v}"))
+        .clone();
+    let expected = std::fs::canonicalize(dir.path()).expect("canonicalize dir");
+    assert_eq!(
+        job["cwd"].as_str(),
+        expected.to_str(),
+        "list summary cwd must match the job cwd: {job}"
+    );
+}
+
+/// `list` exposes `duration_ms` from state.json for finished jobs.
+#[test]
+fn list_job_summary_includes_duration_for_finished_jobs() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "sh", "-c", "sleep 0.2"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let list_v = h.run(&["list", "--all"]);
+    assert_envelope(&list_v, "list", true);
+    let job = list_v["jobs"]
+        .as_array()
+        .expect("jobs missing")
+        .iter()
+        .find(|j| j["job_id"].as_str() == Some(&job_id))
+        .unwrap_or_else(|| panic!("job must be listed: {list_v}"))
+        .clone();
+    let duration = job["duration_ms"]
+        .as_u64()
+        .unwrap_or_else(|| panic!("duration_ms missing: {job}"));
+    assert!(duration > 0, "duration_ms must be positive: {job}");
+}
+
 /// Task 4.2b: `list --all` disables cwd filtering and returns all jobs.
 #[test]
 fn list_all_flag_disables_cwd_filter() {
@@ -2316,17 +5466,166 @@ fn list_all_flag_disables_cwd_filter() {
         Some(h.root()),
         Some(dir_b.path()),
     );
-    let job_b_id = vb["job_id"].as_str().expect("job_id missing").to_string();
+    let job_b_id = vb["job_id"].as_str().expect("job_id missing").to_string();
+
+    // list --all from dir_a should return both A and B.
+    let (list_v, _) =
+        run_cmd_with_root_and_cwd(&["list", "--all"], Some(h.root()), Some(dir_a.path()));
+    assert_envelope(&list_v, "list", true);
+    let jobs = list_v["jobs"].as_array().expect("jobs missing");
+    let has_a = jobs.iter().any(|j| j["job_id"].as_str() == Some(&job_a_id));
+    let has_b = jobs.iter().any(|j| j["job_id"].as_str() == Some(&job_b_id));
+    assert!(has_a, "Job A should appear with --all; list: {list_v}");
+    assert!(has_b, "Job B should appear with --all; list: {list_v}");
+}
+
+/// `list` reports the effective cwd filter: the caller's cwd by default, null with --all.
+#[test]
+fn list_reports_filter_cwd() {
+    let h = TestHarness::new();
+    let dir = tempfile::tempdir().expect("create dir");
+    let expected = dir.path().canonicalize().unwrap().display().to_string();
+
+    let (list_v, _) = run_cmd_with_root_and_cwd(&["list"], Some(h.root()), Some(dir.path()));
+    assert_envelope(&list_v, "list", true);
+    assert_eq!(list_v["filter_cwd"], expected.as_str(), "{list_v}");
+
+    let (list_all, _) =
+        run_cmd_with_root_and_cwd(&["list", "--all"], Some(h.root()), Some(dir.path()));
+    assert_envelope(&list_all, "list", true);
+    assert!(list_all["filter_cwd"].is_null(), "{list_all}");
+}
+
+/// `list` exposes `command_preview`: the joined command, truncated to 80 chars with "...".
+#[test]
+fn list_reports_command_preview() {
+    let h = TestHarness::new();
+    let short_v = h.run(&["run", "echo", "a", "b", "c", "d"]);
+    let short_id = short_v["job_id"].as_str().unwrap().to_string();
+    let long_arg = "x".repeat(100);
+    let long_v = h.run(&["run", "echo", &long_arg]);
+    let long_id = long_v["job_id"].as_str().unwrap().to_string();
+
+    let list_v = h.run(&["list", "--all"]);
+    assert_envelope(&list_v, "list", true);
+    let jobs = list_v["jobs"].as_array().expect("jobs missing");
+    let find = |id: &str| {
+        jobs.iter()
+            .find(|j| j["job_id"].as_str() == Some(id))
+            .unwrap_or_else(|| panic!("job {id} missing: {list_v}"))
+    };
+    assert_eq!(find(&short_id)["command_preview"], "echo a b c d");
+    let expected_long = format!("{}...", &format!("echo {long_arg}")[..80]);
+    assert_eq!(find(&long_id)["command_preview"], expected_long.as_str());
+}
+
+/// `list --full` embeds each job's meta.json; plain `list` does not.
+#[test]
+fn list_full_includes_meta() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "echo", "full"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let v = h.run(&["list", "--full"]);
+    assert_envelope(&v, "list", true);
+    let job = &v["jobs"][0];
+    assert_eq!(job["job_id"], job_id.as_str(), "{v}");
+    assert_eq!(
+        job["meta"]["command"],
+        serde_json::json!(["echo", "full"]),
+        "{v}"
+    );
+
+    let plain = h.run(&["list"]);
+    assert!(plain["jobs"][0].get("meta").is_none(), "{plain}");
+}
+
+/// Spec: running jobs report `current_output_bytes`; finished jobs omit it.
+#[test]
+fn list_reports_current_output_bytes_for_running_jobs() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--no-wait", "--", "yes"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    let list_v = h.run(&["list"]);
+    let job = list_v["jobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|j| j["job_id"] == job_id.as_str())
+        .unwrap()
+        .clone();
+    let _ = h.run(&["kill", &job_id]);
+    assert_eq!(job["state"], "running", "{job}");
+    assert!(job["current_output_bytes"].as_u64().unwrap() > 0, "{job}");
+
+    wait_until_terminal(&h, &job_id);
+    let list_v = h.run(&["list"]);
+    let job = &list_v["jobs"][0];
+    assert!(job.get("current_output_bytes").is_none(), "{job}");
+}
+
+/// `list --format ndjson` prints one JSON object per line, ending with the envelope.
+#[test]
+fn list_format_ndjson_streams_one_object_per_line() {
+    let h = TestHarness::new();
+    for word in ["a", "b"] {
+        let v = h.run(&["run", "echo", word]);
+        wait_until_terminal(&h, v["job_id"].as_str().unwrap());
+    }
+
+    let output = run_raw_with_root_and_stdin(
+        &["list", "--all", "--format", "ndjson"],
+        Some(h.root()),
+        None,
+    );
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("each line is one JSON object"))
+        .collect();
+    assert_eq!(lines.len(), 3, "{lines:?}");
+    for job in &lines[..2] {
+        assert_eq!(job["state"], "exited", "{job}");
+        assert!(job["job_id"].is_string(), "{job}");
+    }
+    let last = &lines[2];
+    assert_envelope(last, "list", true);
+    assert_eq!(last["jobs"], serde_json::json!([]), "{last}");
+    assert_eq!(last["stats"]["total"], 2, "{last}");
+    assert_eq!(last["truncated"], false, "{last}");
+}
+
+/// `list --stats-only` counts every job by state without returning summaries.
+#[test]
+fn list_stats_only_counts_jobs_by_state() {
+    let h = TestHarness::new();
+    let done = h.run(&["run", "echo", "done"]);
+    wait_until_terminal(&h, done["job_id"].as_str().unwrap());
+    let sleeper = h.run(&["run", "--no-wait", "sleep", "30"]);
+    let sleeper_id = sleeper["job_id"].as_str().unwrap().to_string();
+    h.run(&["create", "echo", "later"]);
+
+    let v = h.run(&["list", "--stats-only"]);
+    assert_envelope(&v, "list", true);
+    assert_eq!(v["jobs"], serde_json::json!([]), "{v}");
+    assert!(v["filter_cwd"].is_null(), "{v}");
+    let stats = &v["stats"];
+    assert_eq!(stats["total"], 3, "{v}");
+    assert_eq!(stats["running"], 1, "{v}");
+    assert_eq!(stats["finished"], 1, "{v}");
+    assert_eq!(
+        stats["by_state"],
+        serde_json::json!({"created": 1, "exited": 1, "running": 1}),
+        "{v}"
+    );
 
-    // list --all from dir_a should return both A and B.
-    let (list_v, _) =
-        run_cmd_with_root_and_cwd(&["list", "--all"], Some(h.root()), Some(dir_a.path()));
-    assert_envelope(&list_v, "list", true);
-    let jobs = list_v["jobs"].as_array().expect("jobs missing");
-    let has_a = jobs.iter().any(|j| j["job_id"].as_str() == Some(&job_a_id));
-    let has_b = jobs.iter().any(|j| j["job_id"].as_str() == Some(&job_b_id));
-    assert!(has_a, "Job A should appear with --all; list: {list_v}");
-    assert!(has_b, "Job B should appear with --all; list: {list_v}");
+    h.run(&["kill", &sleeper_id]);
+    let plain = h.run(&["list", "--all"]);
+    assert!(plain.get("stats").is_none(), "{plain}");
 }
 
 /// Task 4.3: `list --all --cwd` is a usage error (exit code 2, clap rejects it).
@@ -2352,6 +5651,31 @@ fn list_all_and_cwd_conflict_exits_with_code_2() {
 
 // ── schema ─────────────────────────────────────────────────────────────────────
 
+/// `version` reports the binary version, and jobs record it in meta.json.
+#[test]
+fn version_reported_and_recorded_in_meta() {
+    let v = run_cmd_with_root(&["version"], None);
+    assert_envelope(&v, "version", true);
+    assert_eq!(v["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(v["schema_version"], "0.1");
+
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "echo", "hi"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    let status_v = h.run(&["status", &job_id]);
+    assert_envelope(&status_v, "status", true);
+    let meta_path = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("meta.json");
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(meta_path).unwrap()).unwrap();
+    assert_eq!(
+        meta["agent_exec_version"],
+        env!("CARGO_PKG_VERSION"),
+        "{meta}"
+    );
+}
+
 /// Task 3.1/3.2: `schema` command returns valid JSON envelope with type="schema".
 #[test]
 fn schema_returns_json_envelope() {
@@ -2394,6 +5718,35 @@ fn schema_response_has_schema_object() {
     );
 }
 
+/// Spec: every schema definition and property carries a non-empty description.
+#[test]
+fn schema_definitions_and_properties_have_descriptions() {
+    let v = run_cmd_with_root(&["schema"], None);
+    let definitions = v["schema"]["definitions"].as_object().unwrap();
+
+    let job_id = &definitions["RunData"]["properties"]["job_id"]["description"];
+    assert!(
+        job_id.as_str().is_some_and(|d| !d.is_empty()),
+        "RunData.job_id must be described: {job_id}"
+    );
+
+    let described =
+        |v: &serde_json::Value| v["description"].as_str().is_some_and(|d| !d.is_empty());
+    for (name, definition) in definitions {
+        assert!(
+            described(definition),
+            "definition {name} has no description"
+        );
+        let parts =
+            std::iter::once(definition).chain(definition["allOf"].as_array().into_iter().flatten());
+        for part in parts {
+            for (prop, schema) in part["properties"].as_object().into_iter().flatten() {
+                assert!(described(schema), "{name}.{prop} has no description");
+            }
+        }
+    }
+}
+
 #[test]
 fn schema_validates_actual_wait_responses() {
     let h = TestHarness::new();
@@ -2620,13 +5973,15 @@ fn install_skills_repeated_install_updates_single_lock_entry() {
     let agents_dir = install_root.path().join(".agents");
 
     let bin = binary();
-    for _ in 0..2 {
+    for attempt in 0..2 {
         let output = std::process::Command::new(&bin)
             .args(["install-skills"])
             .current_dir(install_root.path())
             .output()
             .expect("run binary");
         assert!(output.status.success(), "install-skills must succeed");
+        let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(v["skills"][0]["updated"], attempt == 1, "{v}");
     }
 
     let lock_path = agents_dir.join(".skill-lock.json");
@@ -2660,6 +6015,43 @@ fn install_skills_repeated_install_updates_single_lock_entry() {
         "embedded",
         "lock skills[0].source_type must be 'embedded'; got: {lock}"
     );
+    assert!(
+        lock_skills[0]["previous_installed_at"].is_string(),
+        "reinstall must record previous_installed_at; got: {lock}"
+    );
+}
+
+/// `verify-skills` detects an installed skill file modified after installation.
+#[test]
+fn verify_skills_reports_modified_file() {
+    let install_root = tempfile::tempdir().expect("create install root");
+    let bin = binary();
+    let run = |cmd: &str| {
+        let output = std::process::Command::new(&bin)
+            .arg(cmd)
+            .current_dir(install_root.path())
+            .output()
+            .expect("run binary");
+        assert!(output.status.success(), "{cmd} must succeed");
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    run("install-skills");
+    let v = run("verify-skills");
+    assert_envelope(&v, "verify_skills", true);
+    assert_eq!(v["verified"], true, "{v}");
+
+    let skill_md = install_root
+        .path()
+        .join(".agents/skills/agent-exec/SKILL.md");
+    std::fs::write(&skill_md, "modified").unwrap();
+    let v = run("verify-skills");
+    assert_eq!(v["verified"], false, "{v}");
+    assert_eq!(v["skills"][0]["name"], "agent-exec");
+    assert_eq!(
+        v["skills"][0]["mismatches"],
+        serde_json::json!(["SKILL.md"])
+    );
 }
 
 /// `install-skills --claude` installs into `.claude/skills/` and writes
@@ -2883,6 +6275,59 @@ fn notify_file_sink_appends_ndjson_on_job_finish() {
     );
 }
 
+/// File sink: `--notify-file-format append` lets concurrent jobs share one
+/// NDJSON file, one whole line per job.
+#[test]
+fn notify_file_format_append_shares_file_between_jobs() {
+    let h = TestHarness::new();
+    let tmp_dir = tempfile::tempdir().expect("create tempdir");
+    let events_file = tmp_dir.path().join("events.ndjson");
+    let events_file_str = events_file.to_str().unwrap();
+
+    let job_ids: Vec<String> = (0..2)
+        .map(|_| {
+            let v = h.run(&[
+                "run",
+                "--no-wait",
+                "--notify-file",
+                events_file_str,
+                "--notify-file-format",
+                "append",
+                "--",
+                "sleep",
+                "0.2",
+            ]);
+            v["job_id"].as_str().unwrap().to_string()
+        })
+        .collect();
+    for job_id in &job_ids {
+        wait_until_terminal(&h, job_id);
+    }
+
+    // Events are delivered just after the terminal state is written.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let lines = loop {
+        let content = std::fs::read_to_string(&events_file).unwrap_or_default();
+        let lines: Vec<String> = content.lines().map(str::to_string).collect();
+        if lines.len() >= 2 || std::time::Instant::now() >= deadline {
+            break lines;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    };
+    assert_eq!(lines.len(), 2, "{lines:?}");
+    let mut seen: Vec<String> = lines
+        .iter()
+        .map(|l| {
+            let event: serde_json::Value = serde_json::from_str(l).expect("valid JSON line");
+            event["job_id"].as_str().unwrap().to_string()
+        })
+        .collect();
+    seen.sort();
+    let mut expected = job_ids.clone();
+    expected.sort();
+    assert_eq!(seen, expected);
+}
+
 // ── notify command sink ─────────────────────────────────────────────────────────
 
 /// Command sink: event JSON is delivered via stdin and env vars are set.
@@ -2932,6 +6377,158 @@ fn notify_command_sink_receives_event_via_stdin() {
     );
 }
 
+/// Command sink: the exit code is recorded, and a command that outlives
+/// --notify-timeout is killed without changing the job state.
+#[cfg(unix)]
+#[test]
+fn notify_command_timeout_is_recorded_in_delivery_results() {
+    let h = TestHarness::new();
+    let read_results = |job_id: &str| -> serde_json::Value {
+        let path = std::path::Path::new(h.root())
+            .join(job_id)
+            .join("completion_event.json");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let record: Option<serde_json::Value> = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|c| serde_json::from_str(&c).ok());
+            if let Some(record) = record
+                && record["delivery_results"]
+                    .as_array()
+                    .is_some_and(|r| !r.is_empty())
+            {
+                return record["delivery_results"][0].clone();
+            }
+            assert!(std::time::Instant::now() < deadline, "no delivery results");
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    };
+
+    let v = h.run(&["run", "--notify-command", "exit 3", "--", "true"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let result = read_results(&job_id);
+    assert_eq!(result["success"], false, "{result}");
+    assert_eq!(result["exit_code"], 3, "{result}");
+
+    let started = std::time::Instant::now();
+    let v = h.run(&[
+        "run",
+        "--notify-command",
+        "sleep 30",
+        "--notify-timeout",
+        "300",
+        "--",
+        "true",
+    ]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    let wait_v = wait_until_terminal(&h, &job_id);
+    assert_eq!(wait_v["state"], "exited");
+    let result = read_results(&job_id);
+    assert!(started.elapsed() < std::time::Duration::from_secs(20));
+    assert_eq!(result["success"], false, "{result}");
+    assert!(
+        result["error"].as_str().unwrap_or("").contains("timed out"),
+        "{result}"
+    );
+}
+
+/// File sink: `--notify-file-format snapshot` writes the final state plus meta.
+#[test]
+fn notify_file_snapshot_writes_final_state_and_meta() {
+    let h = TestHarness::new();
+    let tmp_dir = tempfile::tempdir().expect("create tempdir");
+    let snapshot_path = tmp_dir.path().join("done").join("snapshot.json");
+
+    let v = h.run(&[
+        "run",
+        "--notify-file",
+        snapshot_path.to_str().unwrap(),
+        "--notify-file-format",
+        "snapshot",
+        "--",
+        "echo",
+        "snap",
+    ]);
+    assert_envelope(&v, "run", true);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !snapshot_path.exists() {
+        assert!(std::time::Instant::now() < deadline, "snapshot not written");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+    assert_eq!(snapshot["job"]["id"], job_id.as_str(), "{snapshot}");
+    assert_eq!(snapshot["job"]["status"], "exited", "{snapshot}");
+    assert_eq!(snapshot["result"]["exit_code"], 0, "{snapshot}");
+    assert_eq!(
+        snapshot["meta"]["command"],
+        serde_json::json!(["echo", "snap"]),
+        "{snapshot}"
+    );
+}
+
+/// The snapshot is written after the webhook has reported, so it carries the
+/// recorded `webhook_status_code`.
+#[test]
+fn notify_file_snapshot_includes_webhook_status_code() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let h = TestHarness::new();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap();
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+    });
+
+    let tmp_dir = tempfile::tempdir().expect("create tempdir");
+    let snapshot_path = tmp_dir.path().join("snapshot.json");
+    let v = h.run(&[
+        "run",
+        "--completion-webhook",
+        &url,
+        "--notify-file",
+        snapshot_path.to_str().unwrap(),
+        "--notify-file-format",
+        "snapshot",
+        "--",
+        "true",
+    ]);
+    assert_envelope(&v, "run", true);
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !snapshot_path.exists() {
+        assert!(std::time::Instant::now() < deadline, "snapshot not written");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+    assert_eq!(snapshot["webhook_status_code"], 204, "{snapshot}");
+}
+
 // ── notify failure non-destructive ─────────────────────────────────────────────
 
 /// Notification failure must not change job state: job remains exited even if
@@ -3554,6 +7151,85 @@ fn gc_skips_unreadable_state() {
     );
 }
 
+// ── purge command ──────────────────────────────────────────────────────────────
+
+/// purge --dry-run lists old terminal jobs oldest first and deletes nothing.
+#[test]
+fn purge_dry_run_lists_would_delete() {
+    let h = TestHarness::new();
+    write_fake_job(
+        h.root(),
+        "purge-newer",
+        "failed",
+        Some("2021-01-01T00:00:00Z"),
+        "2021-01-01T00:00:00Z",
+    );
+    write_fake_job(
+        h.root(),
+        "purge-older",
+        "exited",
+        Some("2020-01-01T00:00:00Z"),
+        "2020-01-01T00:00:00Z",
+    );
+    write_fake_job(
+        h.root(),
+        "purge-running",
+        "running",
+        None,
+        "2020-01-01T00:00:00Z",
+    );
+
+    let v = h.run(&["purge", "--older-than", "3600", "--dry-run"]);
+    assert_envelope(&v, "purge", true);
+    assert_eq!(v["dry_run"], true);
+    assert_eq!(
+        v["would_delete"],
+        serde_json::json!(["purge-older", "purge-newer"])
+    );
+    assert_eq!(v["deleted_count"], 0);
+    assert_eq!(v["skipped_count"], 1);
+    assert!(v["freed_bytes"].as_u64().unwrap() > 0);
+    for id in ["purge-newer", "purge-older", "purge-running"] {
+        assert!(std::path::Path::new(h.root()).join(id).exists());
+    }
+}
+
+/// purge deletes only old jobs in the --state, at most --limit of them.
+#[test]
+fn purge_deletes_matching_jobs_up_to_limit() {
+    let h = TestHarness::new();
+    // A job that just finished is never old enough. Run it first so the
+    // automatic gc after `run` cannot remove the fake jobs below.
+    let run = h.run(&["run", "--wait", "--", "true"]);
+    let job_id = run["job_id"].as_str().unwrap().to_string();
+    let old = "2020-01-01T00:00:00Z";
+    write_fake_job(h.root(), "purge-exited-a", "exited", Some(old), old);
+    write_fake_job(h.root(), "purge-exited-b", "exited", Some(old), old);
+    write_fake_job(h.root(), "purge-killed", "killed", Some(old), old);
+
+    let v = h.run(&[
+        "purge",
+        "--older-than",
+        "3600",
+        "--state",
+        "exited",
+        "--limit",
+        "1",
+    ]);
+    assert_envelope(&v, "purge", true);
+    assert!(v.get("would_delete").is_none(), "{v}");
+    assert_eq!(v["deleted_count"], 1);
+    assert_eq!(v["skipped_count"], 3);
+    let root = std::path::Path::new(h.root());
+    assert!(!root.join("purge-exited-a").exists());
+    assert!(root.join("purge-exited-b").exists());
+    assert!(root.join("purge-killed").exists());
+
+    let v = h.run(&["purge", "--older-than", "3600"]);
+    assert_eq!(v["deleted_count"], 2);
+    assert!(root.join(&job_id).exists());
+}
+
 // ============================================================
 // Tag feature tests (add-job-tags)
 // ============================================================
@@ -5487,6 +9163,21 @@ fn run_yaml(args: &[&str], root: &str) -> serde_json::Value {
     serde_json::to_value(&yaml_val).expect("yaml->json conversion")
 }
 
+/// Spec: `follow` prints its events through the shared output path, so
+/// `--yaml` applies to every event.
+#[test]
+fn yaml_flag_applies_to_follow_events() {
+    let h = TestHarness::new();
+    let run_v = h.run(&["run", "--", "echo", "done"]);
+    let job_id = run_v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+    let raw = run_yaml_raw(&["follow", &job_id], h.root());
+    assert!(!raw.trim_start().starts_with('{'), "stdout is JSON: {raw}");
+    let event: serde_yaml::Value =
+        serde_yaml::from_str(&raw).unwrap_or_else(|e| panic!("not valid YAML: {e}\nstdout: {raw}"));
+    assert_eq!(event["type"].as_str(), Some("finished"), "{raw}");
+}
+
 #[test]
 fn yaml_flag_run_returns_yaml() {
     let h = TestHarness::new();
@@ -6054,6 +9745,110 @@ fn gc_deleted_action_implies_directory_absent_and_categorises_skips() {
     assert!(running_path.exists(), "running job must be preserved");
 }
 
+/// Spec: `status --poll` emits one status envelope per line until the job is terminal.
+#[test]
+fn status_poll_emits_ndjson_until_terminal() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--no-wait", "--", "sleep", "1"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+
+    let output = run_raw_with_root_and_stdin(
+        &[
+            "status",
+            "--poll",
+            "--poll-ms",
+            "100",
+            "--timeout-ms",
+            "2000",
+            &job_id,
+        ],
+        Some(h.root()),
+        None,
+    );
+    assert!(output.status.success(), "status failed: {output:?}");
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("each line is one JSON object"))
+        .collect();
+    assert!(lines.len() >= 2, "{lines:?}");
+    for line in &lines {
+        assert_envelope(line, "status", true);
+    }
+    assert_eq!(lines.last().unwrap()["state"], "exited", "{lines:?}");
+}
+
+/// Spec: the supervisor stamps `state.json` with its version; `status` warns
+/// when that version differs from its own.
+#[test]
+fn state_json_records_agent_version() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--", "true"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let state_path = std::path::Path::new(h.root())
+        .join(&job_id)
+        .join("state.json");
+    let mut state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+    assert_eq!(state["agent_version"], env!("CARGO_PKG_VERSION"), "{state}");
+    let s = h.run(&["status", &job_id]);
+    assert!(s.get("warnings").is_none(), "{s}");
+
+    state["agent_version"] = serde_json::json!("0.0.0-other");
+    std::fs::write(&state_path, state.to_string()).unwrap();
+    let s = h.run(&["status", &job_id]);
+    let warning = s["warnings"][0].as_str().unwrap();
+    assert!(warning.starts_with("agent_version_mismatch"), "{s}");
+}
+
+/// Spec: `tail --full-log` adds the interleaved full.log tail.
+#[test]
+fn tail_full_log_returns_interleaved_tail() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--", "echo", "hi"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let t = h.run(&["tail", "--full-log", &job_id]);
+    assert_envelope(&t, "tail", true);
+    let full = t["full_log_tail"].as_str().unwrap();
+    assert!(full.contains("[STDOUT] hi"), "{t}");
+    assert_eq!(t["full_log_observed_bytes"], full.len() as u64, "{t}");
+
+    let plain = h.run(&["tail", &job_id]);
+    assert!(plain.get("full_log_tail").is_none(), "{plain}");
+}
+
+/// Spec: `--color always` colors JSON responses; `--color never` keeps plain JSON.
+#[test]
+fn color_flag_controls_ansi_output() {
+    let h = TestHarness::new();
+    let v = h.run(&["run", "--", "true"]);
+    let job_id = v["job_id"].as_str().unwrap().to_string();
+    wait_until_terminal(&h, &job_id);
+
+    let colored = run_raw_with_root_and_stdin(
+        &["--color", "always", "status", &job_id],
+        Some(h.root()),
+        None,
+    );
+    assert!(colored.status.success(), "{colored:?}");
+    let text = String::from_utf8(colored.stdout).unwrap();
+    assert!(text.contains("\x1b["), "{text:?}");
+
+    let plain = run_raw_with_root_and_stdin(
+        &["--color", "never", "status", &job_id],
+        Some(h.root()),
+        None,
+    );
+    let text = String::from_utf8(plain.stdout).unwrap();
+    assert!(!text.contains("\x1b["), "{text:?}");
+    let v: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_envelope(&v, "status", true);
+}
+
 /// Run the binary with given args and return raw stdout + exit code (no JSON parsing).
 fn run_raw(args: &[&str]) -> (String, i32) {
     let bin = binary();
@@ -6877,23 +10672,22 @@ fn run_inline_includes_signal_on_signal_terminated_exit() {
 #[test]
 fn stdin_too_large_rejects_oversized_input() {
     let h = TestHarness::new();
-    let src_path = std::path::Path::new(h.root()).join("big.bin");
-    // Create a file just over the limit (use a small --stdin-max-bytes for speed).
+    // Pipe input just over the limit (use a small --stdin-max-bytes for speed).
     let limit: usize = 1024;
-    std::fs::write(&src_path, vec![0u8; limit + 1]).expect("write big file");
+    let input = vec![0u8; limit + 1];
 
     let output = run_raw_with_root_and_stdin(
         &[
             "run",
-            "--stdin-file",
-            src_path.to_str().expect("utf8"),
+            "--stdin",
+            "-",
             "--stdin-max-bytes",
             &limit.to_string(),
             "--",
             "cat",
         ],
         Some(h.root()),
-        None,
+        Some(&input),
     );
     let stdout = String::from_utf8_lossy(&output.stdout);
     let v: serde_json::Value = serde_json::from_str(stdout.trim()).expect("stdout should be JSON");
@@ -8074,3 +11868,58 @@ fn compression_schema_and_help_list_modes_without_auto() {
     assert!(help.contains("--rtk <MODE>"));
     assert!(help.contains("route"));
 }
+
+// ── run --startup-probe ───────────────────────────────────────────────────────
+
+#[test]
+fn run_startup_probe_reports_pass_and_failure() {
+    let h = TestHarness::new();
+    let started = std::time::Instant::now();
+    let v = h.run(&["run", "--startup-probe", "echo ok", "--", "sleep", "5"]);
+    assert_eq!(v["ok"], true, "{v}");
+    assert_eq!(v["state"], "running", "{v}");
+    assert_eq!(v["startup_probe_passed"], true, "{v}");
+    assert!(started.elapsed() < std::time::Duration::from_secs(4), "{v}");
+
+    let v = h.run(&[
+        "run",
+        "--startup-probe",
+        "false",
+        "--startup-probe-interval-ms",
+        "50",
+        "--startup-probe-timeout-ms",
+        "300",
+        "--",
+        "sleep",
+        "5",
+    ]);
+    assert_eq!(v["state"], "running", "{v}");
+    assert_eq!(v["startup_probe_passed"], false, "{v}");
+
+    let v = h.run(&["run", "--", "echo", "hi"]);
+    assert!(v.get("startup_probe_passed").is_none(), "{v}");
+}
+
+/// Spec: the startup probe sees the job's environment, so `--no-inherit-env`
+/// drops parent-only variables while `--env` values still arrive.
+#[test]
+fn run_startup_probe_uses_job_environment() {
+    let h = TestHarness::new();
+    let v = h.run(&[
+        "run",
+        "--no-inherit-env",
+        "--env",
+        "AGENT_EXEC_PROBE_VAR=1",
+        "--startup-probe",
+        "test -z \"$HOME\" && test \"$AGENT_EXEC_PROBE_VAR\" = 1",
+        "--startup-probe-interval-ms",
+        "50",
+        "--startup-probe-timeout-ms",
+        "2000",
+        "--",
+        "sleep",
+        "5",
+    ]);
+    assert_eq!(v["state"], "running", "{v}");
+    assert_eq!(v["startup_probe_passed"], true, "{v}");
+}